@@ -123,6 +123,26 @@ pub fn build_source_command<'a>() -> Command<'a> {
                         .display_order(2),
                 ])
             )
+        .subcommand(
+            Command::new("dead-letters")
+                .about("Lists the documents of a source rejected by its indexing pipeline.")
+                .args(&[
+                    arg!(--index <INDEX_ID> "ID of the target index")
+                        .display_order(1),
+                    arg!(--source <SOURCE_ID> "ID of the source.")
+                        .display_order(2),
+                ])
+            )
+        .subcommand(
+            Command::new("replay-dead-letters")
+                .about("Re-ingests the documents of a source rejected by its indexing pipeline through the ingest API.")
+                .args(&[
+                    arg!(--index <INDEX_ID> "ID of the target index")
+                        .display_order(1),
+                    arg!(--source <SOURCE_ID> "ID of the source.")
+                        .display_order(2),
+                ])
+            )
         .arg_required_else_help(true)
 }
 
@@ -170,6 +190,13 @@ pub struct ResetCheckpointArgs {
     pub assume_yes: bool,
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct DeadLettersArgs {
+    pub cluster_endpoint: Url,
+    pub index_id: String,
+    pub source_id: String,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum SourceCliCommand {
     CreateSource(CreateSourceArgs),
@@ -178,6 +205,8 @@ pub enum SourceCliCommand {
     DescribeSource(DescribeSourceArgs),
     ListSources(ListSourcesArgs),
     ResetCheckpoint(ResetCheckpointArgs),
+    DeadLetters(DeadLettersArgs),
+    ReplayDeadLetters(DeadLettersArgs),
 }
 
 impl SourceCliCommand {
@@ -189,6 +218,8 @@ impl SourceCliCommand {
             Self::DescribeSource(args) => describe_source_cli(args).await,
             Self::ListSources(args) => list_sources_cli(args).await,
             Self::ResetCheckpoint(args) => reset_checkpoint_cli(args).await,
+            Self::DeadLetters(args) => dead_letters_cli(args).await,
+            Self::ReplayDeadLetters(args) => replay_dead_letters_cli(args).await,
         }
     }
 
@@ -210,6 +241,10 @@ impl SourceCliCommand {
             "reset-checkpoint" => {
                 Self::parse_reset_checkpoint_args(submatches).map(Self::ResetCheckpoint)
             }
+            "dead-letters" => Self::parse_dead_letters_args(submatches).map(Self::DeadLetters),
+            "replay-dead-letters" => {
+                Self::parse_dead_letters_args(submatches).map(Self::ReplayDeadLetters)
+            }
             _ => bail!("Source subcommand `{}` is not implemented.", subcommand),
         }
     }
@@ -337,6 +372,26 @@ impl SourceCliCommand {
             assume_yes,
         })
     }
+
+    fn parse_dead_letters_args(matches: &ArgMatches) -> anyhow::Result<DeadLettersArgs> {
+        let cluster_endpoint = matches
+            .value_of("endpoint")
+            .map(Url::from_str)
+            .expect("`endpoint` is a required arg.")?;
+        let index_id = matches
+            .value_of("index")
+            .map(String::from)
+            .expect("`index` is a required arg.");
+        let source_id = matches
+            .value_of("source")
+            .map(String::from)
+            .expect("`source` is a required arg.");
+        Ok(DeadLettersArgs {
+            cluster_endpoint,
+            index_id,
+            source_id,
+        })
+    }
 }
 
 async fn create_source_cli(args: CreateSourceArgs) -> anyhow::Result<()> {
@@ -541,6 +596,47 @@ async fn reset_checkpoint_cli(args: ResetCheckpointArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Number of dead letters fetched per request by the `dead-letters` command.
+const DEAD_LETTERS_PAGE_SIZE: usize = 1_000;
+
+async fn dead_letters_cli(args: DeadLettersArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "dead-letters");
+    let transport = Transport::new(args.cluster_endpoint);
+    let qw_client = QuickwitClient::new(transport);
+    let mut start_after = None;
+    loop {
+        let dead_letters = qw_client
+            .sources(&args.index_id)
+            .dead_letters(&args.source_id, start_after, DEAD_LETTERS_PAGE_SIZE)
+            .await?;
+        let Some(last_dead_letter) = dead_letters.last() else {
+            break;
+        };
+        start_after = Some(last_dead_letter.position);
+
+        for dead_letter in &dead_letters {
+            println!("{}", serde_json::to_string(dead_letter)?);
+        }
+    }
+    Ok(())
+}
+
+async fn replay_dead_letters_cli(args: DeadLettersArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "replay-dead-letters");
+    println!("❯ Replaying dead letters...");
+    let transport = Transport::new(args.cluster_endpoint);
+    let qw_client = QuickwitClient::new(transport);
+    let num_replayed_docs = qw_client
+        .sources(&args.index_id)
+        .replay_dead_letters(&args.source_id)
+        .await?;
+    println!(
+        "{} {num_replayed_docs} documents successfully replayed.",
+        "✔".color(GREEN_COLOR)
+    );
+    Ok(())
+}
+
 /// Recursively flattens a JSON object into a vector of `(path, value)` tuples where `path`
 /// represents the full path of each property in the original object. For instance, `{"root": true,
 /// "parent": {"child": 0}}` yields `[("root", true), ("parent.child", 0)]`. Arrays are not
@@ -573,7 +669,7 @@ mod tests {
     use std::num::NonZeroUsize;
     use std::str::FromStr;
 
-    use quickwit_config::SourceParams;
+    use quickwit_config::{SourceInputFormat, SourceParams};
     use quickwit_metastore::checkpoint::{PartitionId, Position};
     use serde_json::json;
 
@@ -743,6 +839,29 @@ mod tests {
         assert_eq!(command, expected_command);
     }
 
+    #[test]
+    fn test_parse_dead_letters_args() {
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from(vec![
+                "source",
+                "replay-dead-letters",
+                "--index",
+                "hdfs-logs",
+                "--source",
+                "hdfs-logs-source",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(&matches).unwrap();
+        let expected_command =
+            CliCommand::Source(SourceCliCommand::ReplayDeadLetters(DeadLettersArgs {
+                cluster_endpoint: Url::from_str("http://127.0.0.1:7280").unwrap(),
+                index_id: "hdfs-logs".to_string(),
+                source_id: "hdfs-logs-source".to_string(),
+            }));
+        assert_eq!(command, expected_command);
+    }
+
     #[test]
     fn test_make_describe_source_tables() {
         assert!(make_describe_source_tables(
@@ -763,6 +882,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::file("path/to/file"),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         }];
         let expected_source = vec![SourceRow {
             source_id: "foo-source".to_string(),
@@ -823,6 +944,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::stdin(),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
             SourceConfig {
                 source_id: "bar-source".to_string(),
@@ -831,6 +954,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::stdin(),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
         ];
         let expected_sources = [
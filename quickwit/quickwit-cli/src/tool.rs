@@ -36,8 +36,8 @@ use quickwit_common::uri::Uri;
 use quickwit_common::{GREEN_COLOR, RED_COLOR};
 use quickwit_config::service::QuickwitService;
 use quickwit_config::{
-    IndexerConfig, SourceConfig, SourceParams, TransformConfig, VecSourceParams,
-    CLI_INGEST_SOURCE_ID,
+    FileSourceParams, IndexerConfig, SourceConfig, SourceInputFormat, SourceParams,
+    TransformConfig, VecSourceParams, CLI_INGEST_SOURCE_ID,
 };
 use quickwit_core::{clear_cache_directory, IndexService};
 use quickwit_indexing::actors::{IndexingService, MergePipeline, MergePipelineId};
@@ -68,7 +68,7 @@ pub fn build_tool_command<'a>() -> Command<'a> {
                 .args(&[
                     arg!(--index <INDEX> "ID of the target index")
                         .display_order(1),
-                    arg!(--"input-path" <INPUT_PATH> "Location of the input file.")
+                    arg!(--"input-path" <INPUT_PATH> "Location of the input file(s): a path or a storage URI, which may also designate a prefix or a glob pattern. Gzip, zstd, and snappy compressed files are decompressed on the fly.")
                         .required(false),
                     arg!(--overwrite "Overwrites pre-existing index.")
                         .required(false),
@@ -120,7 +120,7 @@ pub fn build_tool_command<'a>() -> Command<'a> {
 pub struct LocalIngestDocsArgs {
     pub config_uri: Uri,
     pub index_id: String,
-    pub input_path_opt: Option<PathBuf>,
+    pub input_path_opt: Option<Uri>,
     pub overwrite: bool,
     pub vrl_script: Option<String>,
     pub clear_cache: bool,
@@ -180,13 +180,10 @@ impl ToolCliCommand {
             .value_of("index")
             .expect("`index` is a required arg.")
             .to_string();
-        let input_path_opt = if let Some(input_path) = matches.value_of("input-path") {
-            Uri::from_str(input_path)?
-                .filepath()
-                .map(|path| path.to_path_buf())
-        } else {
-            None
-        };
+        let input_path_opt = matches
+            .value_of("input-path")
+            .map(Uri::from_str)
+            .transpose()?;
         let overwrite = matches.is_present("overwrite");
         let vrl_script = matches
             .value_of("transform-script")
@@ -287,8 +284,8 @@ pub async fn local_ingest_docs_cli(args: LocalIngestDocsArgs) -> anyhow::Result<
 
     let config = load_quickwit_config(&args.config_uri).await?;
 
-    let source_params = if let Some(filepath) = args.input_path_opt.as_ref() {
-        SourceParams::file(filepath)
+    let source_params = if let Some(input_uri) = args.input_path_opt.as_ref() {
+        SourceParams::File(FileSourceParams::from_uri(input_uri.clone()))
     } else {
         SourceParams::stdin()
     };
@@ -302,6 +299,8 @@ pub async fn local_ingest_docs_cli(args: LocalIngestDocsArgs) -> anyhow::Result<
         enabled: true,
         source_params,
         transform_config,
+        dead_letter_queue_enabled: false,
+        input_format: SourceInputFormat::default(),
     };
     run_index_checklist(&config.metastore_uri, &args.index_id, Some(&source_config)).await?;
     let metastore_uri_resolver = quickwit_metastore_uri_resolver();
@@ -435,6 +434,8 @@ pub async fn merge_cli(args: MergeArgs) -> anyhow::Result<()> {
                 enabled: true,
                 source_params: SourceParams::Vec(VecSourceParams::default()),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
             pipeline_ord: 0,
         })
@@ -548,7 +549,10 @@ async fn extract_split_cli(args: ExtractSplitArgs) -> anyhow::Result<()> {
         .resolve(&quickwit_config.metastore_uri)
         .await?;
     let index_metadata = metastore.index_metadata(&args.index_id).await?;
-    let index_storage = storage_uri_resolver.resolve(index_metadata.index_uri())?;
+    let index_storage = storage_uri_resolver.resolve_with_encryption(
+        index_metadata.index_uri(),
+        index_metadata.index_config().encryption_key_uri(),
+    )?;
     let split_file = PathBuf::from(format!("{}.split", args.split_id));
     let split_data = index_storage.get_all(split_file.as_path()).await?;
     let (_hotcache_bytes, bundle_storage) = BundleStorage::open_from_split_data_with_owned_bytes(
@@ -63,7 +63,7 @@ async fn local_ingest_docs(input_path: &Path, test_env: &TestEnv) -> anyhow::Res
     let args = LocalIngestDocsArgs {
         config_uri: test_env.config_uri.clone(),
         index_id: test_env.index_id.clone(),
-        input_path_opt: Some(input_path.to_path_buf()),
+        input_path_opt: Some(Uri::from_str(&input_path.to_string_lossy()).unwrap()),
         overwrite: false,
         clear_cache: true,
         vrl_script: None,
@@ -163,7 +163,9 @@ async fn test_cmd_ingest_on_non_existing_index() {
     let args = LocalIngestDocsArgs {
         config_uri: test_env.config_uri,
         index_id: "index-does-not-exist".to_string(),
-        input_path_opt: Some(test_env.resource_files["logs"].clone()),
+        input_path_opt: Some(
+            Uri::from_str(&test_env.resource_files["logs"].to_string_lossy()).unwrap(),
+        ),
         overwrite: false,
         clear_cache: true,
         vrl_script: None,
@@ -190,7 +192,9 @@ async fn test_ingest_docs_cli_keep_cache() {
     let args = LocalIngestDocsArgs {
         config_uri: test_env.config_uri,
         index_id,
-        input_path_opt: Some(test_env.resource_files["logs"].clone()),
+        input_path_opt: Some(
+            Uri::from_str(&test_env.resource_files["logs"].to_string_lossy()).unwrap(),
+        ),
         overwrite: false,
         clear_cache: false,
         vrl_script: None,
@@ -213,7 +217,9 @@ async fn test_ingest_docs_cli() {
     let args = LocalIngestDocsArgs {
         config_uri: test_env.config_uri.clone(),
         index_id: index_id.clone(),
-        input_path_opt: Some(test_env.resource_files["logs"].clone()),
+        input_path_opt: Some(
+            Uri::from_str(&test_env.resource_files["logs"].to_string_lossy()).unwrap(),
+        ),
         overwrite: false,
         clear_cache: true,
         vrl_script: None,
@@ -241,7 +247,15 @@ async fn test_ingest_docs_cli() {
     let args = LocalIngestDocsArgs {
         config_uri: test_env.config_uri,
         index_id: test_env.index_id,
-        input_path_opt: Some(test_env.data_dir_path.join("file-does-not-exist.json")),
+        input_path_opt: Some(
+            Uri::from_str(
+                &test_env
+                    .data_dir_path
+                    .join("file-does-not-exist.json")
+                    .to_string_lossy(),
+            )
+            .unwrap(),
+        ),
         overwrite: false,
         clear_cache: true,
         vrl_script: None,
@@ -131,6 +131,29 @@ pub fn is_false(value: &bool) -> bool {
     !*value
 }
 
+/// Matches a field name against a pattern supporting the `*` wildcard.
+pub fn field_matches_pattern(field_name: &str, field_pattern: &str) -> bool {
+    let mut parts = field_pattern.split('*');
+    let Some(first_part) = parts.next() else {
+        return true;
+    };
+    let Some(mut remaining) = field_name.strip_prefix(first_part) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last_part) = parts.pop() else {
+        // No wildcard in the pattern.
+        return remaining.is_empty();
+    };
+    for part in parts {
+        match remaining.find(part) {
+            Some(pos) => remaining = &remaining[pos + part.len()..],
+            None => return false,
+        }
+    }
+    remaining.ends_with(last_part)
+}
+
 pub fn no_color() -> bool {
     matches!(env::var("NO_COLOR"), Ok(value) if !value.is_empty())
 }
@@ -203,6 +226,18 @@ mod tests {
         assert_eq!(truncate_str("hello🧑‍🔬world", 7), "hello");
     }
 
+    #[test]
+    fn test_field_matches_pattern() {
+        assert!(field_matches_pattern("body", "body"));
+        assert!(!field_matches_pattern("body", "bod"));
+        assert!(field_matches_pattern("body", "*"));
+        assert!(field_matches_pattern("attributes.server", "attributes.*"));
+        assert!(field_matches_pattern("response_time", "*_time"));
+        assert!(field_matches_pattern("response_time", "res*_*e"));
+        assert!(!field_matches_pattern("response_date", "*_time"));
+        assert!(!field_matches_pattern("ab", "a*b*b"));
+    }
+
     #[test]
     fn test_ignore_io_error_macro() {
         ignore_error_kind!(
@@ -21,8 +21,9 @@ use std::borrow::{Borrow, Cow};
 
 use prometheus::{Encoder, HistogramOpts, Opts, TextEncoder};
 pub use prometheus::{
-    Histogram, HistogramTimer, HistogramVec as PrometheusHistogramVec, IntCounter,
-    IntCounterVec as PrometheusIntCounterVec, IntGauge, IntGaugeVec as PrometheusIntGaugeVec,
+    exponential_buckets, Histogram, HistogramTimer, HistogramVec as PrometheusHistogramVec,
+    IntCounter, IntCounterVec as PrometheusIntCounterVec, IntGauge,
+    IntGaugeVec as PrometheusIntGaugeVec,
 };
 
 pub struct OwnedPrometheusLabels<const N: usize> {
@@ -141,6 +142,22 @@ pub fn new_histogram_vec<const N: usize>(
     HistogramVec { underlying }
 }
 
+pub fn new_histogram_vec_with_buckets<const N: usize>(
+    name: &str,
+    description: &str,
+    namespace: &str,
+    label_names: [&str; N],
+    buckets: Vec<f64>,
+) -> HistogramVec<N> {
+    let histogram_opts = HistogramOpts::new(name, description)
+        .namespace(namespace)
+        .buckets(buckets);
+    let underlying = PrometheusHistogramVec::new(histogram_opts, &label_names)
+        .expect("Failed to create histogram vec");
+    prometheus::register(Box::new(underlying.clone())).expect("Failed to register histogram vec");
+    HistogramVec { underlying }
+}
+
 pub struct GaugeGuard(&'static IntGauge);
 
 impl GaugeGuard {
@@ -35,6 +35,7 @@ use serde::{Deserialize, Serialize, Serializer};
 pub enum Protocol {
     Azure,
     File,
+    Gcs,
     Grpc,
     PostgreSQL,
     Ram,
@@ -46,6 +47,7 @@ impl Protocol {
         match &self {
             Protocol::Azure => "azure",
             Protocol::File => "file",
+            Protocol::Gcs => "gs",
             Protocol::Grpc => "grpc",
             Protocol::PostgreSQL => "postgresql",
             Protocol::Ram => "ram",
@@ -61,6 +63,10 @@ impl Protocol {
         matches!(&self, Protocol::File)
     }
 
+    pub fn is_gcs(&self) -> bool {
+        matches!(&self, Protocol::Gcs)
+    }
+
     pub fn is_grpc(&self) -> bool {
         matches!(&self, Protocol::Grpc)
     }
@@ -82,7 +88,7 @@ impl Protocol {
     }
 
     pub fn is_object_storage(&self) -> bool {
-        matches!(&self, Protocol::Azure | Protocol::S3)
+        matches!(&self, Protocol::Azure | Protocol::Gcs | Protocol::S3)
     }
 
     pub fn is_database(&self) -> bool {
@@ -103,6 +109,7 @@ impl FromStr for Protocol {
         match protocol {
             "azure" => Ok(Protocol::Azure),
             "file" => Ok(Protocol::File),
+            "gs" => Ok(Protocol::Gcs),
             "grpc" => Ok(Protocol::Grpc),
             "postgres" | "postgresql" => Ok(Protocol::PostgreSQL),
             "ram" => Ok(Protocol::Ram),
@@ -192,7 +199,7 @@ impl Uri {
         }
         let protocol = &self.uri[..self.protocol_idx];
         let path = Path::new(&self.uri[self.protocol_idx + PROTOCOL_SEPARATOR.len()..]);
-        if self.protocol().is_s3() && path.components().count() < 2 {
+        if (self.protocol().is_s3() || self.protocol().is_gcs()) && path.components().count() < 2 {
             return None;
         }
         if self.protocol().is_azure() && path.components().count() < 3 {
@@ -212,7 +219,7 @@ impl Uri {
             return None;
         }
         let path = Path::new(&self.uri[self.protocol_idx + PROTOCOL_SEPARATOR.len()..]);
-        if self.protocol().is_s3() && path.components().count() < 2 {
+        if (self.protocol().is_s3() || self.protocol().is_gcs()) && path.components().count() < 2 {
             return None;
         }
         if self.protocol().is_azure() && path.components().count() < 3 {
@@ -492,6 +499,7 @@ mod tests {
             Uri::for_test("azure://account/bucket/key").protocol(),
             Protocol::Azure
         );
+        assert_eq!(Uri::for_test("gs://bucket/key").protocol(), Protocol::Gcs);
         assert_eq!(
             Uri::for_test("postgres://localhost:5432/metastore").protocol(),
             Protocol::PostgreSQL
@@ -591,6 +599,11 @@ mod tests {
             Uri::for_test("s3://bucket/foo/bar/").parent().unwrap(),
             "s3://bucket/foo"
         );
+        assert!(Uri::for_test("gs://bucket").parent().is_none());
+        assert_eq!(
+            Uri::for_test("gs://bucket/foo/bar").parent().unwrap(),
+            "gs://bucket/foo"
+        );
         assert!(Uri::for_test("azure://account/").parent().is_none());
         assert!(Uri::for_test("azure://account").parent().is_none());
         assert!(Uri::for_test("azure://account/container/")
@@ -653,6 +666,11 @@ mod tests {
             Uri::for_test("s3://bucket/foo/").file_name().unwrap(),
             Path::new("foo"),
         );
+        assert!(Uri::for_test("gs://bucket").file_name().is_none());
+        assert_eq!(
+            Uri::for_test("gs://bucket/foo").file_name().unwrap(),
+            Path::new("foo"),
+        );
         assert!(Uri::for_test("azure://account").file_name().is_none());
         assert!(Uri::for_test("azure://account/").file_name().is_none());
         assert!(Uri::for_test("azure://account/container")
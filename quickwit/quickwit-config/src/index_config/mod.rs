@@ -33,7 +33,7 @@ use humantime::parse_duration;
 use quickwit_common::uri::Uri;
 use quickwit_doc_mapper::{
     DefaultDocMapper, DefaultDocMapperBuilder, DocMapper, FieldMappingEntry, ModeType,
-    QuickwitJsonOptions,
+    QuickwitJsonOptions, TokenizerEntry,
 };
 use serde::{Deserialize, Serialize};
 pub use serialize::load_index_config_from_user_config;
@@ -76,6 +76,11 @@ pub struct DocMapping {
     #[schema(value_type = u32)]
     #[serde(default = "DefaultDocMapper::default_max_num_partitions")]
     pub max_num_partitions: NonZeroU32,
+    /// Custom tokenizers that can be referenced by the text and json field mappings.
+    #[schema(value_type = Vec<Object>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tokenizers: Vec<TokenizerEntry>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
@@ -265,6 +270,37 @@ impl RetentionPolicy {
     }
 }
 
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptionConfig {
+    /// URI of the key encrypting the data keys of the index files: either a local keyfile
+    /// (`file:///path/to/keyfile`) or a key managed by the KMS (`kms://key-id`).
+    pub key_uri: String,
+}
+
+impl EncryptionConfig {
+    /// Maximum length of a key URI, which is written in the header of every encrypted file.
+    const MAX_KEY_URI_LEN: usize = 256;
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !self.key_uri.starts_with("file://") && !self.key_uri.starts_with("kms://") {
+            anyhow::bail!(
+                "Failed to validate encryption key URI `{}`. The key URI must start with \
+                 `file://` or `kms://`.",
+                self.key_uri
+            );
+        }
+        if self.key_uri.len() > Self::MAX_KEY_URI_LEN {
+            anyhow::bail!(
+                "Failed to validate encryption key URI `{}`. The key URI must not exceed {} bytes.",
+                self.key_uri,
+                Self::MAX_KEY_URI_LEN
+            );
+        }
+        Ok(())
+    }
+}
+
 /// Prepends an `@` char at the start of the cron expression if necessary:
 /// `hourly` -> `@hourly`
 fn prepend_at_char(schedule: &str) -> String {
@@ -290,9 +326,17 @@ pub struct IndexConfig {
     pub indexing_settings: IndexingSettings,
     pub search_settings: SearchSettings,
     pub retention_policy: Option<RetentionPolicy>,
+    pub encryption: Option<EncryptionConfig>,
 }
 
 impl IndexConfig {
+    /// Returns the URI of the key encrypting the index files, if encryption is enabled.
+    pub fn encryption_key_uri(&self) -> Option<&str> {
+        self.encryption
+            .as_ref()
+            .map(|encryption| encryption.key_uri.as_str())
+    }
+
     #[cfg(any(test, feature = "testsuite"))]
     pub fn for_test(index_id: &str, index_uri: &str) -> Self {
         let index_uri = Uri::from_str(index_uri).unwrap();
@@ -374,6 +418,7 @@ impl IndexConfig {
             indexing_settings,
             search_settings,
             retention_policy: Default::default(),
+            encryption: None,
         }
     }
 }
@@ -430,6 +475,7 @@ impl TestableForRegression for IndexConfig {
             partition_key: Some("tenant".to_string()),
             max_num_partitions: NonZeroU32::new(100).unwrap(),
             timestamp_field: Some("timestamp".to_string()),
+            tokenizers: Vec::new(),
         };
         let retention_policy = Some(RetentionPolicy::new(
             "90 days".to_string(),
@@ -462,6 +508,7 @@ impl TestableForRegression for IndexConfig {
             indexing_settings,
             retention_policy,
             search_settings,
+            encryption: None,
         }
     }
 
@@ -506,6 +553,7 @@ pub fn build_doc_mapper(
         dynamic_mapping: doc_mapping.dynamic_mapping.clone(),
         partition_key: doc_mapping.partition_key.clone(),
         max_num_partitions: doc_mapping.max_num_partitions,
+        tokenizers: doc_mapping.tokenizers.clone(),
     };
     Ok(Arc::new(builder.try_build()?))
 }
@@ -701,6 +749,57 @@ mod tests {
         assert_eq!(minimal_config.doc_mapping.mode, ModeType::Lenient);
     }
 
+    #[test]
+    fn test_index_config_with_custom_tokenizers() {
+        let config_yaml = r#"
+            version: 0.5
+            index_id: hdfs-logs
+            index_uri: "s3://my-index"
+            doc_mapping:
+              tokenizers:
+                - name: autocomplete
+                  type: edge_ngram
+                  min_gram: 2
+                  max_gram: 10
+                  filters:
+                    - lower_caser
+                    - ascii_folding
+              field_mappings:
+                - name: body
+                  type: text
+                  tokenizer: autocomplete
+        "#;
+        let index_config: IndexConfig = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Uri::from_well_formed("s3://my-index"),
+        )
+        .unwrap();
+        assert_eq!(index_config.doc_mapping.tokenizers.len(), 1);
+        assert_eq!(index_config.doc_mapping.tokenizers[0].name, "autocomplete");
+
+        let config_yaml = r#"
+            version: 0.5
+            index_id: hdfs-logs
+            index_uri: "s3://my-index"
+            doc_mapping:
+              field_mappings:
+                - name: body
+                  type: text
+                  tokenizer: autocomplete
+        "#;
+        let error = load_index_config_from_user_config(
+            ConfigFormat::Yaml,
+            config_yaml.as_bytes(),
+            &Uri::from_well_formed("s3://my-index"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown tokenizer `autocomplete` for field `body`."
+        );
+    }
+
     #[test]
     fn test_index_config_with_malformed_maturation_duration() {
         let config_yaml = r#"
@@ -23,8 +23,8 @@ use serde::{Deserialize, Serialize};
 use tracing::info;
 
 use crate::{
-    build_doc_mapper, validate_identifier, ConfigFormat, DocMapping, IndexConfig, IndexingSettings,
-    RetentionPolicy, SearchSettings,
+    build_doc_mapper, validate_identifier, ConfigFormat, DocMapping, EncryptionConfig, IndexConfig,
+    IndexingSettings, RetentionPolicy, SearchSettings,
 };
 
 /// Alias for the latest serialization format.
@@ -104,6 +104,10 @@ impl IndexConfigForSerialization {
 
         self.indexing_settings.merge_policy.validate()?;
 
+        if let Some(encryption) = &self.encryption {
+            encryption.validate()?;
+        }
+
         Ok(IndexConfig {
             index_id: self.index_id,
             index_uri,
@@ -111,6 +115,7 @@ impl IndexConfigForSerialization {
             indexing_settings: self.indexing_settings,
             search_settings: self.search_settings,
             retention_policy: self.retention_policy,
+            encryption: self.encryption,
         })
     }
 }
@@ -146,6 +151,9 @@ pub struct IndexConfigV0_5 {
     #[serde(rename = "retention")]
     #[serde(default)]
     pub retention_policy: Option<RetentionPolicy>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionConfig>,
 }
 
 impl From<IndexConfig> for IndexConfigV0_5 {
@@ -157,6 +165,7 @@ impl From<IndexConfig> for IndexConfigV0_5 {
             indexing_settings: index_config.indexing_settings,
             search_settings: index_config.search_settings,
             retention_policy: index_config.retention_policy,
+            encryption: index_config.encryption,
         }
     }
 }
@@ -224,6 +233,31 @@ mod test {
         assert!(validation_err.contains("The retention policy requires a timestamp field"));
     }
 
+    #[test]
+    fn test_validate_encryption() {
+        let mut index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        index_config.encryption = Some(EncryptionConfig {
+            key_uri: "file:///etc/quickwit/keys/hdfs-logs.key".to_string(),
+        });
+        let index_config = index_config.validate_and_build(None).unwrap();
+        assert_eq!(
+            index_config.encryption.unwrap().key_uri,
+            "file:///etc/quickwit/keys/hdfs-logs.key"
+        );
+
+        let mut invalid_index_config: IndexConfigForSerialization =
+            minimal_index_config_for_serialization();
+        invalid_index_config.encryption = Some(EncryptionConfig {
+            key_uri: "s3://quickwit-keys/hdfs-logs.key".to_string(),
+        });
+        let validation_err = invalid_index_config
+            .validate_and_build(None)
+            .unwrap_err()
+            .to_string();
+        assert!(validation_err.contains("must start with `file://` or `kms://`"));
+    }
+
     #[test]
     fn test_minimal_index_config_missing_root_uri_no_default_uri() {
         let config_yaml = r#"
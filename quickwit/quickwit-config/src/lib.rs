@@ -40,16 +40,19 @@ mod templating;
 // See #2048
 use index_config::serialize::{IndexConfigV0_5, VersionedIndexConfig};
 pub use index_config::{
-    build_doc_mapper, load_index_config_from_user_config, DocMapping, IndexConfig,
-    IndexingResources, IndexingSettings, RetentionPolicy, SearchSettings,
+    build_doc_mapper, load_index_config_from_user_config, DocMapping, EncryptionConfig,
+    IndexConfig, IndexingResources, IndexingSettings, RetentionPolicy, SearchSettings,
 };
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 pub use source_config::{
-    load_source_config_from_user_config, FileSourceParams, KafkaSourceParams, KinesisSourceParams,
-    PulsarSourceAuth, PulsarSourceParams, RegionOrEndpoint, SourceConfig, SourceParams,
-    TransformConfig, VecSourceParams, VoidSourceParams, CLI_INGEST_SOURCE_ID, INGEST_API_SOURCE_ID,
+    load_source_config_from_user_config, AmqpSourceParams, FileSourceParams, HttpSourceParams,
+    KafkaMetadataFields, KafkaPayloadFormat, KafkaSourceParams, KinesisSourceParams,
+    NatsSourceAuth, NatsSourceParams, PulsarSourceAuth, PulsarSourceParams,
+    RedisStreamsSourceParams, RegionOrEndpoint, SchemaRegistryParams, SourceConfig,
+    SourceInputFormat, SourceParams, TransformConfig, VecSourceParams, VoidSourceParams,
+    CLI_INGEST_SOURCE_ID, INGEST_API_SOURCE_ID,
 };
 use tracing::warn;
 
@@ -68,6 +71,7 @@ use crate::source_config::serialize::{SourceConfigV0_5, VersionedSourceConfig};
     IndexingSettings,
     SearchSettings,
     RetentionPolicy,
+    EncryptionConfig,
     MergePolicyConfig,
     DocMapping,
     VersionedSourceConfig,
@@ -101,6 +101,9 @@ pub struct SearcherConfig {
     pub aggregation_bucket_limit: u32,
     pub fast_field_cache_capacity: Byte,
     pub split_footer_cache_capacity: Byte,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_cache_capacity: Option<Byte>,
+    pub leaf_search_cache_capacity: Byte,
     pub max_num_concurrent_split_searches: usize,
     pub max_num_concurrent_split_streams: usize,
 }
@@ -110,6 +113,8 @@ impl Default for SearcherConfig {
         Self {
             fast_field_cache_capacity: Byte::from_bytes(1_000_000_000), // 1G
             split_footer_cache_capacity: Byte::from_bytes(500_000_000), // 500M
+            disk_cache_capacity: None,
+            leaf_search_cache_capacity: Byte::from_bytes(64_000_000), // 64M
             max_num_concurrent_split_streams: 100,
             max_num_concurrent_split_searches: 100,
             aggregation_memory_limit: Byte::from_bytes(500_000_000), // 500M
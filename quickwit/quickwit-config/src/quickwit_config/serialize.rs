@@ -448,6 +448,8 @@ mod tests {
                 aggregation_bucket_limit: 500_000,
                 fast_field_cache_capacity: Byte::from_str("10G").unwrap(),
                 split_footer_cache_capacity: Byte::from_str("1G").unwrap(),
+                disk_cache_capacity: Some(Byte::from_str("50G").unwrap()),
+                leaf_search_cache_capacity: Byte::from_str("256M").unwrap(),
                 max_num_concurrent_split_searches: 150,
                 max_num_concurrent_split_streams: 120,
             }
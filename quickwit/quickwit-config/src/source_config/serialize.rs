@@ -20,9 +20,10 @@
 use std::num::NonZeroUsize;
 
 use anyhow::bail;
+use quickwit_common::is_false;
 use serde::{Deserialize, Serialize};
 
-use super::TransformConfig;
+use super::{SourceInputFormat, TransformConfig};
 use crate::{
     validate_identifier, ConfigFormat, SourceConfig, SourceParams, CLI_INGEST_SOURCE_ID,
     INGEST_API_SOURCE_ID,
@@ -90,9 +91,32 @@ impl SourceConfigForSerialization {
                     )
                 }
             }
-            SourceParams::Kafka(_) | SourceParams::Kinesis(_) | SourceParams::Pulsar(_) => {
+            SourceParams::Kafka(kafka_params) => {
+                if !kafka_params.payload_format.is_json() && kafka_params.schema_registry.is_none()
+                {
+                    bail!(
+                        "Source `{}` of type `kafka` must define a `schema_registry` to decode \
+                         Avro or Protobuf payloads.",
+                        self.source_id
+                    )
+                }
+            }
+            SourceParams::Kinesis(_)
+            | SourceParams::Pulsar(_)
+            | SourceParams::Nats(_)
+            | SourceParams::Amqp(_)
+            | SourceParams::RedisStreams(_) => {
                 // TODO consider any validation opportunity
             }
+            SourceParams::Http(http_params) => {
+                if http_params.poll_interval_secs == 0 {
+                    bail!(
+                        "Source `{}` of type `http` must have a strictly positive \
+                         `poll_interval_secs`.",
+                        self.source_id
+                    )
+                }
+            }
             SourceParams::Vec(_)
             | SourceParams::Void(_)
             | SourceParams::IngestApi
@@ -106,6 +130,14 @@ impl SourceConfigForSerialization {
                 }
             }
         }
+        if self.input_format == SourceInputFormat::Csv
+            && !matches!(
+                self.source_params,
+                SourceParams::File(_) | SourceParams::Vec(_)
+            )
+        {
+            bail!("The `csv` input format is only supported by file sources.");
+        }
         if let Some(transform_config) = &self.transform {
             transform_config.compile_vrl_script()?;
         }
@@ -116,6 +148,8 @@ impl SourceConfigForSerialization {
             enabled: self.enabled,
             source_params: self.source_params,
             transform_config: self.transform,
+            dead_letter_queue_enabled: self.dead_letter_queue_enabled,
+            input_format: self.input_format,
         })
     }
 }
@@ -129,6 +163,8 @@ impl From<SourceConfig> for SourceConfigV0_5 {
             enabled: source_config.enabled,
             source_params: source_config.source_params,
             transform: source_config.transform_config,
+            dead_letter_queue_enabled: source_config.dead_letter_queue_enabled,
+            input_format: source_config.input_format,
         }
     }
 }
@@ -182,4 +218,10 @@ pub struct SourceConfigV0_5 {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub transform: Option<TransformConfig>,
+
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub dead_letter_queue_enabled: bool,
+
+    #[serde(default, skip_serializing_if = "SourceInputFormat::is_json")]
+    pub input_format: SourceInputFormat,
 }
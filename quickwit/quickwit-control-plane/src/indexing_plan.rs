@@ -321,8 +321,8 @@ mod tests {
     use quickwit_common::rand::append_random_suffix;
     use quickwit_config::service::QuickwitService;
     use quickwit_config::{
-        FileSourceParams, KafkaSourceParams, SourceConfig, SourceParams, CLI_INGEST_SOURCE_ID,
-        INGEST_API_SOURCE_ID,
+        FileSourceParams, KafkaMetadataFields, KafkaPayloadFormat, KafkaSourceParams, SourceConfig,
+        SourceInputFormat, SourceParams, CLI_INGEST_SOURCE_ID, INGEST_API_SOURCE_ID,
     };
     use quickwit_proto::indexing_api::IndexingTask;
     use rand::seq::SliceRandom;
@@ -339,6 +339,9 @@ mod tests {
                 "bootstrap.servers": "localhost:9092",
             }),
             enable_backfill_mode: true,
+            payload_format: KafkaPayloadFormat::default(),
+            schema_registry: None,
+            metadata_fields: KafkaMetadataFields::default(),
         })
     }
 
@@ -393,6 +396,8 @@ mod tests {
                 enabled: true,
                 source_params: kafka_source_params_for_test(),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
         );
 
@@ -427,6 +432,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::IngestApi,
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
         );
 
@@ -469,6 +476,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::File(FileSourceParams { filepath: None }),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
         );
         source_configs_map.insert(
@@ -480,6 +489,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::IngestCli,
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
         );
         source_configs_map.insert(
@@ -491,6 +502,8 @@ mod tests {
                 enabled: false,
                 source_params: kafka_source_params_for_test(),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
         );
         let indexing_tasks = build_indexing_plan(&indexers, &source_configs_map);
@@ -525,6 +538,8 @@ mod tests {
                 enabled: true,
                 source_params: kafka_source_params_for_test(),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
         );
         source_configs_map.insert(
@@ -536,6 +551,8 @@ mod tests {
                 enabled: true,
                 source_params: kafka_source_params_for_test(),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
         );
         let mut indexing_tasks = Vec::new();
@@ -603,6 +620,8 @@ mod tests {
                 enabled: true,
                 source_params: kafka_source_params_for_test(),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             },
         );
         let indexing_tasks = vec![
@@ -664,6 +683,8 @@ mod tests {
               enabled: true,
               source_params: kafka_source_params_for_test(),
               transform_config: None,
+              dead_letter_queue_enabled: false,
+              input_format: SourceInputFormat::default(),
           })
       }
     }
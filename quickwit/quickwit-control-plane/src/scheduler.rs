@@ -522,7 +522,10 @@ mod tests {
     use quickwit_actors::{ActorHandle, Inbox, Universe, HEARTBEAT};
     use quickwit_cluster::{create_cluster_for_test, grpc_addr_from_listen_addr_for_test, Cluster};
     use quickwit_common::test_utils::wait_until_predicate;
-    use quickwit_config::{KafkaSourceParams, SourceConfig, SourceParams};
+    use quickwit_config::{
+        KafkaMetadataFields, KafkaPayloadFormat, KafkaSourceParams, SourceConfig,
+        SourceInputFormat, SourceParams,
+    };
     use quickwit_grpc_clients::service_client_pool::ServiceClientPool;
     use quickwit_indexing::indexing_client::IndexingServiceClient;
     use quickwit_indexing::IndexingService;
@@ -555,8 +558,13 @@ mod tests {
                     "bootstrap.servers": "localhost:9092",
                 }),
                 enable_backfill_mode: true,
+                payload_format: KafkaPayloadFormat::default(),
+                schema_registry: None,
+                metadata_fields: KafkaMetadataFields::default(),
             }),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         index_metadata
             .sources
@@ -366,6 +366,7 @@ pub async fn validate_storage_uri(
     storage_uri_resolver: &StorageUriResolver,
     index_config: &IndexConfig,
 ) -> anyhow::Result<()> {
-    storage_uri_resolver.resolve(&index_config.index_uri)?;
+    storage_uri_resolver
+        .resolve_with_encryption(&index_config.index_uri, index_config.encryption_key_uri())?;
     Ok(())
 }
@@ -26,16 +26,18 @@ use serde::{Deserialize, Serialize};
 use serde_json::{self, Value as JsonValue};
 use tantivy::query::Query;
 use tantivy::schema::{Field, FieldType, Schema, Value as TantivyValue, STORED};
+use tantivy::tokenizer::TokenizerManager;
 use tantivy::Document;
 
 use super::field_mapping_entry::QuickwitTextTokenizer;
-use super::DefaultDocMapperBuilder;
+use super::{DefaultDocMapperBuilder, TokenizerEntry};
 use crate::default_doc_mapper::mapping_tree::{build_mapping_tree, MappingNode};
 use crate::default_doc_mapper::FieldMappingType;
 pub use crate::default_doc_mapper::QuickwitJsonOptions;
 use crate::doc_mapper::{JsonObject, Partition};
 use crate::query_builder::build_query;
 use crate::routing_expression::RoutingExpr;
+use crate::tokenizers::create_tokenizer_manager;
 use crate::{
     Cardinality, DocMapper, DocParsingError, ModeType, QueryParserError, WarmupInfo,
     DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME,
@@ -96,6 +98,10 @@ pub struct DefaultDocMapper {
     required_fields: Vec<Field>,
     /// Defines how unmapped fields should be handle.
     mode: Mode,
+    /// Custom tokenizers declared in the doc mapping.
+    tokenizer_entries: Vec<TokenizerEntry>,
+    /// Tokenizer manager holding the built-in and custom tokenizers.
+    tokenizer_manager: TokenizerManager,
 }
 
 impl DefaultDocMapper {
@@ -127,7 +133,7 @@ fn validate_tag_fields(tag_fields: &[String], schema: &Schema) -> anyhow::Result
                     .get_indexing_options()
                     .map(|text_options| text_options.tokenizer());
 
-                if tokenizer_opt != Some(QuickwitTextTokenizer::Raw.get_name()) {
+                if tokenizer_opt != Some(QuickwitTextTokenizer::raw().get_name()) {
                     bail!(
                         "Tags collection is only allowed on text fields with the `raw` tokenizer."
                     );
@@ -142,6 +148,33 @@ fn validate_tag_fields(tag_fields: &[String], schema: &Schema) -> anyhow::Result
     Ok(())
 }
 
+/// Checks that the tokenizers referenced by the indexed text and json fields are registered.
+fn validate_tokenizers(
+    schema: &Schema,
+    tokenizer_manager: &TokenizerManager,
+) -> anyhow::Result<()> {
+    for (_field, field_entry) in schema.fields() {
+        let tokenizer_name_opt = match field_entry.field_type() {
+            FieldType::Str(text_options) => text_options
+                .get_indexing_options()
+                .map(|text_field_indexing| text_field_indexing.tokenizer()),
+            FieldType::JsonObject(json_options) => json_options
+                .get_text_indexing_options()
+                .map(|text_field_indexing| text_field_indexing.tokenizer()),
+            _ => None,
+        };
+        if let Some(tokenizer_name) = tokenizer_name_opt {
+            if tokenizer_manager.get(tokenizer_name).is_none() {
+                bail!(
+                    "Unknown tokenizer `{tokenizer_name}` for field `{}`.",
+                    field_entry.name()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
 fn validate_timestamp_field_if_any(builder: &DefaultDocMapperBuilder) -> anyhow::Result<()> {
     let Some(timestamp_field_name) = builder.timestamp_field.as_ref() else {
         return Ok(());
@@ -197,6 +230,9 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
         // validate fast fields
         validate_tag_fields(&builder.tag_fields, &schema)?;
 
+        let tokenizer_manager = create_tokenizer_manager(&builder.tokenizers)?;
+        validate_tokenizers(&schema, &tokenizer_manager)?;
+
         // Resolve default search fields
         let mut default_search_field_names = Vec::new();
         for field_name in &builder.default_search_fields {
@@ -236,6 +272,8 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             partition_key,
             max_num_partitions: builder.max_num_partitions,
             mode,
+            tokenizer_entries: builder.tokenizers,
+            tokenizer_manager,
         })
     }
 }
@@ -265,6 +303,7 @@ impl From<DefaultDocMapper> for DefaultDocMapperBuilder {
             dynamic_mapping,
             partition_key: partition_key_opt,
             max_num_partitions: default_doc_mapper.max_num_partitions,
+            tokenizers: default_doc_mapper.tokenizer_entries,
         }
     }
 }
@@ -373,7 +412,12 @@ impl DocMapper for DefaultDocMapper {
                 tantivy_default_search_field_names.push(DYNAMIC_FIELD_NAME.to_string());
             }
         }
-        build_query(split_schema, request, &tantivy_default_search_field_names)
+        build_query(
+            split_schema,
+            request,
+            &tantivy_default_search_field_names,
+            &self.tokenizer_manager,
+        )
     }
 
     fn schema(&self) -> Schema {
@@ -391,6 +435,10 @@ impl DocMapper for DefaultDocMapper {
     fn max_num_partitions(&self) -> NonZeroU32 {
         self.max_num_partitions
     }
+
+    fn tokenizer_manager(&self) -> &TokenizerManager {
+        &self.tokenizer_manager
+    }
 }
 
 #[cfg(test)]
@@ -1136,4 +1184,59 @@ mod tests {
             r#"TermQuery(Term(type=Str, field=1, "toto"))"#
         );
     }
+
+    #[test]
+    fn test_doc_mapper_custom_tokenizer() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "tokenizers": [
+                {
+                    "name": "folding",
+                    "type": "simple",
+                    "filters": ["lower_caser", "ascii_folding"]
+                }
+            ],
+            "field_mappings": [
+                {"name": "body", "type": "text", "tokenizer": "folding"},
+                {"name": "attributes", "type": "json", "tokenizer": "folding"}
+            ]
+        }"#,
+        )
+        .unwrap();
+        assert!(doc_mapper.tokenizer_manager().get("folding").is_some());
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "body:Café").unwrap(),
+            r#"TermQuery(Term(type=Str, field=0, "cafe"))"#
+        );
+        assert_eq!(
+            default_doc_mapper_query_aux(&doc_mapper, "attributes.city:Zürich").unwrap(),
+            r#"TermQuery(Term(type=Json, field=1, path=city, vtype=Str, "zurich"))"#
+        );
+
+        let doc_mapper_json = serde_json::to_value(&doc_mapper).unwrap();
+        assert_eq!(
+            doc_mapper_json["tokenizers"],
+            json!([{
+                "name": "folding",
+                "type": "simple",
+                "filters": ["lower_caser", "ascii_folding"]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_doc_mapper_unknown_tokenizer() {
+        let error = serde_json::from_str::<DefaultDocMapperBuilder>(
+            r#"{
+            "field_mappings": [{"name": "body", "type": "text", "tokenizer": "folding"}]
+        }"#,
+        )
+        .unwrap()
+        .try_build()
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown tokenizer `folding` for field `body`."
+        );
+    }
 }
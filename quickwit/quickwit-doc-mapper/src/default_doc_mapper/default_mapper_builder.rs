@@ -22,7 +22,7 @@ use std::num::NonZeroU32;
 use anyhow::bail;
 use serde::{Deserialize, Serialize};
 
-use super::FieldMappingEntry;
+use super::{FieldMappingEntry, TokenizerEntry};
 use crate::default_doc_mapper::default_mapper::Mode;
 use crate::default_doc_mapper::QuickwitJsonOptions;
 use crate::DefaultDocMapper;
@@ -66,6 +66,9 @@ pub struct DefaultDocMapperBuilder {
     /// how the unmapped fields should be handled.
     #[serde(default)]
     pub dynamic_mapping: Option<QuickwitJsonOptions>,
+    /// Custom tokenizers that can be referenced by the field mappings.
+    #[serde(default)]
+    pub tokenizers: Vec<TokenizerEntry>,
 }
 
 /// `Mode` describing how the unmapped field should be handled.
@@ -139,6 +142,7 @@ mod tests {
         assert!(default_mapper_builder.dynamic_mapping.is_none());
         assert_eq!(default_mapper_builder.store_source, false);
         assert!(default_mapper_builder.timestamp_field.is_none());
+        assert!(default_mapper_builder.tokenizers.is_empty());
     }
 
     #[test]
@@ -25,6 +25,7 @@ use serde_json::Value as JsonValue;
 use tantivy::schema::{IndexRecordOption, JsonObjectOptions, TextFieldIndexing, TextOptions, Type};
 
 use super::date_time_type::QuickwitDateTimeOptions;
+use super::tokenizer_entry::validate_tokenizer_name;
 use super::{default_as_true, FieldMappingType};
 use crate::default_doc_mapper::field_mapping_type::QuickwitFieldType;
 use crate::default_doc_mapper::validate_field_mapping_name;
@@ -128,29 +129,52 @@ impl Default for QuickwitIpAddrOptions {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
-pub enum QuickwitTextTokenizer {
-    #[serde(rename = "raw")]
-    Raw,
-    #[serde(rename = "default")]
-    Default,
-    #[serde(rename = "en_stem")]
-    StemEn,
-    #[serde(rename = "chinese_compatible")]
-    Chinese,
+/// Name of the tokenizer used to index a text field.
+///
+/// It refers either to one of the built-in tokenizers (`raw`, `default`, `en_stem`,
+/// `chinese_compatible`) or to a custom tokenizer declared in the doc mapping. The existence of
+/// the tokenizer is checked when the doc mapper is built.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct QuickwitTextTokenizer {
+    name: String,
 }
 
 impl QuickwitTextTokenizer {
+    pub fn raw() -> Self {
+        Self {
+            name: "raw".to_string(),
+        }
+    }
+
     pub fn get_name(&self) -> &str {
-        match self {
-            QuickwitTextTokenizer::Raw => "raw",
-            QuickwitTextTokenizer::Default => "default",
-            QuickwitTextTokenizer::StemEn => "en_stem",
-            QuickwitTextTokenizer::Chinese => "chinese_compatible",
+        &self.name
+    }
+}
+
+impl Default for QuickwitTextTokenizer {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
         }
     }
 }
 
+impl TryFrom<String> for QuickwitTextTokenizer {
+    type Error = anyhow::Error;
+
+    fn try_from(name: String) -> anyhow::Result<Self> {
+        validate_tokenizer_name(&name)?;
+        Ok(Self { name })
+    }
+}
+
+impl From<QuickwitTextTokenizer> for String {
+    fn from(tokenizer: QuickwitTextTokenizer) -> Self {
+        tokenizer.name
+    }
+}
+
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct QuickwitTextOptions {
@@ -160,6 +184,7 @@ pub struct QuickwitTextOptions {
     pub description: Option<String>,
     #[serde(default = "default_as_true")]
     pub indexed: bool,
+    #[schema(value_type = Option<String>)]
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tokenizer: Option<QuickwitTextTokenizer>,
@@ -221,9 +246,7 @@ impl From<QuickwitTextOptions> for TextOptions {
             let index_record_option = quickwit_text_options
                 .record
                 .unwrap_or(IndexRecordOption::Basic);
-            let tokenizer = quickwit_text_options
-                .tokenizer
-                .unwrap_or(QuickwitTextTokenizer::Default);
+            let tokenizer = quickwit_text_options.tokenizer.unwrap_or_default();
             let text_field_indexing = TextFieldIndexing::default()
                 .set_index_option(index_record_option)
                 .set_fieldnorms(quickwit_text_options.fieldnorms)
@@ -267,6 +290,7 @@ pub struct QuickwitJsonOptions {
     pub indexed: bool,
     /// Sets the tokenize that should be used with the text fields in the
     /// json object.
+    #[schema(value_type = Option<String>)]
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tokenizer: Option<QuickwitTextTokenizer>,
@@ -313,9 +337,7 @@ impl From<QuickwitJsonOptions> for JsonObjectOptions {
             let index_record_option = quickwit_json_options
                 .record
                 .unwrap_or(IndexRecordOption::Basic);
-            let tokenizer = quickwit_json_options
-                .tokenizer
-                .unwrap_or(QuickwitTextTokenizer::Default);
+            let tokenizer = quickwit_json_options.tokenizer.unwrap_or_default();
             let text_field_indexing = TextFieldIndexing::default()
                 .set_tokenizer(tokenizer.get_name())
                 .set_index_option(index_record_option);
@@ -626,17 +648,14 @@ mod tests {
                 "type": "text",
                 "stored": true,
                 "record": "basic",
-                "tokenizer": "notexist"
+                "tokenizer": "not exist"
             }
             "#,
         );
         assert!(mapping_entry.is_err());
-        assert_eq!(
-            mapping_entry.unwrap_err().to_string(),
-            "Error while parsing field `my_field_name`: unknown variant `notexist`, expected one \
-             of `raw`, `default`, `en_stem`, `chinese_compatible`"
-                .to_string()
-        );
+        assert!(mapping_entry.unwrap_err().to_string().starts_with(
+            "Error while parsing field `my_field_name`: Invalid tokenizer name `not exist`."
+        ));
         Ok(())
     }
 
@@ -1311,7 +1330,7 @@ mod tests {
         let expected_json_options = QuickwitJsonOptions {
             description: None,
             indexed: true,
-            tokenizer: Some(QuickwitTextTokenizer::Raw),
+            tokenizer: Some(QuickwitTextTokenizer::raw()),
             record: None,
             stored: false,
             expand_dots: true,
@@ -25,6 +25,7 @@ mod default_mapper_builder;
 mod field_mapping_entry;
 mod field_mapping_type;
 mod mapping_tree;
+mod tokenizer_entry;
 
 use anyhow::bail;
 use once_cell::sync::Lazy;
@@ -37,9 +38,13 @@ pub use self::field_mapping_entry::{
     QuickwitTextOptions,
 };
 pub(crate) use self::field_mapping_entry::{
-    FieldMappingEntryForSerialization, IndexRecordOptionSchema, QuickwitTextTokenizer,
+    FieldMappingEntryForSerialization, IndexRecordOptionSchema,
 };
 pub(crate) use self::field_mapping_type::FieldMappingType;
+pub use self::tokenizer_entry::{
+    NgramTokenizerOptions, RegexTokenizerOptions, StemmerLanguage, TokenFilterType, TokenizerEntry,
+    TokenizerType,
+};
 use crate::QW_RESERVED_FIELD_NAMES;
 
 /// Regular expression validating a field mapping name.
@@ -0,0 +1,398 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Context};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tantivy::tokenizer::{
+    AsciiFoldingFilter, BoxTokenFilter, Language, LowerCaser, NgramTokenizer, RegexTokenizer,
+    RemoveLongFilter, SimpleTokenizer, Stemmer, StopWordFilter, TextAnalyzer,
+};
+
+use crate::QUICKWIT_TOKENIZER_MANAGER;
+
+/// Regular expression validating a tokenizer name.
+const TOKENIZER_NAME_PATTERN: &str = r#"^[a-zA-Z][_\-a-zA-Z0-9]{0,254}$"#;
+
+/// Validates a tokenizer name.
+///
+/// A tokenizer name:
+/// - may only contain uppercase and lowercase ASCII letters `[a-zA-Z]`, digits `[0-9]`, hyphens
+///   `-`, and underscores `_`;
+/// - must start with a letter;
+/// - must not be longer than 255 characters.
+pub(crate) fn validate_tokenizer_name(tokenizer_name: &str) -> anyhow::Result<()> {
+    static TOKENIZER_NAME_PTN: Lazy<Regex> =
+        Lazy::new(|| Regex::new(TOKENIZER_NAME_PATTERN).unwrap());
+
+    if !TOKENIZER_NAME_PTN.is_match(tokenizer_name) {
+        bail!(
+            "Invalid tokenizer name `{tokenizer_name}`. A tokenizer name must start with a \
+             letter, only contain ASCII letters, digits, hyphens and underscores and must not be \
+             longer than 255 characters."
+        );
+    }
+    Ok(())
+}
+
+/// A `TokenizerEntry` defines a custom tokenizer that can be referenced by the text and json
+/// field mappings of the doc mapping.
+///
+/// A custom tokenizer is made of a base tokenizer, splitting the text into tokens, followed by a
+/// chain of token filters applied in the order they are declared.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TokenizerEntry {
+    /// Name of the tokenizer, as referenced by the field mappings.
+    pub name: String,
+    /// Base tokenizer splitting the text into tokens.
+    #[serde(flatten)]
+    pub tokenizer_type: TokenizerType,
+    /// Token filters applied to the tokens emitted by the base tokenizer.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<TokenFilterType>,
+}
+
+impl TokenizerEntry {
+    /// Validates the tokenizer entry and builds the corresponding tantivy [`TextAnalyzer`].
+    pub(crate) fn text_analyzer(&self) -> anyhow::Result<TextAnalyzer> {
+        validate_tokenizer_name(&self.name)?;
+        if QUICKWIT_TOKENIZER_MANAGER.get(&self.name).is_some() {
+            bail!(
+                "Tokenizer name `{}` is reserved for a built-in tokenizer.",
+                self.name
+            );
+        }
+        let mut text_analyzer_builder = match &self.tokenizer_type {
+            TokenizerType::Simple => TextAnalyzer::builder(SimpleTokenizer).dynamic(),
+            TokenizerType::Ngram(options) => {
+                let tokenizer = options.ngram_tokenizer(false)?;
+                TextAnalyzer::builder(tokenizer).dynamic()
+            }
+            TokenizerType::EdgeNgram(options) => {
+                let tokenizer = options.ngram_tokenizer(true)?;
+                TextAnalyzer::builder(tokenizer).dynamic()
+            }
+            TokenizerType::Regex(options) => {
+                let tokenizer = RegexTokenizer::new(&options.pattern).with_context(|| {
+                    format!("Invalid regex tokenizer pattern `{}`.", options.pattern)
+                })?;
+                TextAnalyzer::builder(tokenizer).dynamic()
+            }
+        };
+        for filter in &self.filters {
+            text_analyzer_builder = text_analyzer_builder.filter_dynamic(filter.token_filter()?);
+        }
+        Ok(text_analyzer_builder.build())
+    }
+}
+
+/// Base tokenizer of a custom tokenizer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TokenizerType {
+    /// Splits the text on whitespaces and punctuation.
+    Simple,
+    /// Emits all the n-grams of the text.
+    Ngram(NgramTokenizerOptions),
+    /// Emits the n-grams anchored at the beginning of the text.
+    EdgeNgram(NgramTokenizerOptions),
+    /// Emits the substrings of the text matching a regular expression.
+    Regex(RegexTokenizerOptions),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NgramTokenizerOptions {
+    /// Minimum length of the n-grams, in characters.
+    pub min_gram: usize,
+    /// Maximum length of the n-grams, in characters.
+    pub max_gram: usize,
+}
+
+impl NgramTokenizerOptions {
+    fn ngram_tokenizer(&self, prefix_only: bool) -> anyhow::Result<NgramTokenizer> {
+        if self.min_gram == 0 {
+            bail!("`min_gram` must be strictly positive.");
+        }
+        if self.min_gram > self.max_gram {
+            bail!(
+                "`min_gram` ({}) must be lower than or equal to `max_gram` ({}).",
+                self.min_gram,
+                self.max_gram
+            );
+        }
+        Ok(NgramTokenizer::new(
+            self.min_gram,
+            self.max_gram,
+            prefix_only,
+        ))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RegexTokenizerOptions {
+    /// Regular expression matching the tokens.
+    pub pattern: String,
+}
+
+/// Token filter of a custom tokenizer.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenFilterType {
+    /// Removes the tokens longer than `length_limit` bytes.
+    RemoveLong { length_limit: usize },
+    /// Lowercases the tokens.
+    LowerCaser,
+    /// Converts the alphabetic, numeric, and symbolic characters that are not in the Basic
+    /// Latin Unicode block into their ASCII equivalents, if one exists.
+    AsciiFolding,
+    /// Removes the tokens matching one of the given stop words.
+    StopWords { words: Vec<String> },
+    /// Reduces the tokens to their stem in the given language.
+    Stemmer { language: StemmerLanguage },
+}
+
+impl TokenFilterType {
+    fn token_filter(&self) -> anyhow::Result<BoxTokenFilter> {
+        let token_filter = match self {
+            TokenFilterType::RemoveLong { length_limit } => {
+                if *length_limit == 0 {
+                    bail!("`length_limit` of the `remove_long` filter must be strictly positive.");
+                }
+                BoxTokenFilter::from(RemoveLongFilter::limit(*length_limit))
+            }
+            TokenFilterType::LowerCaser => BoxTokenFilter::from(LowerCaser),
+            TokenFilterType::AsciiFolding => BoxTokenFilter::from(AsciiFoldingFilter),
+            TokenFilterType::StopWords { words } => {
+                BoxTokenFilter::from(StopWordFilter::remove(words.clone()))
+            }
+            TokenFilterType::Stemmer { language } => {
+                BoxTokenFilter::from(Stemmer::new(language.tantivy_language()))
+            }
+        };
+        Ok(token_filter)
+    }
+}
+
+/// Languages supported by the `stemmer` token filter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StemmerLanguage {
+    Arabic,
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    French,
+    German,
+    Greek,
+    Hungarian,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Romanian,
+    Russian,
+    Spanish,
+    Swedish,
+    Tamil,
+    Turkish,
+}
+
+impl StemmerLanguage {
+    fn tantivy_language(self) -> Language {
+        match self {
+            StemmerLanguage::Arabic => Language::Arabic,
+            StemmerLanguage::Danish => Language::Danish,
+            StemmerLanguage::Dutch => Language::Dutch,
+            StemmerLanguage::English => Language::English,
+            StemmerLanguage::Finnish => Language::Finnish,
+            StemmerLanguage::French => Language::French,
+            StemmerLanguage::German => Language::German,
+            StemmerLanguage::Greek => Language::Greek,
+            StemmerLanguage::Hungarian => Language::Hungarian,
+            StemmerLanguage::Italian => Language::Italian,
+            StemmerLanguage::Norwegian => Language::Norwegian,
+            StemmerLanguage::Portuguese => Language::Portuguese,
+            StemmerLanguage::Romanian => Language::Romanian,
+            StemmerLanguage::Russian => Language::Russian,
+            StemmerLanguage::Spanish => Language::Spanish,
+            StemmerLanguage::Swedish => Language::Swedish,
+            StemmerLanguage::Tamil => Language::Tamil,
+            StemmerLanguage::Turkish => Language::Turkish,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::tokenizer::TokenStream;
+
+    use super::{
+        NgramTokenizerOptions, StemmerLanguage, TokenFilterType, TokenizerEntry, TokenizerType,
+    };
+
+    fn tokenize(tokenizer_entry: &TokenizerEntry, text: &str) -> Vec<String> {
+        let text_analyzer = tokenizer_entry.text_analyzer().unwrap();
+        let mut token_stream = text_analyzer.token_stream(text);
+        let mut tokens = Vec::new();
+        while let Some(token) = token_stream.next() {
+            tokens.push(token.text.clone());
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_deserialize_tokenizer_entry() {
+        let tokenizer_entry: TokenizerEntry = serde_json::from_str(
+            r#"{
+                "name": "my_tokenizer",
+                "type": "edge_ngram",
+                "min_gram": 2,
+                "max_gram": 4,
+                "filters": [
+                    "lower_caser",
+                    {"remove_long": {"length_limit": 40}},
+                    {"stemmer": {"language": "french"}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tokenizer_entry,
+            TokenizerEntry {
+                name: "my_tokenizer".to_string(),
+                tokenizer_type: TokenizerType::EdgeNgram(NgramTokenizerOptions {
+                    min_gram: 2,
+                    max_gram: 4,
+                }),
+                filters: vec![
+                    TokenFilterType::LowerCaser,
+                    TokenFilterType::RemoveLong { length_limit: 40 },
+                    TokenFilterType::Stemmer {
+                        language: StemmerLanguage::French
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_tokenizer_entry_invalid() {
+        let error = serde_json::from_str::<TokenizerEntry>(
+            r#"{"name": "my_tokenizer", "type": "unknown"}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unknown variant `unknown`"));
+
+        let error = serde_json::from_str::<TokenizerEntry>(
+            r#"{"name": "my_tokenizer", "type": "simple", "filters": ["unknown"]}"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unknown variant `unknown`"));
+    }
+
+    #[test]
+    fn test_tokenizer_entry_text_analyzer() {
+        let tokenizer_entry: TokenizerEntry = serde_json::from_str(
+            r#"{
+                "name": "my_tokenizer",
+                "type": "simple",
+                "filters": [
+                    "lower_caser",
+                    "ascii_folding",
+                    {"stop_words": {"words": ["the", "a"]}},
+                    {"stemmer": {"language": "english"}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tokenize(&tokenizer_entry, "The Café is running a tests"),
+            ["cafe", "is", "run", "test"]
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_entry_ngram() {
+        let tokenizer_entry: TokenizerEntry = serde_json::from_str(
+            r#"{"name": "my_tokenizer", "type": "ngram", "min_gram": 2, "max_gram": 3}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tokenize(&tokenizer_entry, "abcd"),
+            ["ab", "abc", "bc", "bcd", "cd"]
+        );
+
+        let tokenizer_entry: TokenizerEntry = serde_json::from_str(
+            r#"{"name": "my_tokenizer", "type": "edge_ngram", "min_gram": 1, "max_gram": 3}"#,
+        )
+        .unwrap();
+        assert_eq!(tokenize(&tokenizer_entry, "abcd"), ["a", "ab", "abc"]);
+
+        let tokenizer_entry: TokenizerEntry = serde_json::from_str(
+            r#"{"name": "my_tokenizer", "type": "ngram", "min_gram": 3, "max_gram": 2}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tokenizer_entry.text_analyzer().unwrap_err().to_string(),
+            "`min_gram` (3) must be lower than or equal to `max_gram` (2)."
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_entry_regex() {
+        let tokenizer_entry: TokenizerEntry = serde_json::from_str(
+            r#"{"name": "my_tokenizer", "type": "regex", "pattern": "[0-9]+"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tokenize(&tokenizer_entry, "request 123 took 45ms"),
+            ["123", "45"]
+        );
+
+        let tokenizer_entry: TokenizerEntry =
+            serde_json::from_str(r#"{"name": "my_tokenizer", "type": "regex", "pattern": "[0-9"}"#)
+                .unwrap();
+        assert_eq!(
+            tokenizer_entry.text_analyzer().unwrap_err().to_string(),
+            "Invalid regex tokenizer pattern `[0-9`."
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_entry_invalid_name() {
+        let tokenizer_entry: TokenizerEntry =
+            serde_json::from_str(r#"{"name": "raw", "type": "simple"}"#).unwrap();
+        assert_eq!(
+            tokenizer_entry.text_analyzer().unwrap_err().to_string(),
+            "Tokenizer name `raw` is reserved for a built-in tokenizer."
+        );
+
+        let tokenizer_entry: TokenizerEntry =
+            serde_json::from_str(r#"{"name": "my tokenizer", "type": "simple"}"#).unwrap();
+        assert!(tokenizer_entry
+            .text_analyzer()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Invalid tokenizer name `my tokenizer`."));
+    }
+}
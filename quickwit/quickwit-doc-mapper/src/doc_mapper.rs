@@ -27,13 +27,14 @@ use quickwit_proto::SearchRequest;
 use serde_json::Value as JsonValue;
 use tantivy::query::Query;
 use tantivy::schema::{Field, FieldType, Schema, Value};
+use tantivy::tokenizer::TokenizerManager;
 use tantivy::{Document, Term};
 
 pub type Partition = u64;
 
 pub type JsonObject = serde_json::Map<String, JsonValue>;
 
-use crate::{DocParsingError, QueryParserError};
+use crate::{DocParsingError, QueryParserError, QUICKWIT_TOKENIZER_MANAGER};
 
 /// The `DocMapper` trait defines the way of defining how a (json) document,
 /// and the fields it contains, are stored and indexed.
@@ -137,6 +138,15 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
 
     /// Returns the maximum number of partitions.
     fn max_num_partitions(&self) -> NonZeroU32;
+
+    /// Returns the tokenizer manager used to index and query the text fields.
+    ///
+    /// Besides the built-in tokenizers, it holds the custom tokenizers declared in the
+    /// doc mapping, so it must be registered on every index built or opened with this
+    /// `DocMapper`.
+    fn tokenizer_manager(&self) -> &TokenizerManager {
+        &QUICKWIT_TOKENIZER_MANAGER
+    }
 }
 
 /// A struct to wrap a tantivy field with its name.
@@ -220,7 +230,10 @@ mod tests {
         let json_doc = br#"{"title": "hello", "body": "world"}"#;
         doc_mapper.doc_from_json_bytes(json_doc).unwrap();
 
-        let DocParsingError::NotJsonObject(json_doc_sample) = doc_mapper.doc_from_json_bytes(br#"Not a JSON object"#).unwrap_err() else {
+        let DocParsingError::NotJsonObject(json_doc_sample) = doc_mapper
+            .doc_from_json_bytes(br#"Not a JSON object"#)
+            .unwrap_err()
+        else {
             panic!("Expected `DocParsingError::NotJsonObject` error");
         };
         assert_eq!(json_doc_sample, "Not a JSON object...");
@@ -232,7 +245,10 @@ mod tests {
         let json_doc = r#"{"title": "hello", "body": "world"}"#;
         doc_mapper.doc_from_json_str(json_doc).unwrap();
 
-        let DocParsingError::NotJsonObject(json_doc_sample) = doc_mapper.doc_from_json_str(r#"Not a JSON object"#).unwrap_err() else {
+        let DocParsingError::NotJsonObject(json_doc_sample) = doc_mapper
+            .doc_from_json_str(r#"Not a JSON object"#)
+            .unwrap_err()
+        else {
             panic!("Expected `DocParsingError::NotJsonObject` error");
         };
         assert_eq!(json_doc_sample, "Not a JSON object...");
@@ -323,6 +339,15 @@ mod tests {
             sort_order: None,
             sort_by_field: None,
             aggregation_request: None,
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         };
         let (query, _) = doc_mapper.query(schema, &search_request).unwrap();
         assert_eq!(
@@ -360,6 +385,15 @@ mod tests {
             sort_order: None,
             sort_by_field: Some("text_field".to_string()),
             aggregation_request: None,
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         };
         let query = doc_mapper.query(schema, &search_request).unwrap_err();
         assert_eq!(
@@ -395,6 +429,15 @@ mod tests {
             sort_order: None,
             sort_by_field: None,
             aggregation_request: None,
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         };
         let (query, _) = doc_mapper.query(schema, &search_request).unwrap();
         assert_eq!(
@@ -430,6 +473,15 @@ mod tests {
             sort_order: None,
             sort_by_field: None,
             aggregation_request: None,
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         };
         let (query, _) = doc_mapper.query(schema, &search_request).unwrap();
         assert_eq!(
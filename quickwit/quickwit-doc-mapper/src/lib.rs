@@ -36,13 +36,14 @@ mod tokenizers;
 pub mod tag_pruning;
 
 pub use default_doc_mapper::{
-    DefaultDocMapper, DefaultDocMapperBuilder, FieldMappingEntry, ModeType, QuickwitJsonOptions,
-};
-use default_doc_mapper::{
-    FieldMappingEntryForSerialization, IndexRecordOptionSchema, QuickwitTextTokenizer,
+    DefaultDocMapper, DefaultDocMapperBuilder, FieldMappingEntry, ModeType, NgramTokenizerOptions,
+    QuickwitJsonOptions, RegexTokenizerOptions, StemmerLanguage, TokenFilterType, TokenizerEntry,
+    TokenizerType,
 };
+use default_doc_mapper::{FieldMappingEntryForSerialization, IndexRecordOptionSchema};
 pub use doc_mapper::{DocMapper, NamedField, WarmupInfo};
 pub use error::{DocParsingError, QueryParserError};
+pub use query_builder::validate_sort_by_field;
 pub use tokenizers::QUICKWIT_TOKENIZER_MANAGER;
 
 /// Field name reserved for storing the source document.
@@ -64,7 +65,6 @@ pub(crate) enum Cardinality {
 #[openapi(components(schemas(
     QuickwitJsonOptions,
     ModeType,
-    QuickwitTextTokenizer,
     IndexRecordOptionSchema,
     FieldMappingEntryForSerialization,
 )))]
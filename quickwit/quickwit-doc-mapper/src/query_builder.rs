@@ -24,14 +24,16 @@ use quickwit_proto::SearchRequest;
 use tantivy::query::{Query, QueryParser, QueryParserError as TantivyQueryParserError};
 use tantivy::query_grammar::{UserInputAst, UserInputLeaf, UserInputLiteral};
 use tantivy::schema::{Field, FieldEntry, FieldType, Schema};
+use tantivy::tokenizer::TokenizerManager;
 
-use crate::{QueryParserError, WarmupInfo, DYNAMIC_FIELD_NAME, QUICKWIT_TOKENIZER_MANAGER};
+use crate::{QueryParserError, WarmupInfo, DYNAMIC_FIELD_NAME};
 
 /// Build a `Query` with field resolution & forbidding range clauses.
 pub(crate) fn build_query(
     schema: Schema,
     request: &SearchRequest,
     default_field_names: &[String],
+    tokenizer_manager: &TokenizerManager,
 ) -> Result<(Box<dyn Query>, WarmupInfo), QueryParserError> {
     let user_input_ast = tantivy::query_grammar::parse_query(&request.query)
         .map_err(|_| TantivyQueryParserError::SyntaxError(request.query.to_string()))?;
@@ -58,9 +60,11 @@ pub(crate) fn build_query(
     if let Some(sort_by_field) = &request.sort_by_field {
         validate_sort_by_field(sort_by_field, &schema, Some(&search_fields))?;
     }
+    for sort_field in &request.sort_fields {
+        validate_sort_by_field(&sort_field.field_name, &schema, Some(&search_fields))?;
+    }
 
-    let mut query_parser =
-        QueryParser::new(schema, search_fields, QUICKWIT_TOKENIZER_MANAGER.clone());
+    let mut query_parser = QueryParser::new(schema, search_fields, tokenizer_manager.clone());
     query_parser.set_conjunction_by_default();
     let query = query_parser.parse_query(&request.query)?;
 
@@ -252,7 +256,9 @@ fn validate_requested_snippet_fields(
     Ok(())
 }
 
-pub(crate) fn validate_sort_by_field(
+/// Checks that the documents can be sorted by `field_name`: the field must be a fast field and
+/// must not be a text field. Sorting by `_score` requires the fieldnorms of the search fields.
+pub fn validate_sort_by_field(
     field_name: &str,
     schema: &Schema,
     search_fields_opt: Option<&Vec<Field>>,
@@ -306,7 +312,7 @@ mod test {
     use tantivy::schema::{Schema, FAST, INDEXED, STORED, TEXT};
 
     use super::{build_query, validate_requested_snippet_fields};
-    use crate::{DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME};
+    use crate::{DYNAMIC_FIELD_NAME, QUICKWIT_TOKENIZER_MANAGER, SOURCE_FIELD_NAME};
 
     enum TestExpectation {
         Err(&'static str),
@@ -351,12 +357,26 @@ mod test {
             start_offset: 0,
             sort_order: None,
             sort_by_field: None,
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         };
 
         let default_field_names =
             default_search_fields.unwrap_or_else(|| vec!["title".to_string(), "desc".to_string()]);
 
-        let query_result = build_query(make_schema(), &request, &default_field_names);
+        let query_result = build_query(
+            make_schema(),
+            &request,
+            &default_field_names,
+            &QUICKWIT_TOKENIZER_MANAGER,
+        );
         match expected {
             TestExpectation::Err(sub_str) => {
                 assert!(
@@ -619,6 +639,15 @@ mod test {
             start_offset: 0,
             sort_order: None,
             sort_by_field: None,
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         };
         let user_input_ast = tantivy::query_grammar::parse_query(&request.query)
             .map_err(|_| QueryParserError::SyntaxError(request.query.clone()))
@@ -731,6 +760,15 @@ mod test {
             start_offset: 0,
             sort_order: None,
             sort_by_field: None,
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         };
         let request_without_set = SearchRequest {
             aggregation_request: None,
@@ -744,6 +782,15 @@ mod test {
             start_offset: 0,
             sort_order: None,
             sort_by_field: None,
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         };
 
         let default_field_names = vec!["title".to_string(), "desc".to_string()];
@@ -17,14 +17,18 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashSet;
 use std::str::CharIndices;
 
+use anyhow::{bail, Context};
 use once_cell::sync::Lazy;
 use tantivy::tokenizer::{
     LowerCaser, RawTokenizer, RemoveLongFilter, TextAnalyzer, Token, TokenStream, Tokenizer,
     TokenizerManager,
 };
 
+use crate::TokenizerEntry;
+
 fn get_quickwit_tokenizer_manager() -> TokenizerManager {
     let raw_tokenizer = TextAnalyzer::builder(RawTokenizer)
         .filter(RemoveLongFilter::limit(100))
@@ -43,6 +47,26 @@ fn get_quickwit_tokenizer_manager() -> TokenizerManager {
     tokenizer_manager
 }
 
+/// Creates a tokenizer manager holding the built-in tokenizers as well as the custom tokenizers
+/// declared in the doc mapping.
+pub(crate) fn create_tokenizer_manager(
+    tokenizer_entries: &[TokenizerEntry],
+) -> anyhow::Result<TokenizerManager> {
+    let tokenizer_manager = get_quickwit_tokenizer_manager();
+    let mut tokenizer_names = HashSet::new();
+
+    for tokenizer_entry in tokenizer_entries {
+        if !tokenizer_names.insert(tokenizer_entry.name.as_str()) {
+            bail!("Duplicated tokenizer: `{}`", tokenizer_entry.name);
+        }
+        let text_analyzer = tokenizer_entry
+            .text_analyzer()
+            .with_context(|| format!("Invalid tokenizer `{}`.", tokenizer_entry.name))?;
+        tokenizer_manager.register(&tokenizer_entry.name, text_analyzer);
+    }
+    Ok(tokenizer_manager)
+}
+
 #[derive(Clone)]
 struct ChineseTokenizer;
 
@@ -157,7 +181,8 @@ pub static QUICKWIT_TOKENIZER_MANAGER: Lazy<TokenizerManager> =
 mod tests {
     use tantivy::tokenizer::Token;
 
-    use super::get_quickwit_tokenizer_manager;
+    use super::{create_tokenizer_manager, get_quickwit_tokenizer_manager};
+    use crate::TokenizerEntry;
 
     #[test]
     fn test_raw_tokenizer() {
@@ -302,6 +327,61 @@ mod tests {
         assert_eq!(dbg!(res), dbg!(expected));
     }
 
+    #[test]
+    fn test_create_tokenizer_manager() {
+        let tokenizer_entries: Vec<TokenizerEntry> = serde_json::from_str(
+            r#"[
+                {
+                    "name": "autocomplete",
+                    "type": "edge_ngram",
+                    "min_gram": 1,
+                    "max_gram": 3,
+                    "filters": ["lower_caser"]
+                },
+                {"name": "digits", "type": "regex", "pattern": "[0-9]+"}
+            ]"#,
+        )
+        .unwrap();
+        let tokenizer_manager = create_tokenizer_manager(&tokenizer_entries).unwrap();
+        assert!(tokenizer_manager.get("raw").is_some());
+        assert!(tokenizer_manager.get("default").is_some());
+        assert!(tokenizer_manager.get("digits").is_some());
+
+        let tokenizer = tokenizer_manager.get("autocomplete").unwrap();
+        let mut token_stream = tokenizer.token_stream("Hello");
+        let mut tokens = Vec::new();
+        while let Some(token) = token_stream.next() {
+            tokens.push(token.text.clone());
+        }
+        assert_eq!(tokens, ["h", "he", "hel"]);
+    }
+
+    #[test]
+    fn test_create_tokenizer_manager_errors() {
+        let tokenizer_entries: Vec<TokenizerEntry> = serde_json::from_str(
+            r#"[
+                {"name": "digits", "type": "regex", "pattern": "[0-9]+"},
+                {"name": "digits", "type": "simple"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            create_tokenizer_manager(&tokenizer_entries)
+                .unwrap_err()
+                .to_string(),
+            "Duplicated tokenizer: `digits`"
+        );
+
+        let tokenizer_entries: Vec<TokenizerEntry> =
+            serde_json::from_str(r#"[{"name": "en_stem", "type": "simple"}]"#).unwrap();
+        assert_eq!(
+            create_tokenizer_manager(&tokenizer_entries)
+                .unwrap_err()
+                .to_string(),
+            "Invalid tokenizer `en_stem`."
+        );
+    }
+
     proptest::proptest! {
         #[test]
         fn test_proptest_ascii_default_chinese_equal(text in "[ -~]{0,64}") {
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use bytes::Bytes;
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use quickwit_actors::{ActorHandle, Mailbox, Universe};
-use quickwit_config::TransformConfig;
+use quickwit_config::{SourceInputFormat, TransformConfig};
 use quickwit_doc_mapper::DefaultDocMapper;
 use quickwit_indexing::actors::DocProcessor;
 use quickwit_indexing::models::RawDocBatch;
@@ -131,7 +131,9 @@ fn create_doc_processor(
         source_id,
         doc_mapper,
         indexer_mailbox,
+        SourceInputFormat::Json,
         transform_config_opt,
+        None,
     )
     .unwrap();
     let (mailbox, handle) = universe.spawn_builder().spawn(doc_processor);
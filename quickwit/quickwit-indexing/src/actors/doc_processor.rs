@@ -24,8 +24,13 @@ use anyhow::Context;
 use async_trait::async_trait;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, QueueCapacity};
 use quickwit_common::runtimes::RuntimeType;
-use quickwit_config::TransformConfig;
+use quickwit_config::{SourceInputFormat, TransformConfig};
 use quickwit_doc_mapper::{DocMapper, DocParsingError};
+use quickwit_ingest::{
+    dead_letter_queue_id, CommitType, CreateQueueIfNotExistsRequest, DeadLetter,
+    DeadLetterErrorKind, DeadLetterSourcePosition, DocBatchBuilder, IngestApiService,
+    IngestRequest,
+};
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 use tantivy::schema::{Field, Value};
@@ -34,19 +39,40 @@ use tokio::runtime::Handle;
 use tracing::warn;
 use vrl::{Program, Runtime, TargetValueRef, Terminate, TimeZone};
 
+use crate::actors::input_format::InputFormatParser;
 use crate::actors::Indexer;
-use crate::models::{NewPublishLock, PreparedDoc, PreparedDocBatch, PublishLock, RawDocBatch};
+use crate::models::{
+    NewPublishLock, NewSourceFile, PreparedDoc, PreparedDocBatch, PublishLock, RawDocBatch,
+};
 
 type VrlValue = ::value::Value;
 type VrlSecrets = ::value::Secrets;
 
 #[derive(Debug)]
 pub enum PrepareDocumentError {
-    ParsingError,
-    MissingField,
+    ParsingError(String),
+    MissingField(String),
     TransformError(Terminate),
 }
 
+impl PrepareDocumentError {
+    fn error_kind(&self) -> DeadLetterErrorKind {
+        match self {
+            PrepareDocumentError::ParsingError(_) => DeadLetterErrorKind::ParsingError,
+            PrepareDocumentError::MissingField(_) => DeadLetterErrorKind::MissingField,
+            PrepareDocumentError::TransformError(_) => DeadLetterErrorKind::TransformError,
+        }
+    }
+
+    fn error_message(&self) -> String {
+        match self {
+            PrepareDocumentError::ParsingError(error_message)
+            | PrepareDocumentError::MissingField(error_message) => error_message.clone(),
+            PrepareDocumentError::TransformError(terminate) => terminate.to_string(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct DocProcessorCounters {
     index_id: String,
@@ -181,7 +207,12 @@ pub struct DocProcessor {
     timestamp_field_opt: Option<Field>,
     counters: DocProcessorCounters,
     publish_lock: PublishLock,
+    /// Converts the records of non-JSON sources into JSON objects. `None` for JSON sources.
+    input_format_parser_opt: Option<InputFormatParser>,
     transform_opt: Option<VrlProgram>,
+    /// Ingest API service hosting the dead-letter queue in which rejected documents are stored.
+    /// Rejected documents are dropped if `None`.
+    dead_letter_queue_opt: Option<Mailbox<IngestApiService>>,
 }
 
 impl DocProcessor {
@@ -190,7 +221,9 @@ impl DocProcessor {
         source_id: String,
         doc_mapper: Arc<dyn DocMapper>,
         indexer_mailbox: Mailbox<Indexer>,
+        input_format: SourceInputFormat,
         transform_config_opt: Option<TransformConfig>,
+        dead_letter_queue_opt: Option<Mailbox<IngestApiService>>,
     ) -> anyhow::Result<Self> {
         let timestamp_field_opt = extract_timestamp_field(doc_mapper.as_ref())?;
         let transform_opt = transform_config_opt
@@ -203,7 +236,9 @@ impl DocProcessor {
             timestamp_field_opt,
             counters: DocProcessorCounters::new(index_id, source_id),
             publish_lock: PublishLock::default(),
+            input_format_parser_opt: InputFormatParser::new(input_format),
             transform_opt,
+            dead_letter_queue_opt,
         };
         Ok(doc_processor)
     }
@@ -219,42 +254,115 @@ impl DocProcessor {
         let timestamp = doc
             .get_first(timestamp_field)
             .and_then(Value::as_date)
-            .ok_or(PrepareDocumentError::MissingField)?;
+            .ok_or_else(|| {
+                PrepareDocumentError::MissingField(format!(
+                    "Timestamp field `{}` is missing.",
+                    self.doc_mapper.timestamp_field_name().unwrap_or_default()
+                ))
+            })?;
         Ok(Some(timestamp))
     }
 
+    // Converts, transforms, and parses a raw document. Returns `None` if the raw document is not
+    // a document but a CSV header.
     fn prepare_document(
         &mut self,
-        json_doc: &[u8],
+        raw_doc: &[u8],
         ctx: &ActorContext<Self>,
-    ) -> Result<PreparedDoc, PrepareDocumentError> {
+    ) -> Result<Option<PreparedDoc>, PrepareDocumentError> {
         let _protect_guard = ctx.protect_zone();
 
+        // Convert the document into a JSON object if the source does not emit JSON.
+        let json_obj_opt = match self.input_format_parser_opt.as_mut() {
+            Some(input_format_parser) => match input_format_parser
+                .parse(raw_doc)
+                .map_err(PrepareDocumentError::ParsingError)?
+            {
+                Some(json_obj) => Some(json_obj),
+                None => return Ok(None),
+            },
+            None => None,
+        };
         // Transform and parse the document
         let doc_parsing_result = if let Some(vrl_program) = self.transform_opt.as_mut() {
-            let vrl_value = vrl_program.transform_doc(json_doc)?;
+            let vrl_value = match json_obj_opt {
+                Some(json_obj) => serde_json::from_value::<VrlValue>(JsonValue::Object(json_obj)),
+                None => serde_json::from_slice::<VrlValue>(raw_doc),
+            }
+            .ok()
+            .filter(VrlValue::is_object)
+            .ok_or_else(|| {
+                PrepareDocumentError::ParsingError(
+                    "Document is not a valid JSON object.".to_string(),
+                )
+            })?;
+            let vrl_value = vrl_program.transform_doc(vrl_value)?;
             let json_obj = match serde_json::to_value(vrl_value) {
                 Ok(JsonValue::Object(json_obj)) => json_obj,
-                _ => return Err(PrepareDocumentError::ParsingError),
+                _ => {
+                    return Err(PrepareDocumentError::ParsingError(
+                        "VRL transform did not return a JSON object.".to_string(),
+                    ))
+                }
             };
             self.doc_mapper.doc_from_json_obj(json_obj)
+        } else if let Some(json_obj) = json_obj_opt {
+            self.doc_mapper.doc_from_json_obj(json_obj)
         } else {
-            self.doc_mapper.doc_from_json_bytes(json_doc)
+            self.doc_mapper.doc_from_json_bytes(raw_doc)
         };
         let (partition, doc) = doc_parsing_result.map_err(|doc_parsing_error| {
             warn!(err=?doc_parsing_error);
+            let error_message = doc_parsing_error.to_string();
             match doc_parsing_error {
-                DocParsingError::RequiredField(_) => PrepareDocumentError::MissingField,
-                _ => PrepareDocumentError::ParsingError,
+                DocParsingError::RequiredField(_) => {
+                    PrepareDocumentError::MissingField(error_message)
+                }
+                _ => PrepareDocumentError::ParsingError(error_message),
             }
         })?;
         let timestamp_opt = self.extract_timestamp(&doc)?;
-        Ok(PreparedDoc {
+        Ok(Some(PreparedDoc {
             doc,
             timestamp_opt,
             partition,
-            num_bytes: json_doc.len(),
-        })
+            num_bytes: raw_doc.len(),
+        }))
+    }
+
+    /// Stores the rejected documents in the dead-letter queue of the source. Failing to do so
+    /// fails the pipeline, so that the batch is never published without its dead letters.
+    async fn send_to_dead_letter_queue(
+        &self,
+        dead_letters: Vec<DeadLetter>,
+        ctx: &ActorContext<Self>,
+    ) -> anyhow::Result<()> {
+        let Some(ingest_api_service) = &self.dead_letter_queue_opt else {
+            return Ok(());
+        };
+        let queue_id = dead_letter_queue_id(&self.counters.index_id, &self.counters.source_id);
+        let mut doc_batch = DocBatchBuilder::new(queue_id.clone()).json_writer();
+        for dead_letter in dead_letters {
+            doc_batch
+                .ingest_doc(dead_letter)
+                .context("Failed to serialize dead letter.")?;
+        }
+        let ingest_request = IngestRequest {
+            doc_batches: vec![doc_batch.build()],
+            commit: CommitType::Auto as u32,
+        };
+        let create_queue_request = CreateQueueIfNotExistsRequest {
+            queue_id: queue_id.clone(),
+        };
+        ctx.ask_for_res(ingest_api_service, create_queue_request)
+            .await
+            .with_context(|| format!("Failed to create dead-letter queue `{queue_id}`."))?;
+        ctx.ask_for_res(ingest_api_service, ingest_request)
+            .await
+            .with_context(|| {
+                format!("Failed to store rejected documents in dead-letter queue `{queue_id}`.")
+            })?;
+        Ok(())
     }
 }
 
@@ -321,26 +429,46 @@ impl Handler<RawDocBatch> for DocProcessor {
             return Ok(());
         }
         let mut prepared_docs: Vec<PreparedDoc> = Vec::with_capacity(raw_doc_batch.docs.len());
-        for json_doc in raw_doc_batch.docs {
+        let mut dead_letters: Vec<DeadLetter> = Vec::new();
+        for (doc_offset, json_doc) in raw_doc_batch.docs.into_iter().enumerate() {
             let json_doc_num_bytes = json_doc.len() as u64;
 
             match self.prepare_document(&json_doc, ctx) {
-                Ok(document) => {
+                Ok(Some(document)) => {
                     self.counters.record_valid(json_doc_num_bytes);
                     prepared_docs.push(document);
                 }
-                Err(PrepareDocumentError::ParsingError) => {
-                    self.counters.record_parsing_error(json_doc_num_bytes);
-                }
-                Err(PrepareDocumentError::TransformError(_)) => {
-                    self.counters.record_transform_error(json_doc_num_bytes);
-                }
-                Err(PrepareDocumentError::MissingField) => {
-                    self.counters.record_missing_field(json_doc_num_bytes);
+                Ok(None) => {}
+                Err(prepare_document_error) => {
+                    match &prepare_document_error {
+                        PrepareDocumentError::ParsingError(_) => {
+                            self.counters.record_parsing_error(json_doc_num_bytes);
+                        }
+                        PrepareDocumentError::TransformError(_) => {
+                            self.counters.record_transform_error(json_doc_num_bytes);
+                        }
+                        PrepareDocumentError::MissingField(_) => {
+                            self.counters.record_missing_field(json_doc_num_bytes);
+                        }
+                    }
+                    if self.dead_letter_queue_opt.is_some() {
+                        dead_letters.push(DeadLetter {
+                            doc: json_doc,
+                            error_kind: prepare_document_error.error_kind(),
+                            error_message: prepare_document_error.error_message(),
+                            source_position: DeadLetterSourcePosition {
+                                batch_delta: format!("{:?}", raw_doc_batch.checkpoint_delta),
+                                doc_offset: doc_offset as u64,
+                            },
+                        });
+                    }
                 }
             }
             ctx.record_progress();
         }
+        if !dead_letters.is_empty() {
+            self.send_to_dead_letter_queue(dead_letters, ctx).await?;
+        }
         let prepared_doc_batch = PreparedDocBatch {
             docs: prepared_docs,
             checkpoint_delta: raw_doc_batch.checkpoint_delta,
@@ -352,6 +480,22 @@ impl Handler<RawDocBatch> for DocProcessor {
     }
 }
 
+#[async_trait]
+impl Handler<NewSourceFile> for DocProcessor {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _new_source_file: NewSourceFile,
+        _ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if let Some(input_format_parser) = self.input_format_parser_opt.as_mut() {
+            input_format_parser.reset();
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Handler<NewPublishLock> for DocProcessor {
     type Reply = ();
@@ -375,11 +519,7 @@ struct VrlProgram {
 }
 
 impl VrlProgram {
-    fn transform_doc(&mut self, json_doc: &[u8]) -> Result<VrlValue, PrepareDocumentError> {
-        let mut value = match serde_json::from_slice::<VrlValue>(json_doc) {
-            Ok(value) if value.is_object() => value,
-            _ => return Err(PrepareDocumentError::ParsingError),
-        };
+    fn transform_doc(&mut self, mut value: VrlValue) -> Result<VrlValue, PrepareDocumentError> {
         let mut metadata = VrlValue::Object(BTreeMap::new());
         let mut secrets = VrlSecrets::new();
         let mut target = TargetValueRef {
@@ -419,7 +559,9 @@ mod tests {
 
     use bytes::Bytes;
     use quickwit_actors::Universe;
+    use quickwit_config::IngestApiConfig;
     use quickwit_doc_mapper::{default_doc_mapper_for_test, DefaultDocMapper};
+    use quickwit_ingest::{fetch_dead_letters, init_ingest_api};
     use quickwit_metastore::checkpoint::SourceCheckpointDelta;
     use serde_json::Value as JsonValue;
     use tantivy::schema::NamedFieldDocument;
@@ -439,6 +581,8 @@ mod tests {
             source_id.to_string(),
             doc_mapper.clone(),
             indexer_mailbox,
+            SourceInputFormat::Json,
+            None,
             None,
         )
         .unwrap();
@@ -507,6 +651,98 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_doc_processor_dead_letter_queue() {
+        let universe = Universe::with_accelerated_time();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ingest_api_service =
+            init_ingest_api(&universe, temp_dir.path(), &IngestApiConfig::default())
+                .await
+                .unwrap();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            SourceInputFormat::Json,
+            None,
+            Some(ingest_api_service.clone()),
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    r#"{"body": "happy", "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}"#, // missing timestamp
+                    r#"{"body": "happy", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:59+00:00", "response_time": 2, "response_payload": "YWJj"}"#, // ok
+                    "{", // invalid json
+                ],
+                0..3,
+            ))
+            .await
+            .unwrap();
+        doc_processor_handle.process_pending_and_observe().await;
+
+        let prepared_doc_batches: Vec<PreparedDocBatch> = indexer_inbox.drain_for_test_typed();
+        assert_eq!(prepared_doc_batches.len(), 1);
+        assert_eq!(prepared_doc_batches[0].docs.len(), 1);
+
+        let dead_letters =
+            fetch_dead_letters(&ingest_api_service, "my-index", "my-source", None, 10)
+                .await
+                .unwrap();
+        assert_eq!(dead_letters.len(), 2);
+
+        let batch_delta = format!("{:?}", SourceCheckpointDelta::from_range(0..3));
+        let dead_letter = &dead_letters[0].dead_letter;
+        assert!(String::from_utf8_lossy(&dead_letter.doc).contains(r#""response_time": 12"#));
+        assert_eq!(dead_letter.error_kind, DeadLetterErrorKind::MissingField);
+        assert!(dead_letter.error_message.contains("timestamp"));
+        assert_eq!(dead_letter.source_position.batch_delta, batch_delta);
+        assert_eq!(dead_letter.source_position.doc_offset, 0);
+        let dead_letter = &dead_letters[1].dead_letter;
+        assert_eq!(dead_letter.doc, "{");
+        assert_eq!(dead_letter.error_kind, DeadLetterErrorKind::ParsingError);
+        assert_eq!(dead_letter.source_position.batch_delta, batch_delta);
+        assert_eq!(dead_letter.source_position.doc_offset, 2);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_doc_processor_fails_if_dead_letter_queue_is_unavailable() {
+        let universe = Universe::with_accelerated_time();
+        let (ingest_api_service, ingest_api_inbox) =
+            universe.create_test_mailbox::<IngestApiService>();
+        drop(ingest_api_inbox);
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            SourceInputFormat::Json,
+            None,
+            Some(ingest_api_service),
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(&["{"], 0..1))
+            .await
+            .unwrap();
+        let (exit_status, _) = doc_processor_handle.join().await;
+        assert!(matches!(exit_status, ActorExitStatus::Failure(_)));
+        assert!(indexer_inbox
+            .drain_for_test_typed::<PreparedDocBatch>()
+            .is_empty());
+        universe.assert_quit().await;
+    }
+
     const DOCMAPPER_WITH_PARTITION_JSON: &str = r#"
         {
             "tag_fields": ["tenant"],
@@ -529,6 +765,8 @@ mod tests {
             "my-source".to_string(),
             doc_mapper,
             indexer_mailbox,
+            SourceInputFormat::Json,
+            None,
             None,
         )
         .unwrap();
@@ -584,6 +822,8 @@ mod tests {
             "my-source".to_string(),
             doc_mapper,
             indexer_mailbox,
+            SourceInputFormat::Json,
+            None,
             None,
         )
         .unwrap();
@@ -615,6 +855,8 @@ mod tests {
             "my-source".to_string(),
             doc_mapper,
             indexer_mailbox,
+            SourceInputFormat::Json,
+            None,
             None,
         )
         .unwrap();
@@ -659,7 +901,9 @@ mod tests {
             source_id.to_string(),
             doc_mapper.clone(),
             indexer_mailbox,
+            SourceInputFormat::Json,
             Some(transform_config),
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
@@ -728,4 +972,70 @@ mod tests {
         universe.assert_quit().await;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_doc_processor_csv_input_format() -> anyhow::Result<()> {
+        let index_id = "my-index";
+        let source_id = "my-source";
+        let universe = Universe::with_accelerated_time();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let transform_config = TransformConfig::for_test(
+            ".timestamp = to_int!(.timestamp)\n.response_time = to_float!(.response_time)",
+        );
+        let doc_processor = DocProcessor::try_new(
+            index_id.to_string(),
+            source_id.to_string(),
+            doc_mapper.clone(),
+            indexer_mailbox,
+            SourceInputFormat::Csv,
+            Some(transform_config),
+            None,
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    "body,timestamp,response_time\n", // header
+                    "happy,1628837062,12\n",          // ok
+                    "sad,1628837062\n",               // missing column
+                    "body,timestamp,response_time\n", // repeated header
+                ],
+                0..4,
+            ))
+            .await?;
+        let doc_processor_counters = doc_processor_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(
+            doc_processor_counters,
+            DocProcessorCounters {
+                index_id: index_id.to_string(),
+                source_id: source_id.to_string(),
+                num_parse_errors: 1,
+                num_transform_errors: 0,
+                num_docs_with_missing_fields: 0,
+                num_valid_docs: 1,
+                overall_num_bytes: 35,
+            }
+        );
+        let batch: PreparedDocBatch = indexer_inbox
+            .drain_for_test_typed::<PreparedDocBatch>()
+            .into_iter()
+            .next()
+            .unwrap();
+        assert_eq!(batch.docs.len(), 1);
+
+        let schema = doc_mapper.schema();
+        let NamedFieldDocument(named_field_doc_map) = schema.to_named_doc(&batch.docs[0].doc);
+        let doc_json = JsonValue::Object(doc_mapper.doc_to_json(named_field_doc_map)?);
+        assert_eq!(doc_json["body"], "happy");
+        assert_eq!(doc_json["response_time"], 12.0);
+        assert_eq!(doc_json["timestamp"], 1628837062);
+        universe.assert_quit().await;
+        Ok(())
+    }
 }
@@ -29,6 +29,7 @@ use quickwit_actors::{
 use quickwit_common::KillSwitch;
 use quickwit_config::{IndexingSettings, SourceConfig};
 use quickwit_doc_mapper::DocMapper;
+use quickwit_ingest::get_ingest_api_service;
 use quickwit_metastore::{Metastore, MetastoreError};
 use quickwit_storage::Storage;
 use tokio::join;
@@ -343,12 +344,21 @@ impl IndexingPipeline {
             .set_kill_switch(self.kill_switch.clone())
             .spawn(indexer);
 
+        // Doc Processor
+        let dead_letter_queue_opt = if self.params.source_config.dead_letter_queue_enabled {
+            let ingest_api_service = get_ingest_api_service(&self.params.queues_dir_path).await?;
+            Some(ingest_api_service)
+        } else {
+            None
+        };
         let doc_processor = DocProcessor::try_new(
             index_id.to_string(),
             source_id.to_string(),
             self.params.doc_mapper.clone(),
             indexer_mailbox,
+            self.params.source_config.input_format,
             self.params.source_config.transform_config.clone(),
+            dead_letter_queue_opt,
         )?;
         let (doc_processor_mailbox, doc_processor_handle) = ctx
             .spawn_actor()
@@ -536,7 +546,7 @@ mod tests {
     use std::sync::Arc;
 
     use quickwit_actors::{Command, Universe};
-    use quickwit_config::{IndexingSettings, SourceParams, VoidSourceParams};
+    use quickwit_config::{IndexingSettings, SourceInputFormat, SourceParams, VoidSourceParams};
     use quickwit_doc_mapper::{default_doc_mapper_for_test, DefaultDocMapper};
     use quickwit_metastore::{IndexMetadata, MetastoreError, MockMetastore};
     use quickwit_storage::RamStorage;
@@ -617,6 +627,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::file(PathBuf::from("data/test_corpus.json")),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store(storage.clone());
@@ -707,6 +719,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::file(PathBuf::from("data/test_corpus.json")),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store(storage.clone());
@@ -766,6 +780,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::Void(VoidSourceParams),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store(storage.clone());
@@ -876,6 +892,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::file(PathBuf::from("data/test_corpus.json")),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store(storage.clone());
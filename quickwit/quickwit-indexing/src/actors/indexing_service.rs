@@ -34,7 +34,9 @@ use quickwit_common::fs::get_cache_directory_path;
 use quickwit_config::{
     build_doc_mapper, IndexConfig, IndexerConfig, SourceConfig, INGEST_API_SOURCE_ID,
 };
-use quickwit_ingest::{DropQueueRequest, IngestApiService, ListQueuesRequest, QUEUES_DIR_NAME};
+use quickwit_ingest::{
+    dead_letter_queue_id, DropQueueRequest, IngestApiService, ListQueuesRequest, QUEUES_DIR_NAME,
+};
 use quickwit_metastore::{IndexMetadata, Metastore, MetastoreError};
 use quickwit_proto::indexing_api::{ApplyIndexingPlanRequest, IndexingTask};
 use quickwit_proto::{ServiceError, ServiceErrorCode};
@@ -260,7 +262,9 @@ impl IndexingService {
         let indexing_directory = self
             .get_or_create_indexing_directory(&pipeline_id, indexing_dir_path)
             .await?;
-        let storage = self.storage_resolver.resolve(&index_config.index_uri)?;
+        let storage = self
+            .storage_resolver
+            .resolve_with_encryption(&index_config.index_uri, index_config.encryption_key_uri())?;
         let queues_dir_path = self.data_dir_path.join(QUEUES_DIR_NAME);
         let merge_policy =
             crate::merge_policy::merge_policy_from_settings(&index_config.indexing_settings);
@@ -598,17 +602,27 @@ impl IndexingService {
             .collect();
         debug!(queues=?queues, "List ingest API queues.");
 
-        let index_ids: HashSet<String> = self
+        let indexes_metadatas = self
             .metastore
             .list_indexes_metadatas()
             .await
-            .context("Failed to list queues")?
-            .into_iter()
+            .context("Failed to list queues")?;
+        let index_ids: HashSet<String> = indexes_metadatas
+            .iter()
             .map(|index_metadata| index_metadata.index_id().to_string())
             .collect();
         debug!(index_ids=?index_ids, "List indexes.");
 
-        let queue_ids_to_delete = queues.difference(&index_ids);
+        // Dead-letter queues are kept as long as their source exists.
+        let dead_letter_queue_ids = indexes_metadatas.iter().flat_map(|index_metadata| {
+            index_metadata
+                .sources
+                .keys()
+                .map(|source_id| dead_letter_queue_id(index_metadata.index_id(), source_id))
+        });
+        let queue_ids_to_keep: HashSet<String> =
+            index_ids.into_iter().chain(dead_letter_queue_ids).collect();
+        let queue_ids_to_delete = queues.difference(&queue_ids_to_keep);
 
         for queue_id in queue_ids_to_delete {
             let delete_queue_res = ingest_api_service
@@ -769,7 +783,9 @@ mod tests {
     use quickwit_cluster::create_cluster_for_test;
     use quickwit_common::rand::append_random_suffix;
     use quickwit_common::uri::Uri;
-    use quickwit_config::{IngestApiConfig, SourceConfig, SourceParams, VecSourceParams};
+    use quickwit_config::{
+        IngestApiConfig, SourceConfig, SourceInputFormat, SourceParams, VecSourceParams,
+    };
     use quickwit_ingest::{init_ingest_api, CreateQueueIfNotExistsRequest};
     use quickwit_metastore::{quickwit_metastore_uri_resolver, MockMetastore};
     use quickwit_proto::indexing_api::IndexingTask;
@@ -845,6 +861,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         let spawn_pipeline_msg = SpawnPipeline {
             index_id: index_id.clone(),
@@ -939,6 +957,8 @@ mod tests {
                 partition: "0".to_string(),
             }),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         indexing_service
             .ask_for_res(SpawnPipeline {
@@ -996,6 +1016,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         metastore
             .add_source(&index_id, source_config_1.clone())
@@ -1030,6 +1052,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         metastore
             .add_source(&index_id, source_config_2.clone())
@@ -1146,6 +1170,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         metastore.create_index(index_config).await.unwrap();
         metastore
@@ -1264,6 +1290,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         index_metadata
             .sources
@@ -1327,6 +1355,10 @@ mod tests {
             .await
             .unwrap();
         metastore.create_index(index_config).await.unwrap();
+        metastore
+            .add_source(&index_id, SourceConfig::ingest_api_default())
+            .await
+            .unwrap();
 
         // Setup ingest api objects
         let universe = Universe::with_accelerated_time();
@@ -1336,13 +1368,17 @@ mod tests {
             init_ingest_api(&universe, &queues_dir_path, &IngestApiConfig::default())
                 .await
                 .unwrap();
-        let create_queue_req = CreateQueueIfNotExistsRequest {
-            queue_id: index_id.clone(),
-        };
-        ingest_api_service
-            .ask_for_res(create_queue_req)
-            .await
-            .unwrap();
+        for queue_id in [
+            index_id.clone(),
+            dead_letter_queue_id(&index_id, INGEST_API_SOURCE_ID),
+            dead_letter_queue_id(&index_id, "deleted-source"),
+        ] {
+            let create_queue_req = CreateQueueIfNotExistsRequest { queue_id };
+            ingest_api_service
+                .ask_for_res(create_queue_req)
+                .await
+                .unwrap();
+        }
 
         // Setup `IndexingService`
         let data_dir_path = temp_dir.path().to_path_buf();
@@ -1361,12 +1397,12 @@ mod tests {
         .unwrap();
 
         indexing_server.run_ingest_api_queues_gc().await.unwrap();
-        assert_eq!(indexing_server.counters.num_deleted_queues, 0);
+        assert_eq!(indexing_server.counters.num_deleted_queues, 1);
 
         metastore.delete_index(&index_id).await.unwrap();
 
         indexing_server.run_ingest_api_queues_gc().await.unwrap();
-        assert_eq!(indexing_server.counters.num_deleted_queues, 1);
+        assert_eq!(indexing_server.counters.num_deleted_queues, 3);
 
         universe.assert_quit().await;
     }
@@ -0,0 +1,450 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+use csv::StringRecord;
+use quickwit_config::SourceInputFormat;
+use serde_json::Value as JsonValue;
+
+type JsonObject = serde_json::Map<String, JsonValue>;
+
+const SYSLOG_FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+const SYSLOG_SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// Converts the non-JSON records emitted by a source into JSON objects.
+pub(crate) enum InputFormatParser {
+    Csv {
+        /// Names of the columns, read from the first record of the current file.
+        header_opt: Option<StringRecord>,
+    },
+    Logfmt,
+    Syslog,
+    PlainText,
+}
+
+impl InputFormatParser {
+    /// Returns `None` for JSON sources, which do not need any conversion.
+    pub fn new(input_format: SourceInputFormat) -> Option<Self> {
+        let parser = match input_format {
+            SourceInputFormat::Json => return None,
+            SourceInputFormat::Csv => InputFormatParser::Csv { header_opt: None },
+            SourceInputFormat::Logfmt => InputFormatParser::Logfmt,
+            SourceInputFormat::Syslog => InputFormatParser::Syslog,
+            SourceInputFormat::PlainText => InputFormatParser::PlainText,
+        };
+        Some(parser)
+    }
+
+    /// Forgets the CSV header, so that the next record is read as the header of a new file.
+    pub fn reset(&mut self) {
+        if let InputFormatParser::Csv { header_opt } = self {
+            *header_opt = None;
+        }
+    }
+
+    /// Parses a record into a JSON object. Returns `None` if the record is a CSV header.
+    pub fn parse(&mut self, raw_doc: &[u8]) -> Result<Option<JsonObject>, String> {
+        let line = std::str::from_utf8(raw_doc)
+            .map_err(|_| "Document is not valid UTF-8.".to_string())?
+            .trim_end_matches(|c: char| c == '\n' || c == '\r');
+        let json_obj = match self {
+            InputFormatParser::Csv { header_opt } => return parse_csv(line, header_opt),
+            InputFormatParser::Logfmt => parse_logfmt(line)?,
+            InputFormatParser::Syslog => parse_syslog(line)?,
+            InputFormatParser::PlainText => {
+                let mut json_obj = JsonObject::new();
+                json_obj.insert("message".to_string(), JsonValue::String(line.to_string()));
+                json_obj
+            }
+        };
+        Ok(Some(json_obj))
+    }
+}
+
+/// Parses a CSV record. The first record is the header: it is stored in `header_opt` and
+/// skipped, as are the subsequent records identical to the header.
+fn parse_csv(
+    line: &str,
+    header_opt: &mut Option<StringRecord>,
+) -> Result<Option<JsonObject>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(line.as_bytes());
+    let mut record = StringRecord::new();
+    let has_record = reader
+        .read_record(&mut record)
+        .map_err(|error| format!("Failed to parse CSV record: {error}."))?;
+    if !has_record {
+        return Err("CSV record is empty.".to_string());
+    }
+    let Some(header) = header_opt.as_ref() else {
+        *header_opt = Some(record);
+        return Ok(None);
+    };
+    if record.iter().eq(header.iter()) {
+        return Ok(None);
+    }
+    if record.len() != header.len() {
+        return Err(format!(
+            "CSV record has {} fields but the header has {} columns.",
+            record.len(),
+            header.len()
+        ));
+    }
+    let json_obj = header
+        .iter()
+        .zip(record.iter())
+        .map(|(column, value)| (column.to_string(), JsonValue::String(value.to_string())))
+        .collect();
+    Ok(Some(json_obj))
+}
+
+/// Parses a logfmt line, i.e. space-separated `key=value` pairs. Values may be double-quoted.
+/// Keys without a value are set to `true`.
+fn parse_logfmt(line: &str) -> Result<JsonObject, String> {
+    let mut json_obj = JsonObject::new();
+    let mut chars = line.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && !c.is_whitespace()) {
+            key.push(c);
+        }
+        if key.is_empty() || key.contains('"') {
+            return Err(format!("Invalid logfmt key in `{line}`."));
+        }
+        let value = if chars.next_if_eq(&'=').is_none() {
+            JsonValue::Bool(true)
+        } else if chars.next_if_eq(&'"').is_some() {
+            JsonValue::String(parse_quoted_string(&mut chars, '"')?)
+        } else {
+            let mut value = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+            JsonValue::String(value)
+        };
+        json_obj.insert(key, value);
+    }
+    Ok(json_obj)
+}
+
+/// Consumes a quoted string up to and including the closing quote. The opening quote must have
+/// been consumed already.
+fn parse_quoted_string(chars: &mut Peekable<Chars>, quote: char) -> Result<String, String> {
+    let mut value = String::new();
+
+    while let Some(c) = chars.next() {
+        if c == quote {
+            return Ok(value);
+        }
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            Some(escaped_char @ ('\\' | '"' | ']')) => value.push(escaped_char),
+            Some(other_char) => {
+                value.push('\\');
+                value.push(other_char);
+            }
+            None => break,
+        }
+    }
+    Err("Quoted string is not terminated.".to_string())
+}
+
+/// Parses an RFC 5424 syslog message:
+/// `<PRI>VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA [MSG]`.
+///
+/// Header fields set to the nil value `-` are omitted. Structured data elements are stored in the
+/// `structured_data` object, keyed by SD-ID.
+fn parse_syslog(line: &str) -> Result<JsonObject, String> {
+    let invalid_message_error = || format!("Invalid RFC 5424 syslog message `{line}`.");
+
+    let (pri_str, header) = line
+        .strip_prefix('<')
+        .and_then(|rest| rest.split_once('>'))
+        .ok_or_else(invalid_message_error)?;
+    let pri = pri_str
+        .parse::<usize>()
+        .ok()
+        .filter(|pri| pri_str.len() <= 3 && *pri < SYSLOG_FACILITIES.len() * 8)
+        .ok_or_else(invalid_message_error)?;
+    let mut header_fields = header.splitn(7, ' ');
+    let mut next_header_field = || header_fields.next().ok_or_else(invalid_message_error);
+
+    let version = next_header_field()?
+        .parse::<u64>()
+        .map_err(|_| invalid_message_error())?;
+    let mut json_obj = JsonObject::new();
+    json_obj.insert(
+        "facility".to_string(),
+        JsonValue::String(SYSLOG_FACILITIES[pri / 8].to_string()),
+    );
+    json_obj.insert(
+        "severity".to_string(),
+        JsonValue::String(SYSLOG_SEVERITIES[pri % 8].to_string()),
+    );
+    json_obj.insert("version".to_string(), JsonValue::from(version));
+
+    for field_name in ["timestamp", "hostname", "appname", "procid", "msgid"] {
+        let field_value = next_header_field()?;
+        if field_value.is_empty() {
+            return Err(invalid_message_error());
+        }
+        if field_value != "-" {
+            json_obj.insert(
+                field_name.to_string(),
+                JsonValue::String(field_value.to_string()),
+            );
+        }
+    }
+    let mut chars = next_header_field()?.chars().peekable();
+    let structured_data = parse_structured_data(&mut chars)?;
+
+    if !structured_data.is_empty() {
+        json_obj.insert(
+            "structured_data".to_string(),
+            JsonValue::Object(structured_data),
+        );
+    }
+    match chars.next() {
+        Some(' ') => {
+            let message: String = chars.collect();
+            let message = message.trim_start_matches('\u{feff}');
+            json_obj.insert(
+                "message".to_string(),
+                JsonValue::String(message.to_string()),
+            );
+        }
+        Some(_) => return Err(invalid_message_error()),
+        None => {}
+    }
+    Ok(json_obj)
+}
+
+/// Parses the structured data of a syslog message: either the nil value `-` or a sequence of
+/// `[SD-ID PARAM-NAME="PARAM-VALUE" ...]` elements.
+fn parse_structured_data(chars: &mut Peekable<Chars>) -> Result<JsonObject, String> {
+    let mut structured_data = JsonObject::new();
+
+    if chars.next_if_eq(&'-').is_some() {
+        return Ok(structured_data);
+    }
+    while chars.next_if_eq(&'[').is_some() {
+        let mut sd_id = String::new();
+        while let Some(c) = chars.next_if(|c| *c != ' ' && *c != ']') {
+            sd_id.push(c);
+        }
+        let mut params = JsonObject::new();
+
+        loop {
+            match chars.next() {
+                Some(']') => break,
+                Some(' ') => {}
+                _ => return Err("Invalid syslog structured data element.".to_string()),
+            }
+            let mut param_name = String::new();
+            while let Some(c) = chars.next_if(|c| *c != '=' && *c != ' ' && *c != ']') {
+                param_name.push(c);
+            }
+            if param_name.is_empty()
+                || chars.next_if_eq(&'=').is_none()
+                || chars.next_if_eq(&'"').is_none()
+            {
+                return Err("Invalid syslog structured data parameter.".to_string());
+            }
+            let param_value = parse_quoted_string(chars, '"')?;
+            params.insert(param_name, JsonValue::String(param_value));
+        }
+        if sd_id.is_empty() {
+            return Err("Syslog structured data element has no ID.".to_string());
+        }
+        structured_data.insert(sd_id, JsonValue::Object(params));
+    }
+    if structured_data.is_empty() {
+        return Err("Syslog message has no structured data.".to_string());
+    }
+    Ok(structured_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn parse(parser: &mut InputFormatParser, raw_doc: &str) -> Option<JsonValue> {
+        parser
+            .parse(raw_doc.as_bytes())
+            .unwrap()
+            .map(JsonValue::Object)
+    }
+
+    #[test]
+    fn test_input_format_parser_json() {
+        assert!(InputFormatParser::new(SourceInputFormat::Json).is_none());
+    }
+
+    #[test]
+    fn test_input_format_parser_csv() {
+        let mut parser = InputFormatParser::new(SourceInputFormat::Csv).unwrap();
+        assert!(parse(&mut parser, "name,age,city\n").is_none());
+        assert_eq!(
+            parse(&mut parser, "alice,42,\"Paris, France\"\n").unwrap(),
+            json!({"name": "alice", "age": "42", "city": "Paris, France"})
+        );
+        assert_eq!(
+            parse(&mut parser, "bob,,\"Lyon\"\"\"\r\n").unwrap(),
+            json!({"name": "bob", "age": "", "city": "Lyon\""})
+        );
+        // Repeated headers are skipped.
+        assert!(parse(&mut parser, "name,age,city\n").is_none());
+
+        let error = parser.parse(b"carol,42\n").unwrap_err();
+        assert_eq!(
+            error,
+            "CSV record has 2 fields but the header has 3 columns."
+        );
+        // The next file has its own header.
+        parser.reset();
+        assert!(parse(&mut parser, "city,name\n").is_none());
+        assert_eq!(
+            parse(&mut parser, "Nantes,carol\n").unwrap(),
+            json!({"city": "Nantes", "name": "carol"})
+        );
+    }
+
+    #[test]
+    fn test_input_format_parser_logfmt() {
+        let mut parser = InputFormatParser::new(SourceInputFormat::Logfmt).unwrap();
+        assert_eq!(
+            parse(
+                &mut parser,
+                r#"ts=2023-01-01T00:00:00Z level=info msg="request \"served\"" path=/ empty= debug"#
+            )
+            .unwrap(),
+            json!({
+                "ts": "2023-01-01T00:00:00Z",
+                "level": "info",
+                "msg": "request \"served\"",
+                "path": "/",
+                "empty": "",
+                "debug": true,
+            })
+        );
+        assert_eq!(parse(&mut parser, "\n").unwrap(), json!({}));
+        parser.parse(br#"msg="unterminated"#).unwrap_err();
+        parser.parse(b"=value").unwrap_err();
+    }
+
+    #[test]
+    fn test_input_format_parser_syslog() {
+        let mut parser = InputFormatParser::new(SourceInputFormat::Syslog).unwrap();
+        assert_eq!(
+            parse(
+                &mut parser,
+                "<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 \
+                 [exampleSDID@32473 iut=\"3\" eventSource=\"Application\"][origin \
+                 ip=\"10.0.0.1\"] \u{feff}An application event log entry...\n"
+            )
+            .unwrap(),
+            json!({
+                "facility": "local4",
+                "severity": "notice",
+                "version": 1,
+                "timestamp": "2003-10-11T22:14:15.003Z",
+                "hostname": "mymachine.example.com",
+                "appname": "evntslog",
+                "msgid": "ID47",
+                "structured_data": {
+                    "exampleSDID@32473": {"iut": "3", "eventSource": "Application"},
+                    "origin": {"ip": "10.0.0.1"},
+                },
+                "message": "An application event log entry...",
+            })
+        );
+        assert_eq!(
+            parse(&mut parser, "<34>1 - - su - - -").unwrap(),
+            json!({
+                "facility": "auth",
+                "severity": "crit",
+                "version": 1,
+                "appname": "su",
+            })
+        );
+        parser
+            .parse(b"Oct 11 22:14:15 mymachine su: 'su root' failed")
+            .unwrap_err();
+        parser.parse(b"<192>1 - - - - - -").unwrap_err();
+        parser.parse(b"<34>1 - - su - -").unwrap_err();
+        parser
+            .parse(b"<34>1 - - su - - [origin ip=10.0.0.1]")
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_input_format_parser_plain_text() {
+        let mut parser = InputFormatParser::new(SourceInputFormat::PlainText).unwrap();
+        assert_eq!(
+            parse(&mut parser, "Hello, \"World\"!\r\n").unwrap(),
+            json!({"message": "Hello, \"World\"!"})
+        );
+        parser.parse(&[0xff, 0xfe]).unwrap_err();
+    }
+}
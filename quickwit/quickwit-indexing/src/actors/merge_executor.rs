@@ -31,11 +31,12 @@ use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, Qu
 use quickwit_common::io::IoControls;
 use quickwit_common::runtimes::RuntimeType;
 use quickwit_directories::UnionDirectory;
-use quickwit_doc_mapper::{DocMapper, QUICKWIT_TOKENIZER_MANAGER};
+use quickwit_doc_mapper::DocMapper;
 use quickwit_metastore::{Metastore, SplitMetadata};
 use quickwit_proto::metastore_api::DeleteTask;
 use quickwit_proto::SearchRequest;
 use tantivy::directory::{DirectoryClone, MmapDirectory, RamDirectory};
+use tantivy::tokenizer::TokenizerManager;
 use tantivy::{DateTime, Directory, Index, IndexMeta, SegmentId, SegmentReader};
 use tokio::runtime::Handle;
 use tracing::{debug, info, instrument, warn};
@@ -159,7 +160,7 @@ fn open_split_directories(
     for tantivy_dir in tantivy_dirs {
         directories.push(tantivy_dir.clone());
 
-        let index_meta = open_index(tantivy_dir.clone())?.load_metas()?;
+        let index_meta = Index::open(tantivy_dir.clone())?.load_metas()?;
         index_metas.push(index_meta);
     }
     let union_index_meta = combine_index_meta(index_metas)?;
@@ -305,7 +306,10 @@ impl MergeExecutor {
 
         // This will have the side effect of deleting the directory containing the downloaded
         // splits.
-        let merged_index = open_index(controlled_directory.clone())?;
+        let merged_index = open_index(
+            controlled_directory.clone(),
+            self.doc_mapper.tokenizer_manager(),
+        )?;
         ctx.record_progress();
 
         let split_attrs = merge_split_attrs(merge_split_id, &self.pipeline_id, &splits);
@@ -365,7 +369,7 @@ impl MergeExecutor {
         // This will have the side effect of deleting the directory containing the downloaded split.
         let mut merged_index = Index::open(controlled_directory.clone())?;
         ctx.record_progress();
-        merged_index.set_tokenizers(QUICKWIT_TOKENIZER_MANAGER.clone());
+        merged_index.set_tokenizers(self.doc_mapper.tokenizer_manager().clone());
 
         ctx.record_progress();
 
@@ -449,7 +453,7 @@ impl MergeExecutor {
         ];
         directory_stack.extend(split_directories.into_iter());
         let union_directory = UnionDirectory::union_of(directory_stack);
-        let union_index = open_index(union_directory)?;
+        let union_index = open_index(union_directory, self.doc_mapper.tokenizer_manager())?;
 
         ctx.record_progress();
         let _protect_guard = ctx.protect_zone();
@@ -506,9 +510,12 @@ impl MergeExecutor {
     }
 }
 
-fn open_index<T: Into<Box<dyn Directory>>>(directory: T) -> tantivy::Result<Index> {
+fn open_index<T: Into<Box<dyn Directory>>>(
+    directory: T,
+    tokenizer_manager: &TokenizerManager,
+) -> tantivy::Result<Index> {
     let mut index = Index::open(directory)?;
-    index.set_tokenizers(QUICKWIT_TOKENIZER_MANAGER.clone());
+    index.set_tokenizers(tokenizer_manager.clone());
     Ok(index)
 }
 
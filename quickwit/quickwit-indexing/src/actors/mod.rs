@@ -24,6 +24,7 @@ mod doc_processor;
 mod index_serializer;
 mod indexer;
 mod indexing_service;
+mod input_format;
 mod packager;
 mod publisher;
 mod sequencer;
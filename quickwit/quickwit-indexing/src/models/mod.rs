@@ -50,7 +50,7 @@ pub use packaged_split::{PackagedSplit, PackagedSplitBatch};
 pub use prepared_doc::{PreparedDoc, PreparedDocBatch};
 pub use publish_lock::{NewPublishLock, PublishLock};
 pub use publisher_message::SplitsUpdate;
-pub use raw_doc_batch::RawDocBatch;
+pub use raw_doc_batch::{NewSourceFile, RawDocBatch};
 pub use scratch_directory::{ScratchDirectory, WeakScratchDirectory};
 pub use split_attrs::{create_split_metadata, SplitAttrs};
 
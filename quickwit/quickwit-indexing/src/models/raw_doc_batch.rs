@@ -70,6 +70,12 @@ impl RawDocBatch {
     }
 }
 
+/// Sent by the file source before the lines of each file when the first line of the files is a
+/// CSV header, so that the doc processor reads the header of every file rather than only the
+/// first one.
+#[derive(Debug)]
+pub struct NewSourceFile;
+
 impl fmt::Debug for RawDocBatch {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         formatter
@@ -0,0 +1,239 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::io;
+use std::path::Path;
+
+use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder};
+use bytes::Bytes;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncReadExt};
+use tokio_util::io::StreamReader;
+
+const GZIP_MAGIC_BYTES: &[u8] = &[0x1f, 0x8b];
+
+const ZSTD_MAGIC_BYTES: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Stream identifier chunk opening a stream in the Snappy framing format.
+const SNAPPY_MAGIC_BYTES: &[u8] = &[0xff, 0x06, 0x00, 0x00, b's', b'N', b'a', b'P', b'p', b'Y'];
+
+/// Number of bytes to read from the beginning of a file to detect its compression.
+pub(crate) const MAGIC_BYTES_MAX_LEN: usize = SNAPPY_MAGIC_BYTES.len();
+
+/// Compression formats the file source decompresses transparently.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Compression {
+    Gzip,
+    Zstd,
+    /// Snappy framing format, as produced by `snzip` or the `snap` crate.
+    Snappy,
+}
+
+impl Compression {
+    /// Detects the compression of a file from its extension or, if the extension is not a
+    /// compression extension, from its first bytes. Returns `None` for uncompressed files.
+    pub(crate) fn detect(path: &Path, prefix_bytes: &[u8]) -> Option<Self> {
+        Self::from_extension(path).or_else(|| Self::from_magic_bytes(prefix_bytes))
+    }
+
+    fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" | "gzip" => Some(Self::Gzip),
+            "zst" | "zstd" => Some(Self::Zstd),
+            "sz" | "snappy" => Some(Self::Snappy),
+            _ => None,
+        }
+    }
+
+    fn from_magic_bytes(prefix_bytes: &[u8]) -> Option<Self> {
+        if prefix_bytes.starts_with(GZIP_MAGIC_BYTES) {
+            Some(Self::Gzip)
+        } else if prefix_bytes.starts_with(ZSTD_MAGIC_BYTES) {
+            Some(Self::Zstd)
+        } else if prefix_bytes.starts_with(SNAPPY_MAGIC_BYTES) {
+            Some(Self::Snappy)
+        } else {
+            None
+        }
+    }
+
+    /// Wraps a reader of compressed bytes into a reader of decompressed bytes.
+    pub(crate) fn decoder<R>(self, reader: R) -> Box<dyn AsyncRead + Send + Sync + Unpin>
+    where R: AsyncBufRead + Send + Sync + Unpin + 'static {
+        match self {
+            Self::Gzip => {
+                let mut decoder = GzipDecoder::new(reader);
+                // Concatenated gzip files are valid gzip files.
+                decoder.multiple_members(true);
+                Box::new(decoder)
+            }
+            Self::Zstd => {
+                let mut decoder = ZstdDecoder::new(reader);
+                decoder.multiple_members(true);
+                Box::new(decoder)
+            }
+            Self::Snappy => {
+                let chunk_stream = futures::stream::try_unfold(reader, |mut reader| async move {
+                    let chunk_opt = read_snappy_chunk(&mut reader).await?;
+                    Ok::<_, io::Error>(chunk_opt.map(|chunk| (chunk, reader)))
+                });
+                Box::new(StreamReader::new(Box::pin(chunk_stream)))
+            }
+        }
+    }
+}
+
+/// Reads the next data chunk of a stream in the Snappy framing format and returns its
+/// decompressed content, or `None` at the end of the stream.
+///
+/// See <https://github.com/google/snappy/blob/main/framing_format.txt>. Checksums are not
+/// verified.
+async fn read_snappy_chunk<R>(reader: &mut R) -> io::Result<Option<Bytes>>
+where R: AsyncRead + Unpin {
+    loop {
+        let mut chunk_header = [0u8; 4];
+        // Reaching the end of the stream is only legit at a chunk boundary.
+        if reader.read(&mut chunk_header[..1]).await? == 0 {
+            return Ok(None);
+        }
+        reader.read_exact(&mut chunk_header[1..]).await?;
+        let chunk_type = chunk_header[0];
+        let chunk_len =
+            u32::from_le_bytes([chunk_header[1], chunk_header[2], chunk_header[3], 0]) as usize;
+        let mut chunk = vec![0u8; chunk_len];
+        reader.read_exact(&mut chunk).await?;
+
+        match chunk_type {
+            // Compressed data, preceded by a 4-byte checksum.
+            0x00 if chunk_len >= 4 => {
+                let data = snap::raw::Decoder::new()
+                    .decompress_vec(&chunk[4..])
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+                return Ok(Some(Bytes::from(data)));
+            }
+            // Uncompressed data, preceded by a 4-byte checksum.
+            0x01 if chunk_len >= 4 => {
+                return Ok(Some(Bytes::from(chunk).slice(4..)));
+            }
+            // Stream identifier, padding, and reserved skippable chunks.
+            0x80..=0xff => continue,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid Snappy chunk of type `{chunk_type:#04x}`."),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use async_compression::tokio::bufread::{GzipEncoder, ZstdEncoder};
+
+    use super::*;
+
+    const DOCS: &[u8] = b"{\"body\": \"foo\"}\n{\"body\": \"bar\"}\n";
+
+    async fn compress(compression: Compression, data: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        match compression {
+            Compression::Gzip => {
+                GzipEncoder::new(data)
+                    .read_to_end(&mut compressed)
+                    .await
+                    .unwrap();
+            }
+            Compression::Zstd => {
+                ZstdEncoder::new(data)
+                    .read_to_end(&mut compressed)
+                    .await
+                    .unwrap();
+            }
+            Compression::Snappy => {
+                let mut encoder = snap::write::FrameEncoder::new(&mut compressed);
+                encoder.write_all(data).unwrap();
+                encoder.flush().unwrap();
+                drop(encoder);
+            }
+        }
+        compressed
+    }
+
+    #[test]
+    fn test_compression_detect() {
+        assert_eq!(
+            Compression::detect(Path::new("logs.ndjson.gz"), b""),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            Compression::detect(Path::new("logs.ndjson.zst"), b""),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(
+            Compression::detect(Path::new("logs.ndjson.sz"), b""),
+            Some(Compression::Snappy)
+        );
+        assert_eq!(
+            Compression::detect(Path::new("logs"), &[0x1f, 0x8b, 0x08]),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(
+            Compression::detect(Path::new("logs"), &[0x28, 0xb5, 0x2f, 0xfd]),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(
+            Compression::detect(Path::new("logs"), SNAPPY_MAGIC_BYTES),
+            Some(Compression::Snappy)
+        );
+        assert_eq!(
+            Compression::detect(Path::new("logs.ndjson"), b"{\"body"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compression_decoder() {
+        for compression in [Compression::Gzip, Compression::Zstd, Compression::Snappy] {
+            let compressed = compress(compression, DOCS).await;
+            assert_eq!(
+                Compression::detect(Path::new("logs"), &compressed),
+                Some(compression)
+            );
+            let mut decoder = compression.decoder(io::Cursor::new(compressed));
+            let mut decompressed = Vec::new();
+            decoder.read_to_end(&mut decompressed).await.unwrap();
+            assert_eq!(decompressed, DOCS, "{compression:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compression_decoder_concatenated_members() {
+        for compression in [Compression::Gzip, Compression::Zstd, Compression::Snappy] {
+            let mut compressed = compress(compression, DOCS).await;
+            compressed.extend(compress(compression, DOCS).await);
+
+            let mut decoder = compression.decoder(io::Cursor::new(compressed));
+            let mut decompressed = Vec::new();
+            decoder.read_to_end(&mut decompressed).await.unwrap();
+            assert_eq!(decompressed, [DOCS, DOCS].concat(), "{compression:?}");
+        }
+    }
+}
@@ -17,41 +17,79 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::VecDeque;
 use std::fmt;
 use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use async_trait::async_trait;
 use bytes::Bytes;
+use glob::{MatchOptions, Pattern};
 use quickwit_actors::{ActorExitStatus, Mailbox};
-use quickwit_config::FileSourceParams;
+use quickwit_common::uri::{Protocol, Uri};
+use quickwit_config::{FileSourceParams, SourceInputFormat};
 use quickwit_metastore::checkpoint::{PartitionId, Position, SourceCheckpoint};
+use quickwit_storage::{quickwit_storage_uri_resolver, Storage};
 use serde::Serialize;
+use tempfile::NamedTempFile;
 use tokio::fs::File;
-use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncSeekExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncSeekExt, BufReader};
 use tracing::info;
 
 use crate::actors::DocProcessor;
-use crate::models::RawDocBatch;
+use crate::models::{NewSourceFile, RawDocBatch};
+use crate::source::compression::{Compression, MAGIC_BYTES_MAX_LEN};
 use crate::source::{Source, SourceContext, SourceExecutionContext, TypedSourceFactory};
 
 /// Number of bytes after which a new batch is cut.
-pub(crate) const BATCH_NUM_BYTES_LIMIT: u64 = 500_000u64;
+const FILE_BATCH_NUM_BYTES_LIMIT: u64 = 500_000u64;
+
+/// Characters turning a file source URI into a glob pattern.
+const GLOB_METACHARACTERS: &[char] = &['*', '?', '['];
+
+/// Suffix appended to the checkpointed offset of a compressed file once it has been read up to its
+/// end. Its decompressed size is unknown, so its offset alone does not tell whether it was fully
+/// indexed. The suffixed position sorts right after the offset.
+const END_OF_FILE_POSITION_SUFFIX: &str = ":eof";
 
 #[derive(Default, Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct FileSourceCounters {
+    /// Offsets within the file currently being read.
     pub previous_offset: u64,
     pub current_offset: u64,
     pub num_lines_processed: u64,
+    pub num_files_processed: u64,
+}
+
+/// A file waiting to be read, along with the offset to resume from.
+struct PendingFile {
+    path: PathBuf,
+    partition_id: PartitionId,
+    offset: u64,
+}
+
+struct FileReader {
+    /// `None` when reading from stdin, in which case no checkpoint is recorded.
+    partition_id_opt: Option<PartitionId>,
+    reader: BufReader<Box<dyn AsyncRead + Send + Sync + Unpin>>,
+    is_compressed: bool,
+    // Local copy of a remote file, deleted once the file has been read.
+    _temp_file_opt: Option<NamedTempFile>,
 }
 
 pub struct FileSource {
     source_id: String,
-    params: FileSourceParams,
+    storage_opt: Option<Arc<dyn Storage>>,
+    pending_files: VecDeque<PendingFile>,
+    current_file_opt: Option<FileReader>,
+    /// Whether the first line of the files is a header, which must be sent to the doc processor
+    /// again when resuming a partially indexed file. Each file is then sent in its own batches,
+    /// preceded by a [`NewSourceFile`] message.
+    has_header: bool,
     counters: FileSourceCounters,
-    reader: BufReader<Box<dyn AsyncRead + Send + Sync + Unpin>>,
 }
 
 impl fmt::Debug for FileSource {
@@ -60,6 +98,44 @@ impl fmt::Debug for FileSource {
     }
 }
 
+impl FileSource {
+    /// Records the documents read from the current file since the last call into the checkpoint
+    /// delta of the batch. When `is_end_of_file` is set and the file is compressed, the end of the
+    /// file is recorded as well.
+    fn record_partition_delta(
+        &mut self,
+        doc_batch: &mut RawDocBatch,
+        is_end_of_file: bool,
+    ) -> anyhow::Result<()> {
+        let mark_end_of_file = is_end_of_file
+            && self
+                .current_file_opt
+                .as_ref()
+                .map_or(false, |current_file| current_file.is_compressed);
+        if self.counters.current_offset == self.counters.previous_offset && !mark_end_of_file {
+            return Ok(());
+        }
+        if let Some(partition_id) = self
+            .current_file_opt
+            .as_ref()
+            .and_then(|current_file| current_file.partition_id_opt.clone())
+        {
+            let to_position = if mark_end_of_file {
+                end_of_file_position(self.counters.current_offset)
+            } else {
+                Position::from(self.counters.current_offset)
+            };
+            doc_batch.checkpoint_delta.record_partition_delta(
+                partition_id,
+                Position::from(self.counters.previous_offset),
+                to_position,
+            )?;
+        }
+        self.counters.previous_offset = self.counters.current_offset;
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Source for FileSource {
     async fn emit_batches(
@@ -68,42 +144,64 @@ impl Source for FileSource {
         ctx: &SourceContext,
     ) -> Result<Duration, ActorExitStatus> {
         // We collect batches of documents before sending them to the indexer.
-        let limit_num_bytes = self.counters.previous_offset + BATCH_NUM_BYTES_LIMIT;
+        let mut batch_num_bytes = 0;
         let mut reached_eof = false;
         let mut doc_batch = RawDocBatch::default();
-        while self.counters.current_offset < limit_num_bytes {
+        while batch_num_bytes < FILE_BATCH_NUM_BYTES_LIMIT {
+            if self.current_file_opt.is_none() {
+                // The lines of the next file must not follow the lines of the previous file in
+                // the same batch, since the doc processor resets the header in between.
+                if self.has_header && !doc_batch.docs.is_empty() {
+                    break;
+                }
+                let Some(pending_file) = self.pending_files.pop_front() else {
+                    reached_eof = true;
+                    break;
+                };
+                let storage = self
+                    .storage_opt
+                    .as_ref()
+                    .expect("The storage should be set when there are files to read.");
+                self.counters.previous_offset = pending_file.offset;
+                self.counters.current_offset = pending_file.offset;
+                // Downloading a remote file may take a while.
+                let (file_reader, header_line_opt) = ctx
+                    .protect_future(open_file(storage, pending_file, self.has_header))
+                    .await?;
+                if self.has_header {
+                    ctx.send_message(doc_processor_mailbox, NewSourceFile)
+                        .await?;
+                }
+                if let Some(header_line) = header_line_opt {
+                    doc_batch.docs.push(Bytes::from(header_line));
+                }
+                self.current_file_opt = Some(file_reader);
+            }
+            let current_file = self
+                .current_file_opt
+                .as_mut()
+                .expect("The current file should be open.");
             let mut doc_line = String::new();
             // guard the zone in case of slow read, such as reading from someone
             // typing to stdin
             let num_bytes = ctx
-                .protect_future(self.reader.read_line(&mut doc_line))
+                .protect_future(current_file.reader.read_line(&mut doc_line))
                 .await
                 .map_err(anyhow::Error::from)?;
             if num_bytes == 0 {
-                reached_eof = true;
-                break;
+                self.record_partition_delta(&mut doc_batch, true)?;
+                self.current_file_opt = None;
+                self.counters.num_files_processed += 1;
+                continue;
             }
             doc_batch.docs.push(Bytes::from(doc_line));
+            batch_num_bytes += num_bytes as u64;
             self.counters.current_offset += num_bytes as u64;
             self.counters.num_lines_processed += 1;
         }
+        self.record_partition_delta(&mut doc_batch, false)?;
+
         if !doc_batch.docs.is_empty() {
-            if let Some(filepath) = &self.params.filepath {
-                let filepath_str = filepath
-                    .to_str()
-                    .context("Path is invalid utf-8")?
-                    .to_string();
-                let partition_id = PartitionId::from(filepath_str);
-                doc_batch
-                    .checkpoint_delta
-                    .record_partition_delta(
-                        partition_id,
-                        Position::from(self.counters.previous_offset),
-                        Position::from(self.counters.current_offset),
-                    )
-                    .unwrap();
-            }
-            self.counters.previous_offset = self.counters.current_offset;
             ctx.send_message(doc_processor_mailbox, doc_batch).await?;
         }
         if reached_eof {
@@ -123,6 +221,205 @@ impl Source for FileSource {
     }
 }
 
+/// Opens a file and seeks to the offset to resume from. Remote files are first downloaded to a
+/// local temporary file so they can be read line by line. Compressed files are decompressed on the
+/// fly, in which case offsets are offsets within the decompressed stream.
+///
+/// If `has_header` is set and the file is resumed past its first line, the first line is returned
+/// along with the reader.
+async fn open_file(
+    storage: &Arc<dyn Storage>,
+    pending_file: PendingFile,
+    has_header: bool,
+) -> anyhow::Result<(FileReader, Option<String>)> {
+    let storage_uri = storage.uri();
+    let (local_path, temp_file_opt) = match storage_uri.filepath() {
+        Some(root) if storage_uri.protocol() == Protocol::File => {
+            (root.join(&pending_file.path), None)
+        }
+        _ => {
+            let temp_file = NamedTempFile::new()?;
+            storage
+                .copy_to_file(&pending_file.path, temp_file.path())
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to download source file `{}/{}`.",
+                        storage_uri,
+                        pending_file.path.display()
+                    )
+                })?;
+            (temp_file.path().to_path_buf(), Some(temp_file))
+        }
+    };
+    let mut file = File::open(&local_path)
+        .await
+        .with_context(|| format!("Failed to open source file `{}`.", local_path.display()))?;
+    let mut prefix_bytes = Vec::with_capacity(MAGIC_BYTES_MAX_LEN);
+    (&mut file)
+        .take(MAGIC_BYTES_MAX_LEN as u64)
+        .read_to_end(&mut prefix_bytes)
+        .await?;
+    let compression_opt = Compression::detect(&pending_file.path, &prefix_bytes);
+    let read_header = has_header && pending_file.offset > 0;
+    let mut header_line = String::new();
+
+    let reader: Box<dyn AsyncRead + Send + Sync + Unpin> =
+        if let Some(compression) = compression_opt {
+            file.seek(SeekFrom::Start(0)).await?;
+            let mut decoder = BufReader::new(compression.decoder(BufReader::new(file)));
+
+            if read_header {
+                decoder.read_line(&mut header_line).await?;
+            }
+            // Compressed streams are not seekable, so we decompress and discard the bytes read
+            // before the source was restarted.
+            let num_bytes_to_skip = pending_file.offset.saturating_sub(header_line.len() as u64);
+            let num_skipped_bytes = tokio::io::copy(
+                &mut (&mut decoder).take(num_bytes_to_skip),
+                &mut tokio::io::sink(),
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to decompress source file `{}`.",
+                    local_path.display()
+                )
+            })?;
+            if num_skipped_bytes < num_bytes_to_skip {
+                bail!(
+                    "Source file `{}` is shorter than its checkpointed offset.",
+                    local_path.display()
+                );
+            }
+            Box::new(decoder)
+        } else {
+            if read_header {
+                file.seek(SeekFrom::Start(0)).await?;
+                BufReader::new(&mut file)
+                    .read_line(&mut header_line)
+                    .await?;
+            }
+            file.seek(SeekFrom::Start(pending_file.offset)).await?;
+            Box::new(file)
+        };
+    info!(
+        partition_id=%pending_file.partition_id.0,
+        offset=%pending_file.offset,
+        compression=?compression_opt,
+        "Reading source file."
+    );
+    let file_reader = FileReader {
+        partition_id_opt: Some(pending_file.partition_id),
+        reader: BufReader::new(reader),
+        is_compressed: compression_opt.is_some(),
+        _temp_file_opt: temp_file_opt,
+    };
+    let header_line_opt = if header_line.is_empty() {
+        None
+    } else {
+        Some(header_line)
+    };
+    Ok((file_reader, header_line_opt))
+}
+
+/// Returns the checkpoint position of a compressed file read up to its end.
+fn end_of_file_position(offset: u64) -> Position {
+    let offset_position = Position::from(offset);
+    Position::from(format!(
+        "{}{END_OF_FILE_POSITION_SUFFIX}",
+        offset_position.as_str()
+    ))
+}
+
+/// Returns whether an uncompressed file has been read up to its end before the source was
+/// restarted. Compressed files read up to their end are identified by their checkpoint position
+/// instead, see [`END_OF_FILE_POSITION_SUFFIX`].
+async fn is_fully_indexed(storage: &dyn Storage, path: &Path, offset: u64) -> anyhow::Result<bool> {
+    let num_bytes = storage.file_num_bytes(path).await?;
+    if offset < num_bytes {
+        return Ok(false);
+    }
+    let prefix_num_bytes = num_bytes.min(MAGIC_BYTES_MAX_LEN as u64) as usize;
+    let prefix_bytes = storage.get_slice(path, 0..prefix_num_bytes).await?;
+    Ok(Compression::detect(path, &prefix_bytes).is_none())
+}
+
+/// Returns the partition ID of a file. Local files are identified by their path rather than by
+/// their URI so that checkpoints recorded by earlier versions of the source remain valid.
+fn file_partition_id(storage: &dyn Storage, path: &Path) -> PartitionId {
+    let storage_uri = storage.uri();
+    match storage_uri.filepath() {
+        Some(root) if storage_uri.protocol() == Protocol::File => {
+            PartitionId::from(root.join(path).to_string_lossy().to_string())
+        }
+        _ => PartitionId::from(format!(
+            "{}/{}",
+            storage_uri.as_str().trim_end_matches('/'),
+            path.display()
+        )),
+    }
+}
+
+/// Resolves the storage hosting the file(s) designated by a file source URI and returns their
+/// paths relative to that storage, sorted in lexicographic order.
+///
+/// The URI designates:
+/// - all the files matching the pattern if it contains glob metacharacters;
+/// - all the files under the prefix if it ends with `/` or is a local directory;
+/// - a single file otherwise, which must exist.
+pub(crate) async fn resolve_file_source_uri(
+    uri: &Uri,
+) -> anyhow::Result<(Arc<dyn Storage>, Vec<PathBuf>)> {
+    let storage_resolver = quickwit_storage_uri_resolver();
+    let uri_str = uri.as_str();
+
+    if let Some(glob_idx) = uri_str.find(GLOB_METACHARACTERS) {
+        // We list the files from the deepest directory free of glob metacharacters, using the
+        // literal part of the pattern as listing prefix, and then filter them with the pattern.
+        let path_idx = uri_str.find("://").map(|idx| idx + 3).unwrap_or_default();
+        let root_idx = match uri_str[..glob_idx].rfind('/') {
+            Some(root_idx) if root_idx > path_idx => root_idx,
+            _ => bail!("URI `{uri}` must contain a directory before its glob pattern."),
+        };
+        let storage_uri = Uri::from_well_formed(&uri_str[..root_idx]);
+        let storage = storage_resolver.resolve(&storage_uri)?;
+        let glob_str = &uri_str[root_idx + 1..];
+        let pattern = Pattern::new(glob_str)
+            .with_context(|| format!("Failed to parse glob pattern `{glob_str}`."))?;
+        let match_options = MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        let listing_prefix = Path::new(&uri_str[root_idx + 1..glob_idx]);
+        let paths = storage
+            .list_files(listing_prefix)
+            .await?
+            .into_iter()
+            .filter(|path| pattern.matches_path_with(path, match_options))
+            .collect();
+        return Ok((storage, paths));
+    }
+    let is_local_directory =
+        uri.protocol() == Protocol::File && uri.filepath().map(Path::is_dir).unwrap_or(false);
+
+    if uri_str.ends_with('/') || is_local_directory {
+        let storage = storage_resolver.resolve(uri)?;
+        let paths = storage.list_files(Path::new("")).await?;
+        return Ok((storage, paths));
+    }
+    let (Some(parent_uri), Some(file_name)) = (uri.parent(), uri.file_name()) else {
+        bail!("URI `{uri}` does not designate a file.");
+    };
+    let storage = storage_resolver.resolve(&parent_uri)?;
+    let path = file_name.to_path_buf();
+
+    if !storage.exists(&path).await? {
+        bail!("File `{uri}` does not exist.");
+    }
+    Ok((storage, vec![path]))
+}
+
 pub struct FileSourceFactory;
 
 #[async_trait]
@@ -130,39 +427,70 @@ impl TypedSourceFactory for FileSourceFactory {
     type Source = FileSource;
     type Params = FileSourceParams;
 
-    // TODO handle checkpoint for files.
     async fn typed_create_source(
         ctx: Arc<SourceExecutionContext>,
         params: FileSourceParams,
         checkpoint: SourceCheckpoint,
     ) -> anyhow::Result<FileSource> {
-        let mut offset = 0;
-        let reader: Box<dyn AsyncRead + Send + Sync + Unpin> =
-            if let Some(filepath) = &params.filepath {
-                let mut file = File::open(&filepath).await.with_context(|| {
-                    format!("Failed to open source file `{}`.", filepath.display())
-                })?;
-                let partition_id = PartitionId::from(filepath.to_string_lossy().to_string());
-                if let Some(Position::Offset(offset_str)) =
-                    checkpoint.position_for_partition(&partition_id).cloned()
+        let source_id = ctx.source_config.source_id.clone();
+        let has_header = ctx.source_config.input_format == SourceInputFormat::Csv;
+
+        let Some(uri) = &params.filepath else {
+            // We cannot use the checkpoint.
+            let stdin_reader = FileReader {
+                partition_id_opt: None,
+                reader: BufReader::new(Box::new(tokio::io::stdin())),
+                is_compressed: false,
+                _temp_file_opt: None,
+            };
+            let file_source = FileSource {
+                source_id,
+                storage_opt: None,
+                pending_files: VecDeque::new(),
+                current_file_opt: Some(stdin_reader),
+                has_header,
+                counters: FileSourceCounters::default(),
+            };
+            return Ok(file_source);
+        };
+        let (storage, paths) = resolve_file_source_uri(uri).await?;
+        let num_files = paths.len();
+        let mut pending_files = VecDeque::with_capacity(num_files);
+
+        for path in paths {
+            let partition_id = file_partition_id(&*storage, &path);
+            let offset = match checkpoint.position_for_partition(&partition_id) {
+                Some(Position::Offset(offset_str))
+                    if offset_str.ends_with(END_OF_FILE_POSITION_SUFFIX) =>
                 {
-                    offset = offset_str.parse::<u64>()?;
-                    file.seek(SeekFrom::Start(offset)).await?;
+                    continue;
                 }
-                Box::new(file)
-            } else {
-                // We cannot use the checkpoint.
-                Box::new(tokio::io::stdin())
+                Some(Position::Offset(offset_str)) => offset_str.parse::<u64>()?,
+                _ => 0,
             };
+            // Files entirely indexed before the source was restarted are skipped.
+            if offset > 0 && is_fully_indexed(&*storage, &path, offset).await? {
+                continue;
+            }
+            pending_files.push_back(PendingFile {
+                path,
+                partition_id,
+                offset,
+            });
+        }
+        info!(
+            uri=%uri,
+            num_files=%num_files,
+            num_pending_files=%pending_files.len(),
+            "Resolved file source URI."
+        );
         let file_source = FileSource {
-            source_id: ctx.source_config.source_id.clone(),
-            counters: FileSourceCounters {
-                previous_offset: offset,
-                current_offset: offset,
-                num_lines_processed: 0,
-            },
-            reader: BufReader::new(reader),
-            params,
+            source_id,
+            storage_opt: Some(storage),
+            pending_files,
+            current_file_opt: None,
+            has_header,
+            counters: FileSourceCounters::default(),
         };
         Ok(file_source)
     }
@@ -172,14 +500,16 @@ impl TypedSourceFactory for FileSourceFactory {
 mod tests {
     use std::io::Write;
     use std::num::NonZeroUsize;
-    use std::path::PathBuf;
 
     use quickwit_actors::{Command, Universe};
-    use quickwit_config::{SourceConfig, SourceParams};
+    use quickwit_config::{SourceConfig, SourceParams, TransformConfig};
+    use quickwit_doc_mapper::{default_doc_mapper_for_test, DocMapper};
     use quickwit_metastore::checkpoint::{SourceCheckpoint, SourceCheckpointDelta};
     use quickwit_metastore::metastore_for_test;
+    use serde_json::Value as JsonValue;
 
     use super::*;
+    use crate::models::PreparedDocBatch;
     use crate::source::SourceActor;
 
     #[tokio::test]
@@ -201,6 +531,8 @@ mod tests {
                     enabled: true,
                     source_params: SourceParams::File(params.clone()),
                     transform_config: None,
+                    dead_letter_queue_enabled: false,
+                    input_format: SourceInputFormat::default(),
                 },
             ),
             params,
@@ -220,7 +552,8 @@ mod tests {
             serde_json::json!({
                 "previous_offset": 1030u64,
                 "current_offset": 1030u64,
-                "num_lines_processed": 4u32
+                "num_lines_processed": 4u32,
+                "num_files_processed": 1u32
             })
         );
         let batch = indexer_inbox.drain_for_test();
@@ -250,6 +583,8 @@ mod tests {
             .filepath
             .as_ref()
             .unwrap()
+            .filepath()
+            .unwrap()
             .to_string_lossy()
             .to_string();
 
@@ -266,6 +601,8 @@ mod tests {
                     enabled: true,
                     source_params: SourceParams::File(params.clone()),
                     transform_config: None,
+                    dead_letter_queue_enabled: false,
+                    input_format: SourceInputFormat::default(),
                 },
             ),
             params,
@@ -285,7 +622,8 @@ mod tests {
             serde_json::json!({
                 "previous_offset": 700_000u64,
                 "current_offset": 700_000u64,
-                "num_lines_processed": 20_000u64
+                "num_lines_processed": 20_000u64,
+                "num_files_processed": 1u64
             })
         );
         let indexer_msgs = doc_processor_inbox.drain_for_test();
@@ -355,6 +693,8 @@ mod tests {
                     enabled: true,
                     source_params: SourceParams::File(params.clone()),
                     transform_config: None,
+                    dead_letter_queue_enabled: false,
+                    input_format: SourceInputFormat::default(),
                 },
             ),
             params,
@@ -375,10 +715,456 @@ mod tests {
             serde_json::json!({
                 "previous_offset": 290u64,
                 "current_offset": 290u64,
-                "num_lines_processed": 98u64
+                "num_lines_processed": 98u64,
+                "num_files_processed": 1u64
             })
         );
         let indexer_messages: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
         assert!(&indexer_messages[0].docs[0].starts_with(b"2\n"));
     }
+
+    #[tokio::test]
+    async fn test_file_source_resume_csv_file_resends_header() {
+        let universe = Universe::with_accelerated_time();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file
+            .write_all(b"name,age\nalice,42\nbob,37\n")
+            .unwrap();
+        temp_file.flush().unwrap();
+        let temp_file_path = temp_file.path().canonicalize().unwrap();
+        let params = FileSourceParams::file(&temp_file_path);
+        let mut checkpoint = SourceCheckpoint::default();
+        let partition_id = PartitionId::from(temp_file_path.to_string_lossy().to_string());
+        let checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            partition_id.clone(),
+            Position::from(0u64),
+            Position::from(18u64),
+        )
+        .unwrap();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+
+        let mut source_config = file_source_config_for_test(params.clone());
+        source_config.input_format = SourceInputFormat::Csv;
+
+        let metastore = metastore_for_test();
+        let source = FileSourceFactory::typed_create_source(
+            SourceExecutionContext::for_test(
+                metastore,
+                "test-index",
+                PathBuf::from("./queues"),
+                source_config,
+            ),
+            params,
+            checkpoint,
+        )
+        .await
+        .unwrap();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            doc_processor_mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_builder().spawn(file_source_actor);
+        let (actor_termination, _counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+
+        let indexer_messages: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert_eq!(indexer_messages.len(), 1);
+        assert_eq!(
+            &indexer_messages[0].docs[..],
+            &[Bytes::from("name,age\n"), Bytes::from("bob,37\n")]
+        );
+        let expected_checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            partition_id,
+            Position::from(18u64),
+            Position::from(25u64),
+        )
+        .unwrap();
+        assert_eq!(
+            indexer_messages[0].checkpoint_delta,
+            expected_checkpoint_delta
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_source_csv_files_with_different_headers() {
+        let universe = Universe::with_accelerated_time();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp_dir.path().join("01.csv"),
+            "body,timestamp\nhappy,1628837062\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("02.csv"),
+            "timestamp,body\n1628837063,sad\n",
+        )
+        .unwrap();
+        let params = FileSourceParams::file(temp_dir.path());
+        let mut source_config = file_source_config_for_test(params.clone());
+        source_config.input_format = SourceInputFormat::Csv;
+
+        let doc_processor = DocProcessor::try_new(
+            "test-index".to_string(),
+            "test-file-source".to_string(),
+            Arc::new(default_doc_mapper_for_test()),
+            indexer_mailbox,
+            SourceInputFormat::Csv,
+            Some(TransformConfig::for_test(
+                ".timestamp = to_int!(.timestamp)",
+            )),
+            None,
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        let source = FileSourceFactory::typed_create_source(
+            SourceExecutionContext::for_test(
+                metastore_for_test(),
+                "test-index",
+                PathBuf::from("./queues"),
+                source_config,
+            ),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await
+        .unwrap();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            doc_processor_mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_builder().spawn(file_source_actor);
+        let (actor_termination, _counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        doc_processor_handle.join().await;
+
+        let doc_mapper = default_doc_mapper_for_test();
+        let schema = doc_mapper.schema();
+        let docs: Vec<JsonValue> = indexer_inbox
+            .drain_for_test_typed::<PreparedDocBatch>()
+            .into_iter()
+            .flat_map(|batch| batch.docs)
+            .map(|prepared_doc| {
+                let named_doc = schema.to_named_doc(&prepared_doc.doc);
+                JsonValue::Object(doc_mapper.doc_to_json(named_doc.0).unwrap())
+            })
+            .collect();
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0]["body"], "happy");
+        assert_eq!(docs[0]["timestamp"], 1628837062);
+        assert_eq!(docs[1]["body"], "sad");
+        assert_eq!(docs[1]["timestamp"], 1628837063);
+        universe.assert_quit().await;
+    }
+
+    fn file_source_config_for_test(params: FileSourceParams) -> SourceConfig {
+        SourceConfig {
+            source_id: "test-file-source".to_string(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::File(params),
+            transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_source_glob_on_object_storage() {
+        let universe = Universe::with_accelerated_time();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let storage = quickwit_storage_uri_resolver()
+            .resolve(&Uri::for_test("ram:///test-file-source-glob"))
+            .unwrap();
+        let files: [(&str, &[u8]); 4] = [
+            ("logs-01.json", b"{\"body\": \"a\"}\n{\"body\": \"b\"}\n"),
+            ("logs-02.json", b"{\"body\": \"c\"}\n"),
+            ("archive/logs-03.json", b"{\"body\": \"d\"}\n"),
+            ("metrics-01.json", b"{\"body\": \"e\"}\n"),
+        ];
+        for (path, content) in files {
+            storage
+                .put(Path::new(path), Box::new(content.to_vec()))
+                .await
+                .unwrap();
+        }
+        let params =
+            FileSourceParams::from_uri(Uri::for_test("ram:///test-file-source-glob/logs-*.json"));
+
+        let metastore = metastore_for_test();
+        let source = FileSourceFactory::typed_create_source(
+            SourceExecutionContext::for_test(
+                metastore,
+                "test-index",
+                PathBuf::from("./queues"),
+                file_source_config_for_test(params.clone()),
+            ),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await
+        .unwrap();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            doc_processor_mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_builder().spawn(file_source_actor);
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": 14u64,
+                "current_offset": 14u64,
+                "num_lines_processed": 3u64,
+                "num_files_processed": 2u64
+            })
+        );
+        let batches: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].docs.len(), 3);
+
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint
+            .try_apply_delta(batches[0].checkpoint_delta.clone())
+            .unwrap();
+        assert_eq!(
+            checkpoint.position_for_partition(&PartitionId::from(
+                "ram:///test-file-source-glob/logs-01.json"
+            )),
+            Some(&Position::from(28u64))
+        );
+        assert_eq!(
+            checkpoint.position_for_partition(&PartitionId::from(
+                "ram:///test-file-source-glob/logs-02.json"
+            )),
+            Some(&Position::from(14u64))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_source_resume_directory_backfill_from_checkpoint() {
+        let universe = Universe::with_accelerated_time();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("file-1.json"), "a1\na2\n").unwrap();
+        std::fs::write(temp_dir.path().join("file-2.json"), "b1\nb2\nb3\n").unwrap();
+        std::fs::write(temp_dir.path().join("file-3.json"), "c1\n").unwrap();
+
+        let params = FileSourceParams::file(temp_dir.path());
+        let mut checkpoint = SourceCheckpoint::default();
+
+        for (file_name, offset) in [("file-1.json", 6u64), ("file-2.json", 3u64)] {
+            let partition_id = PartitionId::from(
+                temp_dir
+                    .path()
+                    .join(file_name)
+                    .to_string_lossy()
+                    .to_string(),
+            );
+            let checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+                partition_id,
+                Position::from(0u64),
+                Position::from(offset),
+            )
+            .unwrap();
+            checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+        }
+        let metastore = metastore_for_test();
+        let source = FileSourceFactory::typed_create_source(
+            SourceExecutionContext::for_test(
+                metastore,
+                "test-index",
+                PathBuf::from("./queues"),
+                file_source_config_for_test(params.clone()),
+            ),
+            params,
+            checkpoint,
+        )
+        .await
+        .unwrap();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            doc_processor_mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_builder().spawn(file_source_actor);
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": 3u64,
+                "current_offset": 3u64,
+                "num_lines_processed": 3u64,
+                "num_files_processed": 2u64
+            })
+        );
+        let batches: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(
+            batches[0].docs,
+            [
+                Bytes::from_static(b"b2\n"),
+                Bytes::from_static(b"b3\n"),
+                Bytes::from_static(b"c1\n"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_file_source_resume_compressed_file_from_checkpoint() {
+        use async_compression::tokio::bufread::GzipEncoder;
+
+        let universe = Universe::with_accelerated_time();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let filepath = temp_dir.path().join("logs.ndjson.gz");
+        let mut compressed = Vec::new();
+        GzipEncoder::new(&b"a1\na2\na3\n"[..])
+            .read_to_end(&mut compressed)
+            .await
+            .unwrap();
+        std::fs::write(&filepath, compressed).unwrap();
+
+        let params = FileSourceParams::file(&filepath);
+        let mut checkpoint = SourceCheckpoint::default();
+        let partition_id = PartitionId::from(filepath.to_string_lossy().to_string());
+        let checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            partition_id.clone(),
+            Position::from(0u64),
+            Position::from(3u64),
+        )
+        .unwrap();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+
+        let metastore = metastore_for_test();
+        let source = FileSourceFactory::typed_create_source(
+            SourceExecutionContext::for_test(
+                metastore.clone(),
+                "test-index",
+                PathBuf::from("./queues"),
+                file_source_config_for_test(params.clone()),
+            ),
+            params.clone(),
+            checkpoint.clone(),
+        )
+        .await
+        .unwrap();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            doc_processor_mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_builder().spawn(file_source_actor);
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": 9u64,
+                "current_offset": 9u64,
+                "num_lines_processed": 2u64,
+                "num_files_processed": 1u64
+            })
+        );
+        let batches: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(
+            batches[0].docs,
+            [Bytes::from_static(b"a2\n"), Bytes::from_static(b"a3\n")]
+        );
+        // The end of the compressed file is recorded in the checkpoint.
+        assert_eq!(
+            &extract_position_delta(&batches[0].checkpoint_delta).unwrap(),
+            "00000000000000000003..00000000000000000009:eof"
+        );
+        checkpoint
+            .try_apply_delta(batches[0].checkpoint_delta.clone())
+            .unwrap();
+
+        // Once fully indexed, the compressed file is skipped on restart.
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let source = FileSourceFactory::typed_create_source(
+            SourceExecutionContext::for_test(
+                metastore,
+                "test-index",
+                PathBuf::from("./queues"),
+                file_source_config_for_test(params.clone()),
+            ),
+            params,
+            checkpoint,
+        )
+        .await
+        .unwrap();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            doc_processor_mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_builder().spawn(file_source_actor);
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(counters["num_files_processed"], 0);
+        let batches: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert!(batches.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_file_source_uri() {
+        let storage = quickwit_storage_uri_resolver()
+            .resolve(&Uri::for_test("ram:///test-resolve-file-source-uri"))
+            .unwrap();
+        for path in [
+            "2023/01.json",
+            "2023/02.json",
+            "2023/03.csv",
+            "2024/01.json",
+        ] {
+            storage
+                .put(Path::new(path), Box::new(b"{}\n".to_vec()))
+                .await
+                .unwrap();
+        }
+        {
+            let uri = Uri::for_test("ram:///test-resolve-file-source-uri/2023/02.json");
+            let (_storage, paths) = resolve_file_source_uri(&uri).await.unwrap();
+            assert_eq!(paths, [PathBuf::from("02.json")]);
+        }
+        {
+            let uri = Uri::for_test("ram:///test-resolve-file-source-uri/2023/");
+            let (_storage, paths) = resolve_file_source_uri(&uri).await.unwrap();
+            assert_eq!(
+                paths,
+                [
+                    PathBuf::from("01.json"),
+                    PathBuf::from("02.json"),
+                    PathBuf::from("03.csv")
+                ]
+            );
+        }
+        {
+            let uri = Uri::for_test("ram:///test-resolve-file-source-uri/*/01.json");
+            let (_storage, paths) = resolve_file_source_uri(&uri).await.unwrap();
+            assert_eq!(
+                paths,
+                [PathBuf::from("2023/01.json"), PathBuf::from("2024/01.json")]
+            );
+        }
+        {
+            let uri = Uri::for_test("ram:///test-resolve-file-source-uri/2023/*.json");
+            let (_storage, paths) = resolve_file_source_uri(&uri).await.unwrap();
+            assert_eq!(paths, [PathBuf::from("01.json"), PathBuf::from("02.json")]);
+        }
+        {
+            let uri = Uri::for_test("ram:///test-resolve-file-source-uri/2025/01.json");
+            resolve_file_source_uri(&uri).await.unwrap_err();
+        }
+    }
 }
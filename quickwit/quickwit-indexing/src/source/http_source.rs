@@ -0,0 +1,788 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use bytes::Bytes;
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_config::HttpSourceParams;
+use quickwit_metastore::checkpoint::{
+    PartitionId, Position, SourceCheckpoint, SourceCheckpointDelta,
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::{json, Value as JsonValue};
+use tracing::{debug, info, warn};
+
+use crate::actors::DocProcessor;
+use crate::models::RawDocBatch;
+use crate::source::{Source, SourceContext, SourceExecutionContext, TypedSourceFactory};
+
+/// Timeout of a single HTTP request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct HttpSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for HttpSourceFactory {
+    type Source = HttpSource;
+    type Params = HttpSourceParams;
+
+    async fn typed_create_source(
+        ctx: Arc<SourceExecutionContext>,
+        params: HttpSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self::Source> {
+        HttpSource::try_new(ctx, params, checkpoint)
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct HttpSourceState {
+    /// Number of requests sent by the source.
+    pub num_requests: u64,
+    /// Number of requests that failed or returned an unexpected body.
+    pub num_failed_requests: u64,
+    /// Number of bytes processed by the source.
+    pub num_bytes_processed: u64,
+    /// Number of records processed by the source.
+    pub num_records_processed: u64,
+}
+
+/// Polls a JSON API and follows its cursor-based pagination.
+///
+/// The URL is tracked as a single partition whose position holds the cursor of the next page to
+/// request. When the API does not return a next cursor, the source keeps polling the last page
+/// and skips the records it already indexed.
+pub struct HttpSource {
+    ctx: Arc<SourceExecutionContext>,
+    params: HttpSourceParams,
+    client: reqwest::Client,
+    partition_id: PartitionId,
+    records_path: Vec<JsonPathSegment>,
+    cursor_path_opt: Option<Vec<JsonPathSegment>>,
+    page_cursor: PageCursor,
+    current_position: Position,
+    num_consecutive_failures: u32,
+    state: HttpSourceState,
+}
+
+impl HttpSource {
+    pub fn try_new(
+        ctx: Arc<SourceExecutionContext>,
+        params: HttpSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self> {
+        info!(
+            index_id=%ctx.index_id,
+            source_id=%ctx.source_config.source_id,
+            url=%params.url,
+            "Create HTTP source."
+        );
+        let client = build_client(&params)?;
+        let records_path = parse_json_path(&params.records_path)?;
+        let cursor_path_opt = params
+            .cursor_path
+            .as_deref()
+            .map(parse_json_path)
+            .transpose()?;
+        let partition_id = PartitionId::from(params.url.as_str());
+        let current_position = checkpoint
+            .position_for_partition(&partition_id)
+            .cloned()
+            .unwrap_or(Position::Beginning);
+        let page_cursor = PageCursor::from_position(&current_position)?;
+
+        Ok(Self {
+            ctx,
+            params,
+            client,
+            partition_id,
+            records_path,
+            cursor_path_opt,
+            page_cursor,
+            current_position,
+            num_consecutive_failures: 0,
+            state: HttpSourceState::default(),
+        })
+    }
+
+    async fn fetch_page(&self) -> anyhow::Result<JsonValue> {
+        let mut request = self.client.get(&self.params.url);
+
+        if let Some(cursor) = &self.page_cursor.cursor_opt {
+            request = request.query(&[(self.params.cursor_param.as_str(), cursor.as_str())]);
+        }
+        let page = request
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<JsonValue>()
+            .await
+            .context("Failed to parse response body as JSON.")?;
+        Ok(page)
+    }
+
+    /// Extracts the records not indexed yet and the cursor of the page to request next.
+    fn process_page(&self, page: &JsonValue) -> anyhow::Result<(Vec<Bytes>, PageCursor)> {
+        let records = resolve_json_path(page, &self.records_path)
+            .and_then(JsonValue::as_array)
+            .with_context(|| {
+                format!(
+                    "Response body has no array of records at `{}`.",
+                    self.params.records_path
+                )
+            })?;
+        let docs: Vec<Bytes> = records
+            .iter()
+            .skip(self.page_cursor.num_consumed_records as usize)
+            .map(record_to_doc)
+            .collect();
+        // Without a cursor path, the URL is polled as a single page.
+        let next_cursor_opt = self
+            .cursor_path_opt
+            .as_ref()
+            .and_then(|cursor_path| resolve_json_path(page, cursor_path))
+            .and_then(cursor_from_json);
+
+        let next_page_cursor = match next_cursor_opt {
+            Some(next_cursor) if self.page_cursor.cursor_opt.as_ref() != Some(&next_cursor) => {
+                PageCursor {
+                    sequence: self.page_cursor.sequence + 1,
+                    num_consumed_records: 0,
+                    cursor_opt: Some(next_cursor),
+                }
+            }
+            _ => PageCursor {
+                sequence: self.page_cursor.sequence + 1,
+                num_consumed_records: self
+                    .page_cursor
+                    .num_consumed_records
+                    .max(records.len() as u64),
+                cursor_opt: self.page_cursor.cursor_opt.clone(),
+            },
+        };
+        Ok((docs, next_page_cursor))
+    }
+
+    fn backoff_delay(&self) -> Duration {
+        let backoff_secs = 1u64
+            .checked_shl(self.num_consecutive_failures)
+            .unwrap_or(u64::MAX)
+            .min(self.params.max_backoff_secs);
+        Duration::from_secs(backoff_secs)
+    }
+}
+
+#[async_trait]
+impl Source for HttpSource {
+    async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        self.state.num_requests += 1;
+
+        let page_result = ctx.protect_future(self.fetch_page()).await;
+        ctx.record_progress();
+
+        let (docs, next_page_cursor) = match page_result.and_then(|page| self.process_page(&page)) {
+            Ok(page_output) => page_output,
+            Err(error) => {
+                let backoff_delay = self.backoff_delay();
+                self.state.num_failed_requests += 1;
+                self.num_consecutive_failures += 1;
+                warn!(url=%self.params.url, error=?error, backoff_delay=?backoff_delay, "Failed to poll HTTP source.");
+                return Ok(backoff_delay);
+            }
+        };
+        self.num_consecutive_failures = 0;
+
+        let has_next_page = next_page_cursor.cursor_opt != self.page_cursor.cursor_opt;
+
+        if docs.is_empty() && !has_next_page {
+            return Ok(Duration::from_secs(self.params.poll_interval_secs));
+        }
+        let next_position = next_page_cursor.to_position();
+        let mut checkpoint_delta = SourceCheckpointDelta::default();
+        checkpoint_delta
+            .record_partition_delta(
+                self.partition_id.clone(),
+                self.current_position.clone(),
+                next_position.clone(),
+            )
+            .context("Failed to record partition delta.")?;
+
+        let num_bytes: u64 = docs.iter().map(|doc| doc.len() as u64).sum();
+        self.state.num_bytes_processed += num_bytes;
+        self.state.num_records_processed += docs.len() as u64;
+
+        debug!(
+            num_docs=%docs.len(),
+            num_bytes=%num_bytes,
+            "Sending doc batch to indexer.");
+        let batch = RawDocBatch {
+            docs,
+            checkpoint_delta,
+            force_commit: false,
+        };
+        ctx.send_message(doc_processor_mailbox, batch).await?;
+
+        self.page_cursor = next_page_cursor;
+        self.current_position = next_position;
+
+        if has_next_page {
+            return Ok(Duration::from_millis(
+                self.params.min_request_interval_millis,
+            ));
+        }
+        Ok(Duration::from_secs(self.params.poll_interval_secs))
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "HttpSource{{source_id={}}}",
+            self.ctx.source_config.source_id
+        )
+    }
+
+    fn observable_state(&self) -> JsonValue {
+        json!({
+            "index_id": self.ctx.index_id,
+            "source_id": self.ctx.source_config.source_id,
+            "url": self.params.url,
+            "cursor": self.page_cursor.cursor_opt,
+            "num_requests": self.state.num_requests,
+            "num_failed_requests": self.state.num_failed_requests,
+            "num_bytes_processed": self.state.num_bytes_processed,
+            "num_records_processed": self.state.num_records_processed,
+        })
+    }
+}
+
+/// Cursor of the page to request next, as recorded in the checkpoint.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct PageCursor {
+    /// Incremented for every batch so that positions are strictly increasing, even though
+    /// cursors are opaque.
+    sequence: u64,
+    /// Number of records of the page that were already indexed.
+    num_consumed_records: u64,
+    cursor_opt: Option<String>,
+}
+
+impl PageCursor {
+    fn from_position(position: &Position) -> anyhow::Result<Self> {
+        let Position::Offset(offset_str) = position else {
+            return Ok(Self::default());
+        };
+        let mut parts = offset_str.splitn(3, ':');
+        let (Some(sequence_str), Some(num_consumed_records_str), Some(cursor)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            bail!("Failed to parse HTTP source position `{offset_str}`.");
+        };
+        let sequence = sequence_str
+            .parse::<u64>()
+            .with_context(|| format!("Failed to parse HTTP source position `{offset_str}`."))?;
+        let num_consumed_records = num_consumed_records_str
+            .parse::<u64>()
+            .with_context(|| format!("Failed to parse HTTP source position `{offset_str}`."))?;
+        let cursor_opt = if cursor.is_empty() {
+            None
+        } else {
+            Some(cursor.to_string())
+        };
+        Ok(Self {
+            sequence,
+            num_consumed_records,
+            cursor_opt,
+        })
+    }
+
+    fn to_position(&self) -> Position {
+        Position::from(format!(
+            "{:0>20}:{:0>20}:{}",
+            self.sequence,
+            self.num_consumed_records,
+            self.cursor_opt.as_deref().unwrap_or_default()
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum JsonPathSegment {
+    Field(String),
+    Index(usize),
+}
+
+/// Parses the subset of JSONPath made of field names and array indices, such as
+/// `$.data.items` or `$.pages[0].records`.
+fn parse_json_path(path: &str) -> anyhow::Result<Vec<JsonPathSegment>> {
+    let Some(mut remaining) = path.strip_prefix('$') else {
+        bail!("Invalid JSONPath `{path}`: it must start with `$`.");
+    };
+    let mut segments = Vec::new();
+
+    while !remaining.is_empty() {
+        if let Some(after_dot) = remaining.strip_prefix('.') {
+            let field_len = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+            if field_len == 0 {
+                bail!("Invalid JSONPath `{path}`: empty field name.");
+            }
+            segments.push(JsonPathSegment::Field(after_dot[..field_len].to_string()));
+            remaining = &after_dot[field_len..];
+        } else if let Some(after_bracket) = remaining.strip_prefix('[') {
+            let Some((index_str, after_index)) = after_bracket.split_once(']') else {
+                bail!("Invalid JSONPath `{path}`: unclosed bracket.");
+            };
+            let index = index_str
+                .parse::<usize>()
+                .with_context(|| format!("Invalid JSONPath `{path}`: invalid array index."))?;
+            segments.push(JsonPathSegment::Index(index));
+            remaining = after_index;
+        } else {
+            bail!("Invalid JSONPath `{path}`: expected `.` or `[`.");
+        }
+    }
+    Ok(segments)
+}
+
+fn resolve_json_path<'a>(
+    value: &'a JsonValue,
+    segments: &[JsonPathSegment],
+) -> Option<&'a JsonValue> {
+    segments
+        .iter()
+        .try_fold(value, |value, segment| match segment {
+            JsonPathSegment::Field(field) => value.get(field),
+            JsonPathSegment::Index(index) => value.get(index),
+        })
+}
+
+fn cursor_from_json(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::String(cursor) if !cursor.is_empty() => Some(cursor.clone()),
+        JsonValue::Number(cursor) => Some(cursor.to_string()),
+        _ => None,
+    }
+}
+
+fn record_to_doc(record: &JsonValue) -> Bytes {
+    Bytes::from(record.to_string())
+}
+
+fn build_client(params: &HttpSourceParams) -> anyhow::Result<reqwest::Client> {
+    let mut headers = HeaderMap::with_capacity(params.headers.len());
+
+    for (name, value) in &params.headers {
+        let header_name = HeaderName::try_from(name.as_str())
+            .with_context(|| format!("Invalid HTTP header name `{name}`."))?;
+        let mut header_value = HeaderValue::try_from(value.as_str())
+            .with_context(|| format!("Invalid value for HTTP header `{name}`."))?;
+        header_value.set_sensitive(true);
+        headers.insert(header_name, header_value);
+    }
+    let client = reqwest::Client::builder()
+        .default_headers(headers)
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+    Ok(client)
+}
+
+/// Checks whether the URL can be polled with the configured headers.
+pub(crate) async fn check_connectivity(params: &HttpSourceParams) -> anyhow::Result<()> {
+    let client = build_client(params)?;
+    client.get(&params.url).send().await?.error_for_status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+
+    use quickwit_actors::{ActorHandle, Health, Inbox, Supervisable, Universe, HEARTBEAT};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_metastore::metastore_for_test;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::source::{quickwit_supported_sources, SourceActor};
+
+    #[test]
+    fn test_parse_json_path() {
+        assert!(parse_json_path("$").unwrap().is_empty());
+        assert_eq!(
+            parse_json_path("$.data[1].items").unwrap(),
+            vec![
+                JsonPathSegment::Field("data".to_string()),
+                JsonPathSegment::Index(1),
+                JsonPathSegment::Field("items".to_string()),
+            ]
+        );
+        parse_json_path("data.items").unwrap_err();
+        parse_json_path("$..items").unwrap_err();
+        parse_json_path("$.data[one]").unwrap_err();
+        parse_json_path("$.data[1").unwrap_err();
+    }
+
+    #[test]
+    fn test_resolve_json_path() {
+        let page = json!({"data": [{"items": [1, 2]}], "next": 42});
+        let items_path = parse_json_path("$.data[0].items").unwrap();
+        assert_eq!(
+            resolve_json_path(&page, &items_path).unwrap(),
+            &json!([1, 2])
+        );
+        let missing_path = parse_json_path("$.data[1].items").unwrap();
+        assert!(resolve_json_path(&page, &missing_path).is_none());
+
+        let next_path = parse_json_path("$.next").unwrap();
+        assert_eq!(
+            resolve_json_path(&page, &next_path).and_then(cursor_from_json),
+            Some("42".to_string())
+        );
+    }
+
+    #[test]
+    fn test_page_cursor_position_round_trip() {
+        assert_eq!(
+            PageCursor::from_position(&Position::Beginning).unwrap(),
+            PageCursor::default()
+        );
+        let page_cursor = PageCursor {
+            sequence: 3,
+            num_consumed_records: 10,
+            cursor_opt: Some("abc:def".to_string()),
+        };
+        let position = page_cursor.to_position();
+        assert_eq!(PageCursor::from_position(&position).unwrap(), page_cursor);
+
+        let next_page_cursor = PageCursor {
+            sequence: 4,
+            num_consumed_records: 0,
+            cursor_opt: None,
+        };
+        assert!(position < next_page_cursor.to_position());
+        assert_eq!(
+            PageCursor::from_position(&next_page_cursor.to_position()).unwrap(),
+            next_page_cursor
+        );
+        PageCursor::from_position(&Position::from("not-a-cursor".to_string())).unwrap_err();
+    }
+
+    fn get_source_config(url: String) -> SourceConfig {
+        SourceConfig {
+            source_id: "test-http-source".to_string(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Http(HttpSourceParams {
+                url,
+                headers: BTreeMap::from([(
+                    "Authorization".to_string(),
+                    "Bearer my-token".to_string(),
+                )]),
+                records_path: "$.data".to_string(),
+                cursor_path: Some("$.next_cursor".to_string()),
+                cursor_param: "cursor".to_string(),
+                poll_interval_secs: 60,
+                min_request_interval_millis: 0,
+                max_backoff_secs: 300,
+            }),
+            transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
+        }
+    }
+
+    async fn create_source(
+        universe: &Universe,
+        source_config: SourceConfig,
+        start_checkpoint: SourceCheckpoint,
+    ) -> (ActorHandle<SourceActor>, Inbox<DocProcessor>) {
+        let ctx = SourceExecutionContext::for_test(
+            metastore_for_test(),
+            "test-http-source-index",
+            PathBuf::from("./queues"),
+            source_config,
+        );
+        let source = quickwit_supported_sources()
+            .load_source(ctx, start_checkpoint)
+            .await
+            .unwrap();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let source_actor = SourceActor {
+            source,
+            doc_processor_mailbox,
+        };
+        let (_source_mailbox, source_handle) = universe.spawn_builder().spawn(source_actor);
+        (source_handle, doc_processor_inbox)
+    }
+
+    async fn wait_for_num_requests(source_handle: &ActorHandle<SourceActor>, num_requests: u64) {
+        loop {
+            let observation = source_handle.observe().await;
+            if observation.state["num_requests"].as_u64().unwrap() >= num_requests {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    fn merge_doc_batches(batches: Vec<RawDocBatch>) -> RawDocBatch {
+        let mut merged_batch = RawDocBatch::default();
+        for batch in batches {
+            merged_batch.docs.extend(batch.docs);
+            merged_batch
+                .checkpoint_delta
+                .extend(batch.checkpoint_delta)
+                .unwrap();
+        }
+        merged_batch
+    }
+
+    #[tokio::test]
+    async fn test_http_source_follows_pagination() {
+        let mock_server = MockServer::start().await;
+        // Mocks are matched in the order they were mounted.
+        Mock::given(method("GET"))
+            .and(path("/audit-logs"))
+            .and(query_param("cursor", "page-2"))
+            .and(header("Authorization", "Bearer my-token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"data": [{"event": "logout"}], "next_cursor": null})),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/audit-logs"))
+            .and(header("Authorization", "Bearer my-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{"event": "login"}, {"event": "read"}],
+                "next_cursor": "page-2"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let universe = Universe::with_accelerated_time();
+        let source_config = get_source_config(format!("{}/audit-logs", mock_server.uri()));
+        let partition_id = PartitionId::from(format!("{}/audit-logs", mock_server.uri()));
+        let (source_handle, doc_processor_inbox) =
+            create_source(&universe, source_config, SourceCheckpoint::default()).await;
+        // The third request polls the last page again and finds no new records.
+        wait_for_num_requests(&source_handle, 3).await;
+
+        let batch = merge_doc_batches(doc_processor_inbox.drain_for_test_typed());
+        assert_eq!(
+            batch.docs,
+            vec![
+                Bytes::from(r#"{"event":"login"}"#),
+                Bytes::from(r#"{"event":"read"}"#),
+                Bytes::from(r#"{"event":"logout"}"#),
+            ]
+        );
+        let expected_position = PageCursor {
+            sequence: 2,
+            num_consumed_records: 1,
+            cursor_opt: Some("page-2".to_string()),
+        }
+        .to_position();
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::default();
+        expected_checkpoint_delta
+            .record_partition_delta(partition_id, Position::Beginning, expected_position)
+            .unwrap();
+        assert_eq!(batch.checkpoint_delta, expected_checkpoint_delta);
+
+        let observation = source_handle.observe().await;
+        assert_eq!(observation.state["num_records_processed"], 3);
+        assert_eq!(observation.state["cursor"], "page-2");
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_http_source_resumes_from_checkpoint() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/audit-logs"))
+            .and(query_param("cursor", "page-2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{"event": "logout"}, {"event": "delete"}],
+                "next_cursor": "page-2"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let universe = Universe::with_accelerated_time();
+        let source_config = get_source_config(format!("{}/audit-logs", mock_server.uri()));
+        let partition_id = PartitionId::from(format!("{}/audit-logs", mock_server.uri()));
+        let start_position = PageCursor {
+            sequence: 2,
+            num_consumed_records: 1,
+            cursor_opt: Some("page-2".to_string()),
+        }
+        .to_position();
+        let mut checkpoint_delta = SourceCheckpointDelta::default();
+        checkpoint_delta
+            .record_partition_delta(
+                partition_id.clone(),
+                Position::Beginning,
+                start_position.clone(),
+            )
+            .unwrap();
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+
+        let (source_handle, doc_processor_inbox) =
+            create_source(&universe, source_config, checkpoint).await;
+        wait_for_num_requests(&source_handle, 2).await;
+
+        let batch = merge_doc_batches(doc_processor_inbox.drain_for_test_typed());
+        assert_eq!(batch.docs, vec![Bytes::from(r#"{"event":"delete"}"#)]);
+
+        let expected_position = PageCursor {
+            sequence: 3,
+            num_consumed_records: 2,
+            cursor_opt: Some("page-2".to_string()),
+        }
+        .to_position();
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::default();
+        expected_checkpoint_delta
+            .record_partition_delta(partition_id, start_position, expected_position)
+            .unwrap();
+        assert_eq!(batch.checkpoint_delta, expected_checkpoint_delta);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_http_source_without_cursor_path_skips_indexed_records() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/audit-logs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{"event": "login"}, {"event": "read"}]
+            })))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/audit-logs"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{"event": "login"}, {"event": "read"}, {"event": "logout"}]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let universe = Universe::with_accelerated_time();
+        let mut source_config = get_source_config(format!("{}/audit-logs", mock_server.uri()));
+        if let SourceParams::Http(params) = &mut source_config.source_params {
+            params.cursor_path = None;
+        }
+        let partition_id = PartitionId::from(format!("{}/audit-logs", mock_server.uri()));
+        let (source_handle, doc_processor_inbox) =
+            create_source(&universe, source_config, SourceCheckpoint::default()).await;
+        // The third request finds no new records.
+        wait_for_num_requests(&source_handle, 3).await;
+
+        let batch = merge_doc_batches(doc_processor_inbox.drain_for_test_typed());
+        assert_eq!(
+            batch.docs,
+            vec![
+                Bytes::from(r#"{"event":"login"}"#),
+                Bytes::from(r#"{"event":"read"}"#),
+                Bytes::from(r#"{"event":"logout"}"#),
+            ]
+        );
+        let expected_position = PageCursor {
+            sequence: 2,
+            num_consumed_records: 3,
+            cursor_opt: None,
+        }
+        .to_position();
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::default();
+        expected_checkpoint_delta
+            .record_partition_delta(partition_id, Position::Beginning, expected_position)
+            .unwrap();
+        assert_eq!(batch.checkpoint_delta, expected_checkpoint_delta);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_http_source_backs_off_on_failure() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/audit-logs"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+
+        let universe = Universe::with_accelerated_time();
+        let source_config = get_source_config(format!("{}/audit-logs", mock_server.uri()));
+        let (source_handle, doc_processor_inbox) =
+            create_source(&universe, source_config, SourceCheckpoint::default()).await;
+        wait_for_num_requests(&source_handle, 3).await;
+
+        let observation = source_handle.observe().await;
+        assert_eq!(
+            observation.state["num_failed_requests"],
+            observation.state["num_requests"]
+        );
+        assert!(doc_processor_inbox
+            .drain_for_test_typed::<RawDocBatch>()
+            .is_empty());
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_http_source_stays_healthy_during_slow_requests() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/audit-logs"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"data": [{"event": "login"}], "next_cursor": null}))
+                    .set_delay(HEARTBEAT * 3),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let universe = Universe::with_accelerated_time();
+        let source_config = get_source_config(format!("{}/audit-logs", mock_server.uri()));
+        let (source_handle, doc_processor_inbox) =
+            create_source(&universe, source_config, SourceCheckpoint::default()).await;
+        wait_for_num_requests(&source_handle, 1).await;
+
+        // The supervisor harvests the health of the source once per heartbeat while the first
+        // response is delayed.
+        for _ in 0..2 {
+            tokio::time::sleep(HEARTBEAT).await;
+            assert_eq!(source_handle.harvest_health(), Health::Healthy);
+        }
+        wait_for_num_requests(&source_handle, 2).await;
+
+        let batch = merge_doc_batches(doc_processor_inbox.drain_for_test_typed());
+        assert_eq!(batch.docs, vec![Bytes::from(r#"{"event":"login"}"#)]);
+        universe.assert_quit().await;
+    }
+}
@@ -28,7 +28,7 @@ use bytes::Bytes;
 use itertools::Itertools;
 use oneshot;
 use quickwit_actors::{ActorExitStatus, Mailbox};
-use quickwit_config::KafkaSourceParams;
+use quickwit_config::{KafkaMetadataFields, KafkaSourceParams};
 use quickwit_metastore::checkpoint::{
     PartitionId, Position, SourceCheckpoint, SourceCheckpointDelta,
 };
@@ -37,10 +37,10 @@ use rdkafka::consumer::{
     BaseConsumer, Consumer, ConsumerContext, DefaultConsumerContext, Rebalance,
 };
 use rdkafka::error::KafkaError;
-use rdkafka::message::BorrowedMessage;
+use rdkafka::message::{BorrowedMessage, Headers};
 use rdkafka::util::Timeout;
 use rdkafka::{ClientContext, Message, Offset, TopicPartitionList};
-use serde_json::{json, Value as JsonValue};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
 use tokio::sync::mpsc;
 use tokio::task::{spawn_blocking, JoinHandle};
 use tokio::time;
@@ -48,6 +48,7 @@ use tracing::{debug, info, warn};
 
 use crate::actors::DocProcessor;
 use crate::models::{NewPublishLock, PublishLock, RawDocBatch};
+use crate::source::schema_registry::{DecodeError, PayloadDecoder};
 use crate::source::{Source, SourceContext, SourceExecutionContext, TypedSourceFactory};
 
 /// Number of bytes after which we cut a new batch.
@@ -100,15 +101,45 @@ struct KafkaMessage {
     payload_len: u64,
     partition: i32,
     offset: i64,
+    /// Only captured when metadata fields are configured.
+    metadata_opt: Option<KafkaMessageMetadata>,
 }
 
-impl From<BorrowedMessage<'_>> for KafkaMessage {
-    fn from(message: BorrowedMessage<'_>) -> Self {
+impl KafkaMessage {
+    fn new(message: BorrowedMessage<'_>, capture_metadata: bool) -> Self {
+        let metadata_opt = capture_metadata.then(|| KafkaMessageMetadata::from(&message));
         Self {
             doc_opt: message_payload_to_doc(&message),
             payload_len: message.payload_len() as u64,
             partition: message.partition(),
             offset: message.offset(),
+            metadata_opt,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct KafkaMessageMetadata {
+    key_opt: Option<Vec<u8>>,
+    headers: Vec<(String, Vec<u8>)>,
+    timestamp_millis_opt: Option<i64>,
+}
+
+impl From<&BorrowedMessage<'_>> for KafkaMessageMetadata {
+    fn from(message: &BorrowedMessage<'_>) -> Self {
+        let headers = message
+            .headers()
+            .map(|headers| {
+                (0..headers.count())
+                    .filter_map(|idx| headers.get(idx))
+                    .map(|(name, value)| (name.to_string(), value.to_vec()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            key_opt: message.key().map(|key| key.to_vec()),
+            headers,
+            timestamp_millis_opt: message.timestamp().to_millis(),
         }
     }
 }
@@ -209,6 +240,9 @@ pub struct KafkaSourceState {
     pub num_messages_processed: u64,
     // Number of invalid messages, i.e., that were empty or could not be parsed.
     pub num_invalid_messages: u64,
+    /// Number of messages that could not be decoded with their Avro or Protobuf schema. These
+    /// messages are also counted as invalid.
+    pub num_undecodable_messages: u64,
     /// Number of rebalances the consumer went through.
     pub num_rebalances: usize,
 }
@@ -219,6 +253,8 @@ pub struct KafkaSource {
     topic: String,
     state: KafkaSourceState,
     backfill_mode_enabled: bool,
+    payload_decoder_opt: Option<PayloadDecoder>,
+    metadata_fields: KafkaMetadataFields,
     events_rx: mpsc::Receiver<KafkaEvent>,
     poll_loop_jh: JoinHandle<()>,
     publish_lock: PublishLock,
@@ -244,6 +280,10 @@ impl KafkaSource {
     ) -> anyhow::Result<Self> {
         let topic = params.topic.clone();
         let backfill_mode_enabled = params.enable_backfill_mode;
+        let payload_decoder_opt =
+            PayloadDecoder::try_new(params.payload_format, params.schema_registry.as_ref())?;
+        let metadata_fields = params.metadata_fields.clone();
+        let capture_metadata = !metadata_fields.is_empty();
 
         let (events_tx, events_rx) = mpsc::channel(100);
         let (client_config, consumer) = create_consumer(
@@ -261,7 +301,8 @@ impl KafkaSource {
             .get("max.poll.interval.ms")?
             .parse::<u64>()?;
 
-        let poll_loop_jh = spawn_consumer_poll_loop(consumer, topic.clone(), events_tx);
+        let poll_loop_jh =
+            spawn_consumer_poll_loop(consumer, topic.clone(), events_tx, capture_metadata);
         let publish_lock = PublishLock::default();
 
         info!(
@@ -285,6 +326,8 @@ impl KafkaSource {
             topic,
             state: KafkaSourceState::default(),
             backfill_mode_enabled,
+            payload_decoder_opt,
+            metadata_fields,
             events_rx,
             poll_loop_jh,
             publish_lock,
@@ -301,9 +344,45 @@ impl KafkaSource {
             payload_len,
             partition,
             offset,
-            ..
+            metadata_opt,
         } = message;
 
+        let doc_opt = match (doc_opt, &self.payload_decoder_opt) {
+            (Some(payload), Some(payload_decoder)) => {
+                match payload_decoder.decode(&payload).await {
+                    Ok(doc) => Some(doc),
+                    // The message is neither counted nor checkpointed: failing the source makes
+                    // the pipeline restart from the last checkpoint and consume it again.
+                    Err(error @ DecodeError::RegistryUnavailable(_)) => {
+                        return Err(anyhow::Error::new(error).context(format!(
+                            "Failed to decode message at partition `{partition}` and offset \
+                             `{offset}`."
+                        )));
+                    }
+                    Err(DecodeError::InvalidPayload(error)) => {
+                        debug!(
+                            partition=%partition,
+                            offset=%offset,
+                            error=?error,
+                            "Failed to decode message payload."
+                        );
+                        self.state.num_undecodable_messages += 1;
+                        None
+                    }
+                }
+            }
+            (doc_opt, _) => doc_opt,
+        };
+        let doc_opt = match (doc_opt, metadata_opt) {
+            (Some(doc), Some(metadata)) => Some(inject_metadata(
+                doc,
+                &self.metadata_fields,
+                &metadata,
+                partition,
+                offset,
+            )),
+            (doc_opt, _) => doc_opt,
+        };
         if let Some(doc) = doc_opt {
             batch.push(doc, payload_len);
         } else {
@@ -566,6 +645,7 @@ impl Source for KafkaSource {
             "num_bytes_processed": self.state.num_bytes_processed,
             "num_messages_processed": self.state.num_messages_processed,
             "num_invalid_messages": self.state.num_invalid_messages,
+            "num_undecodable_messages": self.state.num_undecodable_messages,
             "num_rebalances": self.state.num_rebalances,
         })
     }
@@ -580,6 +660,7 @@ fn spawn_consumer_poll_loop(
     consumer: RdKafkaConsumer,
     topic: String,
     events_tx: mpsc::Sender<KafkaEvent>,
+    capture_metadata: bool,
 ) -> JoinHandle<()> {
     spawn_blocking(move || {
         // `subscribe()` returns immediately but triggers the execution of synchronous code (e.g.
@@ -596,7 +677,9 @@ fn spawn_consumer_poll_loop(
         while !events_tx.is_closed() {
             if let Some(message_res) = consumer.poll(Some(Duration::from_secs(1))) {
                 let event = match message_res {
-                    Ok(message) => KafkaEvent::Message(message.into()),
+                    Ok(message) => {
+                        KafkaEvent::Message(KafkaMessage::new(message, capture_metadata))
+                    }
                     Err(KafkaError::PartitionEOF(partition)) => KafkaEvent::PartitionEOF(partition),
                     Err(error) => KafkaEvent::Error(anyhow!(error)),
                 };
@@ -752,6 +835,110 @@ fn message_payload_to_doc(message: &BorrowedMessage) -> Option<Bytes> {
     None
 }
 
+/// Injects the metadata of the message into the document. Documents that are not JSON objects are
+/// left untouched.
+fn inject_metadata(
+    doc: Bytes,
+    metadata_fields: &KafkaMetadataFields,
+    metadata: &KafkaMessageMetadata,
+    partition: i32,
+    offset: i64,
+) -> Bytes {
+    let Ok(mut doc_json) = serde_json::from_slice::<JsonMap<String, JsonValue>>(&doc) else {
+        return doc;
+    };
+    let mut insert_if_absent = |field_opt: &Option<String>, value: JsonValue| {
+        if let Some(field) = field_opt {
+            doc_json.entry(field.clone()).or_insert(value);
+        }
+    };
+    let key_json = metadata
+        .key_opt
+        .as_ref()
+        .map(|key| JsonValue::String(String::from_utf8_lossy(key).into_owned()))
+        .unwrap_or(JsonValue::Null);
+    insert_if_absent(&metadata_fields.key, key_json);
+
+    if metadata_fields.headers.is_some() {
+        let headers_json: JsonMap<String, JsonValue> = metadata
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                let value_json = JsonValue::String(String::from_utf8_lossy(value).into_owned());
+                (name.clone(), value_json)
+            })
+            .collect();
+        insert_if_absent(&metadata_fields.headers, JsonValue::Object(headers_json));
+    }
+    insert_if_absent(&metadata_fields.partition, json!(partition));
+    insert_if_absent(&metadata_fields.offset, json!(offset));
+    insert_if_absent(
+        &metadata_fields.timestamp,
+        json!(metadata.timestamp_millis_opt),
+    );
+    match serde_json::to_vec(&doc_json) {
+        Ok(doc_bytes) => Bytes::from(doc_bytes),
+        Err(_) => doc,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inject_metadata() {
+        let metadata = KafkaMessageMetadata {
+            key_opt: Some(b"my-key".to_vec()),
+            headers: vec![("trace-id".to_string(), b"1234".to_vec())],
+            timestamp_millis_opt: Some(1_680_000_000_000),
+        };
+        let metadata_fields = KafkaMetadataFields {
+            key: Some("kafka_key".to_string()),
+            headers: Some("kafka_headers".to_string()),
+            partition: Some("kafka_partition".to_string()),
+            offset: Some("kafka_offset".to_string()),
+            timestamp: Some("timestamp".to_string()),
+        };
+        let doc = Bytes::from_static(br#"{"message": "hello"}"#);
+        let doc = inject_metadata(doc, &metadata_fields, &metadata, 3, 42);
+        assert_eq!(
+            serde_json::from_slice::<JsonValue>(&doc).unwrap(),
+            json!({
+                "message": "hello",
+                "kafka_key": "my-key",
+                "kafka_headers": {"trace-id": "1234"},
+                "kafka_partition": 3,
+                "kafka_offset": 42,
+                "timestamp": 1_680_000_000_000i64,
+            })
+        );
+    }
+
+    #[test]
+    fn test_inject_metadata_does_not_overwrite_fields() {
+        let metadata = KafkaMessageMetadata {
+            timestamp_millis_opt: Some(1_680_000_000_000),
+            ..Default::default()
+        };
+        let metadata_fields = KafkaMetadataFields {
+            key: Some("kafka_key".to_string()),
+            timestamp: Some("timestamp".to_string()),
+            ..Default::default()
+        };
+        let doc = Bytes::from_static(br#"{"timestamp": 1234}"#);
+        let doc = inject_metadata(doc, &metadata_fields, &metadata, 0, 0);
+        assert_eq!(
+            serde_json::from_slice::<JsonValue>(&doc).unwrap(),
+            json!({"timestamp": 1234, "kafka_key": null})
+        );
+
+        let doc = Bytes::from_static(b"not-json");
+        let doc = inject_metadata(doc, &metadata_fields, &metadata, 0, 0);
+        assert_eq!(doc, Bytes::from_static(b"not-json"));
+    }
+}
+
 #[cfg(all(test, feature = "kafka-broker-tests"))]
 mod kafka_broker_tests {
     use std::num::NonZeroUsize;
@@ -759,7 +946,10 @@ mod kafka_broker_tests {
 
     use quickwit_actors::{ActorContext, Universe};
     use quickwit_common::rand::append_random_suffix;
-    use quickwit_config::{IndexConfig, SourceConfig, SourceParams};
+    use quickwit_config::{
+        IndexConfig, KafkaMetadataFields, KafkaPayloadFormat, SourceConfig, SourceInputFormat,
+        SourceParams,
+    };
     use quickwit_metastore::checkpoint::{IndexCheckpointDelta, SourceCheckpointDelta};
     use quickwit_metastore::{metastore_for_test, Metastore, SplitMetadata};
     use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
@@ -872,8 +1062,13 @@ mod kafka_broker_tests {
                     "bootstrap.servers": "localhost:9092",
                 }),
                 enable_backfill_mode: true,
+                payload_format: KafkaPayloadFormat::default(),
+                schema_registry: None,
+                metadata_fields: KafkaMetadataFields::default(),
             }),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         (source_id, source_config)
     }
@@ -977,6 +1172,7 @@ mod kafka_broker_tests {
             payload_len: 7,
             partition: 1,
             offset: 0,
+            metadata_opt: None,
         };
         kafka_source
             .process_message(message, &mut batch)
@@ -998,6 +1194,7 @@ mod kafka_broker_tests {
             payload_len: 8,
             partition: 1,
             offset: 1,
+            metadata_opt: None,
         };
         kafka_source
             .process_message(message, &mut batch)
@@ -1020,6 +1217,7 @@ mod kafka_broker_tests {
             payload_len: 8,
             partition: 2,
             offset: 42,
+            metadata_opt: None,
         };
         kafka_source
             .process_message(message, &mut batch)
@@ -1052,6 +1250,7 @@ mod kafka_broker_tests {
             payload_len: 8,
             partition: 3,
             offset: 42,
+            metadata_opt: None,
         };
         kafka_source
             .process_message(message, &mut batch)
@@ -1264,6 +1463,7 @@ mod kafka_broker_tests {
                 "num_bytes_processed": 0,
                 "num_messages_processed": 0,
                 "num_invalid_messages": 0,
+                "num_undecodable_messages": 0,
                 "num_rebalances": 0,
             });
             assert_eq!(exit_state, expected_state);
@@ -1347,6 +1547,7 @@ mod kafka_broker_tests {
                 "num_bytes_processed": 72,
                 "num_messages_processed": 9,
                 "num_invalid_messages": 3,
+                "num_undecodable_messages": 0,
                 "num_rebalances": 0,
             });
             assert_eq!(exit_state, expected_state);
@@ -1415,6 +1616,7 @@ mod kafka_broker_tests {
                 "num_bytes_processed": 36,
                 "num_messages_processed": 5,
                 "num_invalid_messages": 2,
+                "num_undecodable_messages": 0,
                 "num_rebalances": 0,
             });
             assert_eq!(exit_state, expected_exit_state);
@@ -1436,6 +1638,9 @@ mod kafka_broker_tests {
             client_log_level: None,
             client_params: json!({ "bootstrap.servers": bootstrap_servers }),
             enable_backfill_mode: true,
+            payload_format: KafkaPayloadFormat::default(),
+            schema_registry: None,
+            metadata_fields: KafkaMetadataFields::default(),
         })
         .await
         .unwrap();
@@ -1447,6 +1652,9 @@ mod kafka_broker_tests {
             client_log_level: None,
             client_params: json!({ "bootstrap.servers": bootstrap_servers }),
             enable_backfill_mode: true,
+            payload_format: KafkaPayloadFormat::default(),
+            schema_registry: None,
+            metadata_fields: KafkaMetadataFields::default(),
         })
         .await
         .unwrap_err();
@@ -1459,6 +1667,9 @@ mod kafka_broker_tests {
                 "bootstrap.servers": "192.0.2.10:9092"
             }),
             enable_backfill_mode: true,
+            payload_format: KafkaPayloadFormat::default(),
+            schema_registry: None,
+            metadata_fields: KafkaMetadataFields::default(),
         })
         .await
         .unwrap_err();
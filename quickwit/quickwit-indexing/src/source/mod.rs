@@ -53,33 +53,49 @@
 //! # Example sources
 //!
 //! Right now two sources are implemented in quickwit.
-//! - the file source: there partition here is a filepath, and the position is a byte-offset within
-//!   that file.
+//! - the file source: there partition here is a filepath or an object URI, and the position is a
+//!   byte-offset within that file.
 //! - the kafka source: the partition id is a kafka topic partition id, and the position is a kafka
 //!   offset.
+#[cfg(feature = "amqp")]
+mod amqp_source;
+mod compression;
 mod file_source;
+mod http_source;
 mod ingest_api_source;
 #[cfg(feature = "kafka")]
 mod kafka_source;
 #[cfg(feature = "kinesis")]
 mod kinesis;
+#[cfg(feature = "nats")]
+mod nats_source;
 #[cfg(feature = "pulsar")]
 mod pulsar_source;
+#[cfg(feature = "redis")]
+mod redis_streams_source;
+#[cfg(feature = "kafka")]
+mod schema_registry;
 mod source_factory;
 mod vec_source;
 mod void_source;
 
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
+#[cfg(feature = "amqp")]
+pub use amqp_source::{AmqpSource, AmqpSourceFactory};
 use anyhow::bail;
 use async_trait::async_trait;
+use file_source::resolve_file_source_uri;
 pub use file_source::{FileSource, FileSourceFactory};
+pub use http_source::{HttpSource, HttpSourceFactory};
 #[cfg(feature = "kafka")]
 pub use kafka_source::{KafkaSource, KafkaSourceFactory};
 #[cfg(feature = "kinesis")]
 pub use kinesis::kinesis_source::{KinesisSource, KinesisSourceFactory};
+#[cfg(feature = "nats")]
+pub use nats_source::{NatsSource, NatsSourceFactory};
 use once_cell::sync::OnceCell;
 #[cfg(feature = "pulsar")]
 pub use pulsar_source::{PulsarSource, PulsarSourceFactory};
@@ -88,6 +104,8 @@ use quickwit_common::runtimes::RuntimeType;
 use quickwit_config::{SourceConfig, SourceParams};
 use quickwit_metastore::checkpoint::SourceCheckpoint;
 use quickwit_metastore::Metastore;
+#[cfg(feature = "redis")]
+pub use redis_streams_source::{RedisStreamsSource, RedisStreamsSourceFactory};
 use serde_json::Value as JsonValue;
 pub use source_factory::{SourceFactory, SourceLoader, TypedSourceFactory};
 use tokio::runtime::Handle;
@@ -98,6 +116,20 @@ pub use void_source::{VoidSource, VoidSourceFactory};
 use crate::actors::DocProcessor;
 use crate::source::ingest_api_source::IngestApiSourceFactory;
 
+/// Number of bytes after which we cut a new batch.
+///
+/// We try to emit chewable batches for the indexer.
+/// One batch = one message to the indexer actor.
+///
+/// If batches are too large:
+/// - we might not be able to observe the state of the indexer for 5 seconds.
+/// - we will be needlessly occupying resident memory in the mailbox.
+/// - we will not have a precise control of the timeout before commit.
+///
+/// 5MB seems like a good one size fits all value.
+#[cfg(any(feature = "amqp", feature = "nats", feature = "redis"))]
+pub(crate) const BATCH_NUM_BYTES_LIMIT: u64 = 5_000_000;
+
 /// Runtime configuration used during execution of a source actor.
 pub struct SourceExecutionContext {
     pub metastore: Arc<dyn Metastore>,
@@ -292,6 +324,13 @@ pub fn quickwit_supported_sources() -> &'static SourceLoader {
         source_factory.add_source("kinesis", KinesisSourceFactory);
         #[cfg(feature = "pulsar")]
         source_factory.add_source("pulsar", PulsarSourceFactory);
+        #[cfg(feature = "nats")]
+        source_factory.add_source("nats", NatsSourceFactory);
+        #[cfg(feature = "amqp")]
+        source_factory.add_source("amqp", AmqpSourceFactory);
+        #[cfg(feature = "redis")]
+        source_factory.add_source("redis-streams", RedisStreamsSourceFactory);
+        source_factory.add_source("http", HttpSourceFactory);
         source_factory.add_source("vec", VecSourceFactory);
         source_factory.add_source("void", VoidSourceFactory);
         source_factory.add_source("ingest-api", IngestApiSourceFactory);
@@ -302,13 +341,18 @@ pub fn quickwit_supported_sources() -> &'static SourceLoader {
 pub async fn check_source_connectivity(source_config: &SourceConfig) -> anyhow::Result<()> {
     match &source_config.source_params {
         SourceParams::File(params) => {
-            if let Some(filepath) = &params.filepath {
-                if !Path::new(filepath).try_exists()? {
-                    bail!("File `{}` does not exist.", filepath.display())
+            if let Some(uri) = &params.filepath {
+                let (_storage, paths) = resolve_file_source_uri(uri).await?;
+                if paths.is_empty() {
+                    bail!("No file matches URI `{uri}`.")
                 }
             }
             Ok(())
         }
+        SourceParams::Http(params) => {
+            http_source::check_connectivity(params).await?;
+            Ok(())
+        }
         #[allow(unused_variables)]
         SourceParams::Kafka(params) => {
             #[cfg(not(feature = "kafka"))]
@@ -342,6 +386,39 @@ pub async fn check_source_connectivity(source_config: &SourceConfig) -> anyhow::
                 Ok(())
             }
         }
+        #[allow(unused_variables)]
+        SourceParams::Nats(params) => {
+            #[cfg(not(feature = "nats"))]
+            bail!("Quickwit binary was not compiled with the `nats` feature.");
+
+            #[cfg(feature = "nats")]
+            {
+                nats_source::check_connectivity(params).await?;
+                Ok(())
+            }
+        }
+        #[allow(unused_variables)]
+        SourceParams::Amqp(params) => {
+            #[cfg(not(feature = "amqp"))]
+            bail!("Quickwit binary was not compiled with the `amqp` feature.");
+
+            #[cfg(feature = "amqp")]
+            {
+                amqp_source::check_connectivity(params).await?;
+                Ok(())
+            }
+        }
+        #[allow(unused_variables)]
+        SourceParams::RedisStreams(params) => {
+            #[cfg(not(feature = "redis"))]
+            bail!("Quickwit binary was not compiled with the `redis` feature.");
+
+            #[cfg(feature = "redis")]
+            {
+                redis_streams_source::check_connectivity(params).await?;
+                Ok(())
+            }
+        }
         _ => Ok(()),
     }
 }
@@ -373,7 +450,7 @@ mod tests {
 
     use std::num::NonZeroUsize;
 
-    use quickwit_config::VecSourceParams;
+    use quickwit_config::{SourceInputFormat, VecSourceParams};
 
     use super::*;
 
@@ -387,6 +464,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::void(),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             };
             check_source_connectivity(&source_config).await?;
         }
@@ -398,6 +477,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::Vec(VecSourceParams::default()),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             };
             check_source_connectivity(&source_config).await?;
         }
@@ -409,6 +490,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::file("file-does-not-exist.json"),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             };
             assert!(check_source_connectivity(&source_config).await.is_err());
         }
@@ -420,6 +503,8 @@ mod tests {
                 enabled: true,
                 source_params: SourceParams::file("data/test_corpus.json"),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             };
             assert!(check_source_connectivity(&source_config).await.is_ok());
         }
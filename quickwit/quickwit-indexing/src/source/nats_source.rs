@@ -0,0 +1,699 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use async_nats::jetstream::consumer::{pull, AckPolicy, DeliverPolicy, PullConsumer};
+use async_nats::jetstream::{self, Message};
+use async_nats::ConnectOptions;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::StreamExt;
+use quickwit_actors::{ActorContext, ActorExitStatus, Mailbox};
+use quickwit_config::{NatsSourceAuth, NatsSourceParams};
+use quickwit_metastore::checkpoint::{
+    PartitionId, Position, SourceCheckpoint, SourceCheckpointDelta,
+};
+use serde_json::{json, Value as JsonValue};
+use tokio::sync::Mutex;
+use tokio::time;
+use tracing::{debug, info, warn};
+
+use crate::actors::DocProcessor;
+use crate::models::RawDocBatch;
+use crate::source::{
+    Source, SourceActor, SourceContext, SourceExecutionContext, TypedSourceFactory,
+    BATCH_NUM_BYTES_LIMIT,
+};
+
+/// Messages are acknowledged only once they are published in a split, which takes at least the
+/// commit timeout of the index. Until then, the server redelivers them every `ACK_WAIT`, and
+/// redelivered messages are skipped by the source.
+const ACK_WAIT: Duration = Duration::from_secs(10 * 60);
+
+/// Maximum number of messages delivered but not acknowledged yet. The server stops delivering
+/// messages to the consumer when it is reached, so it must be large enough to cover a commit
+/// timeout worth of messages.
+const MAX_ACK_PENDING: i64 = 1_000_000;
+
+type NatsMessages = Mutex<pull::Stream>;
+
+pub struct NatsSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for NatsSourceFactory {
+    type Source = NatsSource;
+    type Params = NatsSourceParams;
+
+    async fn typed_create_source(
+        ctx: Arc<SourceExecutionContext>,
+        params: NatsSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self::Source> {
+        NatsSource::try_new(ctx, params, checkpoint).await
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct NatsSourceState {
+    /// Number of bytes processed by the source.
+    pub num_bytes_processed: u64,
+    /// Number of messages processed by the source (including invalid messages).
+    pub num_messages_processed: u64,
+    /// Number of invalid messages, i.e., that were empty.
+    pub num_invalid_messages: u64,
+    /// Number of messages redelivered by the server and skipped because they were older than the
+    /// current position.
+    pub num_skipped_messages: u64,
+    /// Number of messages remaining in the stream for the consumer, as of the last message
+    /// received.
+    pub num_pending_messages: u64,
+}
+
+/// Consumes a NATS JetStream stream through a durable pull consumer.
+///
+/// The stream is a single partition identified by the stream name, and positions are stream
+/// sequence numbers. Messages are acknowledged once the checkpoint recording them is published.
+pub struct NatsSource {
+    ctx: Arc<SourceExecutionContext>,
+    params: NatsSourceParams,
+    consumer_name: String,
+    partition_id: PartitionId,
+    messages: NatsMessages,
+    current_position: Position,
+    /// Last message of each emitted batch, keyed by stream sequence number. Acknowledging a
+    /// message acknowledges all the messages preceding it.
+    pending_acks: Mutex<BTreeMap<u64, Message>>,
+    state: NatsSourceState,
+}
+
+impl NatsSource {
+    pub async fn try_new(
+        ctx: Arc<SourceExecutionContext>,
+        params: NatsSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self> {
+        let consumer_name = params
+            .consumer_name
+            .clone()
+            .unwrap_or_else(|| default_consumer_name(&ctx.index_id, &ctx.source_config.source_id));
+        let partition_id = PartitionId::from(params.stream.as_str());
+        let current_position = checkpoint
+            .position_for_partition(&partition_id)
+            .cloned()
+            .unwrap_or(Position::Beginning);
+        let start_sequence = sequence_from_position(&current_position)? + 1;
+        info!(
+            index_id=%ctx.index_id,
+            source_id=%ctx.source_config.source_id,
+            stream=%params.stream,
+            consumer_name=%consumer_name,
+            start_sequence=%start_sequence,
+            "Create NATS source."
+        );
+        let jetstream = jetstream::new(connect_nats(&params).await?);
+        let stream = jetstream
+            .get_stream(&params.stream)
+            .await
+            .map_err(|error| anyhow!("Failed to get NATS stream `{}`: {error}", params.stream))?;
+
+        // The checkpoint is the source of truth, so an existing durable consumer is always
+        // recreated to start delivering right after the checkpoint. Reusing it would lose the
+        // messages delivered before a restart but not checkpointed yet: the server redelivers
+        // them only after `ACK_WAIT`, when newer messages have already moved the current
+        // position past them.
+        if stream.consumer_info(&consumer_name).await.is_ok() {
+            info!(consumer_name=%consumer_name, "Recreate NATS consumer from checkpoint.");
+            stream
+                .delete_consumer(&consumer_name)
+                .await
+                .map_err(|error| anyhow!("Failed to delete NATS consumer: {error}"))?;
+        }
+        let deliver_policy = if start_sequence > 1 {
+            DeliverPolicy::ByStartSequence { start_sequence }
+        } else {
+            DeliverPolicy::All
+        };
+        let consumer_config = pull::Config {
+            durable_name: Some(consumer_name.clone()),
+            deliver_policy,
+            ack_policy: AckPolicy::All,
+            ack_wait: ACK_WAIT,
+            max_ack_pending: MAX_ACK_PENDING,
+            filter_subject: params.filter_subject.clone().unwrap_or_default(),
+            ..Default::default()
+        };
+        let consumer: PullConsumer = stream
+            .get_or_create_consumer(&consumer_name, consumer_config)
+            .await
+            .map_err(|error| anyhow!("Failed to create NATS consumer: {error}"))?;
+        let num_pending_messages = consumer.cached_info().num_pending;
+        let messages = consumer
+            .messages()
+            .await
+            .map_err(|error| anyhow!("Failed to subscribe to NATS consumer: {error}"))?;
+
+        Ok(Self {
+            ctx,
+            params,
+            consumer_name,
+            partition_id,
+            messages: Mutex::new(messages),
+            current_position,
+            pending_acks: Mutex::new(BTreeMap::new()),
+            state: NatsSourceState {
+                num_pending_messages,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn process_message(
+        &mut self,
+        message: Message,
+        batch: &mut BatchBuilder,
+    ) -> anyhow::Result<()> {
+        let message_info = message
+            .info()
+            .map_err(|error| anyhow!("Failed to read NATS message metadata: {error}"))?;
+        let stream_sequence = message_info.stream_sequence;
+        self.state.num_pending_messages = message_info.pending;
+
+        let msg_position = Position::from(stream_sequence);
+
+        // We skip messages older or equal to the current recorded position. The server redelivers
+        // the messages that are not acknowledged after `ACK_WAIT`, and after a restart.
+        if msg_position <= self.current_position {
+            self.state.num_skipped_messages += 1;
+            return Ok(());
+        }
+        let doc = message.payload.clone();
+        let num_bytes = doc.len() as u64;
+
+        let previous_position = std::mem::replace(&mut self.current_position, msg_position);
+        batch
+            .checkpoint_delta
+            .record_partition_delta(
+                self.partition_id.clone(),
+                previous_position,
+                self.current_position.clone(),
+            )
+            .context("Failed to record partition delta.")?;
+        batch.last_message_opt = Some((stream_sequence, message));
+
+        self.state.num_bytes_processed += num_bytes;
+        self.state.num_messages_processed += 1;
+
+        if doc.is_empty() {
+            warn!("Message received from stream was empty.");
+            self.state.num_invalid_messages += 1;
+            return Ok(());
+        }
+        batch.push(doc, num_bytes);
+        Ok(())
+    }
+
+    async fn try_ack_messages(&self, checkpoint: SourceCheckpoint) -> anyhow::Result<()> {
+        debug!(ckpt = ?checkpoint, "Acknowledging NATS messages.");
+        let Some(position) = checkpoint.position_for_partition(&self.partition_id) else {
+            return Ok(());
+        };
+        let stream_sequence = sequence_from_position(position)?;
+        let mut pending_acks = self.pending_acks.lock().await;
+        let not_yet_published = pending_acks.split_off(&(stream_sequence + 1));
+        let published = std::mem::replace(&mut *pending_acks, not_yet_published);
+
+        if let Some((_, message)) = published.into_iter().next_back() {
+            message
+                .ack()
+                .await
+                .map_err(|error| anyhow!("Failed to acknowledge NATS message: {error}"))?;
+        }
+        Ok(())
+    }
+
+    fn should_exit(&self) -> bool {
+        self.params.enable_backfill_mode && self.state.num_pending_messages == 0
+    }
+}
+
+#[async_trait]
+impl Source for NatsSource {
+    async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        let now = Instant::now();
+        let mut batch = BatchBuilder::default();
+        let deadline = time::sleep(quickwit_actors::HEARTBEAT / 2);
+        tokio::pin!(deadline);
+
+        while !self.should_exit() {
+            tokio::select! {
+                // This does not actually acquire the lock of the mutex internally
+                // we're using the mutex in order to convince the Rust compiler
+                // that we can use the message stream within this Sync context.
+                message = self.messages.get_mut().next() => {
+                    let message = message
+                        .ok_or_else(|| ActorExitStatus::from(anyhow!("Consumer was dropped.")))?
+                        .map_err(|error| ActorExitStatus::from(anyhow!("Failed to get message from consumer: {error}")))?;
+
+                    self.process_message(message, &mut batch).map_err(ActorExitStatus::from)?;
+
+                    if batch.num_bytes >= BATCH_NUM_BYTES_LIMIT {
+                        break;
+                    }
+                }
+                _ = &mut deadline => {
+                    break;
+                }
+            }
+            ctx.record_progress();
+        }
+
+        if !batch.checkpoint_delta.is_empty() {
+            debug!(
+                num_docs=%batch.docs.len(),
+                num_bytes=%batch.num_bytes,
+                num_millis=%now.elapsed().as_millis(),
+                "Sending doc batch to indexer.");
+            if let Some((stream_sequence, message)) = batch.last_message_opt.take() {
+                self.pending_acks.get_mut().insert(stream_sequence, message);
+            }
+            let message = batch.build();
+            ctx.send_message(doc_processor_mailbox, message).await?;
+        }
+        if self.should_exit() {
+            info!(stream = %self.params.stream, "Reached end of stream.");
+            ctx.send_exit_with_success(doc_processor_mailbox).await?;
+            return Err(ActorExitStatus::Success);
+        }
+        Ok(Duration::default())
+    }
+
+    async fn suggest_truncate(
+        &self,
+        checkpoint: SourceCheckpoint,
+        _ctx: &ActorContext<SourceActor>,
+    ) -> anyhow::Result<()> {
+        self.try_ack_messages(checkpoint).await
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "NatsSource{{source_id={}}}",
+            self.ctx.source_config.source_id
+        )
+    }
+
+    fn observable_state(&self) -> JsonValue {
+        json!({
+            "index_id": self.ctx.index_id,
+            "source_id": self.ctx.source_config.source_id,
+            "stream": self.params.stream,
+            "consumer_name": self.consumer_name,
+            "current_position": self.current_position.as_str(),
+            "num_bytes_processed": self.state.num_bytes_processed,
+            "num_messages_processed": self.state.num_messages_processed,
+            "num_invalid_messages": self.state.num_invalid_messages,
+            "num_skipped_messages": self.state.num_skipped_messages,
+            "num_pending_messages": self.state.num_pending_messages,
+        })
+    }
+}
+
+#[derive(Default)]
+struct BatchBuilder {
+    docs: Vec<Bytes>,
+    num_bytes: u64,
+    checkpoint_delta: SourceCheckpointDelta,
+    /// Last message of the batch along with its stream sequence number.
+    last_message_opt: Option<(u64, Message)>,
+}
+
+impl BatchBuilder {
+    fn build(self) -> RawDocBatch {
+        RawDocBatch {
+            docs: self.docs,
+            checkpoint_delta: self.checkpoint_delta,
+            force_commit: false,
+        }
+    }
+
+    fn push(&mut self, doc: Bytes, num_bytes: u64) {
+        self.docs.push(doc);
+        self.num_bytes += num_bytes;
+    }
+}
+
+/// Returns the stream sequence number of the last message recorded at this position, `0` if no
+/// message was recorded yet.
+fn sequence_from_position(position: &Position) -> anyhow::Result<u64> {
+    match position {
+        Position::Beginning => Ok(0),
+        Position::Offset(offset_str) => offset_str
+            .parse::<u64>()
+            .with_context(|| format!("Failed to parse NATS stream sequence `{offset_str}`.")),
+    }
+}
+
+async fn connect_nats(params: &NatsSourceParams) -> anyhow::Result<async_nats::Client> {
+    let connect_options = match params.authentication.clone() {
+        None => ConnectOptions::new(),
+        Some(NatsSourceAuth::Token(token)) => ConnectOptions::with_token(token),
+        Some(NatsSourceAuth::UserPassword { user, password }) => {
+            ConnectOptions::with_user_and_password(user, password)
+        }
+        Some(NatsSourceAuth::CredentialsFile(path)) => {
+            ConnectOptions::with_credentials_file(path.into())
+                .await
+                .context("Failed to read NATS credentials file.")?
+        }
+    };
+    let client = connect_options
+        .connect(params.address.as_str())
+        .await
+        .with_context(|| format!("Failed to connect to NATS server `{}`.", params.address))?;
+    Ok(client)
+}
+
+/// Checks whether we can establish a connection to the NATS server and whether the stream
+/// exists.
+pub(crate) async fn check_connectivity(params: &NatsSourceParams) -> anyhow::Result<()> {
+    let jetstream = jetstream::new(connect_nats(params).await?);
+    jetstream
+        .get_stream(&params.stream)
+        .await
+        .map_err(|error| anyhow!("Failed to get NATS stream `{}`: {error}", params.stream))?;
+    Ok(())
+}
+
+fn default_consumer_name(index_id: &str, source_id: &str) -> String {
+    format!("quickwit-{index_id}-{source_id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence_from_position() {
+        assert_eq!(sequence_from_position(&Position::Beginning).unwrap(), 0);
+        assert_eq!(sequence_from_position(&Position::from(42u64)).unwrap(), 42);
+        sequence_from_position(&Position::from("not-a-sequence".to_string())).unwrap_err();
+    }
+
+    #[test]
+    fn test_default_consumer_name() {
+        assert_eq!(
+            default_consumer_name("my-index", "my-source"),
+            "quickwit-my-index-my-source"
+        );
+    }
+}
+
+#[cfg(all(test, feature = "nats-broker-tests"))]
+mod nats_broker_tests {
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+
+    use async_nats::jetstream::stream;
+    use quickwit_actors::{ActorHandle, Inbox, Universe};
+    use quickwit_common::rand::append_random_suffix;
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_metastore::metastore_for_test;
+
+    use super::*;
+    use crate::source::{quickwit_supported_sources, SuggestTruncate};
+
+    static NATS_URI: &str = "nats://localhost:4222";
+
+    fn get_source_config(stream_name: &str) -> SourceConfig {
+        let source_id = append_random_suffix("test-nats-source--source");
+        SourceConfig {
+            source_id,
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Nats(NatsSourceParams {
+                address: NATS_URI.to_string(),
+                stream: stream_name.to_string(),
+                filter_subject: None,
+                consumer_name: None,
+                authentication: None,
+                enable_backfill_mode: true,
+            }),
+            transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
+        }
+    }
+
+    /// Creates a stream capturing the subject of the same name and publishes the messages on it.
+    async fn populate_stream(stream_name: &str, messages: &[&str]) -> jetstream::Context {
+        let client = async_nats::connect(NATS_URI).await.unwrap();
+        let jetstream = jetstream::new(client);
+        jetstream
+            .get_or_create_stream(stream::Config {
+                name: stream_name.to_string(),
+                subjects: vec![stream_name.to_string()],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        publish_messages(&jetstream, stream_name, messages).await;
+        jetstream
+    }
+
+    async fn publish_messages(
+        jetstream: &jetstream::Context,
+        stream_name: &str,
+        messages: &[&str],
+    ) {
+        for message in messages {
+            jetstream
+                .publish(stream_name.to_string(), Bytes::from(message.to_string()))
+                .await
+                .unwrap()
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn create_source(
+        universe: &Universe,
+        source_config: SourceConfig,
+        start_checkpoint: SourceCheckpoint,
+    ) -> (ActorHandle<SourceActor>, Inbox<DocProcessor>) {
+        let ctx = SourceExecutionContext::for_test(
+            metastore_for_test(),
+            "test-nats-source-index",
+            PathBuf::from("./queues"),
+            source_config,
+        );
+        let source = quickwit_supported_sources()
+            .load_source(ctx, start_checkpoint)
+            .await
+            .unwrap();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let source_actor = SourceActor {
+            source,
+            doc_processor_mailbox,
+        };
+        let (_source_mailbox, source_handle) = universe.spawn_builder().spawn(source_actor);
+        (source_handle, doc_processor_inbox)
+    }
+
+    fn merge_doc_batches(batches: Vec<RawDocBatch>) -> RawDocBatch {
+        let mut merged_batch = RawDocBatch::default();
+        for batch in batches {
+            merged_batch.docs.extend(batch.docs);
+            merged_batch
+                .checkpoint_delta
+                .extend(batch.checkpoint_delta)
+                .unwrap();
+        }
+        merged_batch
+    }
+
+    #[tokio::test]
+    async fn test_nats_source_backfill_and_resume() {
+        let universe = Universe::with_accelerated_time();
+        let stream_name = append_random_suffix("test-nats-source-stream");
+        let jetstream = populate_stream(&stream_name, &["doc-1", "", "doc-3"]).await;
+        let source_config = get_source_config(&stream_name);
+
+        let (source_handle, doc_processor_inbox) = create_source(
+            &universe,
+            source_config.clone(),
+            SourceCheckpoint::default(),
+        )
+        .await;
+        let (exit_status, exit_state) = source_handle.join().await;
+        assert!(exit_status.is_success());
+        assert_eq!(exit_state["num_messages_processed"], 3);
+        assert_eq!(exit_state["num_invalid_messages"], 1);
+
+        let batch = merge_doc_batches(doc_processor_inbox.drain_for_test_typed());
+        assert_eq!(batch.docs, vec![Bytes::from("doc-1"), Bytes::from("doc-3")]);
+
+        let partition_id = PartitionId::from(stream_name.as_str());
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::default();
+        expected_checkpoint_delta
+            .record_partition_delta(
+                partition_id.clone(),
+                Position::Beginning,
+                Position::from(3u64),
+            )
+            .unwrap();
+        assert_eq!(batch.checkpoint_delta, expected_checkpoint_delta);
+
+        // The source resumes from the checkpoint, even though the durable consumer never
+        // acknowledged the messages.
+        publish_messages(&jetstream, &stream_name, &["doc-4"]).await;
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint
+            .try_apply_delta(expected_checkpoint_delta)
+            .unwrap();
+
+        let (source_handle, doc_processor_inbox) =
+            create_source(&universe, source_config, checkpoint).await;
+        let (exit_status, exit_state) = source_handle.join().await;
+        assert!(exit_status.is_success());
+        assert_eq!(exit_state["num_messages_processed"], 1);
+
+        let batch = merge_doc_batches(doc_processor_inbox.drain_for_test_typed());
+        assert_eq!(batch.docs, vec![Bytes::from("doc-4")]);
+
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::default();
+        expected_checkpoint_delta
+            .record_partition_delta(partition_id, Position::from(3u64), Position::from(4u64))
+            .unwrap();
+        assert_eq!(batch.checkpoint_delta, expected_checkpoint_delta);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_nats_source_restart_redelivers_uncheckpointed_messages() {
+        let universe = Universe::with_accelerated_time();
+        let stream_name = append_random_suffix("test-nats-source-stream");
+        let jetstream = populate_stream(&stream_name, &["doc-1", "doc-2", "doc-3"]).await;
+        let source_config = get_source_config(&stream_name);
+
+        // All the messages are delivered but none of them is acknowledged.
+        let (source_handle, doc_processor_inbox) = create_source(
+            &universe,
+            source_config.clone(),
+            SourceCheckpoint::default(),
+        )
+        .await;
+        let (exit_status, exit_state) = source_handle.join().await;
+        assert!(exit_status.is_success());
+        assert_eq!(exit_state["num_messages_processed"], 3);
+        doc_processor_inbox.drain_for_test();
+
+        // Only the first message made it to the checkpoint before the restart.
+        publish_messages(&jetstream, &stream_name, &["doc-4"]).await;
+        let partition_id = PartitionId::from(stream_name.as_str());
+        let mut checkpoint = SourceCheckpoint::default();
+        let mut checkpoint_delta = SourceCheckpointDelta::default();
+        checkpoint_delta
+            .record_partition_delta(
+                partition_id.clone(),
+                Position::Beginning,
+                Position::from(1u64),
+            )
+            .unwrap();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+
+        let (source_handle, doc_processor_inbox) =
+            create_source(&universe, source_config, checkpoint).await;
+        let (exit_status, exit_state) = source_handle.join().await;
+        assert!(exit_status.is_success());
+        assert_eq!(exit_state["num_messages_processed"], 3);
+        assert_eq!(exit_state["num_skipped_messages"], 0);
+
+        let batch = merge_doc_batches(doc_processor_inbox.drain_for_test_typed());
+        assert_eq!(
+            batch.docs,
+            vec![
+                Bytes::from("doc-2"),
+                Bytes::from("doc-3"),
+                Bytes::from("doc-4")
+            ]
+        );
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::default();
+        expected_checkpoint_delta
+            .record_partition_delta(partition_id, Position::from(1u64), Position::from(4u64))
+            .unwrap();
+        assert_eq!(batch.checkpoint_delta, expected_checkpoint_delta);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_nats_source_acks_on_suggest_truncate() {
+        let universe = Universe::with_accelerated_time();
+        let stream_name = append_random_suffix("test-nats-source-stream");
+        let jetstream = populate_stream(&stream_name, &["doc-1", "doc-2"]).await;
+        let mut source_config = get_source_config(&stream_name);
+        let SourceParams::Nats(params) = &mut source_config.source_params else {
+            unreachable!()
+        };
+        params.enable_backfill_mode = false;
+        let consumer_name =
+            default_consumer_name("test-nats-source-index", &source_config.source_id);
+
+        let (source_handle, _doc_processor_inbox) =
+            create_source(&universe, source_config, SourceCheckpoint::default()).await;
+        loop {
+            let observation = source_handle.observe().await;
+            if observation.state["num_messages_processed"] == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let mut checkpoint = SourceCheckpoint::default();
+        let mut checkpoint_delta = SourceCheckpointDelta::default();
+        checkpoint_delta
+            .record_partition_delta(
+                PartitionId::from(stream_name.as_str()),
+                Position::Beginning,
+                Position::from(2u64),
+            )
+            .unwrap();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+        source_handle
+            .mailbox()
+            .send_message(SuggestTruncate(checkpoint))
+            .await
+            .unwrap();
+        source_handle.process_pending_and_observe().await;
+
+        let stream = jetstream.get_stream(&stream_name).await.unwrap();
+        let consumer_info = stream.consumer_info(&consumer_name).await.unwrap();
+        assert_eq!(consumer_info.ack_floor.stream_sequence, 2);
+
+        source_handle.quit().await;
+        universe.assert_quit().await;
+    }
+}
@@ -0,0 +1,694 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use bytes::Bytes;
+use quickwit_actors::{ActorContext, ActorExitStatus, Mailbox};
+use quickwit_config::RedisStreamsSourceParams;
+use quickwit_metastore::checkpoint::{
+    PartitionId, Position, SourceCheckpoint, SourceCheckpointDelta,
+};
+use redis::aio::MultiplexedConnection;
+use redis::streams::{StreamId, StreamPendingCountReply, StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, Client, RedisError};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+use tracing::{debug, info, warn};
+
+use crate::actors::DocProcessor;
+use crate::models::RawDocBatch;
+use crate::source::{
+    Source, SourceActor, SourceContext, SourceExecutionContext, TypedSourceFactory,
+    BATCH_NUM_BYTES_LIMIT,
+};
+
+/// Maximum number of entries returned by a single `XREADGROUP` or `XPENDING` command.
+const MAX_ENTRIES_PER_COMMAND: usize = 1_000;
+
+pub struct RedisStreamsSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for RedisStreamsSourceFactory {
+    type Source = RedisStreamsSource;
+    type Params = RedisStreamsSourceParams;
+
+    async fn typed_create_source(
+        ctx: Arc<SourceExecutionContext>,
+        params: RedisStreamsSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self::Source> {
+        RedisStreamsSource::try_new(ctx, params, checkpoint).await
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct RedisStreamsSourceState {
+    /// Number of bytes processed by the source.
+    pub num_bytes_processed: u64,
+    /// Number of entries processed by the source (including invalid entries).
+    pub num_messages_processed: u64,
+    /// Number of invalid entries, i.e., that were empty or lacked the payload field.
+    pub num_invalid_messages: u64,
+}
+
+/// Consumes one or several Redis streams through a consumer group.
+///
+/// Each stream is tracked as a partition and entry IDs are recorded as positions. On startup, the
+/// last delivered ID of the consumer group is reset to the checkpointed position, so the entries
+/// that were delivered but not published in a split are read again. Entries are acknowledged with
+/// `XACK` once they are published.
+pub struct RedisStreamsSource {
+    ctx: Arc<SourceExecutionContext>,
+    params: RedisStreamsSourceParams,
+    consumer_group: String,
+    connection: MultiplexedConnection,
+    current_positions: BTreeMap<PartitionId, Position>,
+    /// Number of entries acknowledged after the publication of a split.
+    num_acked_messages: AtomicU64,
+    state: RedisStreamsSourceState,
+}
+
+impl RedisStreamsSource {
+    pub async fn try_new(
+        ctx: Arc<SourceExecutionContext>,
+        params: RedisStreamsSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self> {
+        let consumer_group = params
+            .consumer_group
+            .clone()
+            .unwrap_or_else(|| default_consumer_group(&ctx.index_id, &ctx.source_config.source_id));
+        info!(
+            index_id=%ctx.index_id,
+            source_id=%ctx.source_config.source_id,
+            streams=?params.streams,
+            consumer_group=%consumer_group,
+            "Create Redis Streams source."
+        );
+        let mut connection = connect_redis(&params).await?;
+        let mut current_positions = BTreeMap::new();
+
+        for stream in &params.streams {
+            let partition_id = PartitionId::from(stream.as_str());
+            let position = checkpoint
+                .position_for_partition(&partition_id)
+                .cloned()
+                .unwrap_or(Position::Beginning);
+            let start_id = entry_id_from_position(&position)?;
+            reset_consumer_group(&mut connection, stream, &consumer_group, &start_id).await?;
+            current_positions.insert(partition_id, position);
+        }
+        Ok(Self {
+            ctx,
+            params,
+            consumer_group,
+            connection,
+            current_positions,
+            num_acked_messages: AtomicU64::new(0),
+            state: RedisStreamsSourceState::default(),
+        })
+    }
+
+    fn process_entry(
+        &mut self,
+        stream: &str,
+        entry: StreamId,
+        batch: &mut BatchBuilder,
+    ) -> anyhow::Result<()> {
+        let partition_id = PartitionId::from(stream);
+        let entry_position = position_from_entry_id(&entry.id)?;
+        let previous_position = self
+            .current_positions
+            .insert(partition_id.clone(), entry_position.clone())
+            .unwrap_or(Position::Beginning);
+
+        // Invalid entries are still recorded in the checkpoint so that they get acknowledged.
+        batch
+            .checkpoint_delta
+            .record_partition_delta(partition_id, previous_position, entry_position)
+            .context("Failed to record partition delta.")?;
+        self.state.num_messages_processed += 1;
+
+        let Some(doc) = self.entry_to_doc(entry) else {
+            warn!(stream=%stream, "Entry received from stream was empty or invalid.");
+            self.state.num_invalid_messages += 1;
+            return Ok(());
+        };
+        let num_bytes = doc.len() as u64;
+        self.state.num_bytes_processed += num_bytes;
+        batch.push(doc, num_bytes);
+        Ok(())
+    }
+
+    fn entry_to_doc(&self, entry: StreamId) -> Option<Bytes> {
+        if let Some(payload_field) = &self.params.payload_field {
+            let value = entry.map.get(payload_field)?;
+            let payload: Vec<u8> = redis::from_redis_value(value).ok()?;
+            return (!payload.is_empty()).then(|| Bytes::from(payload));
+        }
+        if entry.map.is_empty() {
+            return None;
+        }
+        let mut doc = JsonMap::with_capacity(entry.map.len());
+
+        for (field, value) in entry.map {
+            let value: String = redis::from_redis_value(&value).ok()?;
+            doc.insert(field, JsonValue::String(value));
+        }
+        serde_json::to_vec(&doc).ok().map(Bytes::from)
+    }
+
+    async fn try_ack_entries(&self, checkpoint: SourceCheckpoint) -> anyhow::Result<()> {
+        debug!(ckpt = ?checkpoint, "Acknowledging Redis stream entries.");
+        let mut connection = self.connection.clone();
+
+        for (partition_id, position) in checkpoint.iter() {
+            if !self.current_positions.contains_key(&partition_id) {
+                continue;
+            }
+            let stream = partition_id.0.as_str();
+            let end_id = entry_id_from_position(&position)?;
+
+            // Acknowledging the pending entries rather than the entries read by this source
+            // also acknowledges the entries published by a previous incarnation of the source.
+            loop {
+                let pending_reply: StreamPendingCountReply = connection
+                    .xpending_count(
+                        stream,
+                        &self.consumer_group,
+                        "-",
+                        &end_id,
+                        MAX_ENTRIES_PER_COMMAND,
+                    )
+                    .await?;
+                if pending_reply.ids.is_empty() {
+                    break;
+                }
+                let entry_ids: Vec<&str> = pending_reply
+                    .ids
+                    .iter()
+                    .map(|pending_id| pending_id.id.as_str())
+                    .collect();
+                let num_acked_entries: u64 = connection
+                    .xack(stream, &self.consumer_group, &entry_ids)
+                    .await?;
+                self.num_acked_messages
+                    .fetch_add(num_acked_entries, Ordering::Relaxed);
+
+                if pending_reply.ids.len() < MAX_ENTRIES_PER_COMMAND {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Source for RedisStreamsSource {
+    async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        let now = Instant::now();
+        let deadline = now + quickwit_actors::HEARTBEAT / 2;
+        let mut batch = BatchBuilder::default();
+        let streams = self.params.streams.clone();
+        // `>` requests the entries never delivered to the consumer group.
+        let entry_ids = vec![">"; streams.len()];
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            if remaining.is_zero() {
+                break;
+            }
+            let read_options = StreamReadOptions::default()
+                .group(&self.consumer_group, &self.params.consumer_name)
+                .count(MAX_ENTRIES_PER_COMMAND)
+                // `BLOCK 0` blocks indefinitely.
+                .block((remaining.as_millis() as usize).max(1));
+            let read_reply_opt: Option<StreamReadReply> = self
+                .connection
+                .xread_options(&streams, &entry_ids, &read_options)
+                .await
+                .map_err(|error| {
+                    ActorExitStatus::from(
+                        anyhow::Error::new(error).context("Failed to read from Redis streams."),
+                    )
+                })?;
+
+            if let Some(read_reply) = read_reply_opt {
+                for stream_key in read_reply.keys {
+                    for entry in stream_key.ids {
+                        self.process_entry(&stream_key.key, entry, &mut batch)
+                            .map_err(ActorExitStatus::from)?;
+                    }
+                }
+            }
+            ctx.record_progress();
+
+            if batch.num_bytes >= BATCH_NUM_BYTES_LIMIT {
+                break;
+            }
+        }
+
+        if !batch.checkpoint_delta.is_empty() {
+            debug!(
+                num_docs=%batch.docs.len(),
+                num_bytes=%batch.num_bytes,
+                num_millis=%now.elapsed().as_millis(),
+                "Sending doc batch to indexer.");
+            let message = batch.build();
+            ctx.send_message(doc_processor_mailbox, message).await?;
+        }
+
+        Ok(Duration::default())
+    }
+
+    async fn suggest_truncate(
+        &self,
+        checkpoint: SourceCheckpoint,
+        _ctx: &ActorContext<SourceActor>,
+    ) -> anyhow::Result<()> {
+        self.try_ack_entries(checkpoint).await
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "RedisStreamsSource{{source_id={}}}",
+            self.ctx.source_config.source_id
+        )
+    }
+
+    fn observable_state(&self) -> JsonValue {
+        let current_positions: BTreeMap<&str, &str> = self
+            .current_positions
+            .iter()
+            .map(|(partition_id, position)| (partition_id.0.as_str(), position.as_str()))
+            .collect();
+        json!({
+            "index_id": self.ctx.index_id,
+            "source_id": self.ctx.source_config.source_id,
+            "streams": self.params.streams,
+            "consumer_group": self.consumer_group,
+            "consumer_name": self.params.consumer_name,
+            "current_positions": current_positions,
+            "num_bytes_processed": self.state.num_bytes_processed,
+            "num_messages_processed": self.state.num_messages_processed,
+            "num_invalid_messages": self.state.num_invalid_messages,
+            "num_acked_messages": self.num_acked_messages.load(Ordering::Relaxed),
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct BatchBuilder {
+    docs: Vec<Bytes>,
+    num_bytes: u64,
+    checkpoint_delta: SourceCheckpointDelta,
+}
+
+impl BatchBuilder {
+    fn build(self) -> RawDocBatch {
+        RawDocBatch {
+            docs: self.docs,
+            checkpoint_delta: self.checkpoint_delta,
+            force_commit: false,
+        }
+    }
+
+    fn push(&mut self, doc: Bytes, num_bytes: u64) {
+        self.docs.push(doc);
+        self.num_bytes += num_bytes;
+    }
+}
+
+/// Converts an entry ID `<millis>-<sequence>` into a position. Both parts are zero-padded so that
+/// positions sort like entry IDs.
+fn position_from_entry_id(entry_id: &str) -> anyhow::Result<Position> {
+    let (millis, sequence) = parse_entry_id(entry_id)?;
+    Ok(Position::from(format!("{millis:0>20}-{sequence:0>20}")))
+}
+
+/// Converts a position back into an entry ID, `0` standing for the beginning of the stream.
+fn entry_id_from_position(position: &Position) -> anyhow::Result<String> {
+    match position {
+        Position::Beginning => Ok("0".to_string()),
+        Position::Offset(offset_str) => {
+            let (millis, sequence) = parse_entry_id(offset_str)?;
+            Ok(format!("{millis}-{sequence}"))
+        }
+    }
+}
+
+fn parse_entry_id(entry_id: &str) -> anyhow::Result<(u64, u64)> {
+    let parse = || {
+        let (millis_str, sequence_str) = entry_id.split_once('-')?;
+        let millis = millis_str.parse::<u64>().ok()?;
+        let sequence = sequence_str.parse::<u64>().ok()?;
+        Some((millis, sequence))
+    };
+    parse().with_context(|| format!("Failed to parse Redis stream entry ID `{entry_id}`."))
+}
+
+/// Creates the consumer group or, if it already exists, moves its last delivered ID to
+/// `start_id`.
+async fn reset_consumer_group(
+    connection: &mut MultiplexedConnection,
+    stream: &str,
+    consumer_group: &str,
+    start_id: &str,
+) -> anyhow::Result<()> {
+    let create_result: Result<(), RedisError> = connection
+        .xgroup_create_mkstream(stream, consumer_group, start_id)
+        .await;
+    match create_result {
+        Ok(()) => Ok(()),
+        Err(error) if error.code() == Some("BUSYGROUP") => {
+            info!(stream=%stream, consumer_group=%consumer_group, start_id=%start_id, "Resetting consumer group to last checkpoint position.");
+            redis::cmd("XGROUP")
+                .arg("SETID")
+                .arg(stream)
+                .arg(consumer_group)
+                .arg(start_id)
+                .query_async(connection)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to reset consumer group `{consumer_group}` of stream `{stream}`."
+                    )
+                })
+        }
+        Err(error) => Err(error).with_context(|| {
+            format!("Failed to create consumer group `{consumer_group}` on stream `{stream}`.")
+        }),
+    }
+}
+
+async fn connect_redis(params: &RedisStreamsSourceParams) -> anyhow::Result<MultiplexedConnection> {
+    let client = Client::open(params.address.as_str()).context("Invalid Redis address.")?;
+    let connection = client
+        .get_multiplexed_tokio_connection()
+        .await
+        .context("Failed to connect to Redis.")?;
+    Ok(connection)
+}
+
+/// Checks whether we can establish a connection to Redis and that the streams exist.
+pub(crate) async fn check_connectivity(params: &RedisStreamsSourceParams) -> anyhow::Result<()> {
+    let mut connection = connect_redis(params).await?;
+
+    for stream in &params.streams {
+        let key_type: String = redis::cmd("TYPE")
+            .arg(stream)
+            .query_async(&mut connection)
+            .await?;
+        if key_type != "stream" {
+            bail!("Redis stream `{stream}` does not exist.");
+        }
+    }
+    Ok(())
+}
+
+fn default_consumer_group(index_id: &str, source_id: &str) -> String {
+    format!("quickwit-{index_id}-{source_id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_entry_id_round_trip() {
+        assert_eq!(entry_id_from_position(&Position::Beginning).unwrap(), "0");
+
+        let position = position_from_entry_id("1526919030474-55").unwrap();
+        assert_eq!(
+            position.as_str(),
+            "00000001526919030474-00000000000000000055"
+        );
+        assert_eq!(
+            entry_id_from_position(&position).unwrap(),
+            "1526919030474-55"
+        );
+        assert!(
+            position_from_entry_id("999-1").unwrap() < position_from_entry_id("1000-0").unwrap()
+        );
+        position_from_entry_id("1526919030474").unwrap_err();
+        position_from_entry_id("not-an-id").unwrap_err();
+    }
+}
+
+#[cfg(all(test, feature = "redis-broker-tests"))]
+mod redis_broker_tests {
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+
+    use quickwit_actors::{ActorHandle, Inbox, Universe};
+    use quickwit_common::rand::append_random_suffix;
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_metastore::metastore_for_test;
+
+    use super::*;
+    use crate::source::{quickwit_supported_sources, SuggestTruncate};
+
+    static REDIS_URI: &str = "redis://localhost:6379";
+
+    fn get_source_config(streams: &[String], payload_field: Option<&str>) -> SourceConfig {
+        let source_id = append_random_suffix("test-redis-streams-source--source");
+        SourceConfig {
+            source_id,
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::RedisStreams(RedisStreamsSourceParams {
+                address: REDIS_URI.to_string(),
+                streams: streams.to_vec(),
+                consumer_group: None,
+                consumer_name: "quickwit".to_string(),
+                payload_field: payload_field.map(ToString::to_string),
+            }),
+            transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
+        }
+    }
+
+    /// Appends the entries to the stream and returns their IDs.
+    async fn populate_stream(
+        connection: &mut MultiplexedConnection,
+        stream: &str,
+        entries: &[&[(&str, &str)]],
+    ) -> Vec<String> {
+        let mut entry_ids = Vec::with_capacity(entries.len());
+
+        for fields in entries {
+            let entry_id: String = connection.xadd(stream, "*", *fields).await.unwrap();
+            entry_ids.push(entry_id);
+        }
+        entry_ids
+    }
+
+    async fn create_source(
+        universe: &Universe,
+        source_config: SourceConfig,
+        start_checkpoint: SourceCheckpoint,
+    ) -> (ActorHandle<SourceActor>, Inbox<DocProcessor>) {
+        let ctx = SourceExecutionContext::for_test(
+            metastore_for_test(),
+            "test-redis-streams-source-index",
+            PathBuf::from("./queues"),
+            source_config,
+        );
+        let source = quickwit_supported_sources()
+            .load_source(ctx, start_checkpoint)
+            .await
+            .unwrap();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let source_actor = SourceActor {
+            source,
+            doc_processor_mailbox,
+        };
+        let (_source_mailbox, source_handle) = universe.spawn_builder().spawn(source_actor);
+        (source_handle, doc_processor_inbox)
+    }
+
+    async fn wait_for_num_messages(source_handle: &ActorHandle<SourceActor>, num_messages: u64) {
+        loop {
+            let observation = source_handle.observe().await;
+            if observation.state["num_messages_processed"] == num_messages {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    fn merge_doc_batches(batches: Vec<RawDocBatch>) -> RawDocBatch {
+        let mut merged_batch = RawDocBatch::default();
+        for batch in batches {
+            merged_batch.docs.extend(batch.docs);
+            merged_batch
+                .checkpoint_delta
+                .extend(batch.checkpoint_delta)
+                .unwrap();
+        }
+        merged_batch
+    }
+
+    async fn num_pending_entries(
+        connection: &mut MultiplexedConnection,
+        stream: &str,
+        consumer_group: &str,
+    ) -> usize {
+        let pending_reply: StreamPendingCountReply = connection
+            .xpending_count(stream, consumer_group, "-", "+", 100)
+            .await
+            .unwrap();
+        pending_reply.ids.len()
+    }
+
+    #[tokio::test]
+    async fn test_redis_streams_source() {
+        let universe = Universe::with_accelerated_time();
+        let mut connection = Client::open(REDIS_URI)
+            .unwrap()
+            .get_multiplexed_tokio_connection()
+            .await
+            .unwrap();
+        let stream_1 = append_random_suffix("test-redis-streams-source-stream-1");
+        let stream_2 = append_random_suffix("test-redis-streams-source-stream-2");
+        let entry_ids_1 = populate_stream(
+            &mut connection,
+            &stream_1,
+            &[&[("payload", "doc-1")], &[("payload", "doc-2")]],
+        )
+        .await;
+        let entry_ids_2 = populate_stream(
+            &mut connection,
+            &stream_2,
+            &[&[("payload", "doc-3")], &[("other", "doc-4")]],
+        )
+        .await;
+        let source_config =
+            get_source_config(&[stream_1.clone(), stream_2.clone()], Some("payload"));
+        let consumer_group =
+            default_consumer_group("test-redis-streams-source-index", &source_config.source_id);
+
+        let (source_handle, doc_processor_inbox) = create_source(
+            &universe,
+            source_config.clone(),
+            SourceCheckpoint::default(),
+        )
+        .await;
+        wait_for_num_messages(&source_handle, 4).await;
+
+        let batch = merge_doc_batches(doc_processor_inbox.drain_for_test_typed());
+        let mut docs = batch.docs.clone();
+        docs.sort();
+        assert_eq!(
+            docs,
+            vec![
+                Bytes::from("doc-1"),
+                Bytes::from("doc-2"),
+                Bytes::from("doc-3")
+            ]
+        );
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::default();
+        expected_checkpoint_delta
+            .record_partition_delta(
+                PartitionId::from(stream_1.as_str()),
+                Position::Beginning,
+                position_from_entry_id(&entry_ids_1[1]).unwrap(),
+            )
+            .unwrap();
+        expected_checkpoint_delta
+            .record_partition_delta(
+                PartitionId::from(stream_2.as_str()),
+                Position::Beginning,
+                position_from_entry_id(&entry_ids_2[1]).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(batch.checkpoint_delta, expected_checkpoint_delta);
+
+        let observation = source_handle.observe().await;
+        assert_eq!(observation.state["num_invalid_messages"], 1);
+        assert_eq!(
+            num_pending_entries(&mut connection, &stream_1, &consumer_group).await,
+            2
+        );
+
+        // Only the first entry of the first stream makes it into a published split.
+        let mut checkpoint_delta = SourceCheckpointDelta::default();
+        checkpoint_delta
+            .record_partition_delta(
+                PartitionId::from(stream_1.as_str()),
+                Position::Beginning,
+                position_from_entry_id(&entry_ids_1[0]).unwrap(),
+            )
+            .unwrap();
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+        source_handle
+            .mailbox()
+            .send_message(SuggestTruncate(checkpoint.clone()))
+            .await
+            .unwrap();
+        let observation = source_handle.process_pending_and_observe().await;
+        assert_eq!(observation.state["num_acked_messages"], 1);
+        assert_eq!(
+            num_pending_entries(&mut connection, &stream_1, &consumer_group).await,
+            1
+        );
+        source_handle.quit().await;
+
+        // The next incarnation of the source resumes from the checkpoint.
+        let (source_handle, doc_processor_inbox) =
+            create_source(&universe, source_config, checkpoint).await;
+        wait_for_num_messages(&source_handle, 3).await;
+
+        let batch = merge_doc_batches(doc_processor_inbox.drain_for_test_typed());
+        let mut docs = batch.docs.clone();
+        docs.sort();
+        assert_eq!(docs, vec![Bytes::from("doc-2"), Bytes::from("doc-3")]);
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::default();
+        expected_checkpoint_delta
+            .record_partition_delta(
+                PartitionId::from(stream_1.as_str()),
+                position_from_entry_id(&entry_ids_1[0]).unwrap(),
+                position_from_entry_id(&entry_ids_1[1]).unwrap(),
+            )
+            .unwrap();
+        expected_checkpoint_delta
+            .record_partition_delta(
+                PartitionId::from(stream_2.as_str()),
+                Position::Beginning,
+                position_from_entry_id(&entry_ids_2[1]).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(batch.checkpoint_delta, expected_checkpoint_delta);
+        source_handle.quit().await;
+        universe.assert_quit().await;
+    }
+}
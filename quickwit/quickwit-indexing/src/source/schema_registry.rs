@@ -0,0 +1,662 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context};
+use apache_avro::Schema as AvroSchema;
+use bytes::Bytes;
+use prost_reflect::{DescriptorPool, DynamicMessage, FileDescriptor, MessageDescriptor};
+use protox::file::{ChainFileResolver, File, FileResolver, GoogleFileResolver};
+use quickwit_config::{KafkaPayloadFormat, SchemaRegistryParams};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+
+/// Magic byte opening the payloads framed with the Confluent Schema Registry wire format.
+const MAGIC_BYTE: u8 = 0;
+
+/// Timeout of a single request to the schema registry.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Name under which the main Protobuf schema is compiled.
+const MAIN_PROTO_FILE_NAME: &str = "__schema_registry_main.proto";
+
+#[derive(Debug, Error)]
+pub(crate) enum DecodeError {
+    /// The schema registry could not be reached or failed to serve the schema. The payload may
+    /// decode successfully once the registry is available again.
+    #[error("Schema registry is unavailable: {0:#}")]
+    RegistryUnavailable(anyhow::Error),
+    /// The payload, or the schema it refers to, is invalid.
+    #[error("Invalid payload: {0:#}")]
+    InvalidPayload(anyhow::Error),
+}
+
+/// Decodes Avro and Protobuf payloads framed with the Confluent Schema Registry wire format into
+/// JSON documents. The wire format consists of a zero magic byte, the schema ID as a big-endian
+/// `u32`, and, for Protobuf only, the path to the message type within the schema, followed by the
+/// encoded message.
+///
+/// Schemas are fetched from the schema registry on first use and cached afterwards.
+pub(crate) struct PayloadDecoder {
+    payload_format: KafkaPayloadFormat,
+    registry_client: SchemaRegistryClient,
+    schemas: Mutex<HashMap<u32, Arc<CachedSchema>>>,
+}
+
+enum CachedSchema {
+    Avro(AvroSchema),
+    Protobuf(FileDescriptor),
+}
+
+impl PayloadDecoder {
+    /// Returns `None` for JSON payloads, which need no decoding.
+    pub fn try_new(
+        payload_format: KafkaPayloadFormat,
+        schema_registry_opt: Option<&SchemaRegistryParams>,
+    ) -> anyhow::Result<Option<Self>> {
+        if payload_format.is_json() {
+            return Ok(None);
+        }
+        let schema_registry = schema_registry_opt
+            .context("A schema registry is required to decode Avro or Protobuf payloads.")?;
+        let registry_client = SchemaRegistryClient::try_new(schema_registry)?;
+        let payload_decoder = Self {
+            payload_format,
+            registry_client,
+            schemas: Mutex::default(),
+        };
+        Ok(Some(payload_decoder))
+    }
+
+    /// Decodes the payload into a JSON document.
+    pub async fn decode(&self, payload: &[u8]) -> Result<Bytes, DecodeError> {
+        let (schema_id, encoded_message) =
+            parse_header(payload).map_err(DecodeError::InvalidPayload)?;
+        let schema = self.schema(schema_id).await?;
+        decode_message(&schema, encoded_message).map_err(DecodeError::InvalidPayload)
+    }
+
+    async fn schema(&self, schema_id: u32) -> Result<Arc<CachedSchema>, DecodeError> {
+        if let Some(schema) = self.schemas.lock().unwrap().get(&schema_id) {
+            return Ok(schema.clone());
+        }
+        let registry_schema = self.registry_client.schema_by_id(schema_id).await?;
+        let references = self
+            .registry_client
+            .resolve_references(&registry_schema.references)
+            .await?;
+        let schema = self
+            .build_schema(schema_id, registry_schema, references)
+            .map_err(DecodeError::InvalidPayload)?;
+        let schema = Arc::new(schema);
+        self.schemas
+            .lock()
+            .unwrap()
+            .insert(schema_id, schema.clone());
+        Ok(schema)
+    }
+
+    fn build_schema(
+        &self,
+        schema_id: u32,
+        registry_schema: RegistrySchema,
+        references: HashMap<String, String>,
+    ) -> anyhow::Result<CachedSchema> {
+        let schema = match self.payload_format {
+            KafkaPayloadFormat::Avro => {
+                if !references.is_empty() {
+                    bail!("Avro schema `{schema_id}` has references, which are not supported.");
+                }
+                let avro_schema = AvroSchema::parse_str(&registry_schema.schema)
+                    .with_context(|| format!("Failed to parse Avro schema `{schema_id}`."))?;
+                CachedSchema::Avro(avro_schema)
+            }
+            KafkaPayloadFormat::Protobuf => {
+                let file_descriptor = compile_protobuf_schema(&registry_schema.schema, references)
+                    .with_context(|| format!("Failed to compile Protobuf schema `{schema_id}`."))?;
+                CachedSchema::Protobuf(file_descriptor)
+            }
+            KafkaPayloadFormat::Json => unreachable!("JSON payloads are not decoded."),
+        };
+        Ok(schema)
+    }
+}
+
+fn decode_message(schema: &CachedSchema, encoded_message: &[u8]) -> anyhow::Result<Bytes> {
+    let json_doc = match schema {
+        CachedSchema::Avro(avro_schema) => decode_avro(avro_schema, encoded_message)?,
+        CachedSchema::Protobuf(file_descriptor) => {
+            decode_protobuf(file_descriptor, encoded_message)?
+        }
+    };
+    let doc = serde_json::to_vec(&json_doc)?;
+    Ok(Bytes::from(doc))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RegistrySchema {
+    schema: String,
+    #[serde(default)]
+    references: Vec<SchemaReference>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SchemaReference {
+    /// Name under which the referencing schema imports the referenced schema.
+    name: String,
+    subject: String,
+    version: i32,
+}
+
+struct SchemaRegistryClient {
+    client: reqwest::Client,
+    params: SchemaRegistryParams,
+}
+
+impl SchemaRegistryClient {
+    fn try_new(params: &SchemaRegistryParams) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            client,
+            params: params.clone(),
+        })
+    }
+
+    /// Connection errors, timeouts, throttling and server errors are reported as
+    /// [`DecodeError::RegistryUnavailable`], other failures as [`DecodeError::InvalidPayload`].
+    async fn get(&self, path: &str) -> Result<RegistrySchema, DecodeError> {
+        let url = format!("{}/{path}", self.params.url.trim_end_matches('/'));
+        let mut request = self
+            .client
+            .get(&url)
+            .header("Accept", "application/vnd.schemaregistry.v1+json");
+
+        if let Some(username) = &self.params.username {
+            request = request.basic_auth(username, self.params.password.as_ref());
+        }
+        let response = request.send().await.map_err(|error| {
+            DecodeError::RegistryUnavailable(
+                anyhow::Error::new(error).context(format!("Failed to request `{url}`.")),
+            )
+        })?;
+        let status = response.status();
+
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(DecodeError::RegistryUnavailable(anyhow!(
+                "Request to `{url}` failed with status `{status}`."
+            )));
+        }
+        if !status.is_success() {
+            return Err(DecodeError::InvalidPayload(anyhow!(
+                "Request to `{url}` failed with status `{status}`."
+            )));
+        }
+        response.json::<RegistrySchema>().await.map_err(|error| {
+            let is_decode_error = error.is_decode();
+            let error = anyhow::Error::new(error).context(format!(
+                "Failed to read schema registry response for `{url}`."
+            ));
+            if is_decode_error {
+                DecodeError::InvalidPayload(error)
+            } else {
+                DecodeError::RegistryUnavailable(error)
+            }
+        })
+    }
+
+    async fn schema_by_id(&self, schema_id: u32) -> Result<RegistrySchema, DecodeError> {
+        self.get(&format!("schemas/ids/{schema_id}")).await
+    }
+
+    /// Fetches the schemas referenced, directly or transitively, by a schema and returns them
+    /// keyed by their import name.
+    async fn resolve_references(
+        &self,
+        references: &[SchemaReference],
+    ) -> Result<HashMap<String, String>, DecodeError> {
+        let mut resolved_references = HashMap::new();
+        let mut pending_references: Vec<(String, String, i32)> = references
+            .iter()
+            .map(|reference| {
+                (
+                    reference.name.clone(),
+                    reference.subject.clone(),
+                    reference.version,
+                )
+            })
+            .collect();
+
+        while let Some((name, subject, version)) = pending_references.pop() {
+            if resolved_references.contains_key(&name) {
+                continue;
+            }
+            let registry_schema = self
+                .get(&format!("subjects/{subject}/versions/{version}"))
+                .await?;
+            pending_references.extend(
+                registry_schema
+                    .references
+                    .into_iter()
+                    .map(|reference| (reference.name, reference.subject, reference.version)),
+            );
+            resolved_references.insert(name, registry_schema.schema);
+        }
+        Ok(resolved_references)
+    }
+}
+
+/// Splits the payload into the schema ID and the encoded message.
+fn parse_header(payload: &[u8]) -> anyhow::Result<(u32, &[u8])> {
+    if payload.len() < 5 {
+        bail!("Payload is too short to hold a schema registry header.");
+    }
+    if payload[0] != MAGIC_BYTE {
+        bail!(
+            "Payload starts with byte `{}` instead of the schema registry magic byte.",
+            payload[0]
+        );
+    }
+    let schema_id = u32::from_be_bytes([payload[1], payload[2], payload[3], payload[4]]);
+    Ok((schema_id, &payload[5..]))
+}
+
+fn decode_avro(avro_schema: &AvroSchema, encoded_message: &[u8]) -> anyhow::Result<JsonValue> {
+    let mut reader = encoded_message;
+    let avro_value = apache_avro::from_avro_datum(avro_schema, &mut reader, None)
+        .context("Failed to decode Avro payload.")?;
+    let json_value =
+        JsonValue::try_from(avro_value).context("Failed to convert Avro payload to JSON.")?;
+    Ok(json_value)
+}
+
+fn decode_protobuf(
+    file_descriptor: &FileDescriptor,
+    encoded_message: &[u8],
+) -> anyhow::Result<JsonValue> {
+    let mut reader = encoded_message;
+    let message_indexes = read_message_indexes(&mut reader)?;
+    let message_descriptor = find_message_descriptor(file_descriptor, &message_indexes)
+        .with_context(|| {
+            format!("Protobuf schema has no message type at indexes `{message_indexes:?}`.")
+        })?;
+    let message = DynamicMessage::decode(message_descriptor, reader)
+        .context("Failed to decode Protobuf payload.")?;
+    let json_value = serde_json::to_value(&message)?;
+    Ok(json_value)
+}
+
+/// Reads the path to the message type within the schema. The path is encoded as an array of
+/// zigzag varints, with the array `[0]` shortened to a single zero byte.
+fn read_message_indexes(reader: &mut &[u8]) -> anyhow::Result<Vec<usize>> {
+    let num_indexes = read_zigzag_varint(reader)?;
+
+    if num_indexes == 0 {
+        return Ok(vec![0]);
+    }
+    if num_indexes < 0 {
+        bail!("Invalid number of Protobuf message indexes `{num_indexes}`.");
+    }
+    (0..num_indexes)
+        .map(|_| {
+            let index = read_zigzag_varint(reader)?;
+            usize::try_from(index)
+                .with_context(|| format!("Invalid Protobuf message index `{index}`."))
+        })
+        .collect()
+}
+
+fn read_zigzag_varint(reader: &mut &[u8]) -> anyhow::Result<i64> {
+    let mut value: u64 = 0;
+
+    for shift in (0..64).step_by(7) {
+        let Some((&byte, remaining)) = reader.split_first() else {
+            bail!("Payload ended in the middle of a varint.");
+        };
+        *reader = remaining;
+        value |= u64::from(byte & 0x7F) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok((value >> 1) as i64 ^ -((value & 1) as i64));
+        }
+    }
+    bail!("Varint is longer than 10 bytes.")
+}
+
+fn find_message_descriptor(
+    file_descriptor: &FileDescriptor,
+    message_indexes: &[usize],
+) -> Option<MessageDescriptor> {
+    let (first_index, child_indexes) = message_indexes.split_first()?;
+    let mut message_descriptor = file_descriptor.messages().nth(*first_index)?;
+
+    for child_index in child_indexes {
+        message_descriptor = message_descriptor.child_messages().nth(*child_index)?;
+    }
+    Some(message_descriptor)
+}
+
+/// Resolves the imports of the main schema against its references, then against the well-known
+/// Google types.
+struct ReferencesFileResolver {
+    files: HashMap<String, String>,
+}
+
+impl FileResolver for ReferencesFileResolver {
+    fn open_file(&self, name: &str) -> Result<File, protox::Error> {
+        match self.files.get(name) {
+            Some(source) => File::from_source(name, source),
+            None => Err(protox::Error::file_not_found(name)),
+        }
+    }
+}
+
+fn compile_protobuf_schema(
+    schema: &str,
+    mut references: HashMap<String, String>,
+) -> anyhow::Result<FileDescriptor> {
+    references.insert(MAIN_PROTO_FILE_NAME.to_string(), schema.to_string());
+
+    let mut file_resolver = ChainFileResolver::new();
+    file_resolver.add(ReferencesFileResolver { files: references });
+    file_resolver.add(GoogleFileResolver::new());
+
+    let mut compiler = protox::Compiler::with_file_resolver(file_resolver);
+    compiler.include_imports(true);
+    compiler.open_file(MAIN_PROTO_FILE_NAME)?;
+
+    let descriptor_pool: DescriptorPool = compiler.descriptor_pool();
+    let file_descriptor = descriptor_pool
+        .get_file_by_name(MAIN_PROTO_FILE_NAME)
+        .context("Compiled Protobuf schema is missing.")?;
+    Ok(file_descriptor)
+}
+
+#[cfg(test)]
+mod tests {
+    use apache_avro::types::Record;
+    use prost_reflect::Value as ProtobufValue;
+    use serde_json::json;
+    use wiremock::matchers::{basic_auth, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    const AVRO_SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "Event",
+        "fields": [
+            {"name": "user", "type": "string"},
+            {"name": "count", "type": "long"},
+            {"name": "tag", "type": ["null", "string"], "default": null}
+        ]
+    }"#;
+
+    const PROTOBUF_SCHEMA: &str = r#"
+        syntax = "proto3";
+        package test;
+
+        import "common.proto";
+
+        message Unused {
+            string unused = 1;
+        }
+
+        message Event {
+            message Nested {
+                string user = 1;
+                test.common.Level level = 2;
+            }
+        }
+    "#;
+
+    const PROTOBUF_COMMON_SCHEMA: &str = r#"
+        syntax = "proto3";
+        package test.common;
+
+        enum Level {
+            INFO = 0;
+            WARN = 1;
+        }
+    "#;
+
+    fn frame_payload(schema_id: u32, prefix: &[u8], encoded_message: &[u8]) -> Vec<u8> {
+        let mut payload = vec![MAGIC_BYTE];
+        payload.extend_from_slice(&schema_id.to_be_bytes());
+        payload.extend_from_slice(prefix);
+        payload.extend_from_slice(encoded_message);
+        payload
+    }
+
+    fn schema_registry_params(mock_server: &MockServer) -> SchemaRegistryParams {
+        SchemaRegistryParams {
+            url: mock_server.uri(),
+            username: Some("my-user".to_string()),
+            password: Some("my-password".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let payload = frame_payload(42, &[], b"message");
+        let (schema_id, encoded_message) = parse_header(&payload).unwrap();
+        assert_eq!(schema_id, 42);
+        assert_eq!(encoded_message, b"message");
+
+        parse_header(&[MAGIC_BYTE, 0, 0]).unwrap_err();
+        parse_header(b"{\"user\": \"alice\"}").unwrap_err();
+    }
+
+    #[test]
+    fn test_read_message_indexes() {
+        let mut reader: &[u8] = &[0, 1];
+        assert_eq!(read_message_indexes(&mut reader).unwrap(), vec![0]);
+        assert_eq!(reader, &[1]);
+
+        // Zigzag encoding of `[1, 0]`.
+        let mut reader: &[u8] = &[4, 2, 0];
+        assert_eq!(read_message_indexes(&mut reader).unwrap(), vec![1, 0]);
+        assert!(reader.is_empty());
+
+        let mut reader: &[u8] = &[4, 2];
+        read_message_indexes(&mut reader).unwrap_err();
+    }
+
+    #[test]
+    fn test_payload_decoder_is_none_for_json() {
+        assert!(PayloadDecoder::try_new(KafkaPayloadFormat::Json, None)
+            .unwrap()
+            .is_none());
+        assert!(PayloadDecoder::try_new(KafkaPayloadFormat::Avro, None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_payload_decoder_avro() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/schemas/ids/7"))
+            .and(basic_auth("my-user", "my-password"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "schema": AVRO_SCHEMA,
+            })))
+            // The schema is fetched once and then served from the cache.
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let payload_decoder = PayloadDecoder::try_new(
+            KafkaPayloadFormat::Avro,
+            Some(&schema_registry_params(&mock_server)),
+        )
+        .unwrap()
+        .unwrap();
+
+        let avro_schema = AvroSchema::parse_str(AVRO_SCHEMA).unwrap();
+        for (user, count) in [("alice", 1), ("bob", 2)] {
+            let mut record = Record::new(&avro_schema).unwrap();
+            record.put("user", user);
+            record.put("count", count as i64);
+            record.put(
+                "tag",
+                apache_avro::types::Value::Union(0, Box::new(apache_avro::types::Value::Null)),
+            );
+            let encoded_message = apache_avro::to_avro_datum(&avro_schema, record).unwrap();
+            let payload = frame_payload(7, &[], &encoded_message);
+
+            let doc = payload_decoder.decode(&payload).await.unwrap();
+            let json_doc: JsonValue = serde_json::from_slice(&doc).unwrap();
+            assert_eq!(json_doc, json!({"user": user, "count": count, "tag": null}));
+        }
+        // Corrupted payloads are rejected.
+        let payload = frame_payload(7, &[], &[0xFF]);
+        let decode_error = payload_decoder.decode(&payload).await.unwrap_err();
+        assert!(matches!(decode_error, DecodeError::InvalidPayload(_)));
+    }
+
+    #[tokio::test]
+    async fn test_payload_decoder_protobuf() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/schemas/ids/3"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "schemaType": "PROTOBUF",
+                "schema": PROTOBUF_SCHEMA,
+                "references": [
+                    {"name": "common.proto", "subject": "common", "version": 2}
+                ],
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/subjects/common/versions/2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "subject": "common",
+                "version": 2,
+                "id": 2,
+                "schemaType": "PROTOBUF",
+                "schema": PROTOBUF_COMMON_SCHEMA,
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let payload_decoder = PayloadDecoder::try_new(
+            KafkaPayloadFormat::Protobuf,
+            Some(&schema_registry_params(&mock_server)),
+        )
+        .unwrap()
+        .unwrap();
+
+        let file_descriptor = compile_protobuf_schema(
+            PROTOBUF_SCHEMA,
+            HashMap::from([(
+                "common.proto".to_string(),
+                PROTOBUF_COMMON_SCHEMA.to_string(),
+            )]),
+        )
+        .unwrap();
+        let message_descriptor = find_message_descriptor(&file_descriptor, &[1, 0]).unwrap();
+        assert_eq!(message_descriptor.full_name(), "test.Event.Nested");
+
+        let mut message = DynamicMessage::new(message_descriptor);
+        message.set_field_by_name("user", ProtobufValue::String("alice".to_string()));
+        message.set_field_by_name("level", ProtobufValue::EnumNumber(1));
+        let encoded_message = prost::Message::encode_to_vec(&message);
+        // Zigzag encoding of the message indexes `[1, 0]`.
+        let payload = frame_payload(3, &[4, 2, 0], &encoded_message);
+
+        for _ in 0..2 {
+            let doc = payload_decoder.decode(&payload).await.unwrap();
+            let json_doc: JsonValue = serde_json::from_slice(&doc).unwrap();
+            assert_eq!(json_doc, json!({"user": "alice", "level": "WARN"}));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_payload_decoder_unknown_schema() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/schemas/ids/1"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(json!({
+                "error_code": 40403,
+                "message": "Schema not found",
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let payload_decoder = PayloadDecoder::try_new(
+            KafkaPayloadFormat::Avro,
+            Some(&schema_registry_params(&mock_server)),
+        )
+        .unwrap()
+        .unwrap();
+        let payload = frame_payload(1, &[], &[2]);
+        let decode_error = payload_decoder.decode(&payload).await.unwrap_err();
+        assert!(matches!(decode_error, DecodeError::InvalidPayload(_)));
+    }
+
+    #[tokio::test]
+    async fn test_payload_decoder_registry_unavailable() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/schemas/ids/7"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/schemas/ids/7"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "schema": AVRO_SCHEMA,
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let payload_decoder = PayloadDecoder::try_new(
+            KafkaPayloadFormat::Avro,
+            Some(&schema_registry_params(&mock_server)),
+        )
+        .unwrap()
+        .unwrap();
+
+        let avro_schema = AvroSchema::parse_str(AVRO_SCHEMA).unwrap();
+        let mut record = Record::new(&avro_schema).unwrap();
+        record.put("user", "alice");
+        record.put("count", 1i64);
+        record.put(
+            "tag",
+            apache_avro::types::Value::Union(0, Box::new(apache_avro::types::Value::Null)),
+        );
+        let encoded_message = apache_avro::to_avro_datum(&avro_schema, record).unwrap();
+        let payload = frame_payload(7, &[], &encoded_message);
+
+        let decode_error = payload_decoder.decode(&payload).await.unwrap_err();
+        assert!(matches!(decode_error, DecodeError::RegistryUnavailable(_)));
+
+        // The failure is not cached, so the payload decodes once the registry is back.
+        payload_decoder.decode(&payload).await.unwrap();
+    }
+}
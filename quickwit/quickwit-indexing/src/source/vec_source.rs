@@ -130,7 +130,7 @@ mod tests {
 
     use bytes::Bytes;
     use quickwit_actors::{Actor, Command, Universe};
-    use quickwit_config::{SourceConfig, SourceParams};
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
     use quickwit_metastore::metastore_for_test;
     use serde_json::json;
 
@@ -162,6 +162,8 @@ mod tests {
                     enabled: true,
                     source_params: SourceParams::Vec(params.clone()),
                     transform_config: None,
+                    dead_letter_queue_enabled: false,
+                    input_format: SourceInputFormat::default(),
                 },
             ),
             params,
@@ -221,6 +223,8 @@ mod tests {
                     enabled: true,
                     source_params: SourceParams::Vec(params.clone()),
                     transform_config: None,
+                    dead_letter_queue_enabled: false,
+                    input_format: SourceInputFormat::default(),
                 },
             ),
             params,
@@ -75,7 +75,7 @@ mod tests {
     use std::path::PathBuf;
 
     use quickwit_actors::{Health, Supervisable, Universe};
-    use quickwit_config::SourceParams;
+    use quickwit_config::{SourceInputFormat, SourceParams};
     use quickwit_metastore::checkpoint::SourceCheckpoint;
     use quickwit_metastore::metastore_for_test;
     use serde_json::json;
@@ -92,6 +92,8 @@ mod tests {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         let metastore = metastore_for_test();
         let ctx = SourceExecutionContext::for_test(
@@ -123,6 +125,8 @@ mod tests {
                     enabled: true,
                     source_params: SourceParams::void(),
                     transform_config: None,
+                    dead_letter_queue_enabled: false,
+                    input_format: SourceInputFormat::default(),
                 },
             ),
             VoidSourceParams,
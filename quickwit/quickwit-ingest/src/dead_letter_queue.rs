@@ -0,0 +1,353 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Dead-letter queues store the documents rejected by the doc processor of an indexing pipeline
+//! along with the reason why they were rejected. They are regular ingest API queues identified by
+//! `_dead-letters.{index_id}.{source_id}`. Since neither index IDs nor source IDs may contain a
+//! dot, the queue ID is unambiguous.
+
+use bytes::Bytes;
+use quickwit_actors::Mailbox;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    CommitType, DocBatchBuilder, DocCommand, FetchRequest, IngestApiService, IngestRequest,
+    IngestServiceError, SuggestTruncateRequest,
+};
+
+const DEAD_LETTER_QUEUE_PREFIX: &str = "_dead-letters.";
+
+/// Number of dead letters re-ingested at once by [`replay_dead_letters`].
+const REPLAY_BATCH_NUM_DEAD_LETTERS: usize = 1_000;
+
+/// Returns the ID of the dead-letter queue of the source `source_id` of index `index_id`.
+pub fn dead_letter_queue_id(index_id: &str, source_id: &str) -> String {
+    format!("{DEAD_LETTER_QUEUE_PREFIX}{index_id}.{source_id}")
+}
+
+/// Parses a dead-letter queue ID into an `(index_id, source_id)` pair. Returns `None` if the queue
+/// is not a dead-letter queue.
+pub fn parse_dead_letter_queue_id(queue_id: &str) -> Option<(&str, &str)> {
+    queue_id
+        .strip_prefix(DEAD_LETTER_QUEUE_PREFIX)?
+        .split_once('.')
+}
+
+/// Reason why a document was rejected by the doc processor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadLetterErrorKind {
+    /// The document could not be parsed or does not match the doc mapping.
+    ParsingError,
+    /// The VRL transform failed or did not return an object.
+    TransformError,
+    /// The document is missing a field required by the doc mapping (timestamp, partition key...).
+    MissingField,
+}
+
+/// A document rejected by the doc processor.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeadLetter {
+    /// Raw document, encoded in base64.
+    #[serde(with = "serde_base64")]
+    #[schema(value_type = String)]
+    pub doc: Bytes,
+    pub error_kind: DeadLetterErrorKind,
+    pub error_message: String,
+    pub source_position: DeadLetterSourcePosition,
+}
+
+/// Position of a rejected document in the source.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeadLetterSourcePosition {
+    /// Checkpoint delta of the batch the document belongs to.
+    pub batch_delta: String,
+    /// Offset of the document within the batch.
+    pub doc_offset: u64,
+}
+
+mod serde_base64 {
+    use base64::prelude::{Engine, BASE64_STANDARD};
+    use bytes::Bytes;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Bytes, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Bytes, D::Error>
+    where D: Deserializer<'de> {
+        let b64_bytes = String::deserialize(deserializer)?;
+        let bytes = BASE64_STANDARD
+            .decode(b64_bytes)
+            .map_err(de::Error::custom)?;
+        Ok(Bytes::from(bytes))
+    }
+}
+
+/// A dead letter and its position in the dead-letter queue.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeadLetterRecord {
+    pub position: u64,
+    #[serde(flatten)]
+    pub dead_letter: DeadLetter,
+}
+
+/// Returns up to `max_num_dead_letters` dead letters of the source `source_id` of index
+/// `index_id`, starting after position `start_after`. Returns an empty list if no document has
+/// ever been rejected.
+pub async fn fetch_dead_letters(
+    ingest_api_service: &Mailbox<IngestApiService>,
+    index_id: &str,
+    source_id: &str,
+    mut start_after: Option<u64>,
+    max_num_dead_letters: usize,
+) -> crate::Result<Vec<DeadLetterRecord>> {
+    let queue_id = dead_letter_queue_id(index_id, source_id);
+    let mut dead_letter_records = Vec::new();
+
+    while dead_letter_records.len() < max_num_dead_letters {
+        let fetch_request = FetchRequest {
+            index_id: queue_id.clone(),
+            start_after,
+            num_bytes_limit: None,
+        };
+        let fetch_response = match ingest_api_service.ask_for_res(fetch_request).await {
+            Ok(fetch_response) => fetch_response,
+            Err(error) => match IngestServiceError::from(error) {
+                IngestServiceError::IndexNotFound { .. } => return Ok(Vec::new()),
+                error => return Err(error),
+            },
+        };
+        let (Some(first_position), Some(doc_batch)) =
+            (fetch_response.first_position, fetch_response.doc_batch)
+        else {
+            break;
+        };
+        if doc_batch.is_empty() {
+            break;
+        }
+        for (position, doc_command) in (first_position..).zip(doc_batch.iter()) {
+            if dead_letter_records.len() == max_num_dead_letters {
+                break;
+            }
+            start_after = Some(position);
+
+            if let DocCommand::Ingest { payload } = doc_command {
+                let dead_letter: DeadLetter =
+                    serde_json::from_slice(&payload).map_err(|error| {
+                        IngestServiceError::Corruption(format!(
+                            "Failed to deserialize dead letter at position {position} in queue \
+                             `{queue_id}`: {error}"
+                        ))
+                    })?;
+                dead_letter_records.push(DeadLetterRecord {
+                    position,
+                    dead_letter,
+                });
+            }
+        }
+    }
+    Ok(dead_letter_records)
+}
+
+/// Re-ingests the dead letters of the source `source_id` into the ingest API queue of index
+/// `index_id` and truncates the dead-letter queue. Returns the number of replayed documents.
+///
+/// Replayed documents are consumed by the ingest API source of the index, so they do not go
+/// through the input format nor the VRL transform of the source `source_id`: callers must make
+/// sure the source has neither.
+///
+/// Dead letters are replayed in batches, and a batch is only truncated from the dead-letter queue
+/// once it has been re-ingested. If the replay fails, the dead letters not replayed yet remain in
+/// the queue.
+pub async fn replay_dead_letters(
+    ingest_api_service: &Mailbox<IngestApiService>,
+    index_id: &str,
+    source_id: &str,
+) -> crate::Result<u64> {
+    let mut num_replayed_docs = 0;
+    let mut start_after: Option<u64> = None;
+    loop {
+        let dead_letter_records = fetch_dead_letters(
+            ingest_api_service,
+            index_id,
+            source_id,
+            start_after,
+            REPLAY_BATCH_NUM_DEAD_LETTERS,
+        )
+        .await?;
+        let Some(last_position) = dead_letter_records.last().map(|record| record.position) else {
+            break;
+        };
+        let mut doc_batch = DocBatchBuilder::new(index_id.to_string());
+        for dead_letter_record in &dead_letter_records {
+            doc_batch.ingest_doc(&dead_letter_record.dead_letter.doc[..]);
+        }
+        let ingest_request = IngestRequest {
+            doc_batches: vec![doc_batch.build()],
+            commit: CommitType::Auto as u32,
+        };
+        let ingest_response = ingest_api_service
+            .ask_for_res(ingest_request)
+            .await
+            .map_err(IngestServiceError::from)?;
+        num_replayed_docs += ingest_response.num_docs_for_processing;
+
+        let suggest_truncate_request = SuggestTruncateRequest {
+            index_id: dead_letter_queue_id(index_id, source_id),
+            up_to_position_included: last_position,
+        };
+        ingest_api_service
+            .ask_for_res(suggest_truncate_request)
+            .await
+            .map_err(IngestServiceError::from)?;
+        start_after = Some(last_position);
+    }
+    Ok(num_replayed_docs)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_actors::Universe;
+    use quickwit_config::IngestApiConfig;
+
+    use super::*;
+    use crate::{init_ingest_api, CreateQueueIfNotExistsRequest};
+
+    #[test]
+    fn test_dead_letter_queue_id() {
+        let queue_id = dead_letter_queue_id("test-index", "test-source");
+        assert_eq!(queue_id, "_dead-letters.test-index.test-source");
+        assert_eq!(
+            parse_dead_letter_queue_id(&queue_id),
+            Some(("test-index", "test-source"))
+        );
+        assert!(parse_dead_letter_queue_id("test-index").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_and_replay_dead_letters() {
+        let universe = Universe::with_accelerated_time();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let ingest_api_service =
+            init_ingest_api(&universe, temp_dir.path(), &IngestApiConfig::default())
+                .await
+                .unwrap();
+        let dead_letters =
+            fetch_dead_letters(&ingest_api_service, "test-index", "test-source", None, 10)
+                .await
+                .unwrap();
+        assert!(dead_letters.is_empty());
+
+        ingest_api_service
+            .ask_for_res(CreateQueueIfNotExistsRequest {
+                queue_id: dead_letter_queue_id("test-index", "test-source"),
+            })
+            .await
+            .unwrap();
+        let mut doc_batch =
+            DocBatchBuilder::new(dead_letter_queue_id("test-index", "test-source")).json_writer();
+        let docs: [&'static [u8]; 3] = [
+            br#"{"body": "foo"}"#,
+            b"not json \xff",
+            br#"{"body": "bar"}"#,
+        ];
+        for (doc_offset, doc) in docs.into_iter().enumerate() {
+            let dead_letter = DeadLetter {
+                doc: Bytes::from_static(doc),
+                error_kind: DeadLetterErrorKind::ParsingError,
+                error_message: "Failed to parse document.".to_string(),
+                source_position: DeadLetterSourcePosition {
+                    batch_delta: "∆(:(..00000000000000000002])".to_string(),
+                    doc_offset: doc_offset as u64,
+                },
+            };
+            doc_batch.ingest_doc(dead_letter).unwrap();
+        }
+        let ingest_request = IngestRequest {
+            doc_batches: vec![doc_batch.build()],
+            commit: CommitType::Auto as u32,
+        };
+        ingest_api_service
+            .ask_for_res(ingest_request)
+            .await
+            .unwrap();
+
+        let dead_letters =
+            fetch_dead_letters(&ingest_api_service, "test-index", "test-source", None, 2)
+                .await
+                .unwrap();
+        assert_eq!(dead_letters.len(), 2);
+        assert_eq!(dead_letters[0].position, 0);
+        assert_eq!(dead_letters[0].dead_letter.doc, r#"{"body": "foo"}"#);
+        assert_eq!(dead_letters[1].position, 1);
+        assert_eq!(dead_letters[1].dead_letter.doc, &b"not json \xff"[..]);
+        assert_eq!(dead_letters[1].dead_letter.source_position.doc_offset, 1);
+
+        let dead_letters =
+            fetch_dead_letters(&ingest_api_service, "test-index", "test-source", Some(1), 2)
+                .await
+                .unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].position, 2);
+        assert_eq!(dead_letters[0].dead_letter.doc, r#"{"body": "bar"}"#);
+
+        // The index has no ingest API queue yet: the replay fails and the dead letters are kept.
+        let replay_error = replay_dead_letters(&ingest_api_service, "test-index", "test-source")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            replay_error,
+            IngestServiceError::IndexNotFound { .. }
+        ));
+        let dead_letters =
+            fetch_dead_letters(&ingest_api_service, "test-index", "test-source", None, 10)
+                .await
+                .unwrap();
+        assert_eq!(dead_letters.len(), 3);
+
+        ingest_api_service
+            .ask_for_res(CreateQueueIfNotExistsRequest {
+                queue_id: "test-index".to_string(),
+            })
+            .await
+            .unwrap();
+        let num_replayed_docs =
+            replay_dead_letters(&ingest_api_service, "test-index", "test-source")
+                .await
+                .unwrap();
+        assert_eq!(num_replayed_docs, 3);
+
+        let dead_letters =
+            fetch_dead_letters(&ingest_api_service, "test-index", "test-source", None, 10)
+                .await
+                .unwrap();
+        assert!(dead_letters.is_empty());
+
+        let fetch_request = FetchRequest {
+            index_id: "test-index".to_string(),
+            start_after: None,
+            num_bytes_limit: None,
+        };
+        let fetch_response = ingest_api_service.ask_for_res(fetch_request).await.unwrap();
+        assert_eq!(fetch_response.doc_batch.unwrap().num_docs(), 3);
+        universe.assert_quit().await;
+    }
+}
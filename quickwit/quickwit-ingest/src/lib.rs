@@ -19,6 +19,7 @@
 
 #![deny(clippy::disallowed_methods)]
 
+mod dead_letter_queue;
 mod errors;
 mod ingest_api_service;
 #[path = "codegen/ingest_service.rs"]
@@ -33,6 +34,10 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context};
+pub use dead_letter_queue::{
+    dead_letter_queue_id, fetch_dead_letters, parse_dead_letter_queue_id, replay_dead_letters,
+    DeadLetter, DeadLetterErrorKind, DeadLetterRecord, DeadLetterSourcePosition,
+};
 pub use errors::IngestServiceError;
 pub use ingest_api_service::{GetMemoryCapacity, GetPartitionId, IngestApiService};
 pub use ingest_service::*;
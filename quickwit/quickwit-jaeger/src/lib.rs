@@ -260,6 +260,15 @@ impl JaegerService {
             sort_order: None,
             sort_by_field: None,
             snippet_fields: Vec::new(),
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         };
         let search_response = self.search_service.root_search(search_request).await?;
 
@@ -309,6 +318,15 @@ impl JaegerService {
             sort_by_field: None,
             aggregation_request: None,
             snippet_fields: Vec::new(),
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         };
         let search_response = match self.search_service.root_search(search_request).await {
             Ok(search_response) => search_response,
@@ -214,6 +214,7 @@ impl DeleteTaskPipeline {
         let task_planner = DeleteTaskPlanner::new(
             self.index_id.clone(),
             index_uri.clone(),
+            index_config.encryption_key_uri().map(str::to_string),
             doc_mapper_str,
             self.metastore.clone(),
             self.search_job_placer.clone(),
@@ -76,6 +76,7 @@ const NUM_STALE_SPLITS_TO_FETCH: usize = 1000;
 pub struct DeleteTaskPlanner {
     index_id: String,
     index_uri: Uri,
+    encryption_key_uri_opt: Option<String>,
     doc_mapper_str: String,
     metastore: Arc<dyn Metastore>,
     search_job_placer: SearchJobPlacer,
@@ -121,6 +122,7 @@ impl DeleteTaskPlanner {
     pub fn new(
         index_id: String,
         index_uri: Uri,
+        encryption_key_uri_opt: Option<String>,
         doc_mapper_str: String,
         metastore: Arc<dyn Metastore>,
         search_job_placer: SearchJobPlacer,
@@ -130,6 +132,7 @@ impl DeleteTaskPlanner {
         Self {
             index_id,
             index_uri,
+            encryption_key_uri_opt,
             doc_mapper_str,
             metastore,
             search_job_placer,
@@ -303,6 +306,7 @@ impl DeleteTaskPlanner {
                 &search_request,
                 doc_mapper_str,
                 index_uri,
+                self.encryption_key_uri_opt.as_deref(),
                 vec![search_job.clone()],
             );
             let response = search_client.leaf_search(leaf_search_request).await?;
@@ -502,6 +506,7 @@ mod tests {
         let delete_planner_executor = DeleteTaskPlanner::new(
             index_id.to_string(),
             index_config.index_uri.clone(),
+            None,
             doc_mapper_str,
             metastore.clone(),
             search_job_placer,
@@ -141,8 +141,9 @@ impl DeleteTaskService {
         ctx: &ActorContext<Self>,
     ) -> anyhow::Result<()> {
         let delete_task_service_dir = self.data_dir_path.join(DELETE_SERVICE_TASK_DIR_NAME);
-        let index_uri = index_config.index_uri.clone();
-        let index_storage = self.storage_resolver.resolve(&index_uri)?;
+        let index_storage = self
+            .storage_resolver
+            .resolve_with_encryption(&index_config.index_uri, index_config.encryption_key_uri())?;
         let pipeline = DeleteTaskPipeline::new(
             index_config.index_id.clone(),
             self.metastore.clone(),
@@ -22,7 +22,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
-use quickwit_storage::{Storage, StorageError, StorageErrorKind};
+use quickwit_storage::{decrypt_bytes, encrypt_bytes, Storage, StorageError, StorageErrorKind};
 use serde::{Deserialize, Serialize};
 
 use super::{IndexState, LazyFileBackedIndex};
@@ -162,6 +162,11 @@ pub(crate) async fn fetch_index(
         .get_all(&metadata_path)
         .await
         .map_err(|storage_err| convert_error(index_id, storage_err))?;
+    // Encrypted manifests carry their key URI in their header, so they can be decrypted before
+    // we know anything about the index config.
+    let content = decrypt_bytes(content)
+        .await
+        .map_err(|storage_err| convert_error(index_id, storage_err))?;
 
     let index: FileBackedIndex = serde_json::from_slice(&content[..]).map_err(|serde_err| {
         MetastoreError::InvalidManifest {
@@ -206,6 +211,12 @@ pub(crate) async fn put_index_given_index_id(
             message: "Failed to serialize Metadata set".to_string(),
             cause: serde_err.to_string(),
         })?;
+    let content = match index.metadata().index_config().encryption_key_uri() {
+        Some(key_uri) => encrypt_bytes(key_uri, &content)
+            .await
+            .map_err(|storage_err| convert_error(index_id, storage_err))?,
+        None => content,
+    };
 
     let metadata_path = meta_path(index_id);
     // Put data back into storage.
@@ -108,6 +108,23 @@ pub fn quickwit_metastore_uri_resolver() -> &'static MetastoreUriResolver {
             )
         }
 
+        #[cfg(feature = "gcs")]
+        {
+            builder = builder.register(Protocol::Gcs, FileBackedMetastoreFactory::default());
+        }
+
+        #[cfg(not(feature = "gcs"))]
+        {
+            builder = builder.register(
+                Protocol::Gcs,
+                UnsupportedMetastore {
+                    message: "gcs unsupported, quickwit was compiled without the `gcs` feature \
+                              flag"
+                        .to_string(),
+                },
+            )
+        }
+
         builder.build()
     })
 }
@@ -28,7 +28,7 @@ pub mod test_suite {
     use futures::future::try_join_all;
     use itertools::Itertools;
     use quickwit_common::rand::append_random_suffix;
-    use quickwit_config::{IndexConfig, SourceConfig, SourceParams};
+    use quickwit_config::{IndexConfig, SourceConfig, SourceInputFormat, SourceParams};
     use quickwit_doc_mapper::tag_pruning::{no_tag, tag, TagFilterAst};
     use quickwit_proto::metastore_api::DeleteQuery;
     use time::OffsetDateTime;
@@ -245,6 +245,8 @@ pub mod test_suite {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
 
         assert_eq!(
@@ -308,6 +310,8 @@ pub mod test_suite {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
         metastore
             .add_source(&index_id, source.clone())
@@ -352,6 +356,8 @@ pub mod test_suite {
             enabled: true,
             source_params: SourceParams::void(),
             transform_config: None,
+            dead_letter_queue_enabled: false,
+            input_format: SourceInputFormat::default(),
         };
 
         let index_config = IndexConfig::for_test(&index_id, index_uri.as_str());
@@ -404,6 +410,8 @@ pub mod test_suite {
                 enabled: true,
                 source_params: SourceParams::void(),
                 transform_config: None,
+                dead_letter_queue_enabled: false,
+                input_format: SourceInputFormat::default(),
             };
             metastore
                 .add_source(&index_id, source.clone())
@@ -191,6 +191,15 @@ impl From<SearchStreamRequest> for SearchRequest {
             sort_by_field: None,
             sort_order: None,
             aggregation_request: None,
+            query_dsl: None,
+            search_after: None,
+            split_ids: Vec::new(),
+            pin_splits: false,
+            sort_fields: Vec::new(),
+            explain: false,
+            highlight: None,
+            source_includes: Vec::new(),
+            source_excludes: Vec::new(),
         }
     }
 }
@@ -40,6 +40,79 @@ pub struct SearchRequest {
     /// Fields to extract snippet on
     #[prost(string, repeated, tag = "12")]
     pub snippet_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// JSON-serialized query DSL, partially compatible with Elasticsearch's.
+    /// If set, it takes precedence over `query`.
+    #[prost(string, optional, tag = "13")]
+    pub query_dsl: ::core::option::Option<::prost::alloc::string::String>,
+    /// If set, only the hits that come strictly after this hit in the sort order
+    /// are returned. Passing the last hit of a page makes it possible to fetch the
+    /// next page without collecting the previous ones.
+    #[prost(message, optional, tag = "14")]
+    pub search_after: ::core::option::Option<PartialHit>,
+    /// If not empty, the search is restricted to these splits, even if they have
+    /// been marked for deletion in the meantime. Together with `search_after`,
+    /// this makes it possible to paginate over a consistent set of splits.
+    #[prost(string, repeated, tag = "15")]
+    pub split_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// If true, the IDs of the searched splits are returned in the response.
+    #[prost(bool, tag = "16")]
+    pub pin_splits: bool,
+    /// Fields to sort by. If not empty, it takes precedence over `sort_by_field`
+    /// and `sort_order`. Each field breaks the ties of the previous ones.
+    #[prost(message, repeated, tag = "17")]
+    pub sort_fields: ::prost::alloc::vec::Vec<SortField>,
+    /// If set, the response explains how the query was parsed, which splits were
+    /// searched or pruned, and how the score of each hit was computed.
+    #[prost(bool, tag = "18")]
+    pub explain: bool,
+    /// Controls how the `snippet_fields` are highlighted.
+    #[prost(message, optional, tag = "19")]
+    pub highlight: ::core::option::Option<HighlightSpec>,
+    /// If not empty, the returned documents only contain the fields matching one
+    /// of these patterns. Patterns are dot-separated field paths supporting the `*`
+    /// wildcard.
+    #[prost(string, repeated, tag = "20")]
+    pub source_includes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// The fields matching one of these patterns are removed from the returned
+    /// documents. Excludes take precedence over includes.
+    #[prost(string, repeated, tag = "21")]
+    pub source_excludes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SortField {
+    /// Name of the fast field to sort by, or `_score`.
+    #[prost(string, tag = "1")]
+    pub field_name: ::prost::alloc::string::String,
+    #[prost(enumeration = "SortOrder", tag = "2")]
+    pub sort_order: i32,
+    /// If true, the documents that do not have a value for this field come first.
+    /// By default, they come last.
+    #[prost(bool, tag = "3")]
+    pub missing_first: bool,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HighlightSpec {
+    /// Maximum number of characters of each fragment. Defaults to 150.
+    #[prost(uint32, optional, tag = "1")]
+    pub fragment_size: ::core::option::Option<u32>,
+    /// Maximum number of fragments returned per field. If 0, the whole field
+    /// values are highlighted. By default, one fragment is returned per value.
+    #[prost(uint32, optional, tag = "2")]
+    pub number_of_fragments: ::core::option::Option<u32>,
+    /// Tag inserted before each highlighted term. Defaults to `<b>`.
+    #[prost(string, optional, tag = "3")]
+    pub pre_tag: ::core::option::Option<::prost::alloc::string::String>,
+    /// Tag inserted after each highlighted term. Defaults to `</b>`.
+    #[prost(string, optional, tag = "4")]
+    pub post_tag: ::core::option::Option<::prost::alloc::string::String>,
+    /// If true, only the query terms targeting a field are highlighted in it.
+    /// Otherwise, the query terms of every field are. Defaults to true.
+    #[prost(bool, optional, tag = "5")]
+    pub require_field_match: ::core::option::Option<bool>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -61,6 +134,13 @@ pub struct SearchResponse {
     /// Serialized aggregation response
     #[prost(string, optional, tag = "5")]
     pub aggregation: ::core::option::Option<::prost::alloc::string::String>,
+    /// IDs of the searched splits. Only populated if `pin_splits` is set.
+    #[prost(string, repeated, tag = "6")]
+    pub split_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Json-serialized explanation of the parsed query and of the split pruning.
+    /// Only populated if `explain` is set.
+    #[prost(string, optional, tag = "7")]
+    pub explanation: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -95,6 +175,9 @@ pub struct LeafSearchRequest {
     /// split files.
     #[prost(string, tag = "6")]
     pub index_uri: ::prost::alloc::string::String,
+    /// URI of the key encrypting the split files, if the index is encrypted.
+    #[prost(string, optional, tag = "7")]
+    pub encryption_key_uri: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -110,6 +193,12 @@ pub struct SplitIdAndFooterOffsets {
     /// The offset of the end of the footer in split bundle. The footer contains the file bundle metadata and the hotcache.
     #[prost(uint64, tag = "3")]
     pub split_footer_end: u64,
+    /// The lowest timestamp appearing in the split, in seconds since epoch.
+    #[prost(int64, optional, tag = "4")]
+    pub timestamp_start: ::core::option::Option<i64>,
+    /// The highest timestamp appearing in the split, in seconds since epoch.
+    #[prost(int64, optional, tag = "5")]
+    pub timestamp_end: ::core::option::Option<i64>,
 }
 /// / Hits returned by a FetchDocRequest.
 /// /
@@ -137,6 +226,9 @@ pub struct LeafHit {
     /// A snippet of the matching content
     #[prost(string, optional, tag = "3")]
     pub leaf_snippet_json: ::core::option::Option<::prost::alloc::string::String>,
+    /// Json-serialized explanation of the score of the hit
+    #[prost(string, optional, tag = "4")]
+    pub leaf_explanation_json: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -151,6 +243,12 @@ pub struct Hit {
     /// A snippet of the matching content
     #[prost(string, optional, tag = "3")]
     pub snippet: ::core::option::Option<::prost::alloc::string::String>,
+    /// Json-serialized explanation of the score of the hit
+    #[prost(string, optional, tag = "4")]
+    pub explanation: ::core::option::Option<::prost::alloc::string::String>,
+    /// Json-serialized array of the values of the fields the hit is sorted by
+    #[prost(string, optional, tag = "5")]
+    pub sort_values_json: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// A partial hit, is a hit for which we have not fetch the content yet.
 /// Instead, it holds a document_uri which is enough information to
@@ -181,6 +279,10 @@ pub struct PartialHit {
     /// The DocId identifies a unique document at the scale of a tantivy segment.
     #[prost(uint32, tag = "4")]
     pub doc_id: u32,
+    /// Sorting field values of the secondary sort fields, if any. They are mapped
+    /// like `sorting_field_value`, and break its ties before the hit address does.
+    #[prost(uint64, repeated, tag = "5")]
+    pub secondary_sorting_field_values: ::prost::alloc::vec::Vec<u64>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -231,6 +333,9 @@ pub struct FetchDocsRequest {
     /// `DocMapper` as json serialized trait.
     #[prost(string, tag = "6")]
     pub doc_mapper: ::prost::alloc::string::String,
+    /// URI of the key encrypting the split files, if the index is encrypted.
+    #[prost(string, optional, tag = "7")]
+    pub encryption_key_uri: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -243,6 +348,29 @@ pub struct FetchDocsResponse {
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExplainHitRequest {
+    /// Search request whose query should be explained.
+    #[prost(message, optional, tag = "1")]
+    pub search_request: ::core::option::Option<SearchRequest>,
+    /// Address of the document to explain.
+    #[prost(message, optional, tag = "2")]
+    pub partial_hit: ::core::option::Option<PartialHit>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExplainHitResponse {
+    /// Whether the document matches the query.
+    #[prost(bool, tag = "1")]
+    pub matched: bool,
+    /// Json-serialized explanation of the score of the document.
+    /// Only populated if the document matches the query.
+    #[prost(string, optional, tag = "2")]
+    pub explanation: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListTermsRequest {
     /// Index ID
     #[prost(string, tag = "1")]
@@ -297,6 +425,12 @@ pub struct LeafListTermsRequest {
     /// split files.
     #[prost(string, tag = "3")]
     pub index_uri: ::prost::alloc::string::String,
+    /// URI of the key encrypting the split files, if the index is encrypted.
+    #[prost(string, optional, tag = "4")]
+    pub encryption_key_uri: ::core::option::Option<::prost::alloc::string::String>,
+    /// `DocMapper` as json serialized trait.
+    #[prost(string, tag = "5")]
+    pub doc_mapper: ::prost::alloc::string::String,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -366,6 +500,9 @@ pub struct LeafSearchStreamRequest {
     /// split files.
     #[prost(string, tag = "6")]
     pub index_uri: ::prost::alloc::string::String,
+    /// URI of the key encrypting the split files, if the index is encrypted.
+    #[prost(string, optional, tag = "7")]
+    pub encryption_key_uri: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -23,9 +23,10 @@ use bytes::Bytes;
 use quickwit_common::FileEntry;
 use quickwit_config::{ConfigFormat, SourceConfig};
 pub use quickwit_ingest::CommitType;
+use quickwit_ingest::DeadLetterRecord;
 use quickwit_metastore::{IndexMetadata, Split};
 use quickwit_search::SearchResponseRest;
-use quickwit_serve::{ListSplitsQueryParams, SearchRequestQueryString};
+use quickwit_serve::{ListSplitsQueryParams, ReplayDeadLettersResponse, SearchRequestQueryString};
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
 use reqwest::{Client, Method, StatusCode, Url};
 use serde::Serialize;
@@ -398,6 +399,38 @@ impl<'a, 'b> SourceClient<'a, 'b> {
         Ok(())
     }
 
+    pub async fn dead_letters(
+        &self,
+        source_id: &str,
+        start_after: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<DeadLetterRecord>, Error> {
+        let path = format!("{}/{source_id}/dead-letters", self.sources_root_url());
+        let mut query_params = vec![("limit", limit.to_string())];
+        if let Some(start_after) = start_after {
+            query_params.push(("start_after", start_after.to_string()));
+        }
+        let response = self
+            .transport
+            .send(Method::GET, &path, None, Some(&query_params), None)
+            .await?;
+        let dead_letters = response.deserialize().await?;
+        Ok(dead_letters)
+    }
+
+    pub async fn replay_dead_letters(&self, source_id: &str) -> Result<u64, Error> {
+        let path = format!(
+            "{}/{source_id}/dead-letters/replay",
+            self.sources_root_url()
+        );
+        let response = self
+            .transport
+            .send::<()>(Method::POST, &path, None, None, None)
+            .await?;
+        let replay_response: ReplayDeadLettersResponse = response.deserialize().await?;
+        Ok(replay_response.num_replayed_docs)
+    }
+
     pub async fn list(&self) -> Result<Vec<SourceConfig>, Error> {
         let response = self
             .transport
@@ -488,6 +521,10 @@ mod test {
             aggregations: None,
             elapsed_time_micros: 100,
             errors: Vec::new(),
+            next_search_after: None,
+            split_ids: Vec::new(),
+            explanation: None,
+            hit_explanations: None,
         };
         Mock::given(method("POST"))
             .and(path("/api/v1/my-index/search"))
@@ -839,6 +876,55 @@ mod test {
             .await
             .unwrap_err();
 
+        // GET dead letters
+        Mock::given(method("GET"))
+            .and(path(
+                "/api/v1/indexes/my-index/sources/my-source/dead-letters",
+            ))
+            .and(query_param("start_after", "3"))
+            .and(query_param("limit", "10"))
+            .respond_with(ResponseTemplate::new(StatusCode::OK).set_body_json(json!([{
+                "position": 4,
+                "doc": "ew==",
+                "error_kind": "parsing_error",
+                "error_message": "Failed to parse document.",
+                "source_position": {
+                    "batch_delta": "∆(:(..00000000000000000001])",
+                    "doc_offset": 0
+                }
+            }])))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        let dead_letters = qw_client
+            .sources("my-index")
+            .dead_letters("my-source", Some(3), 10)
+            .await
+            .unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].dead_letter.doc, "{");
+
+        // POST replay dead letters
+        Mock::given(method("POST"))
+            .and(path(
+                "/api/v1/indexes/my-index/sources/my-source/dead-letters/replay",
+            ))
+            .respond_with(
+                ResponseTemplate::new(StatusCode::OK)
+                    .set_body_json(json!({"num_replayed_docs": 1})),
+            )
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        assert_eq!(
+            qw_client
+                .sources("my-index")
+                .replay_dead_letters("my-source")
+                .await
+                .unwrap(),
+            1
+        );
+
         // DELETE source
         Mock::given(method("DELETE"))
             .and(path("/api/v1/indexes/my-index/sources/my-source"))
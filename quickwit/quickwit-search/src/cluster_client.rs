@@ -245,6 +245,7 @@ mod tests {
             split_id: split_id.to_string(),
             segment_ord: 1,
             doc_id,
+            secondary_sorting_field_values: Vec::new(),
         }
     }
 
@@ -257,6 +258,8 @@ mod tests {
                 split_id: split_id.to_string(),
                 split_footer_end: 100,
                 split_footer_start: 0,
+                timestamp_start: None,
+                timestamp_end: None,
             }],
             ..Default::default()
         }
@@ -282,13 +285,18 @@ mod tests {
                     split_id: "split_1".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    timestamp_start: None,
+                    timestamp_end: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    timestamp_start: None,
+                    timestamp_end: None,
                 },
             ],
+            encryption_key_uri: None,
         }
     }
 
@@ -313,13 +321,18 @@ mod tests {
                     split_id: "split_1".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    timestamp_start: None,
+                    timestamp_end: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    timestamp_start: None,
+                    timestamp_end: None,
                 },
             ],
+            encryption_key_uri: None,
         }
     }
 
@@ -25,13 +25,15 @@ use itertools::Itertools;
 use quickwit_doc_mapper::{DocMapper, WarmupInfo};
 use quickwit_proto::{LeafSearchResponse, PartialHit, SearchRequest, SortOrder};
 use serde::Deserialize;
+use serde_json::Value as JsonValue;
 use tantivy::aggregation::agg_req::{get_fast_field_names, Aggregations};
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::aggregation::{AggregationLimits, AggregationSegmentCollector};
 use tantivy::collector::{Collector, SegmentCollector};
-use tantivy::columnar::ColumnType;
+use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64};
 use tantivy::fastfield::Column;
-use tantivy::{DocId, Score, SegmentOrdinal, SegmentReader};
+use tantivy::schema::{Schema, Type};
+use tantivy::{DateTime, DocId, Score, SegmentOrdinal, SegmentReader};
 
 use crate::filters::{create_timestamp_filter_builder, TimestampFilter, TimestampFilterBuilder};
 use crate::find_trace_ids_collector::{FindTraceIdsCollector, FindTraceIdsSegmentCollector};
@@ -44,12 +46,28 @@ pub(crate) enum SortBy {
     FastField {
         field_name: String,
         order: SortOrder,
+        /// Whether documents without a value for the field come first.
+        missing_first: bool,
     },
     Score {
         order: SortOrder,
     },
 }
 
+impl SortBy {
+    fn from_field_name(field_name: &str, order: SortOrder, missing_first: bool) -> SortBy {
+        if field_name == "_score" {
+            SortBy::Score { order }
+        } else {
+            SortBy::FastField {
+                field_name: field_name.to_string(),
+                order,
+                missing_first,
+            }
+        }
+    }
+}
+
 /// The `SortingFieldComputer` can be seen as the specialization of `SortBy` applied to a specific
 /// `SegmentReader`. Its role is to compute the sorting field given a `DocId`.
 enum SortingFieldComputer {
@@ -58,6 +76,8 @@ enum SortingFieldComputer {
     FastField {
         sort_column: Column<u64>,
         order: SortOrder,
+        /// Sorting field value of documents without a value for the field.
+        missing_value: u64,
     },
     Score {
         order: SortOrder,
@@ -71,6 +91,7 @@ impl SortingFieldComputer {
             SortingFieldComputer::FastField {
                 sort_column: fast_field_reader,
                 order,
+                missing_value,
             } => {
                 if let Some(field_val) = fast_field_reader.first(doc_id) {
                     match order {
@@ -81,7 +102,7 @@ impl SortingFieldComputer {
                         SortOrder::Asc => u64::MAX - field_val,
                     }
                 } else {
-                    0u64
+                    *missing_value
                 }
             }
             SortingFieldComputer::DocId => doc_id as u64,
@@ -105,6 +126,84 @@ fn f32_to_u64(value: f32) -> u64 {
     (value_u32 ^ mask) as u64
 }
 
+/// Inverse of [`f32_to_u64`].
+fn u64_to_f32(value: u64) -> f32 {
+    let value_u32 = value as u32;
+    let mask = if value_u32 & 0x80000000 != 0 {
+        0x80000000
+    } else {
+        u32::MAX
+    };
+    f32::from_bits(value_u32 ^ mask)
+}
+
+/// Undoes the decreasing mapping applied to the sorting field values of ascending sorts.
+fn undo_sort_order(order: SortOrder, sorting_field_value: u64) -> u64 {
+    match order {
+        SortOrder::Desc => sorting_field_value,
+        SortOrder::Asc => u64::MAX - sorting_field_value,
+    }
+}
+
+/// Decodes a sorting field value back into a value of the type of the field it was computed from.
+///
+/// Documents without a value for the field decode to the greatest or smallest value of the type,
+/// depending on where they are sorted.
+fn decode_sorting_field_value(
+    sort_by: &SortBy,
+    schema: &Schema,
+    sorting_field_value: u64,
+) -> JsonValue {
+    match sort_by {
+        SortBy::DocId => JsonValue::from(sorting_field_value),
+        SortBy::FastField {
+            field_name, order, ..
+        } => {
+            let field_value = undo_sort_order(*order, sorting_field_value);
+            let value_type_opt = schema
+                .get_field(field_name)
+                .ok()
+                .map(|field| schema.get_field_entry(field).field_type().value_type());
+            match value_type_opt {
+                Some(Type::I64) => JsonValue::from(i64::from_u64(field_value)),
+                Some(Type::F64) => JsonValue::from(f64::from_u64(field_value)),
+                Some(Type::Date) => {
+                    JsonValue::from(DateTime::from_u64(field_value).into_timestamp_millis())
+                }
+                _ => JsonValue::from(field_value),
+            }
+        }
+        SortBy::Score { order } => {
+            JsonValue::from(u64_to_f32(undo_sort_order(*order, sorting_field_value)))
+        }
+    }
+}
+
+/// Returns the values of the fields the hit is sorted by, as a JSON-serialized array, or `None` if
+/// the hits are not sorted by any field.
+///
+/// Dates are returned as Unix timestamps in milliseconds, like Elasticsearch does.
+pub(crate) fn sort_values_json(
+    search_request: &SearchRequest,
+    schema: &Schema,
+    partial_hit: &PartialHit,
+) -> Option<String> {
+    let (sort_by, secondary_sort_by) = resolve_sort_fields(search_request);
+    if matches!(sort_by, SortBy::DocId) {
+        return None;
+    }
+    let sorting_field_values = std::iter::once(&partial_hit.sorting_field_value)
+        .chain(&partial_hit.secondary_sorting_field_values);
+    let sort_values: Vec<JsonValue> = std::iter::once(&sort_by)
+        .chain(&secondary_sort_by)
+        .zip(sorting_field_values)
+        .map(|(sort_by, sorting_field_value)| {
+            decode_sorting_field_value(sort_by, schema, *sorting_field_value)
+        })
+        .collect();
+    Some(JsonValue::from(sort_values).to_string())
+}
+
 /// Takes a user-defined sorting criteria and resolves it to a
 /// segment specific `SortFieldComputer`.
 fn resolve_sort_by(
@@ -113,7 +212,11 @@ fn resolve_sort_by(
 ) -> tantivy::Result<SortingFieldComputer> {
     match sort_by {
         SortBy::DocId => Ok(SortingFieldComputer::DocId),
-        SortBy::FastField { field_name, order } => {
+        SortBy::FastField {
+            field_name,
+            order,
+            missing_first,
+        } => {
             let sort_column_opt: Option<(Column<u64>, ColumnType)> =
                 segment_reader.fast_fields().u64_lenient(field_name)?;
             let sort_column = if let Some((sort_column, _column_type)) = sort_column_opt {
@@ -121,9 +224,12 @@ fn resolve_sort_by(
             } else {
                 Column::build_empty_column(segment_reader.max_doc())
             };
+            // Hits are sorted by decreasing sorting field value.
+            let missing_value = if *missing_first { u64::MAX } else { 0u64 };
             Ok(SortingFieldComputer::FastField {
                 sort_column,
                 order: *order,
+                missing_value,
             })
         }
         SortBy::Score { order } => Ok(SortingFieldComputer::Score { order: *order }),
@@ -132,9 +238,10 @@ fn resolve_sort_by(
 
 /// PartialHitHeapItem order is the inverse of the natural order
 /// so that we actually have a min-heap.
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 struct PartialHitHeapItem {
     sorting_field_value: u64,
+    secondary_sorting_field_values: Vec<u64>,
     doc_id: DocId,
 }
 
@@ -152,14 +259,22 @@ impl Ord for PartialHitHeapItem {
             .partial_cmp(&self.sorting_field_value)
             .unwrap_or(Ordering::Equal);
 
+        let lazy_order_by_secondary_sorting_fields = || {
+            other
+                .secondary_sorting_field_values
+                .cmp(&self.secondary_sorting_field_values)
+        };
+
         let lazy_order_by_doc_id = || {
             self.doc_id
                 .partial_cmp(&other.doc_id)
                 .unwrap_or(Ordering::Equal)
         };
 
-        // In case of a tie on the feature, we sort by ascending `DocId`.
-        by_sorting_field.then_with(lazy_order_by_doc_id)
+        // In case of a tie on the features, we sort by ascending `DocId`.
+        by_sorting_field
+            .then_with(lazy_order_by_secondary_sorting_fields)
+            .then_with(lazy_order_by_doc_id)
     }
 }
 
@@ -171,6 +286,55 @@ impl PartialEq for PartialHitHeapItem {
 
 impl Eq for PartialHitHeapItem {}
 
+/// The `search_after` hit, resolved for a given segment.
+///
+/// Hits are ordered by decreasing sorting field values, and ties are broken by increasing split
+/// ID, segment ordinal and doc ID (see `partial_hit_sorting_key`).
+#[derive(Clone)]
+struct SearchAfterSegmentFilter {
+    sorting_field_value: u64,
+    secondary_sorting_field_values: Vec<u64>,
+    /// Comparison of the address of the segment with the one of the `search_after` hit.
+    segment_cmp: Ordering,
+    doc_id: DocId,
+}
+
+impl SearchAfterSegmentFilter {
+    fn new(search_after: &PartialHit, split_id: &str, segment_ord: SegmentOrdinal) -> Self {
+        let segment_cmp = (split_id, segment_ord)
+            .cmp(&(search_after.split_id.as_str(), search_after.segment_ord));
+        SearchAfterSegmentFilter {
+            sorting_field_value: search_after.sorting_field_value,
+            secondary_sorting_field_values: search_after.secondary_sorting_field_values.clone(),
+            segment_cmp,
+            doc_id: search_after.doc_id,
+        }
+    }
+
+    /// Returns true if the hit comes strictly after the `search_after` hit.
+    fn is_after(
+        &self,
+        sorting_field_value: u64,
+        secondary_sorting_field_values: &[u64],
+        doc_id: DocId,
+    ) -> bool {
+        let sorting_field_values_cmp = sorting_field_value
+            .cmp(&self.sorting_field_value)
+            .then_with(|| {
+                secondary_sorting_field_values.cmp(&self.secondary_sorting_field_values[..])
+            });
+        match sorting_field_values_cmp {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => match self.segment_cmp {
+                Ordering::Less => false,
+                Ordering::Greater => true,
+                Ordering::Equal => doc_id > self.doc_id,
+            },
+        }
+    }
+}
+
 enum AggregationSegmentCollectors {
     FindTraceIdsSegmentCollector(Box<FindTraceIdsSegmentCollector>),
     TantivyAggregationSegmentCollector(AggregationSegmentCollector),
@@ -181,10 +345,12 @@ pub struct QuickwitSegmentCollector {
     num_hits: u64,
     split_id: String,
     sort_by: SortingFieldComputer,
+    secondary_sort_by: Vec<SortingFieldComputer>,
     hits: BinaryHeap<PartialHitHeapItem>,
     max_hits: usize,
     segment_ord: u32,
     timestamp_filter_opt: Option<TimestampFilter>,
+    search_after_filter_opt: Option<SearchAfterSegmentFilter>,
     aggregation: Option<AggregationSegmentCollectors>,
 }
 
@@ -196,23 +362,44 @@ impl QuickwitSegmentCollector {
     fn collect_top_k(&mut self, doc_id: DocId, score: Score) {
         let sorting_field_value: u64 = self.sort_by.compute_sorting_field(doc_id, score);
         if self.at_capacity() {
-            if let Some(limit_sorting_field) = self.hits.peek().map(|head| head.sorting_field_value)
-            {
+            // The secondary sorting fields are only computed if the primary one does not
+            // already rule the document out.
+            match self.hits.peek() {
+                Some(head) if head.sorting_field_value <= sorting_field_value => {}
+                _ => return,
+            }
+        }
+        let secondary_sorting_field_values: Vec<u64> = self
+            .secondary_sort_by
+            .iter()
+            .map(|sort_by| sort_by.compute_sorting_field(doc_id, score))
+            .collect();
+        if let Some(search_after_filter) = &self.search_after_filter_opt {
+            // Hits before the cursor still count in `num_hits` and aggregations.
+            if !search_after_filter.is_after(
+                sorting_field_value,
+                &secondary_sorting_field_values,
+                doc_id,
+            ) {
+                return;
+            }
+        }
+        let hit = PartialHitHeapItem {
+            sorting_field_value,
+            secondary_sorting_field_values,
+            doc_id,
+        };
+        if self.at_capacity() {
+            if let Some(mut head) = self.hits.peek_mut() {
                 // In case of a tie, we keep the document with a lower `DocId`.
-                if limit_sorting_field < sorting_field_value {
-                    if let Some(mut head) = self.hits.peek_mut() {
-                        head.sorting_field_value = sorting_field_value;
-                        head.doc_id = doc_id;
-                    }
+                if hit < *head {
+                    *head = hit;
                 }
             }
         } else {
             // we have not reached capacity yet, so we can just push the
             // element.
-            self.hits.push(PartialHitHeapItem {
-                sorting_field_value,
-                doc_id,
-            });
+            self.hits.push(hit);
         }
     }
 
@@ -259,6 +446,7 @@ impl SegmentCollector for QuickwitSegmentCollector {
                 segment_ord,
                 doc_id: hit.doc_id,
                 split_id: split_id.clone(),
+                secondary_sorting_field_values: hit.secondary_sorting_field_values,
             })
             .collect();
 
@@ -319,7 +507,10 @@ pub(crate) struct QuickwitCollector {
     pub start_offset: usize,
     pub max_hits: usize,
     pub sort_by: SortBy,
+    /// Sorting criteria breaking the ties of `sort_by`.
+    pub secondary_sort_by: Vec<SortBy>,
     timestamp_filter_builder_opt: Option<TimestampFilterBuilder>,
+    search_after_opt: Option<PartialHit>,
     pub aggregation: Option<QuickwitAggregations>,
     pub aggregation_limits: AggregationLimits,
 }
@@ -327,10 +518,12 @@ pub(crate) struct QuickwitCollector {
 impl QuickwitCollector {
     pub fn fast_field_names(&self) -> HashSet<String> {
         let mut fast_field_names = HashSet::default();
-        match &self.sort_by {
-            SortBy::DocId | SortBy::Score { .. } => {}
-            SortBy::FastField { field_name, .. } => {
-                fast_field_names.insert(field_name.clone());
+        for sort_by in std::iter::once(&self.sort_by).chain(&self.secondary_sort_by) {
+            match sort_by {
+                SortBy::DocId | SortBy::Score { .. } => {}
+                SortBy::FastField { field_name, .. } => {
+                    fast_field_names.insert(field_name.clone());
+                }
             }
         }
         if let Some(aggregations) = &self.aggregation {
@@ -362,6 +555,11 @@ impl Collector for QuickwitCollector {
         segment_reader: &SegmentReader,
     ) -> tantivy::Result<Self::Child> {
         let sort_by = resolve_sort_by(&self.sort_by, segment_reader)?;
+        let secondary_sort_by = self
+            .secondary_sort_by
+            .iter()
+            .map(|sort_by| resolve_sort_by(sort_by, segment_reader))
+            .collect::<tantivy::Result<Vec<_>>>()?;
         // Regardless of the start_offset, we need to collect top-K
         // starting from 0 for every leaves.
         let leaf_max_hits = self.max_hits + self.start_offset;
@@ -370,6 +568,9 @@ impl Collector for QuickwitCollector {
             Some(timestamp_filter_builder) => timestamp_filter_builder.build(segment_reader)?,
             None => None,
         };
+        let search_after_filter_opt = self.search_after_opt.as_ref().map(|search_after| {
+            SearchAfterSegmentFilter::new(search_after, &self.split_id, segment_ord)
+        });
         let aggregation = match &self.aggregation {
             Some(QuickwitAggregations::FindTraceIdsAggregation(collector)) => {
                 Some(AggregationSegmentCollectors::FindTraceIdsSegmentCollector(
@@ -391,10 +592,12 @@ impl Collector for QuickwitCollector {
             num_hits: 0u64,
             split_id: self.split_id.clone(),
             sort_by,
+            secondary_sort_by,
             hits: BinaryHeap::with_capacity(leaf_max_hits),
             segment_ord,
             max_hits: leaf_max_hits,
             timestamp_filter_opt,
+            search_after_filter_opt,
             aggregation,
         })
     }
@@ -403,10 +606,9 @@ impl Collector for QuickwitCollector {
         // We do not need BM25 scoring in Quickwit if it is not opted-in.
         // By returning false, we inform tantivy that it does not need to decompress
         // term frequencies.
-        match self.sort_by {
-            SortBy::DocId | SortBy::FastField { .. } => false,
-            SortBy::Score { .. } => true,
-        }
+        std::iter::once(&self.sort_by)
+            .chain(&self.secondary_sort_by)
+            .any(|sort_by| matches!(sort_by, SortBy::Score { .. }))
     }
 
     fn merge_fruits(
@@ -528,6 +730,32 @@ fn top_k_partial_hits(mut partial_hits: Vec<PartialHit>, num_hits: usize) -> Vec
     partial_hits
 }
 
+/// Returns the primary sorting criterion of the request, followed by the ones breaking its ties.
+///
+/// `sort_fields` takes precedence over the legacy `sort_by_field` and `sort_order` parameters.
+fn resolve_sort_fields(search_request: &SearchRequest) -> (SortBy, Vec<SortBy>) {
+    if search_request.sort_fields.is_empty() {
+        let sort_order = search_request
+            .sort_order
+            .and_then(SortOrder::from_i32)
+            .unwrap_or(SortOrder::Desc);
+        let sort_by = search_request
+            .sort_by_field
+            .as_ref()
+            .map(|field_name| SortBy::from_field_name(field_name, sort_order, false))
+            .unwrap_or(SortBy::DocId);
+        return (sort_by, Vec::new());
+    }
+    let mut sort_bys = search_request.sort_fields.iter().map(|sort_field| {
+        let sort_order = SortOrder::from_i32(sort_field.sort_order).unwrap_or(SortOrder::Desc);
+        SortBy::from_field_name(&sort_field.field_name, sort_order, sort_field.missing_first)
+    });
+    let sort_by = sort_bys
+        .next()
+        .expect("There should be at least one sort field, as checked above.");
+    (sort_by, sort_bys.collect())
+}
+
 /// Builds the QuickwitCollector, in function of the information that was requested by the user.
 pub(crate) fn make_collector_for_split(
     split_id: String,
@@ -544,31 +772,16 @@ pub(crate) fn make_collector_for_split(
         search_request.start_timestamp,
         search_request.end_timestamp,
     );
-    let sort_order = search_request
-        .sort_order
-        .and_then(SortOrder::from_i32)
-        .unwrap_or(SortOrder::Desc);
-    let sort_by = search_request
-        .sort_by_field
-        .as_ref()
-        .map(|field_name| {
-            if field_name == "_score" {
-                SortBy::Score { order: sort_order }
-            } else {
-                SortBy::FastField {
-                    field_name: field_name.clone(),
-                    order: sort_order,
-                }
-            }
-        })
-        .unwrap_or(SortBy::DocId);
+    let (sort_by, secondary_sort_by) = resolve_sort_fields(search_request);
 
     Ok(QuickwitCollector {
         split_id,
         start_offset: search_request.start_offset as usize,
         max_hits: search_request.max_hits as usize,
         sort_by,
+        secondary_sort_by,
         timestamp_filter_builder_opt,
+        search_after_opt: search_request.search_after.clone(),
         aggregation,
         aggregation_limits,
     })
@@ -605,7 +818,9 @@ pub(crate) fn make_merge_collector(
         start_offset: search_request.start_offset as usize,
         max_hits: search_request.max_hits as usize,
         sort_by: SortBy::DocId,
+        secondary_sort_by: Vec::new(),
         timestamp_filter_builder_opt: None,
+        search_after_opt: None,
         aggregation,
         aggregation_limits: aggregation_limits_from_searcher_context(searcher_context),
     })
@@ -618,22 +833,45 @@ mod tests {
     use proptest::prelude::*;
     use quickwit_proto::PartialHit;
 
-    use super::PartialHitHeapItem;
-    use crate::collector::{f32_to_u64, top_k_partial_hits};
+    use super::{PartialHitHeapItem, SearchAfterSegmentFilter};
+    use crate::collector::{f32_to_u64, sort_values_json, top_k_partial_hits, u64_to_f32};
 
     #[test]
     fn test_partial_hit_ordered_by_sorting_field() {
         let lesser_score = PartialHitHeapItem {
             sorting_field_value: 1u64,
+            secondary_sorting_field_values: Vec::new(),
             doc_id: 1u32,
         };
         let higher_score = PartialHitHeapItem {
             sorting_field_value: 2u64,
+            secondary_sorting_field_values: Vec::new(),
             doc_id: 1u32,
         };
         assert_eq!(lesser_score.cmp(&higher_score), Ordering::Greater);
     }
 
+    #[test]
+    fn test_partial_hit_ordered_by_secondary_sorting_fields() {
+        let make_hit = |secondary_sorting_field_values: Vec<u64>, doc_id: u32| PartialHitHeapItem {
+            sorting_field_value: 1u64,
+            secondary_sorting_field_values,
+            doc_id,
+        };
+        assert_eq!(
+            make_hit(vec![1, 5], 0).cmp(&make_hit(vec![2, 0], 1)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            make_hit(vec![2, 5], 1).cmp(&make_hit(vec![2, 3], 0)),
+            Ordering::Less
+        );
+        assert_eq!(
+            make_hit(vec![2, 3], 0).cmp(&make_hit(vec![2, 3], 1)),
+            Ordering::Less
+        );
+    }
+
     #[test]
     fn test_merge_partial_hits_no_tie() {
         let make_doc = |sorting_field_value: u64| PartialHit {
@@ -641,6 +879,7 @@ mod tests {
             split_id: "split1".to_string(),
             segment_ord: 0u32,
             doc_id: 0u32,
+            secondary_sorting_field_values: Vec::new(),
         };
         assert_eq!(
             top_k_partial_hits(vec![make_doc(1u64), make_doc(3u64), make_doc(2u64),], 2),
@@ -655,6 +894,7 @@ mod tests {
             split_id: format!("split_{split_id}"),
             segment_ord: 0u32,
             doc_id: 0u32,
+            secondary_sorting_field_values: Vec::new(),
         };
         assert_eq!(
             top_k_partial_hits(
@@ -669,6 +909,119 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_merge_partial_hits_with_secondary_sorting_fields() {
+        let make_hit = |sorting_field_value: u64, secondary_sorting_field_value: u64| PartialHit {
+            sorting_field_value,
+            split_id: "split1".to_string(),
+            segment_ord: 0u32,
+            doc_id: 0u32,
+            secondary_sorting_field_values: vec![secondary_sorting_field_value],
+        };
+        assert_eq!(
+            top_k_partial_hits(
+                vec![
+                    make_hit(1, 7),
+                    make_hit(2, 1),
+                    make_hit(1, 9),
+                    make_hit(2, 3)
+                ],
+                3
+            ),
+            vec![make_hit(2, 3), make_hit(2, 1), make_hit(1, 9)]
+        );
+    }
+
+    #[test]
+    fn test_search_after_segment_filter() {
+        let search_after = PartialHit {
+            sorting_field_value: 10u64,
+            split_id: "split_2".to_string(),
+            segment_ord: 1u32,
+            doc_id: 5u32,
+            secondary_sorting_field_values: Vec::new(),
+        };
+        let filter = SearchAfterSegmentFilter::new(&search_after, "split_2", 1);
+        assert!(filter.is_after(9, &[], 0));
+        assert!(!filter.is_after(11, &[], 100));
+        assert!(!filter.is_after(10, &[], 4));
+        assert!(!filter.is_after(10, &[], 5));
+        assert!(filter.is_after(10, &[], 6));
+
+        let filter = SearchAfterSegmentFilter::new(&search_after, "split_1", 3);
+        assert!(filter.is_after(9, &[], 0));
+        assert!(!filter.is_after(10, &[], 100));
+
+        let filter = SearchAfterSegmentFilter::new(&search_after, "split_2", 2);
+        assert!(filter.is_after(10, &[], 0));
+
+        let filter = SearchAfterSegmentFilter::new(&search_after, "split_3", 0);
+        assert!(filter.is_after(10, &[], 0));
+        assert!(!filter.is_after(11, &[], 0));
+    }
+
+    #[test]
+    fn test_search_after_segment_filter_with_secondary_sorting_fields() {
+        let search_after = PartialHit {
+            sorting_field_value: 10u64,
+            split_id: "split_2".to_string(),
+            segment_ord: 1u32,
+            doc_id: 5u32,
+            secondary_sorting_field_values: vec![3u64],
+        };
+        let filter = SearchAfterSegmentFilter::new(&search_after, "split_2", 1);
+        assert!(filter.is_after(10, &[2], 0));
+        assert!(!filter.is_after(10, &[4], 100));
+        assert!(!filter.is_after(10, &[3], 5));
+        assert!(filter.is_after(10, &[3], 6));
+        assert!(filter.is_after(9, &[4], 0));
+    }
+
+    #[test]
+    fn test_sort_values_json() {
+        use quickwit_proto::{SearchRequest, SortField, SortOrder};
+        use tantivy::columnar::MonotonicallyMappableToU64;
+        use tantivy::schema::{Schema, FAST};
+        use tantivy::DateTime;
+
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_i64_field("i64_field", FAST);
+        schema_builder.add_f64_field("f64_field", FAST);
+        schema_builder.add_date_field("date_field", FAST);
+        let schema = schema_builder.build();
+
+        let sort_field = |field_name: &str, sort_order: SortOrder| SortField {
+            field_name: field_name.to_string(),
+            sort_order: sort_order as i32,
+            missing_first: false,
+        };
+        let search_request = SearchRequest {
+            sort_fields: vec![
+                sort_field("i64_field", SortOrder::Asc),
+                sort_field("f64_field", SortOrder::Desc),
+                sort_field("date_field", SortOrder::Desc),
+                sort_field("_score", SortOrder::Asc),
+            ],
+            ..Default::default()
+        };
+        let partial_hit = PartialHit {
+            sorting_field_value: u64::MAX - (-3i64).to_u64(),
+            secondary_sorting_field_values: vec![
+                1.5f64.to_u64(),
+                DateTime::from_timestamp_secs(1_680_000_000).to_u64(),
+                u64::MAX - f32_to_u64(0.25),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            sort_values_json(&search_request, &schema, &partial_hit).unwrap(),
+            "[-3,1.5,1680000000000,0.25]"
+        );
+
+        let search_request = SearchRequest::default();
+        assert!(sort_values_json(&search_request, &schema, &partial_hit).is_none());
+    }
+
     prop_compose! {
         // Turns out, zero's and negative zero's u64 representation is not same.
         // It is not relevant for our use case. For simplicity we filter the negative
@@ -684,5 +1037,10 @@ mod tests {
         fn test_proptest_f32_to_u64_compare_arbitrary(a in any_f32_without_negative_zero(), b in any_f32_without_negative_zero()) {
             prop_assert_eq!(a < b, f32_to_u64(a) < f32_to_u64(b))
         }
+
+        #[test]
+        fn test_proptest_u64_to_f32_inverts_f32_to_u64(a in any::<f32>().prop_filter("Value can't be NaN", |val| !val.is_nan())) {
+            prop_assert_eq!(u64_to_f32(f32_to_u64(a)).to_bits(), a.to_bits())
+        }
     }
 }
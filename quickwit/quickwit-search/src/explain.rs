@@ -0,0 +1,239 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+
+use quickwit_doc_mapper::tag_pruning::{extract_tags_from_query, TagFilterAst};
+use quickwit_metastore::{ListSplitsQuery, Metastore, SplitMetadata, SplitState};
+use quickwit_proto::SearchRequest;
+use serde::Serialize;
+use tantivy::query::Query as TantivyQuery;
+
+/// Explains how a search request is executed: how its query is parsed and
+/// which splits are searched or pruned.
+#[derive(Debug, Serialize)]
+pub(crate) struct SearchExplanation {
+    /// Debug representation of the query built from the request.
+    pub query: String,
+    /// IDs of the searched splits.
+    pub searched_splits: Vec<String>,
+    /// Published splits that are not searched, along with the reason why.
+    pub pruned_splits: Vec<PrunedSplit>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub(crate) struct PrunedSplit {
+    pub split_id: String,
+    pub reason: SplitPruningReason,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SplitPruningReason {
+    /// The request is restricted to a list of splits that does not include this one.
+    NotInSplitIds,
+    /// The split time range does not overlap the request time range.
+    TimeRange,
+    /// The split tags show that it does not contain any document matching the query.
+    Tags,
+    /// None of the above, e.g. the split was published after the search listed the splits.
+    Unknown,
+}
+
+/// Returns the reason why a split was not selected by `list_relevant_splits`.
+fn split_pruning_reason(
+    search_request: &SearchRequest,
+    tags_filter_opt: Option<&TagFilterAst>,
+    split_metadata: &SplitMetadata,
+) -> SplitPruningReason {
+    if !search_request.split_ids.is_empty()
+        && !search_request.split_ids.contains(&split_metadata.split_id)
+    {
+        return SplitPruningReason::NotInSplitIds;
+    }
+    if let Some(time_range) = &split_metadata.time_range {
+        let ends_before_start = search_request
+            .start_timestamp
+            .map_or(false, |start_ts| *time_range.end() < start_ts);
+        let starts_after_end = search_request
+            .end_timestamp
+            .map_or(false, |end_ts| *time_range.start() >= end_ts);
+        if ends_before_start || starts_after_end {
+            return SplitPruningReason::TimeRange;
+        }
+    }
+    if let Some(tags_filter) = tags_filter_opt {
+        if !tags_filter.evaluate(&split_metadata.tags) {
+            return SplitPruningReason::Tags;
+        }
+    }
+    SplitPruningReason::Unknown
+}
+
+/// Builds the json-serialized [`SearchExplanation`] of a search request.
+///
+/// `searched_splits` are the splits returned by `list_relevant_splits`. Every other published
+/// split of the index is reported as pruned.
+pub(crate) async fn explain_search(
+    search_request: &SearchRequest,
+    query: &dyn TantivyQuery,
+    metastore: &dyn Metastore,
+    searched_splits: &[SplitMetadata],
+) -> crate::Result<String> {
+    let searched_split_ids: HashSet<&str> = searched_splits
+        .iter()
+        .map(|split_metadata| split_metadata.split_id())
+        .collect();
+    // Like `list_relevant_splits`, tags are only extracted from the query string.
+    let tags_filter_opt = if search_request.query_dsl.is_none() {
+        extract_tags_from_query(&search_request.query)?
+    } else {
+        None
+    };
+    let list_splits_query = ListSplitsQuery::for_index(&search_request.index_id)
+        .with_split_state(SplitState::Published);
+    let pruned_splits = metastore
+        .list_splits(list_splits_query)
+        .await?
+        .into_iter()
+        .map(|split| split.split_metadata)
+        .filter(|split_metadata| !searched_split_ids.contains(split_metadata.split_id()))
+        .map(|split_metadata| PrunedSplit {
+            reason: split_pruning_reason(search_request, tags_filter_opt.as_ref(), &split_metadata),
+            split_id: split_metadata.split_id,
+        })
+        .collect();
+    let explanation = SearchExplanation {
+        query: format!("{query:#?}"),
+        searched_splits: searched_splits
+            .iter()
+            .map(|split_metadata| split_metadata.split_id.clone())
+            .collect(),
+        pruned_splits,
+    };
+    let explanation_json = serde_json::to_string(&explanation)?;
+    Ok(explanation_json)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_metastore::{MockMetastore, Split};
+    use tantivy::query::AllQuery;
+
+    use super::*;
+
+    fn mock_split(split_id: &str, time_range: std::ops::RangeInclusive<i64>) -> Split {
+        Split {
+            split_metadata: SplitMetadata {
+                split_id: split_id.to_string(),
+                time_range: Some(time_range),
+                ..Default::default()
+            },
+            split_state: SplitState::Published,
+            update_timestamp: 0,
+            publish_timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_split_pruning_reason() {
+        let split_metadata = mock_split("split1", 100..=200).split_metadata;
+        let search_request = SearchRequest {
+            split_ids: vec!["split2".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            split_pruning_reason(&search_request, None, &split_metadata),
+            SplitPruningReason::NotInSplitIds
+        );
+        let search_request = SearchRequest {
+            start_timestamp: Some(201),
+            ..Default::default()
+        };
+        assert_eq!(
+            split_pruning_reason(&search_request, None, &split_metadata),
+            SplitPruningReason::TimeRange
+        );
+        let search_request = SearchRequest {
+            end_timestamp: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(
+            split_pruning_reason(&search_request, None, &split_metadata),
+            SplitPruningReason::TimeRange
+        );
+        let search_request = SearchRequest {
+            start_timestamp: Some(150),
+            end_timestamp: Some(300),
+            ..Default::default()
+        };
+        assert_eq!(
+            split_pruning_reason(&search_request, None, &split_metadata),
+            SplitPruningReason::Unknown
+        );
+        let tags_filter = extract_tags_from_query("tenant:foo").unwrap().unwrap();
+        assert_eq!(
+            split_pruning_reason(&search_request, Some(&tags_filter), &split_metadata),
+            SplitPruningReason::Unknown
+        );
+        let split_metadata = SplitMetadata {
+            tags: ["tenant!".to_string(), "tenant:bar".to_string()]
+                .into_iter()
+                .collect(),
+            ..split_metadata
+        };
+        assert_eq!(
+            split_pruning_reason(&search_request, Some(&tags_filter), &split_metadata),
+            SplitPruningReason::Tags
+        );
+    }
+
+    #[tokio::test]
+    async fn test_explain_search() {
+        let mut metastore = MockMetastore::new();
+        metastore.expect_list_splits().returning(|_filter| {
+            Ok(vec![
+                mock_split("split1", 100..=200),
+                mock_split("split2", 300..=400),
+            ])
+        });
+        let search_request = SearchRequest {
+            index_id: "test-index".to_string(),
+            query: "*".to_string(),
+            start_timestamp: Some(250),
+            explain: true,
+            ..Default::default()
+        };
+        let searched_splits = vec![mock_split("split2", 300..=400).split_metadata];
+        let explanation_json =
+            explain_search(&search_request, &AllQuery, &metastore, &searched_splits)
+                .await
+                .unwrap();
+        let explanation: serde_json::Value = serde_json::from_str(&explanation_json).unwrap();
+        assert_eq!(explanation["query"], "AllQuery");
+        assert_eq!(
+            explanation["searched_splits"],
+            serde_json::json!(["split2"])
+        );
+        assert_eq!(
+            explanation["pruned_splits"],
+            serde_json::json!([{"split_id": "split1", "reason": "time_range"}])
+        );
+    }
+}
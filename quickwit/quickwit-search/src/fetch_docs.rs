@@ -24,18 +24,24 @@ use anyhow::{Context, Ok};
 use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
 use quickwit_doc_mapper::DocMapper;
-use quickwit_proto::{FetchDocsResponse, PartialHit, SearchRequest, SplitIdAndFooterOffsets};
+use quickwit_proto::{
+    FetchDocsResponse, HighlightSpec, PartialHit, SearchRequest, SplitIdAndFooterOffsets,
+};
 use quickwit_storage::Storage;
 use tantivy::query::Query;
 use tantivy::schema::{Field, Value};
-use tantivy::{ReloadPolicy, Score, Searcher, SnippetGenerator, Term};
+use tantivy::{ReloadPolicy, Score, Searcher, Snippet, SnippetGenerator, Term};
 use tracing::error;
 
-use crate::leaf::open_index_with_caches;
+use crate::leaf::{open_index_with_caches, warmup};
+use crate::query_dsl::build_query;
 use crate::service::SearcherContext;
+use crate::source_filter::SourceFilter;
 use crate::{convert_document_to_json_string, GlobalDocAddress};
 
 const SNIPPET_MAX_NUM_CHARS: usize = 150;
+const DEFAULT_PRE_TAG: &str = "<b>";
+const DEFAULT_POST_TAG: &str = "</b>";
 
 /// Given a list of global doc address, fetches all the documents and
 /// returns them as a hashmap.
@@ -46,6 +52,7 @@ async fn fetch_docs_to_map(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
     search_request_opt: Option<&SearchRequest>,
+    disk_cache_enabled: bool,
 ) -> anyhow::Result<HashMap<GlobalDocAddress, Document>> {
     let mut split_fetch_docs_futures = Vec::new();
 
@@ -73,6 +80,7 @@ async fn fetch_docs_to_map(
             split_and_offset,
             doc_mapper.clone(),
             search_request_opt,
+            disk_cache_enabled,
         ));
     }
 
@@ -101,11 +109,22 @@ async fn fetch_docs_to_map(
     Ok(global_doc_addr_to_doc_json)
 }
 
+/// Returns true if the search request has to be sent along with the fetch docs request, to
+/// extract snippets, explain the hits scores or project the documents on some fields.
+pub(crate) fn fetch_docs_requires_search_request(search_request: &SearchRequest) -> bool {
+    !search_request.snippet_fields.is_empty()
+        || search_request.explain
+        || !search_request.source_includes.is_empty()
+        || !search_request.source_excludes.is_empty()
+}
+
 /// `fetch_docs` step of search.
 ///
 /// This function takes a list of partial hits (possibly from different splits)
 /// and the storage associated to an index, fetches the document from
 /// the split document stores, and returns the full hits.
+///
+/// The local disk cache is only used if `disk_cache_enabled` is set.
 pub async fn fetch_docs(
     searcher_context: Arc<SearcherContext>,
     partial_hits: Vec<PartialHit>,
@@ -113,6 +132,7 @@ pub async fn fetch_docs(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
     search_request_opt: Option<&SearchRequest>,
+    disk_cache_enabled: bool,
 ) -> anyhow::Result<FetchDocsResponse> {
     let global_doc_addrs: Vec<GlobalDocAddress> = partial_hits
         .iter()
@@ -126,6 +146,7 @@ pub async fn fetch_docs(
         splits,
         doc_mapper,
         search_request_opt,
+        disk_cache_enabled,
     )
     .await?;
 
@@ -139,6 +160,7 @@ pub async fn fetch_docs(
                     leaf_json: document.content_json,
                     partial_hit: Some(partial_hit.clone()),
                     leaf_snippet_json: document.snippet_json,
+                    leaf_explanation_json: document.explanation_json,
                 })
             } else {
                 None
@@ -151,11 +173,12 @@ pub async fn fetch_docs(
 // number of concurrent fetch allowed for a single split.
 const NUM_CONCURRENT_REQUESTS: usize = 10;
 
-/// A struct for holding a fetched document's content and snippet.
+/// A struct for holding a fetched document's content, snippet and score explanation.
 #[derive(Debug)]
 struct Document {
     content_json: String,
     snippet_json: Option<String>,
+    explanation_json: Option<String>,
 }
 
 /// Fetching docs from a specific split.
@@ -167,13 +190,23 @@ async fn fetch_docs_in_split(
     split: &SplitIdAndFooterOffsets,
     doc_mapper: Arc<dyn DocMapper>,
     search_request_opt: Option<&SearchRequest>,
+    disk_cache_enabled: bool,
 ) -> anyhow::Result<Vec<(GlobalDocAddress, Document)>> {
     global_doc_addrs.sort_by_key(|doc| doc.doc_addr);
+    let explain = search_request_opt.map_or(false, |search_request| search_request.explain);
     // Opens the index without the ephemeral unbounded cache, this cache is indeed not useful
-    // when fetching docs as we will fetch them only once.
-    let index = open_index_with_caches(&searcher_context, index_storage, split, false)
-        .await
-        .with_context(|| "open-index-for-split")?;
+    // when fetching docs as we will fetch them only once. Explaining the hits scores however
+    // requires the warmed up query data to remain available.
+    let index = open_index_with_caches(
+        &searcher_context,
+        index_storage,
+        split,
+        doc_mapper.tokenizer_manager(),
+        explain,
+        disk_cache_enabled,
+    )
+    .await
+    .with_context(|| "open-index-for-split")?;
     let index_reader = index
         .reader_builder()
         // the docs are presorted so a cache size of NUM_CONCURRENT_REQUESTS is fine
@@ -186,11 +219,22 @@ async fn fetch_docs_in_split(
     } else {
         None
     };
+    let explain_query_opt = match search_request_opt {
+        Some(search_request) if search_request.explain => {
+            Some(create_explain_query(&searcher, doc_mapper.as_ref(), search_request).await?)
+        }
+        _ => None,
+    };
+    let source_filter_opt = search_request_opt
+        .and_then(SourceFilter::from_search_request)
+        .map(Arc::new);
 
     let doc_futures = global_doc_addrs.into_iter().map(|global_doc_addr| {
         let moved_searcher = searcher.clone();
         let moved_doc_mapper = doc_mapper.clone();
         let fields_snippet_generator_opt_clone = fields_snippet_generator_opt.clone();
+        let explain_query_opt_clone = explain_query_opt.clone();
+        let source_filter_opt_clone = source_filter_opt.clone();
         tokio::spawn(async move {
             let doc = moved_searcher
                 .doc_async(global_doc_addr.doc_addr)
@@ -198,14 +242,25 @@ async fn fetch_docs_in_split(
                 .context("searcher-doc-async")?;
 
             let named_field_doc = moved_searcher.schema().to_named_doc(&doc);
-            let content_json =
-                convert_document_to_json_string(named_field_doc, &*moved_doc_mapper)?;
+            let content_json = convert_document_to_json_string(
+                named_field_doc,
+                &*moved_doc_mapper,
+                source_filter_opt_clone.as_deref(),
+            )?;
+            // The hits match the query, so failing to explain their score is an error.
+            let explanation_json = explain_query_opt_clone
+                .map(|query| query.explain(&moved_searcher, global_doc_addr.doc_addr))
+                .transpose()
+                .context("explain-hit")?
+                .map(|explanation| serde_json::to_string(&explanation))
+                .transpose()?;
             if fields_snippet_generator_opt_clone.is_none() {
                 return Ok((
                     global_doc_addr,
                     Document {
                         content_json,
                         snippet_json: None,
+                        explanation_json,
                     },
                 ));
             }
@@ -217,6 +272,7 @@ async fn fetch_docs_in_split(
                     Document {
                         content_json,
                         snippet_json: None,
+                        explanation_json,
                     },
                 ));
             }
@@ -236,6 +292,7 @@ async fn fetch_docs_in_split(
                 Document {
                     content_json,
                     snippet_json: Some(snippet_json),
+                    explanation_json,
                 },
             ))
         })
@@ -248,11 +305,102 @@ async fn fetch_docs_in_split(
         .await
 }
 
+// Highlighting options resolved from the `HighlightSpec` of a search request.
+#[derive(Clone, Debug, PartialEq)]
+struct HighlightOptions {
+    fragment_size: usize,
+    // Maximum number of snippets per field. `None` means one snippet per field value.
+    max_num_fragments: Option<usize>,
+    // Custom pre and post tags. `None` means the snippets are rendered as HTML.
+    tags_opt: Option<(String, String)>,
+    require_field_match: bool,
+}
+
+impl HighlightOptions {
+    fn from_spec(highlight_spec_opt: Option<&HighlightSpec>) -> Self {
+        let Some(highlight_spec) = highlight_spec_opt else {
+            return HighlightOptions {
+                fragment_size: SNIPPET_MAX_NUM_CHARS,
+                max_num_fragments: None,
+                tags_opt: None,
+                require_field_match: true,
+            };
+        };
+        // Like Elasticsearch, 0 fragments means that the whole field values are highlighted.
+        let (fragment_size, max_num_fragments) = match highlight_spec.number_of_fragments {
+            Some(0) => (usize::MAX, None),
+            number_of_fragments_opt => (
+                highlight_spec
+                    .fragment_size
+                    .map_or(SNIPPET_MAX_NUM_CHARS, |fragment_size| {
+                        fragment_size as usize
+                    }),
+                number_of_fragments_opt.map(|number_of_fragments| number_of_fragments as usize),
+            ),
+        };
+        let tags_opt = if highlight_spec.pre_tag.is_some() || highlight_spec.post_tag.is_some() {
+            Some((
+                highlight_spec
+                    .pre_tag
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_PRE_TAG.to_string()),
+                highlight_spec
+                    .post_tag
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_POST_TAG.to_string()),
+            ))
+        } else {
+            None
+        };
+        HighlightOptions {
+            fragment_size,
+            max_num_fragments,
+            tags_opt,
+            require_field_match: highlight_spec.require_field_match.unwrap_or(true),
+        }
+    }
+}
+
+// Surrounds the highlighted terms of the snippet with the given tags. Like `Snippet::to_html`,
+// the fragment is HTML-escaped, but the tags are inserted as is.
+fn render_snippet(snippet: &Snippet, pre_tag: &str, post_tag: &str) -> String {
+    let fragment = snippet.fragment();
+    let mut rendered = String::with_capacity(fragment.len());
+    let mut start_offset = 0;
+    for highlighted_range in snippet.highlighted() {
+        push_html_escaped(
+            &fragment[start_offset..highlighted_range.start],
+            &mut rendered,
+        );
+        rendered.push_str(pre_tag);
+        push_html_escaped(&fragment[highlighted_range.clone()], &mut rendered);
+        rendered.push_str(post_tag);
+        start_offset = highlighted_range.end;
+    }
+    push_html_escaped(&fragment[start_offset..], &mut rendered);
+    rendered
+}
+
+// Escapes the same characters as `Snippet::to_html`.
+fn push_html_escaped(text: &str, output: &mut String) {
+    for character in text.chars() {
+        match character {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#x27;"),
+            _ => output.push(character),
+        }
+    }
+}
+
 // A struct to hold the snippet generators associated to
 // the snippet fields from a search request.
 #[derive(Clone)]
 struct FieldsSnippetGenerator {
     field_generators: Arc<HashMap<String, SnippetGenerator>>,
+    highlight_options: Arc<HighlightOptions>,
 }
 
 impl FieldsSnippetGenerator {
@@ -263,17 +411,27 @@ impl FieldsSnippetGenerator {
         field_values: Vec<&Value>,
     ) -> Option<Vec<String>> {
         if let Some(snippet_generator) = self.field_generators.get(field_name) {
+            let max_num_fragments = self
+                .highlight_options
+                .max_num_fragments
+                .unwrap_or(usize::MAX);
             let values = field_values
                 .into_iter()
                 .filter_map(|value| {
                     value.as_text().and_then(|text| {
                         let snippet = snippet_generator.snippet(text);
-                        match snippet.is_empty() {
-                            false => Some(snippet.to_html()),
-                            _ => None,
+                        if snippet.is_empty() {
+                            return None;
+                        }
+                        match &self.highlight_options.tags_opt {
+                            Some((pre_tag, post_tag)) => {
+                                Some(render_snippet(&snippet, pre_tag, post_tag))
+                            }
+                            None => Some(snippet.to_html()),
                         }
                     })
                 })
+                .take(max_num_fragments)
                 .collect();
             Some(values)
         } else {
@@ -293,34 +451,57 @@ async fn create_fields_snippet_generator(
     search_request: &SearchRequest,
 ) -> anyhow::Result<FieldsSnippetGenerator> {
     let schema = searcher.schema();
-    let (query, _) = doc_mapper.query(schema.clone(), search_request)?;
+    let (query, _) = build_query(doc_mapper.as_ref(), schema.clone(), search_request)?;
+    let highlight_options = HighlightOptions::from_spec(search_request.highlight.as_ref());
     let mut snippet_generators = HashMap::new();
     for field_name in &search_request.snippet_fields {
         let field = schema.get_field(field_name)?;
-        let snippet_generator = create_snippet_generator(searcher, &*query, field).await?;
+        let snippet_generator =
+            create_snippet_generator(searcher, &*query, field, &highlight_options).await?;
         snippet_generators.insert(field_name.clone(), snippet_generator);
     }
 
     Ok(FieldsSnippetGenerator {
         field_generators: Arc::new(snippet_generators),
+        highlight_options: Arc::new(highlight_options),
     })
 }
 
+// Builds the query used to explain the hits scores and warms up the data it requires,
+// as `Query::explain` can only perform synchronous reads.
+async fn create_explain_query(
+    searcher: &Searcher,
+    doc_mapper: &dyn DocMapper,
+    search_request: &SearchRequest,
+) -> anyhow::Result<Arc<dyn Query>> {
+    let (query, mut warmup_info) =
+        build_query(doc_mapper, searcher.schema().clone(), search_request)?;
+    warmup_info.field_norms = true;
+    warmup(searcher, &warmup_info).await?;
+    Ok(Arc::from(query))
+}
+
 // Creates a snippet generator associated to a field.
 async fn create_snippet_generator(
     searcher: &Searcher,
     query: &dyn Query,
     field: Field,
+    highlight_options: &HighlightOptions,
 ) -> anyhow::Result<SnippetGenerator> {
-    let mut terms: Vec<&Term> = Vec::new();
+    let mut terms: Vec<Term> = Vec::new();
     // TODO ok with termset?
     query.query_terms(&mut |term, _need_position| {
         if term.field() == field {
-            terms.push(term);
+            terms.push(term.clone());
+        } else if !highlight_options.require_field_match {
+            // The terms targeting other fields are looked up in this field.
+            if let Some(term_str) = term.as_str() {
+                terms.push(Term::from_field_text(field, term_str));
+            }
         }
     });
     let mut terms_text: BTreeMap<String, f32> = BTreeMap::default();
-    for term in terms {
+    for term in &terms {
         let Some(term_str) = term.as_str() else {
             continue;
         };
@@ -335,6 +516,6 @@ async fn create_snippet_generator(
         terms_text,
         tokenizer,
         field,
-        SNIPPET_MAX_NUM_CHARS,
+        highlight_options.fragment_size,
     ))
 }
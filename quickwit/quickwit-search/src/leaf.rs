@@ -26,33 +26,37 @@ use anyhow::Context;
 use futures::future::try_join_all;
 use itertools::{Either, Itertools};
 use quickwit_directories::{CachingDirectory, HotDirectory, StorageDirectory};
-use quickwit_doc_mapper::{DocMapper, WarmupInfo, QUICKWIT_TOKENIZER_MANAGER};
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
 use quickwit_proto::{
     LeafListTermsResponse, LeafSearchResponse, ListTermsRequest, SearchRequest,
     SplitIdAndFooterOffsets, SplitSearchError,
 };
 use quickwit_storage::{
-    wrap_storage_with_long_term_cache, BundleStorage, MemorySizedCache, OwnedBytes, Storage,
+    wrap_storage_with_long_term_cache, BundleStorage, Cache, MemorySizedCache, OwnedBytes,
+    QuickwitCache, Storage,
 };
 use tantivy::aggregation::AggregationLimits;
 use tantivy::collector::Collector;
 use tantivy::directory::FileSlice;
 use tantivy::fastfield::FastFieldReaders;
 use tantivy::schema::{Field, FieldType};
+use tantivy::tokenizer::TokenizerManager;
 use tantivy::{Index, ReloadPolicy, Searcher, Term};
 use tracing::*;
 
 use crate::collector::{
     aggregation_limits_from_searcher_context, make_collector_for_split, make_merge_collector,
 };
+use crate::query_dsl::build_query;
 use crate::service::SearcherContext;
 use crate::SearchError;
 
-#[instrument(skip(index_storage, footer_cache))]
+#[instrument(skip(index_storage, footer_cache, disk_cache_opt))]
 async fn get_split_footer_from_cache_or_fetch(
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
     footer_cache: &MemorySizedCache<String>,
+    disk_cache_opt: Option<&dyn Cache>,
 ) -> anyhow::Result<OwnedBytes> {
     {
         let possible_val = footer_cache.get(&split_and_footer_offsets.split_id);
@@ -60,6 +64,16 @@ async fn get_split_footer_from_cache_or_fetch(
             return Ok(footer_data);
         }
     }
+    let footer_file = PathBuf::from(format!("{}.footer", split_and_footer_offsets.split_id));
+    if let Some(disk_cache) = disk_cache_opt {
+        if let Some(footer_data) = disk_cache.get_all(&footer_file).await {
+            footer_cache.put(
+                split_and_footer_offsets.split_id.to_owned(),
+                footer_data.clone(),
+            );
+            return Ok(footer_data);
+        }
+    }
     let split_file = PathBuf::from(format!("{}.split", split_and_footer_offsets.split_id));
     let footer_data_opt = index_storage
         .get_slice(
@@ -80,6 +94,11 @@ async fn get_split_footer_from_cache_or_fetch(
         split_and_footer_offsets.split_id.to_owned(),
         footer_data_opt.clone(),
     );
+    if let Some(disk_cache) = disk_cache_opt {
+        disk_cache
+            .put_all(footer_file, footer_data_opt.clone())
+            .await;
+    }
 
     Ok(footer_data_opt)
 }
@@ -87,19 +106,32 @@ async fn get_split_footer_from_cache_or_fetch(
 /// Opens a `tantivy::Index` for the given split with several cache layers:
 /// - A split footer cache given by `SearcherContext.split_footer_cache`.
 /// - A fast fields cache given by `SearcherContext.storage_long_term_cache`.
+/// - An optional local disk cache given by `SearcherContext.disk_cache_opt`, backing the two caches
+///   above. It is only used if `disk_cache_enabled` is set.
 /// - An ephemeral unbounded cache directory whose lifetime is tied to the returned `Index`.
 #[instrument(skip(searcher_context, index_storage))]
 pub(crate) async fn open_index_with_caches(
     searcher_context: &Arc<SearcherContext>,
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
+    tokenizer_manager: &TokenizerManager,
     ephemeral_unbounded_cache: bool,
+    disk_cache_enabled: bool,
 ) -> anyhow::Result<Index> {
+    let disk_cache_opt: Option<Arc<dyn Cache>> = if disk_cache_enabled {
+        searcher_context
+            .disk_cache_opt
+            .clone()
+            .map(|disk_cache| disk_cache as Arc<dyn Cache>)
+    } else {
+        None
+    };
     let split_file = PathBuf::from(format!("{}.split", split_and_footer_offsets.split_id));
     let footer_data = get_split_footer_from_cache_or_fetch(
         index_storage.clone(),
         split_and_footer_offsets,
         &searcher_context.split_footer_cache,
+        disk_cache_opt.as_deref(),
     )
     .await?;
 
@@ -108,10 +140,16 @@ pub(crate) async fn open_index_with_caches(
         split_file,
         FileSlice::new(Arc::new(footer_data)),
     )?;
-    let bundle_storage_with_cache = wrap_storage_with_long_term_cache(
-        searcher_context.fast_fields_cache.clone(),
-        Arc::new(bundle_storage),
-    );
+    let long_term_cache: Arc<dyn Cache> = if let Some(disk_cache) = disk_cache_opt {
+        Arc::new(QuickwitCache::with_disk_cache(
+            searcher_context.fast_fields_cache.clone(),
+            disk_cache,
+        ))
+    } else {
+        searcher_context.fast_fields_cache.clone()
+    };
+    let bundle_storage_with_cache =
+        wrap_storage_with_long_term_cache(long_term_cache, Arc::new(bundle_storage));
     let directory = StorageDirectory::new(bundle_storage_with_cache);
     let hot_directory = if ephemeral_unbounded_cache {
         let caching_directory = CachingDirectory::new_unbounded(Arc::new(directory));
@@ -120,7 +158,7 @@ pub(crate) async fn open_index_with_caches(
         HotDirectory::open(directory, hotcache_bytes.read_bytes()?)?
     };
     let mut index = Index::open(hot_directory)?;
-    index.set_tokenizers(QUICKWIT_TOKENIZER_MANAGER.clone());
+    index.set_tokenizers(tokenizer_manager.clone());
     Ok(index)
 }
 
@@ -317,9 +355,24 @@ async fn leaf_search_single_split(
     split: SplitIdAndFooterOffsets,
     doc_mapper: Arc<dyn DocMapper>,
     agg_limits: AggregationLimits,
+    disk_cache_enabled: bool,
 ) -> crate::Result<LeafSearchResponse> {
+    if let Some(cached_response) = searcher_context
+        .leaf_search_cache
+        .get(&split, search_request)
+    {
+        return Ok(cached_response);
+    }
     let split_id = split.split_id.to_string();
-    let index = open_index_with_caches(searcher_context, storage, &split, true).await?;
+    let index = open_index_with_caches(
+        searcher_context,
+        storage,
+        &split,
+        doc_mapper.tokenizer_manager(),
+        true,
+        disk_cache_enabled,
+    )
+    .await?;
     let split_schema = index.schema();
     let quickwit_collector = make_collector_for_split(
         split_id.clone(),
@@ -327,7 +380,7 @@ async fn leaf_search_single_split(
         search_request,
         agg_limits,
     )?;
-    let (query, mut warmup_info) = doc_mapper.query(split_schema, search_request)?;
+    let (query, mut warmup_info) = build_query(doc_mapper.as_ref(), split_schema, search_request)?;
     let reader = index
         .reader_builder()
         .reload_policy(ReloadPolicy::Manual)
@@ -347,6 +400,9 @@ async fn leaf_search_single_split(
     .map_err(|_| {
         crate::SearchError::InternalError(format!("Leaf search panicked. split={split_id}"))
     })??;
+    searcher_context
+        .leaf_search_cache
+        .put(&split, search_request, &leaf_search_response);
     Ok(leaf_search_response)
 }
 
@@ -356,12 +412,15 @@ async fn leaf_search_single_split(
 /// [PartialHit](quickwit_proto::PartialHit) candidates. The root will be in
 /// charge to consolidate, identify the actual final top hits to display, and
 /// fetch the actual documents to convert the partial hits into actual Hits.
+///
+/// The local disk cache is only used if `disk_cache_enabled` is set.
 pub async fn leaf_search(
     searcher_context: Arc<SearcherContext>,
     request: &SearchRequest,
     index_storage: Arc<dyn Storage>,
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
+    disk_cache_enabled: bool,
 ) -> Result<LeafSearchResponse, SearchError> {
     let agg_limits = aggregation_limits_from_searcher_context(&searcher_context);
     let request = Arc::new(request.clone());
@@ -391,6 +450,7 @@ pub async fn leaf_search(
                     split.clone(),
                     doc_mapper_clone,
                     agg_limits,
+                    disk_cache_enabled,
                 )
                 .await;
                 timer.observe_duration();
@@ -440,14 +500,24 @@ pub async fn leaf_search(
 }
 
 /// Apply a leaf list terms on a single split.
-#[instrument(skip(searcher_context, search_request, storage, split))]
+#[instrument(skip(searcher_context, search_request, storage, split, doc_mapper))]
 async fn leaf_list_terms_single_split(
     searcher_context: &Arc<SearcherContext>,
     search_request: &ListTermsRequest,
     storage: Arc<dyn Storage>,
     split: SplitIdAndFooterOffsets,
+    doc_mapper: Arc<dyn DocMapper>,
+    disk_cache_enabled: bool,
 ) -> crate::Result<LeafListTermsResponse> {
-    let index = open_index_with_caches(searcher_context, storage, &split, true).await?;
+    let index = open_index_with_caches(
+        searcher_context,
+        storage,
+        &split,
+        doc_mapper.tokenizer_manager(),
+        true,
+        disk_cache_enabled,
+    )
+    .await?;
     let split_schema = index.schema();
     let reader = index
         .reader_builder()
@@ -548,15 +618,20 @@ fn term_to_data(field: Field, field_type: &FieldType, field_value: &[u8]) -> Vec
 }
 
 /// `leaf` step of list terms.
+///
+/// The local disk cache is only used if `disk_cache_enabled` is set.
 pub async fn leaf_list_terms(
     searcher_context: Arc<SearcherContext>,
     request: &ListTermsRequest,
     index_storage: Arc<dyn Storage>,
     splits: &[SplitIdAndFooterOffsets],
+    doc_mapper: Arc<dyn DocMapper>,
+    disk_cache_enabled: bool,
 ) -> Result<LeafListTermsResponse, SearchError> {
     let leaf_search_single_split_futures: Vec<_> = splits
         .iter()
         .map(|split| {
+            let doc_mapper_clone = doc_mapper.clone();
             let index_storage_clone = index_storage.clone();
             let searcher_context_clone = searcher_context.clone();
             async move {
@@ -574,6 +649,8 @@ pub async fn leaf_list_terms(
                     request,
                     index_storage_clone,
                     split.clone(),
+                    doc_mapper_clone,
+                    disk_cache_enabled,
                 )
                 .await;
                 timer.observe_duration();
@@ -0,0 +1,240 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use prost::Message;
+use quickwit_proto::{LeafSearchResponse, SearchRequest, SplitIdAndFooterOffsets};
+use quickwit_storage::{MemorySizedCache, OwnedBytes};
+
+/// A cache of the leaf search responses of individual splits.
+///
+/// Published splits are immutable, so the response of a split to a given request never changes.
+/// This comes handy for dashboards, which run the same requests at regular intervals over
+/// mostly the same splits.
+pub struct LeafSearchCache {
+    // `None` when the cache is disabled, i.e. when its capacity is 0.
+    content_opt: Option<MemorySizedCache<CacheKey>>,
+}
+
+impl LeafSearchCache {
+    /// Creates a new leaf search cache holding up to `capacity_in_bytes` bytes of encoded
+    /// responses.
+    pub fn new(capacity_in_bytes: usize) -> LeafSearchCache {
+        if capacity_in_bytes == 0 {
+            return LeafSearchCache { content_opt: None };
+        }
+        let content = MemorySizedCache::with_capacity_in_bytes(
+            capacity_in_bytes,
+            &quickwit_storage::STORAGE_METRICS.leaf_search_cache,
+        );
+        LeafSearchCache {
+            content_opt: Some(content),
+        }
+    }
+
+    /// Returns the cached response of the split to the request, if any.
+    pub fn get(
+        &self,
+        split_info: &SplitIdAndFooterOffsets,
+        search_request: &SearchRequest,
+    ) -> Option<LeafSearchResponse> {
+        let content = self.content_opt.as_ref()?;
+        let key = CacheKey::from_split_and_request(split_info, search_request);
+        let encoded_response = content.get(&key)?;
+        // We encoded the response ourselves, so decoding it cannot fail.
+        LeafSearchResponse::decode(encoded_response.as_slice()).ok()
+    }
+
+    /// Caches the response of the split to the request.
+    pub fn put(
+        &self,
+        split_info: &SplitIdAndFooterOffsets,
+        search_request: &SearchRequest,
+        leaf_search_response: &LeafSearchResponse,
+    ) {
+        let Some(content) = self.content_opt.as_ref() else {
+            return;
+        };
+        let key = CacheKey::from_split_and_request(split_info, search_request);
+        let encoded_response = leaf_search_response.encode_to_vec();
+        content.put(key, OwnedBytes::new(encoded_response));
+    }
+}
+
+/// A cache key made of the split ID and of the request, normalized so that equivalent requests
+/// share the same key.
+#[derive(Debug, Hash, PartialEq, Eq)]
+struct CacheKey {
+    split_id: String,
+    encoded_request: Vec<u8>,
+}
+
+impl CacheKey {
+    fn from_split_and_request(
+        split_info: &SplitIdAndFooterOffsets,
+        search_request: &SearchRequest,
+    ) -> Self {
+        let mut search_request = search_request.clone();
+        // The index and the splits are already identified by the split ID.
+        search_request.index_id.clear();
+        search_request.split_ids.clear();
+
+        // A time range that covers the entire split filters out nothing, so the same response
+        // is valid for all such time ranges: this is what happens when dashboards shift their
+        // time window.
+        if let (Some(split_start), Some(split_end)) =
+            (split_info.timestamp_start, split_info.timestamp_end)
+        {
+            if search_request
+                .start_timestamp
+                .map_or(true, |start_timestamp| start_timestamp <= split_start)
+            {
+                search_request.start_timestamp = None;
+            }
+            // The request end timestamp is exclusive whereas the split end timestamp is
+            // inclusive.
+            if search_request
+                .end_timestamp
+                .map_or(true, |end_timestamp| end_timestamp > split_end)
+            {
+                search_request.end_timestamp = None;
+            }
+        }
+        CacheKey {
+            split_id: split_info.split_id.clone(),
+            encoded_request: search_request.encode_to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::{LeafSearchResponse, PartialHit, SearchRequest, SplitIdAndFooterOffsets};
+
+    use super::LeafSearchCache;
+
+    fn split_for_test(
+        split_id: &str,
+        timestamp_range: Option<(i64, i64)>,
+    ) -> SplitIdAndFooterOffsets {
+        SplitIdAndFooterOffsets {
+            split_id: split_id.to_string(),
+            split_footer_start: 0,
+            split_footer_end: 100,
+            timestamp_start: timestamp_range.map(|(start, _)| start),
+            timestamp_end: timestamp_range.map(|(_, end)| end),
+        }
+    }
+
+    fn leaf_search_response_for_test() -> LeafSearchResponse {
+        LeafSearchResponse {
+            num_hits: 1,
+            partial_hits: vec![PartialHit {
+                sorting_field_value: 0,
+                split_id: "split_1".to_string(),
+                segment_ord: 0,
+                doc_id: 0,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_leaf_search_cache_no_timestamp() {
+        let cache = LeafSearchCache::new(64_000_000);
+        let split_1 = split_for_test("split_1", None);
+        let split_2 = split_for_test("split_2", None);
+        let query_1 = SearchRequest {
+            index_id: "test-index".to_string(),
+            query: "body:hello".to_string(),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let query_2 = SearchRequest {
+            query: "body:world".to_string(),
+            ..query_1.clone()
+        };
+        let result = leaf_search_response_for_test();
+
+        assert!(cache.get(&split_1, &query_1).is_none());
+        cache.put(&split_1, &query_1, &result);
+
+        assert_eq!(cache.get(&split_1, &query_1).unwrap(), result);
+        assert!(cache.get(&split_1, &query_2).is_none());
+        assert!(cache.get(&split_2, &query_1).is_none());
+    }
+
+    #[test]
+    fn test_leaf_search_cache_time_range_covering_split() {
+        let cache = LeafSearchCache::new(64_000_000);
+        let split = split_for_test("split_1", Some((100, 199)));
+        let query_1 = SearchRequest {
+            index_id: "test-index".to_string(),
+            query: "body:hello".to_string(),
+            max_hits: 10,
+            start_timestamp: Some(50),
+            end_timestamp: Some(200),
+            ..Default::default()
+        };
+        let result = leaf_search_response_for_test();
+        cache.put(&split, &query_1, &result);
+
+        // Time ranges covering the whole split reuse the cached response.
+        let query_2 = SearchRequest {
+            start_timestamp: Some(100),
+            end_timestamp: Some(300),
+            ..query_1.clone()
+        };
+        assert_eq!(cache.get(&split, &query_2).unwrap(), result);
+
+        let query_3 = SearchRequest {
+            start_timestamp: None,
+            end_timestamp: None,
+            ..query_1.clone()
+        };
+        assert_eq!(cache.get(&split, &query_3).unwrap(), result);
+
+        // Time ranges overlapping the split partially do not.
+        let query_4 = SearchRequest {
+            start_timestamp: Some(101),
+            ..query_1.clone()
+        };
+        assert!(cache.get(&split, &query_4).is_none());
+
+        let query_5 = SearchRequest {
+            end_timestamp: Some(199),
+            ..query_1.clone()
+        };
+        assert!(cache.get(&split, &query_5).is_none());
+    }
+
+    #[test]
+    fn test_leaf_search_cache_disabled() {
+        let cache = LeafSearchCache::new(0);
+        let split = split_for_test("split_1", None);
+        let query = SearchRequest {
+            index_id: "test-index".to_string(),
+            query: "body:hello".to_string(),
+            max_hits: 10,
+            ..Default::default()
+        };
+        cache.put(&split, &query, &leaf_search_response_for_test());
+        assert!(cache.get(&split, &query).is_none());
+    }
+}
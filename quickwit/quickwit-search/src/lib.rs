@@ -26,17 +26,21 @@ mod client;
 mod cluster_client;
 mod collector;
 mod error;
+mod explain;
 mod fetch_docs;
 mod filters;
 mod find_trace_ids_collector;
 mod leaf;
+mod leaf_cache;
 mod query_dsl;
 mod retry;
 mod root;
+mod search_after;
 mod search_job_placer;
 mod search_response_rest;
 mod search_stream;
 mod service;
+mod source_filter;
 mod thread_pool;
 
 mod metrics;
@@ -65,27 +69,40 @@ use quickwit_config::{build_doc_mapper, QuickwitConfig, SearcherConfig};
 use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
 use quickwit_metastore::{ListSplitsQuery, Metastore, SplitMetadata, SplitState};
 use quickwit_proto::{Hit, PartialHit, SearchRequest, SearchResponse, SplitIdAndFooterOffsets};
-use quickwit_storage::StorageUriResolver;
+use quickwit_storage::{LocalDiskCache, StorageUriResolver};
 use tantivy::aggregation::agg_result::AggregationResults;
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::DocAddress;
 
 pub use crate::client::{create_search_service_client, SearchServiceClient};
 pub use crate::cluster_client::ClusterClient;
+use crate::collector::sort_values_json;
 pub use crate::error::{parse_grpc_error, SearchError};
-use crate::fetch_docs::fetch_docs;
+use crate::explain::explain_search;
+use crate::fetch_docs::{fetch_docs, fetch_docs_requires_search_request};
 use crate::leaf::{leaf_list_terms, leaf_search};
-pub use crate::root::{jobs_to_leaf_request, root_list_terms, root_search, SearchJob};
+use crate::query_dsl::build_query;
+pub use crate::query_dsl::QueryDsl;
+pub use crate::root::{
+    jobs_to_leaf_request, root_explain_hit, root_list_terms, root_search, SearchJob,
+};
+pub use crate::search_after::{
+    encode_search_after_cursor, parse_search_after_cursor, search_after_from_sort_values,
+    search_after_to_sort_values,
+};
 pub use crate::search_job_placer::SearchJobPlacer;
 pub use crate::search_response_rest::SearchResponseRest;
 pub use crate::search_stream::root_search_stream;
 pub use crate::service::{MockSearchService, SearchService, SearchServiceImpl};
+use crate::source_filter::SourceFilter;
 use crate::thread_pool::run_cpu_intensive;
 
 /// GlobalDocAddress serves as a hit address.
 #[derive(Clone, Eq, Debug, PartialEq, Hash, Ord, PartialOrd)]
-pub(crate) struct GlobalDocAddress {
+pub struct GlobalDocAddress {
+    /// ID of the split containing the document.
     pub split: String,
+    /// Address of the document within the split.
     pub doc_addr: DocAddress,
 }
 
@@ -101,9 +118,14 @@ impl GlobalDocAddress {
     }
 }
 
-fn partial_hit_sorting_key(partial_hit: &PartialHit) -> (Reverse<u64>, GlobalDocAddress) {
+/// Returns the key ordering hits as the root search does: by decreasing sorting field values,
+/// with ties broken by increasing split ID, segment ordinal and doc ID.
+pub fn partial_hit_sorting_key(
+    partial_hit: &PartialHit,
+) -> (Reverse<u64>, Reverse<Vec<u64>>, GlobalDocAddress) {
     (
         Reverse(partial_hit.sorting_field_value),
+        Reverse(partial_hit.secondary_sorting_field_values.clone()),
         GlobalDocAddress::from_partial_hit(partial_hit),
     )
 }
@@ -113,6 +135,14 @@ fn extract_split_and_footer_offsets(split_metadata: &SplitMetadata) -> SplitIdAn
         split_id: split_metadata.split_id.clone(),
         split_footer_start: split_metadata.footer_offsets.start,
         split_footer_end: split_metadata.footer_offsets.end,
+        timestamp_start: split_metadata
+            .time_range
+            .as_ref()
+            .map(|time_range| *time_range.start()),
+        timestamp_end: split_metadata
+            .time_range
+            .as_ref()
+            .map(|time_range| *time_range.end()),
     }
 }
 
@@ -121,8 +151,14 @@ async fn list_relevant_splits(
     search_request: &SearchRequest,
     metastore: &dyn Metastore,
 ) -> crate::Result<Vec<SplitMetadata>> {
-    let mut query = ListSplitsQuery::for_index(&search_request.index_id)
-        .with_split_state(SplitState::Published);
+    let mut query = ListSplitsQuery::for_index(&search_request.index_id);
+    // Pinned splits may have been marked for deletion by a merge in the meantime. They can still
+    // be searched until they are garbage collected.
+    query = if search_request.split_ids.is_empty() {
+        query.with_split_state(SplitState::Published)
+    } else {
+        query.with_split_states([SplitState::Published, SplitState::MarkedForDeletion])
+    };
 
     if let Some(start_ts) = search_request.start_timestamp {
         query = query.with_time_range_start_gte(start_ts);
@@ -132,14 +168,22 @@ async fn list_relevant_splits(
         query = query.with_time_range_end_lt(end_ts);
     }
 
-    if let Some(tags_filter) = extract_tags_from_query(&search_request.query)? {
-        query = query.with_tags_filter(tags_filter);
+    // Tag pruning is not supported for the query DSL yet, which takes precedence over the query
+    // string.
+    if search_request.query_dsl.is_none() {
+        if let Some(tags_filter) = extract_tags_from_query(&search_request.query)? {
+            query = query.with_tags_filter(tags_filter);
+        }
     }
 
     let split_metas = metastore.list_splits(query).await?;
     Ok(split_metas
         .into_iter()
         .map(|metadata| metadata.split_metadata)
+        .filter(|split_metadata| {
+            search_request.split_ids.is_empty()
+                || search_request.split_ids.contains(&split_metadata.split_id)
+        })
         .collect::<Vec<_>>())
 }
 
@@ -148,12 +192,17 @@ async fn list_relevant_splits(
 ///
 /// We perform this conversion at leaf level only to avoid having
 /// another intermediate json format between the leaves and the root.
+/// For the same reason, the document is projected on the requested fields here.
 fn convert_document_to_json_string(
     named_field_doc: NamedFieldDocument,
     doc_mapper: &dyn DocMapper,
+    source_filter_opt: Option<&SourceFilter>,
 ) -> anyhow::Result<String> {
     let NamedFieldDocument(named_field_doc_map) = named_field_doc;
-    let doc_json_map = doc_mapper.doc_to_json(named_field_doc_map)?;
+    let mut doc_json_map = doc_mapper.doc_to_json(named_field_doc_map)?;
+    if let Some(source_filter) = source_filter_opt {
+        doc_json_map = source_filter.filter_document(doc_json_map);
+    }
     let content_json =
         serde_json::to_string(&doc_json_map).expect("Json serialization should never fail.");
     Ok(content_json)
@@ -179,7 +228,8 @@ pub async fn single_node_search(
     // it should contain an `index_uri`.
     //
     // TODO see if it can be improved.
-    let index_storage = storage_resolver.resolve(&index_config.index_uri)?;
+    let index_storage = storage_resolver
+        .resolve_with_encryption(&index_config.index_uri, index_config.encryption_key_uri())?;
     let metas = list_relevant_splits(search_request, metastore).await?;
     let split_metadata: Vec<SplitIdAndFooterOffsets> =
         metas.iter().map(extract_split_and_footer_offsets).collect();
@@ -191,24 +241,32 @@ pub async fn single_node_search(
     validate_request(search_request)?;
 
     // Validates the query by effectively building it against the current schema.
-    doc_mapper.query(doc_mapper.schema(), search_request)?;
+    let (query, _) = build_query(doc_mapper.as_ref(), doc_mapper.schema(), search_request)?;
+    let explanation = if search_request.explain {
+        Some(explain_search(search_request, query.as_ref(), metastore, &metas).await?)
+    } else {
+        None
+    };
     let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default()));
+    let disk_cache_enabled = index_config.encryption_key_uri().is_none();
     let leaf_search_response = leaf_search(
         searcher_context.clone(),
         search_request,
         index_storage.clone(),
         &split_metadata[..],
         doc_mapper.clone(),
+        disk_cache_enabled,
     )
     .await
     .context("Failed to perform leaf search.")?;
 
-    let search_request_opt = if !search_request.snippet_fields.is_empty() {
+    let search_request_opt = if fetch_docs_requires_search_request(search_request) {
         Some(search_request)
     } else {
         None
     };
 
+    let schema = doc_mapper.schema();
     let fetch_docs_response = fetch_docs(
         searcher_context.clone(),
         leaf_search_response.partial_hits,
@@ -216,6 +274,7 @@ pub async fn single_node_search(
         &split_metadata,
         doc_mapper,
         search_request_opt,
+        disk_cache_enabled,
     )
     .await
     .context("Failed to perform fetch docs.")?;
@@ -223,12 +282,25 @@ pub async fn single_node_search(
         .hits
         .into_iter()
         .map(|leaf_hit| Hit {
+            sort_values_json: leaf_hit
+                .partial_hit
+                .as_ref()
+                .and_then(|partial_hit| sort_values_json(search_request, &schema, partial_hit)),
             json: leaf_hit.leaf_json,
             partial_hit: leaf_hit.partial_hit,
             snippet: leaf_hit.leaf_snippet_json,
+            explanation: leaf_hit.leaf_explanation_json,
         })
         .collect();
     let elapsed = start_instant.elapsed();
+    let split_ids = if search_request.pin_splits {
+        metas
+            .iter()
+            .map(|split_metadata| split_metadata.split_id.clone())
+            .collect()
+    } else {
+        Vec::new()
+    };
     let aggregation = if let Some(intermediate_aggregation_result) =
         leaf_search_response.intermediate_aggregation_result
     {
@@ -263,6 +335,8 @@ pub async fn single_node_search(
             .iter()
             .map(|error| format!("{error:?}"))
             .collect_vec(),
+        split_ids,
+        explanation,
     })
 }
 
@@ -274,12 +348,33 @@ pub async fn start_searcher_service(
     search_job_placer: SearchJobPlacer,
 ) -> anyhow::Result<Arc<dyn SearchService>> {
     let cluster_client = ClusterClient::new(search_job_placer.clone());
+    let disk_cache_opt =
+        if let Some(disk_cache_capacity) = quickwit_config.searcher_config.disk_cache_capacity {
+            let disk_cache_dir_path = quickwit_config.data_dir_path.join("searcher-disk-cache");
+            let disk_cache = LocalDiskCache::open(
+                &disk_cache_dir_path,
+                disk_cache_capacity.get_bytes() as u64,
+                &quickwit_storage::STORAGE_METRICS.disk_cache,
+            )
+            .with_context(|| {
+                format!(
+                    "Failed to open searcher disk cache `{}`.",
+                    disk_cache_dir_path.display()
+                )
+            })?;
+            let disk_cache = Arc::new(disk_cache);
+            disk_cache.spawn_index_flush_loop();
+            Some(disk_cache)
+        } else {
+            None
+        };
     let search_service = Arc::new(SearchServiceImpl::new(
         metastore,
         storage_uri_resolver,
         cluster_client,
         search_job_placer,
         quickwit_config.searcher_config.clone(),
+        disk_cache_opt,
     ));
     Ok(search_service)
 }
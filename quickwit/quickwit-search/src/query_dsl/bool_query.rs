@@ -17,11 +17,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use quickwit_doc_mapper::DocMapper;
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
 use serde::{Deserialize, Serialize};
 use serde_with::formats::PreferMany;
 use serde_with::{serde_as, OneOrMany};
-use tantivy::query::{BooleanQuery, BoostQuery, Occur};
+use tantivy::query::{AllQuery, BooleanQuery, BoostQuery, Occur};
 
 use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
 use crate::query_dsl::QueryDsl;
@@ -93,19 +93,38 @@ impl BuildTantivyQuery for BoolQuery {
             clauses.push((Occur::MustNot, must_not_leaf));
         }
         for should in &self.should {
-            let must_not_leaf = should.build_tantivy_query(doc_mapper)?;
-            clauses.push((Occur::MustNot, must_not_leaf));
+            let should_leaf = should.build_tantivy_query(doc_mapper)?;
+            clauses.push((Occur::Should, should_leaf));
         }
         for filter in &self.filter {
             let filter_leaf = filter.build_tantivy_query(doc_mapper)?;
             clauses.push((Occur::Must, Box::new(BoostQuery::new(filter_leaf, 0.0f32))));
         }
+        if self.must.is_empty() && self.should.is_empty() && self.filter.is_empty() {
+            // Like in Elasticsearch, a bool query without any positive clause matches all
+            // documents, except the ones matching the `must_not` clauses.
+            clauses.push((Occur::Must, Box::new(AllQuery)));
+        }
         let bool_query = Box::new(BooleanQuery::from(clauses));
         if self.boost == DEFAULT_BOOST {
             return Ok(bool_query);
         }
         Ok(Box::new(BoostQuery::new(bool_query, self.boost.0)))
     }
+
+    fn warmup_info(&self, doc_mapper: &dyn DocMapper) -> anyhow::Result<WarmupInfo> {
+        let mut warmup_info = WarmupInfo::default();
+        for sub_query in self
+            .must
+            .iter()
+            .chain(&self.must_not)
+            .chain(&self.should)
+            .chain(&self.filter)
+        {
+            warmup_info.merge(sub_query.warmup_info(doc_mapper)?);
+        }
+        Ok(warmup_info)
+    }
 }
 
 #[cfg(test)]
@@ -17,11 +17,19 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use quickwit_doc_mapper::DocMapper;
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
 
 pub trait BuildTantivyQuery {
     fn build_tantivy_query(
         &self,
         doc_mapper: &dyn DocMapper,
     ) -> anyhow::Result<Box<dyn crate::TantivyQuery>>;
+
+    /// Returns the data that needs to be loaded before running the query on a split.
+    ///
+    /// The terms reported by the tantivy query via `Query::query_terms` are collected
+    /// separately and do not need to be returned here.
+    fn warmup_info(&self, _doc_mapper: &dyn DocMapper) -> anyhow::Result<WarmupInfo> {
+        Ok(WarmupInfo::default())
+    }
 }
@@ -0,0 +1,127 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::ops::Bound;
+
+use anyhow::bail;
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
+use serde::{Deserialize, Serialize};
+use tantivy::query::{RangeQuery as TantivyRangeQuery, RegexQuery};
+use tantivy::schema::{FieldEntry, FieldType};
+
+use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+use crate::query_dsl::utils::resolve_field;
+
+/// Matches the documents that have at least one value for the field.
+///
+/// Only text fields and fast fields are supported.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ExistsQuery {
+    pub field: String,
+}
+
+/// How the presence of a value in a field can be checked.
+enum ExistsStrategy {
+    /// Any term of the term dictionary.
+    TermDictionary,
+    /// Any value of the fast field column.
+    FastField,
+}
+
+fn exists_strategy(field_entry: &FieldEntry) -> anyhow::Result<ExistsStrategy> {
+    match field_entry.field_type() {
+        FieldType::Str(_) if field_entry.is_indexed() => Ok(ExistsStrategy::TermDictionary),
+        FieldType::Bool(_)
+        | FieldType::Date(_)
+        | FieldType::IpAddr(_)
+        | FieldType::F64(_)
+        | FieldType::I64(_)
+        | FieldType::U64(_)
+            if field_entry.is_fast() =>
+        {
+            Ok(ExistsStrategy::FastField)
+        }
+        _ => bail!(
+            "Exists queries are only supported on indexed text fields and fast fields (field \
+             `{}`).",
+            field_entry.name()
+        ),
+    }
+}
+
+impl BuildTantivyQuery for ExistsQuery {
+    fn build_tantivy_query(
+        &self,
+        doc_mapper: &dyn DocMapper,
+    ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
+        let schema = doc_mapper.schema();
+        let (field, field_entry) = resolve_field(&schema, &self.field)?;
+        match exists_strategy(field_entry)? {
+            ExistsStrategy::TermDictionary => {
+                let regex_query = RegexQuery::from_pattern(".*", field)?;
+                Ok(Box::new(regex_query))
+            }
+            ExistsStrategy::FastField => {
+                let range_query = TantivyRangeQuery::new_term_bounds(
+                    field_entry.name().to_string(),
+                    field_entry.field_type().value_type(),
+                    &Bound::Unbounded,
+                    &Bound::Unbounded,
+                );
+                Ok(Box::new(range_query))
+            }
+        }
+    }
+
+    fn warmup_info(&self, doc_mapper: &dyn DocMapper) -> anyhow::Result<WarmupInfo> {
+        let schema = doc_mapper.schema();
+        let (_, field_entry) = resolve_field(&schema, &self.field)?;
+        let field_names: HashSet<String> = [field_entry.name().to_string()].into_iter().collect();
+        let warmup_info = match exists_strategy(field_entry)? {
+            ExistsStrategy::TermDictionary => WarmupInfo {
+                term_dict_field_names: field_names.clone(),
+                posting_field_names: field_names,
+                ..Default::default()
+            },
+            ExistsStrategy::FastField => WarmupInfo {
+                fast_field_names: field_names,
+                ..Default::default()
+            },
+        };
+        Ok(warmup_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExistsQuery;
+
+    #[test]
+    fn test_exists_query_deserialize() {
+        let exists_query: ExistsQuery = serde_json::from_str(r#"{ "field": "severity" }"#).unwrap();
+        assert_eq!(
+            exists_query,
+            ExistsQuery {
+                field: "severity".to_string()
+            }
+        );
+    }
+}
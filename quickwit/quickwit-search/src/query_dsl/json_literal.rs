@@ -0,0 +1,78 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Scalar value targeted by term-level queries.
+///
+/// Elasticsearch clients send numbers and booleans as plain JSON literals (e.g. `"status": 200`),
+/// so we accept them in addition to strings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum JsonLiteral {
+    String(String),
+    Number(serde_json::Number),
+    Bool(bool),
+}
+
+impl fmt::Display for JsonLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonLiteral::String(text) => text.fmt(f),
+            JsonLiteral::Number(number) => number.fmt(f),
+            JsonLiteral::Bool(boolean) => boolean.fmt(f),
+        }
+    }
+}
+
+impl From<String> for JsonLiteral {
+    fn from(text: String) -> Self {
+        JsonLiteral::String(text)
+    }
+}
+
+impl From<&str> for JsonLiteral {
+    fn from(text: &str) -> Self {
+        JsonLiteral::String(text.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonLiteral;
+
+    #[test]
+    fn test_json_literal_deserialize() {
+        let literals: Vec<JsonLiteral> =
+            serde_json::from_str(r#"["hello", 200, -1.5, true]"#).unwrap();
+        assert_eq!(
+            literals,
+            vec![
+                JsonLiteral::String("hello".to_string()),
+                JsonLiteral::Number(200.into()),
+                JsonLiteral::Number(serde_json::Number::from_f64(-1.5).unwrap()),
+                JsonLiteral::Bool(true),
+            ]
+        );
+        let texts: Vec<String> = literals.iter().map(ToString::to_string).collect();
+        assert_eq!(texts, ["hello", "200", "-1.5", "true"]);
+    }
+}
@@ -0,0 +1,39 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_doc_mapper::DocMapper;
+use serde::{Deserialize, Serialize};
+use tantivy::query::AllQuery;
+
+use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+
+/// # Unsupported features
+/// - boost
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MatchAllQuery {}
+
+impl BuildTantivyQuery for MatchAllQuery {
+    fn build_tantivy_query(
+        &self,
+        _doc_mapper: &dyn DocMapper,
+    ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
+        Ok(Box::new(AllQuery))
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use anyhow::bail;
+use quickwit_doc_mapper::DocMapper;
+use serde::{Deserialize, Serialize};
+use tantivy::query::{EmptyQuery, PhraseQuery, TermQuery as TantivyTermQuery};
+
+use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+use crate::query_dsl::utils::{
+    compute_tokenized_terms, extract_single_entry, index_record_option, resolve_field,
+};
+
+/// # Unsupported features
+/// - analyzer
+/// - zero_terms_query (a query producing no tokens matches no documents)
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(
+    into = "HashMap<String, MatchPhraseQueryParams>",
+    try_from = "HashMap<String, MatchPhraseQueryParamsOrText>"
+)]
+pub struct MatchPhraseQuery {
+    pub field: String,
+    pub params: MatchPhraseQueryParams,
+}
+
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MatchPhraseQueryParams {
+    pub query: String,
+    #[serde(default)]
+    pub slop: u32,
+}
+
+impl BuildTantivyQuery for MatchPhraseQuery {
+    fn build_tantivy_query(
+        &self,
+        doc_mapper: &dyn DocMapper,
+    ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
+        let schema = doc_mapper.schema();
+        let (field, field_entry) = resolve_field(&schema, &self.field)?;
+        let mut terms = compute_tokenized_terms(
+            field,
+            field_entry,
+            &self.params.query,
+            doc_mapper.tokenizer_manager(),
+        )?;
+        let index_record_option = index_record_option(field_entry)?;
+        match terms.len() {
+            0 => Ok(Box::new(EmptyQuery)),
+            1 => {
+                let (_position, term) = terms.pop().expect("There should be exactly one term.");
+                Ok(Box::new(TantivyTermQuery::new(term, index_record_option)))
+            }
+            _ => {
+                if !index_record_option.has_positions() {
+                    bail!(
+                        "Field `{}` does not have positions indexed, which are required by phrase \
+                         queries.",
+                        field_entry.name()
+                    );
+                }
+                let mut phrase_query = PhraseQuery::new_with_offset(terms);
+                phrase_query.set_slop(self.params.slop);
+                Ok(Box::new(phrase_query))
+            }
+        }
+    }
+}
+
+// Private enum used for deserialization.
+// Elasticsearch also accepts the short form `{field: <query>}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MatchPhraseQueryParamsOrText {
+    Params(MatchPhraseQueryParams),
+    Text(String),
+}
+
+impl From<MatchPhraseQuery> for HashMap<String, MatchPhraseQueryParams> {
+    fn from(match_phrase_query: MatchPhraseQuery) -> Self {
+        let mut map = HashMap::with_capacity(1);
+        map.insert(match_phrase_query.field, match_phrase_query.params);
+        map
+    }
+}
+
+impl TryFrom<HashMap<String, MatchPhraseQueryParamsOrText>> for MatchPhraseQuery {
+    type Error = &'static str;
+
+    fn try_from(map: HashMap<String, MatchPhraseQueryParamsOrText>) -> Result<Self, Self::Error> {
+        let (field, params_or_text) = extract_single_entry(map)?;
+        let params = match params_or_text {
+            MatchPhraseQueryParamsOrText::Params(params) => params,
+            MatchPhraseQueryParamsOrText::Text(query) => MatchPhraseQueryParams { query, slop: 0 },
+        };
+        Ok(MatchPhraseQuery { field, params })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchPhraseQuery, MatchPhraseQueryParams};
+
+    #[test]
+    fn test_match_phrase_query_deserialize() {
+        let match_phrase_query_json = r#"{ "body": { "query": "quick fox", "slop": 1 } }"#;
+        let match_phrase_query: MatchPhraseQuery =
+            serde_json::from_str(match_phrase_query_json).unwrap();
+        assert_eq!(
+            match_phrase_query,
+            MatchPhraseQuery {
+                field: "body".to_string(),
+                params: MatchPhraseQueryParams {
+                    query: "quick fox".to_string(),
+                    slop: 1,
+                }
+            }
+        );
+        let match_phrase_query_short_json = r#"{ "body": "quick fox" }"#;
+        let match_phrase_query_short: MatchPhraseQuery =
+            serde_json::from_str(match_phrase_query_short_json).unwrap();
+        assert_eq!(match_phrase_query_short.params.slop, 0);
+    }
+}
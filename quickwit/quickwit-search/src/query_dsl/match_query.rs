@@ -0,0 +1,180 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use quickwit_doc_mapper::DocMapper;
+use serde::{Deserialize, Serialize};
+use tantivy::query::{BooleanQuery, EmptyQuery, Occur, TermQuery as TantivyTermQuery};
+
+use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+use crate::query_dsl::json_literal::JsonLiteral;
+use crate::query_dsl::utils::{
+    compute_tokenized_terms, extract_single_entry, index_record_option, resolve_field,
+};
+use crate::TantivyQuery;
+
+/// # Unsupported features
+/// - analyzer
+/// - fuzziness
+/// - minimum_should_match
+/// - zero_terms_query (a query producing no tokens matches no documents)
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(
+    into = "HashMap<String, MatchQueryParams>",
+    try_from = "HashMap<String, MatchQueryParamsOrLiteral>"
+)]
+pub struct MatchQuery {
+    pub field: String,
+    pub params: MatchQueryParams,
+}
+
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct MatchQueryParams {
+    pub query: JsonLiteral,
+    #[serde(default)]
+    pub operator: MatchQueryOperator,
+}
+
+/// Boolean logic used to combine the terms extracted from the query text.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchQueryOperator {
+    #[default]
+    #[serde(alias = "OR")]
+    Or,
+    #[serde(alias = "AND")]
+    And,
+}
+
+impl MatchQuery {
+    #[cfg(test)]
+    pub fn from_field_query(field: impl ToString, query: impl ToString) -> Self {
+        Self {
+            field: field.to_string(),
+            params: MatchQueryParams {
+                query: JsonLiteral::String(query.to_string()),
+                operator: MatchQueryOperator::Or,
+            },
+        }
+    }
+}
+
+impl BuildTantivyQuery for MatchQuery {
+    fn build_tantivy_query(
+        &self,
+        doc_mapper: &dyn DocMapper,
+    ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
+        let schema = doc_mapper.schema();
+        let (field, field_entry) = resolve_field(&schema, &self.field)?;
+        let terms = compute_tokenized_terms(
+            field,
+            field_entry,
+            &self.params.query.to_string(),
+            doc_mapper.tokenizer_manager(),
+        )?;
+        let index_record_option = index_record_option(field_entry)?;
+        let occur = match self.params.operator {
+            MatchQueryOperator::Or => Occur::Should,
+            MatchQueryOperator::And => Occur::Must,
+        };
+        let mut term_queries: Vec<(Occur, Box<dyn TantivyQuery>)> = terms
+            .into_iter()
+            .map(|(_position, term)| {
+                let term_query: Box<dyn TantivyQuery> =
+                    Box::new(TantivyTermQuery::new(term, index_record_option));
+                (occur, term_query)
+            })
+            .collect();
+        match term_queries.len() {
+            0 => Ok(Box::new(EmptyQuery)),
+            1 => Ok(term_queries
+                .pop()
+                .expect("There should be exactly one term query.")
+                .1),
+            _ => Ok(Box::new(BooleanQuery::from(term_queries))),
+        }
+    }
+}
+
+// Private enum used for deserialization.
+// Elasticsearch also accepts the short form `{field: <query>}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MatchQueryParamsOrLiteral {
+    Params(MatchQueryParams),
+    Literal(JsonLiteral),
+}
+
+impl From<MatchQuery> for HashMap<String, MatchQueryParams> {
+    fn from(match_query: MatchQuery) -> Self {
+        let mut map = HashMap::with_capacity(1);
+        map.insert(match_query.field, match_query.params);
+        map
+    }
+}
+
+impl TryFrom<HashMap<String, MatchQueryParamsOrLiteral>> for MatchQuery {
+    type Error = &'static str;
+
+    fn try_from(map: HashMap<String, MatchQueryParamsOrLiteral>) -> Result<Self, Self::Error> {
+        let (field, params_or_literal) = extract_single_entry(map)?;
+        let params = match params_or_literal {
+            MatchQueryParamsOrLiteral::Params(params) => params,
+            MatchQueryParamsOrLiteral::Literal(query) => MatchQueryParams {
+                query,
+                operator: MatchQueryOperator::default(),
+            },
+        };
+        Ok(MatchQuery { field, params })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatchQuery, MatchQueryOperator, MatchQueryParams};
+    use crate::query_dsl::json_literal::JsonLiteral;
+
+    #[test]
+    fn test_match_query_deserialize_short_form() {
+        let match_query_json = r#"{ "body": "hello world" }"#;
+        let match_query: MatchQuery = serde_json::from_str(match_query_json).unwrap();
+        assert_eq!(
+            match_query,
+            MatchQuery::from_field_query("body", "hello world")
+        );
+    }
+
+    #[test]
+    fn test_match_query_deserialize_with_operator() {
+        let match_query_json = r#"{ "body": { "query": "hello world", "operator": "AND" } }"#;
+        let match_query: MatchQuery = serde_json::from_str(match_query_json).unwrap();
+        assert_eq!(
+            match_query,
+            MatchQuery {
+                field: "body".to_string(),
+                params: MatchQueryParams {
+                    query: JsonLiteral::from("hello world"),
+                    operator: MatchQueryOperator::And,
+                }
+            }
+        );
+    }
+}
@@ -29,27 +29,76 @@
 // documentation.
 
 // Full-text queries
+mod match_phrase_query;
+mod match_query;
+mod query_string_query;
 
 // Term-level queries
+mod exists_query;
+mod prefix_query;
+mod range_query;
 mod term_query;
+mod terms_query;
+mod wildcard_query;
 
 // Compound queries
 mod bool_query;
 
+// Other queries
+mod match_all_query;
+
 mod build_tantivy_query;
+mod json_literal;
+mod utils;
+
+use std::collections::HashMap;
 
-use quickwit_doc_mapper::DocMapper;
+use quickwit_doc_mapper::{validate_sort_by_field, DocMapper, WarmupInfo};
+use quickwit_proto::SearchRequest;
 use serde::{Deserialize, Serialize};
+use tantivy::schema::{Field, Schema};
+use tantivy::Term;
 
 use crate::query_dsl::bool_query::BoolQuery;
 use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+use crate::query_dsl::exists_query::ExistsQuery;
+use crate::query_dsl::match_all_query::MatchAllQuery;
+use crate::query_dsl::match_phrase_query::MatchPhraseQuery;
+use crate::query_dsl::match_query::MatchQuery;
+use crate::query_dsl::prefix_query::PrefixQuery;
+use crate::query_dsl::query_string_query::QueryStringQuery;
+use crate::query_dsl::range_query::RangeQuery;
 use crate::query_dsl::term_query::TermQuery;
+use crate::query_dsl::terms_query::TermsQuery;
+use crate::query_dsl::wildcard_query::WildcardQuery;
+use crate::{SearchError, TantivyQuery};
 
+/// Query DSL, partially compatible with Elasticsearch's.
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryDsl {
-    Bool(BoolQuery),
+    /// Full-text query on a single field.
+    Match(MatchQuery),
+    /// Phrase query on a single field.
+    MatchPhrase(MatchPhraseQuery),
+    /// Query expressed in Quickwit's query language.
+    QueryString(QueryStringQuery),
+    /// Exact value query.
     Term(TermQuery),
+    /// Query matching any of several exact values.
+    Terms(TermsQuery),
+    /// Range query on a fast field.
+    Range(RangeQuery),
+    /// Query matching the terms starting with a given prefix.
+    Prefix(PrefixQuery),
+    /// Query matching the documents with a value for a given field.
+    Exists(ExistsQuery),
+    /// Query matching the terms of a wildcard pattern.
+    Wildcard(WildcardQuery),
+    /// Boolean combination of queries.
+    Bool(BoolQuery),
+    /// Query matching all documents.
+    MatchAll(MatchAllQuery),
 }
 
 impl From<TermQuery> for QueryDsl {
@@ -70,16 +119,100 @@ impl BuildTantivyQuery for QueryDsl {
         doc_mapper: &dyn DocMapper,
     ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
         match self {
-            QueryDsl::Bool(bool_query) => bool_query.build_tantivy_query(doc_mapper),
+            QueryDsl::Match(match_query) => match_query.build_tantivy_query(doc_mapper),
+            QueryDsl::MatchPhrase(match_phrase_query) => {
+                match_phrase_query.build_tantivy_query(doc_mapper)
+            }
+            QueryDsl::QueryString(query_string_query) => {
+                query_string_query.build_tantivy_query(doc_mapper)
+            }
             QueryDsl::Term(term_query) => term_query.build_tantivy_query(doc_mapper),
+            QueryDsl::Terms(terms_query) => terms_query.build_tantivy_query(doc_mapper),
+            QueryDsl::Range(range_query) => range_query.build_tantivy_query(doc_mapper),
+            QueryDsl::Prefix(prefix_query) => prefix_query.build_tantivy_query(doc_mapper),
+            QueryDsl::Exists(exists_query) => exists_query.build_tantivy_query(doc_mapper),
+            QueryDsl::Wildcard(wildcard_query) => wildcard_query.build_tantivy_query(doc_mapper),
+            QueryDsl::Bool(bool_query) => bool_query.build_tantivy_query(doc_mapper),
+            QueryDsl::MatchAll(match_all_query) => match_all_query.build_tantivy_query(doc_mapper),
         }
     }
+
+    fn warmup_info(&self, doc_mapper: &dyn DocMapper) -> anyhow::Result<WarmupInfo> {
+        match self {
+            QueryDsl::Match(match_query) => match_query.warmup_info(doc_mapper),
+            QueryDsl::MatchPhrase(match_phrase_query) => match_phrase_query.warmup_info(doc_mapper),
+            QueryDsl::QueryString(query_string_query) => query_string_query.warmup_info(doc_mapper),
+            QueryDsl::Exists(exists_query) => exists_query.warmup_info(doc_mapper),
+            QueryDsl::Prefix(prefix_query) => prefix_query.warmup_info(doc_mapper),
+            QueryDsl::Range(range_query) => range_query.warmup_info(doc_mapper),
+            QueryDsl::Term(term_query) => term_query.warmup_info(doc_mapper),
+            QueryDsl::Terms(terms_query) => terms_query.warmup_info(doc_mapper),
+            QueryDsl::Wildcard(wildcard_query) => wildcard_query.warmup_info(doc_mapper),
+            QueryDsl::Bool(bool_query) => bool_query.warmup_info(doc_mapper),
+            QueryDsl::MatchAll(match_all_query) => match_all_query.warmup_info(doc_mapper),
+        }
+    }
+}
+
+/// Builds the tantivy query of a search request, along with the information required to warm
+/// up a split before running it.
+///
+/// The query DSL takes precedence over the query string when both are set.
+pub(crate) fn build_query(
+    doc_mapper: &dyn DocMapper,
+    split_schema: Schema,
+    search_request: &SearchRequest,
+) -> crate::Result<(Box<dyn TantivyQuery>, WarmupInfo)> {
+    let Some(query_dsl_json) = &search_request.query_dsl else {
+        let (query, warmup_info) = doc_mapper.query(split_schema, search_request)?;
+        return Ok((query, warmup_info));
+    };
+    let query_dsl: QueryDsl = serde_json::from_str(query_dsl_json)
+        .map_err(|error| SearchError::InvalidQuery(format!("Invalid query DSL: {error}")))?;
+
+    let sort_field_names = search_request.sort_by_field.iter().chain(
+        search_request
+            .sort_fields
+            .iter()
+            .map(|sort_field| &sort_field.field_name),
+    );
+    for sort_field_name in sort_field_names {
+        validate_sort_by_field(sort_field_name, &split_schema, None)
+            .map_err(|error| SearchError::InvalidQuery(error.to_string()))?;
+    }
+    let query = query_dsl
+        .build_tantivy_query(doc_mapper)
+        .map_err(|error| SearchError::InvalidQuery(error.to_string()))?;
+    let mut warmup_info = query_dsl
+        .warmup_info(doc_mapper)
+        .map_err(|error| SearchError::InvalidQuery(error.to_string()))?;
+
+    let mut terms_grouped_by_field: HashMap<Field, HashMap<Term, bool>> = Default::default();
+    query.query_terms(&mut |term, need_position| {
+        let field = term.field();
+        *terms_grouped_by_field
+            .entry(field)
+            .or_default()
+            .entry(term.clone())
+            .or_default() |= need_position;
+    });
+    warmup_info.merge(WarmupInfo {
+        terms_grouped_by_field,
+        ..Default::default()
+    });
+    Ok((query, warmup_info))
 }
 
 #[cfg(test)]
 mod tests {
+    use quickwit_doc_mapper::{default_doc_mapper_for_test, DocMapper};
+    use quickwit_proto::{SearchRequest, SortField, SortOrder};
+
+    use super::build_query;
+    use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
     use crate::query_dsl::term_query::TermQuery;
     use crate::query_dsl::QueryDsl;
+    use crate::SearchError;
 
     #[test]
     fn test_query_dsl_deserialize_simple() {
@@ -89,10 +222,148 @@ mod tests {
             }
         }"#;
         let query_dsl = serde_json::from_str(term_query_json).unwrap();
-        let QueryDsl::Term(term_query) = query_dsl else { panic!() };
+        let QueryDsl::Term(term_query) = query_dsl else {
+            panic!()
+        };
         assert_eq!(
             &term_query,
             &TermQuery::from_field_value("product_id", "61809")
         );
     }
+
+    #[test]
+    fn test_query_dsl_deserialize_leaf_queries() {
+        let leaf_query_jsons = [
+            r#"{ "match": { "body": "hello" } }"#,
+            r#"{ "match_phrase": { "body": "hello world" } }"#,
+            r#"{ "query_string": { "query": "body:hello" } }"#,
+            r#"{ "exists": { "field": "body" } }"#,
+            r#"{ "prefix": { "body": "hel" } }"#,
+            r#"{ "range": { "timestamp": { "gte": 0 } } }"#,
+            r#"{ "term": { "body": "hello" } }"#,
+            r#"{ "terms": { "body": ["hello", "world"] } }"#,
+            r#"{ "wildcard": { "body": "h*o" } }"#,
+            r#"{ "match_all": {} }"#,
+        ];
+        for leaf_query_json in leaf_query_jsons {
+            let query_dsl: QueryDsl = serde_json::from_str(leaf_query_json).unwrap();
+            // The leaves rely on the serialized query DSL, so it has to round-trip.
+            let query_dsl_json = serde_json::to_string(&query_dsl).unwrap();
+            let query_dsl_deser: QueryDsl = serde_json::from_str(&query_dsl_json).unwrap();
+            assert_eq!(query_dsl_deser, query_dsl);
+        }
+    }
+
+    #[test]
+    fn test_query_dsl_build_tantivy_query() {
+        let doc_mapper = default_doc_mapper_for_test();
+        let check_query = |query_dsl_json: &str, expected: &str| {
+            let query_dsl: QueryDsl = serde_json::from_str(query_dsl_json).unwrap();
+            let query = query_dsl.build_tantivy_query(&doc_mapper).unwrap();
+            let query_debug = format!("{query:?}");
+            assert!(
+                query_debug.contains(expected),
+                "Expected `{expected}` in `{query_debug}`."
+            );
+        };
+        check_query(r#"{ "match": { "body": "Hello" } }"#, r#""hello""#);
+        check_query(
+            r#"{ "match": { "body": "hello world" } }"#,
+            "(Should, TermQuery",
+        );
+        check_query(
+            r#"{ "match_phrase": { "body": "hello world" } }"#,
+            "PhraseQuery",
+        );
+        check_query(r#"{ "prefix": { "owner": "qui" } }"#, "RegexQuery");
+        check_query(
+            r#"{ "range": { "response_time": { "gte": 1.5 } } }"#,
+            r#"RangeQuery { field: "response_time""#,
+        );
+        check_query(
+            r#"{ "terms": { "owner": ["foo", "bar"] } }"#,
+            "TermSetQuery",
+        );
+        check_query(r#"{ "match_all": {} }"#, "AllQuery");
+    }
+
+    #[test]
+    fn test_query_dsl_build_tantivy_query_errors() {
+        let doc_mapper = default_doc_mapper_for_test();
+        let check_error = |query_dsl_json: &str| {
+            let query_dsl: QueryDsl = serde_json::from_str(query_dsl_json).unwrap();
+            query_dsl.build_tantivy_query(&doc_mapper).unwrap_err();
+        };
+        check_error(r#"{ "match": { "unknown_field": "hello" } }"#);
+        check_error(r#"{ "range": { "body": { "gte": "a" } } }"#);
+        check_error(r#"{ "wildcard": { "response_time": "1*" } }"#);
+    }
+
+    #[test]
+    fn test_build_query_dsl_warmup_info() {
+        let doc_mapper = default_doc_mapper_for_test();
+        let search_request = SearchRequest {
+            index_id: "test-index".to_string(),
+            query: "*".to_string(),
+            query_dsl: Some(
+                r#"{
+                    "bool": {
+                        "must": { "match": { "body": "hello" } },
+                        "filter": { "range": { "response_time": { "lt": 100 } } },
+                        "should": { "prefix": { "owner": "qui" } }
+                    }
+                }"#
+                .to_string(),
+            ),
+            ..Default::default()
+        };
+        let (_, warmup_info) =
+            build_query(&doc_mapper, doc_mapper.schema(), &search_request).unwrap();
+        assert!(warmup_info.fast_field_names.contains("response_time"));
+        assert!(warmup_info.term_dict_field_names.contains("owner"));
+        assert!(warmup_info.posting_field_names.contains("owner"));
+        assert_eq!(warmup_info.terms_grouped_by_field.len(), 1);
+    }
+
+    #[test]
+    fn test_build_query_dsl_validates_sort_fields() {
+        let doc_mapper = default_doc_mapper_for_test();
+        let search_request_sorted_by = |field_name: &str| SearchRequest {
+            index_id: "test-index".to_string(),
+            query: "*".to_string(),
+            query_dsl: Some(r#"{ "match_all": {} }"#.to_string()),
+            sort_fields: vec![SortField {
+                field_name: field_name.to_string(),
+                sort_order: SortOrder::Desc as i32,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        build_query(
+            &doc_mapper,
+            doc_mapper.schema(),
+            &search_request_sorted_by("response_time"),
+        )
+        .unwrap();
+
+        let error = build_query(
+            &doc_mapper,
+            doc_mapper.schema(),
+            &search_request_sorted_by("body"),
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(error, SearchError::InvalidQuery(_)));
+
+        let error = build_query(
+            &doc_mapper,
+            doc_mapper.schema(),
+            &search_request_sorted_by("unknown_field"),
+        )
+        .err()
+        .unwrap();
+        assert!(
+            matches!(error, SearchError::InvalidQuery(message) if message.contains("unknown_field"))
+        );
+    }
 }
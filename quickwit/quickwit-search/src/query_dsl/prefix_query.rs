@@ -0,0 +1,162 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::bail;
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
+use serde::{Deserialize, Serialize};
+use tantivy::query::RegexQuery;
+use tantivy::schema::{Field, FieldEntry, FieldType, Schema};
+
+use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+use crate::query_dsl::utils::{escape_regex, extract_single_entry, resolve_field};
+
+/// # Unsupported features
+/// - boost
+/// - case_insensitive
+/// - rewrite
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(
+    into = "HashMap<String, PrefixQueryValue>",
+    try_from = "HashMap<String, PrefixQueryValueOrText>"
+)]
+pub struct PrefixQuery {
+    pub field: String,
+    pub value: String,
+}
+
+/// Resolves a field on which regex-based queries (prefix, wildcard) can run.
+///
+/// These queries run over the term dictionary, which is only meaningful for text fields.
+pub(crate) fn resolve_text_field<'a>(
+    schema: &'a Schema,
+    field_name: &str,
+) -> anyhow::Result<(Field, &'a FieldEntry)> {
+    let (field, field_entry) = resolve_field(schema, field_name)?;
+    if !matches!(field_entry.field_type(), FieldType::Str(_)) {
+        bail!(
+            "Field `{field_name}` is of type `{:?}`. Prefix and wildcard queries are only \
+             supported on text fields.",
+            field_entry.field_type().value_type()
+        );
+    }
+    if !field_entry.is_indexed() {
+        bail!("Field `{field_name}` is not indexed.");
+    }
+    Ok((field, field_entry))
+}
+
+/// Regex-based queries need the whole term dictionary and the matching posting lists of the
+/// field, because the matching terms are not known in advance.
+pub(crate) fn regex_query_warmup_info(
+    doc_mapper: &dyn DocMapper,
+    field_name: &str,
+) -> anyhow::Result<WarmupInfo> {
+    let schema = doc_mapper.schema();
+    let (_, field_entry) = resolve_text_field(&schema, field_name)?;
+    let field_names: HashSet<String> = [field_entry.name().to_string()].into_iter().collect();
+    Ok(WarmupInfo {
+        term_dict_field_names: field_names.clone(),
+        posting_field_names: field_names,
+        ..Default::default()
+    })
+}
+
+impl BuildTantivyQuery for PrefixQuery {
+    fn build_tantivy_query(
+        &self,
+        doc_mapper: &dyn DocMapper,
+    ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
+        let schema = doc_mapper.schema();
+        let (field, _) = resolve_text_field(&schema, &self.field)?;
+        let mut regex = String::with_capacity(self.value.len() + 2);
+        escape_regex(&self.value, &mut regex);
+        regex.push_str(".*");
+        let regex_query = RegexQuery::from_pattern(&regex, field)?;
+        Ok(Box::new(regex_query))
+    }
+
+    fn warmup_info(&self, doc_mapper: &dyn DocMapper) -> anyhow::Result<WarmupInfo> {
+        regex_query_warmup_info(doc_mapper, &self.field)
+    }
+}
+
+// Private struct used for serialization.
+// It represents the value of a prefix query. in the json form : `{field: <PrefixQueryValue>}`.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PrefixQueryValue {
+    value: String,
+}
+
+// Private enum used for deserialization.
+// Elasticsearch also accepts the short form `{field: <value>}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PrefixQueryValueOrText {
+    PrefixQueryValue(PrefixQueryValue),
+    Text(String),
+}
+
+impl From<PrefixQuery> for HashMap<String, PrefixQueryValue> {
+    fn from(prefix_query: PrefixQuery) -> Self {
+        let mut map = HashMap::with_capacity(1);
+        map.insert(
+            prefix_query.field,
+            PrefixQueryValue {
+                value: prefix_query.value,
+            },
+        );
+        map
+    }
+}
+
+impl TryFrom<HashMap<String, PrefixQueryValueOrText>> for PrefixQuery {
+    type Error = &'static str;
+
+    fn try_from(map: HashMap<String, PrefixQueryValueOrText>) -> Result<Self, Self::Error> {
+        let (field, value_or_text) = extract_single_entry(map)?;
+        let value = match value_or_text {
+            PrefixQueryValueOrText::PrefixQueryValue(prefix_query_value) => {
+                prefix_query_value.value
+            }
+            PrefixQueryValueOrText::Text(text) => text,
+        };
+        Ok(PrefixQuery { field, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrefixQuery;
+
+    #[test]
+    fn test_prefix_query_deserialize() {
+        let expected_prefix_query = PrefixQuery {
+            field: "service".to_string(),
+            value: "api-".to_string(),
+        };
+        let prefix_query: PrefixQuery =
+            serde_json::from_str(r#"{ "service": { "value": "api-" } }"#).unwrap();
+        assert_eq!(prefix_query, expected_prefix_query);
+        let prefix_query: PrefixQuery = serde_json::from_str(r#"{ "service": "api-" }"#).unwrap();
+        assert_eq!(prefix_query, expected_prefix_query);
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::bail;
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
+use quickwit_proto::SearchRequest;
+use serde::{Deserialize, Serialize};
+
+use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+
+/// Query expressed with the same syntax as the `query` parameter of Quickwit's search API.
+///
+/// # Deviations from Elasticsearch
+/// - Elasticsearch combines terms with `OR` when `default_operator` is not set. Quickwit always
+///   combines them with `AND`, and rejects an explicit `OR` default operator rather than silently
+///   returning different hits.
+///
+/// # Unsupported features
+/// - analyzer, fuzziness, lenient, etc.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct QueryStringQuery {
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_field: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_operator: Option<QueryStringDefaultOperator>,
+}
+
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Copy)]
+pub enum QueryStringDefaultOperator {
+    #[serde(rename = "AND", alias = "and")]
+    And,
+    #[serde(rename = "OR", alias = "or")]
+    Or,
+}
+
+impl QueryStringQuery {
+    fn search_request(&self) -> anyhow::Result<SearchRequest> {
+        if self.default_operator == Some(QueryStringDefaultOperator::Or) {
+            bail!("The `OR` default operator is not supported by query string queries.");
+        }
+        let mut search_fields = self.fields.clone();
+        if let Some(default_field) = &self.default_field {
+            search_fields.push(default_field.clone());
+        }
+        Ok(SearchRequest {
+            query: self.query.clone(),
+            search_fields,
+            ..Default::default()
+        })
+    }
+}
+
+impl BuildTantivyQuery for QueryStringQuery {
+    fn build_tantivy_query(
+        &self,
+        doc_mapper: &dyn DocMapper,
+    ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
+        let search_request = self.search_request()?;
+        let (query, _) = doc_mapper.query(doc_mapper.schema(), &search_request)?;
+        Ok(query)
+    }
+
+    fn warmup_info(&self, doc_mapper: &dyn DocMapper) -> anyhow::Result<WarmupInfo> {
+        let search_request = self.search_request()?;
+        let (_, warmup_info) = doc_mapper.query(doc_mapper.schema(), &search_request)?;
+        Ok(warmup_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QueryStringDefaultOperator, QueryStringQuery};
+
+    #[test]
+    fn test_query_string_query_deserialize() {
+        let query_string_query: QueryStringQuery = serde_json::from_str(
+            r#"{ "query": "severity:ERROR AND body:timeout", "default_operator": "AND" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            query_string_query,
+            QueryStringQuery {
+                query: "severity:ERROR AND body:timeout".to_string(),
+                default_field: None,
+                fields: Vec::new(),
+                default_operator: Some(QueryStringDefaultOperator::And),
+            }
+        );
+    }
+
+    #[test]
+    fn test_query_string_query_or_operator_unsupported() {
+        let query_string_query: QueryStringQuery =
+            serde_json::from_str(r#"{ "query": "hello world", "default_operator": "OR" }"#)
+                .unwrap();
+        assert!(query_string_query.search_request().is_err());
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::ops::Bound;
+
+use anyhow::bail;
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
+use serde::{Deserialize, Serialize};
+use tantivy::query::RangeQuery as TantivyRangeQuery;
+use tantivy::schema::{Field, FieldEntry, FieldType};
+use tantivy::{DateTime, Term};
+
+use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+use crate::query_dsl::json_literal::JsonLiteral;
+use crate::query_dsl::utils::{compute_term, extract_single_entry, resolve_field};
+
+/// # Unsupported features
+/// - boost
+/// - relation
+/// - time_zone
+/// - date math expressions (e.g. `now-1h`)
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(
+    into = "HashMap<String, RangeQueryParams>",
+    try_from = "HashMap<String, RangeQueryParams>"
+)]
+pub struct RangeQuery {
+    pub field: String,
+    pub params: RangeQueryParams,
+}
+
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RangeQueryParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt: Option<JsonLiteral>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gte: Option<JsonLiteral>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lt: Option<JsonLiteral>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lte: Option<JsonLiteral>,
+    /// Format of the date bounds. Only `epoch_millis` (the default for numbers), `epoch_second`
+    /// and `strict_date_optional_time` (RFC 3339) are supported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+/// Validates that the field is eligible for range queries. This mirrors the restrictions
+/// applied by the query parser.
+fn validate_field_for_range(field_entry: &FieldEntry) -> anyhow::Result<()> {
+    match field_entry.field_type() {
+        FieldType::Bool(_)
+        | FieldType::Date(_)
+        | FieldType::IpAddr(_)
+        | FieldType::F64(_)
+        | FieldType::I64(_)
+        | FieldType::U64(_) => {
+            if !field_entry.is_fast() {
+                bail!(
+                    "Range queries require having a fast field (field `{}` is not declared as a \
+                     fast field.)",
+                    field_entry.name()
+                );
+            }
+        }
+        other_type => {
+            bail!(
+                "Field `{}` is of type `{:?}`. Range queries are only supported on boolean, \
+                 datetime, IP, and numeric fields at the moment.",
+                field_entry.name(),
+                other_type.value_type()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn compute_bound_term(
+    field: Field,
+    field_entry: &FieldEntry,
+    value: &JsonLiteral,
+    format_opt: Option<&str>,
+) -> anyhow::Result<Term> {
+    if let (FieldType::Date(_), JsonLiteral::Number(number)) = (field_entry.field_type(), value) {
+        let Some(timestamp) = number.as_i64() else {
+            bail!("Failed to parse date bound `{number}`: expected an integer timestamp.");
+        };
+        let date_time = match format_opt {
+            None | Some("epoch_millis") => DateTime::from_timestamp_micros(timestamp * 1_000),
+            Some("epoch_second") => DateTime::from_timestamp_secs(timestamp),
+            Some(format) => bail!("Date format `{format}` is not supported for numeric bounds."),
+        };
+        return Ok(Term::from_field_date(field, date_time));
+    }
+    compute_term(field, field_entry, &value.to_string())
+}
+
+fn compute_bound(
+    field: Field,
+    field_entry: &FieldEntry,
+    included_opt: Option<&JsonLiteral>,
+    excluded_opt: Option<&JsonLiteral>,
+    format_opt: Option<&str>,
+) -> anyhow::Result<Bound<Term>> {
+    match (included_opt, excluded_opt) {
+        (Some(_), Some(_)) => {
+            bail!("Range query bounds are ambiguous: both inclusive and exclusive bounds are set.")
+        }
+        (Some(included), None) => Ok(Bound::Included(compute_bound_term(
+            field,
+            field_entry,
+            included,
+            format_opt,
+        )?)),
+        (None, Some(excluded)) => Ok(Bound::Excluded(compute_bound_term(
+            field,
+            field_entry,
+            excluded,
+            format_opt,
+        )?)),
+        (None, None) => Ok(Bound::Unbounded),
+    }
+}
+
+impl BuildTantivyQuery for RangeQuery {
+    fn build_tantivy_query(
+        &self,
+        doc_mapper: &dyn DocMapper,
+    ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
+        let schema = doc_mapper.schema();
+        let (field, field_entry) = resolve_field(&schema, &self.field)?;
+        validate_field_for_range(field_entry)?;
+        let format_opt = self.params.format.as_deref();
+        let lower_bound = compute_bound(
+            field,
+            field_entry,
+            self.params.gte.as_ref(),
+            self.params.gt.as_ref(),
+            format_opt,
+        )?;
+        let upper_bound = compute_bound(
+            field,
+            field_entry,
+            self.params.lte.as_ref(),
+            self.params.lt.as_ref(),
+            format_opt,
+        )?;
+        let range_query = TantivyRangeQuery::new_term_bounds(
+            field_entry.name().to_string(),
+            field_entry.field_type().value_type(),
+            &lower_bound,
+            &upper_bound,
+        );
+        Ok(Box::new(range_query))
+    }
+
+    fn warmup_info(&self, doc_mapper: &dyn DocMapper) -> anyhow::Result<WarmupInfo> {
+        let schema = doc_mapper.schema();
+        let (_, field_entry) = resolve_field(&schema, &self.field)?;
+        Ok(WarmupInfo {
+            fast_field_names: [field_entry.name().to_string()].into_iter().collect(),
+            ..Default::default()
+        })
+    }
+}
+
+impl From<RangeQuery> for HashMap<String, RangeQueryParams> {
+    fn from(range_query: RangeQuery) -> Self {
+        let mut map = HashMap::with_capacity(1);
+        map.insert(range_query.field, range_query.params);
+        map
+    }
+}
+
+impl TryFrom<HashMap<String, RangeQueryParams>> for RangeQuery {
+    type Error = &'static str;
+
+    fn try_from(map: HashMap<String, RangeQueryParams>) -> Result<Self, Self::Error> {
+        let (field, params) = extract_single_entry(map)?;
+        Ok(RangeQuery { field, params })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RangeQuery, RangeQueryParams};
+    use crate::query_dsl::json_literal::JsonLiteral;
+
+    #[test]
+    fn test_range_query_deserialize() {
+        let range_query_json = r#"{
+            "timestamp": { "gte": 1680000000000, "lt": "2023-04-01T00:00:00Z" }
+        }"#;
+        let range_query: RangeQuery = serde_json::from_str(range_query_json).unwrap();
+        assert_eq!(
+            range_query,
+            RangeQuery {
+                field: "timestamp".to_string(),
+                params: RangeQueryParams {
+                    gte: Some(JsonLiteral::Number(1680000000000u64.into())),
+                    lt: Some(JsonLiteral::from("2023-04-01T00:00:00Z")),
+                    ..Default::default()
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn test_range_query_deserialize_unknown_param() {
+        let range_query_json = r#"{ "timestamp": { "from": 0 } }"#;
+        serde_json::from_str::<RangeQuery>(range_query_json).unwrap_err();
+    }
+}
@@ -18,24 +18,21 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::HashMap;
-use std::net::IpAddr;
-use std::str::FromStr;
 
-use anyhow::{bail, Context};
 use quickwit_doc_mapper::DocMapper;
 use serde::{Deserialize, Serialize};
 use tantivy::query::TermQuery as TantivyTermQuery;
-use tantivy::schema::{Field, FieldEntry, FieldType, IndexRecordOption, IntoIpv6Addr, Type};
-use tantivy::time::format_description::well_known::Rfc3339;
-use tantivy::time::OffsetDateTime;
-use tantivy::{DateTime, Term};
 
 use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+use crate::query_dsl::json_literal::JsonLiteral;
+use crate::query_dsl::utils::{
+    compute_term, extract_single_entry, index_record_option, resolve_field,
+};
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
 #[serde(
     into = "HashMap<String, TermQueryValue>",
-    try_from = "HashMap<String, TermQueryValue>"
+    try_from = "HashMap<String, TermQueryValueOrLiteral>"
 )]
 pub struct TermQuery {
     pub field: String,
@@ -52,87 +49,16 @@ impl TermQuery {
     }
 }
 
-fn compute_term_query(
-    field: Field,
-    field_entry: &FieldEntry,
-    json_path: &str,
-    text: &str,
-) -> anyhow::Result<TantivyTermQuery> {
-    let field_type = field_entry.field_type();
-    let field_name = field_entry.name();
-    if !field_type.is_indexed() {
-        bail!("Field `{field_name}` is not indexed.");
-    }
-    if field_type.value_type() != Type::Json && !json_path.is_empty() {
-        bail!("Field `{field_name}.{json_path}` does not exist.");
-    }
-    match *field_type {
-        FieldType::U64(_) => {
-            let val: u64 = u64::from_str(text)?;
-            let term = Term::from_field_u64(field, val);
-            Ok(TantivyTermQuery::new(term, IndexRecordOption::Basic))
-        }
-        FieldType::I64(_) => {
-            let val: i64 = i64::from_str(text)?;
-            let term = Term::from_field_i64(field, val);
-            Ok(TantivyTermQuery::new(term, IndexRecordOption::Basic))
-        }
-        FieldType::F64(_) => {
-            let val: f64 = f64::from_str(text)?;
-            let term = Term::from_field_f64(field, val);
-            Ok(TantivyTermQuery::new(term, IndexRecordOption::Basic))
-        }
-        FieldType::Bool(_) => {
-            let val: bool = bool::from_str(text)?;
-            let term = Term::from_field_bool(field, val);
-            Ok(TantivyTermQuery::new(term, IndexRecordOption::Basic))
-        }
-        FieldType::Date(_) => {
-            // TODO handle input format.
-            let dt = OffsetDateTime::parse(text, &Rfc3339)?;
-            let term = Term::from_field_date(field, DateTime::from_utc(dt));
-            Ok(TantivyTermQuery::new(term, IndexRecordOption::Basic))
-        }
-        FieldType::Str(ref str_options) => {
-            let option = str_options
-                .get_indexing_options()
-                .context("Text field is not indexed")?; //< It should never happen.
-            let index_record_option = option.index_option();
-            let term = Term::from_field_text(field, text);
-            Ok(TantivyTermQuery::new(term, index_record_option))
-        }
-        FieldType::IpAddr(_) => {
-            let ip_v6 = IpAddr::from_str(text)?.into_ipv6_addr();
-            let term = Term::from_field_ip_addr(field, ip_v6);
-            Ok(TantivyTermQuery::new(term, IndexRecordOption::Basic))
-        }
-        FieldType::JsonObject(ref _json_options) => {
-            // Waiting for us to point to tantivy main.
-            todo!();
-        }
-        FieldType::Facet(_) => {
-            todo!();
-        }
-        FieldType::Bytes(_) => {
-            todo!()
-        }
-    }
-}
-
 impl BuildTantivyQuery for TermQuery {
     fn build_tantivy_query(
         &self,
         doc_mapper: &dyn DocMapper,
     ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
         let schema = doc_mapper.schema();
-        let Some((field, path)) = schema.find_field(&self.field)
-        else {
-            bail!("Failed to find field `{}` in schema.", self.field);
-        };
-        let field_entry = schema.get_field_entry(field);
-        let term_query: TantivyTermQuery =
-            compute_term_query(field, field_entry, path, &self.value)?;
-        Ok(Box::new(term_query))
+        let (field, field_entry) = resolve_field(&schema, &self.field)?;
+        let term = compute_term(field, field_entry, &self.value)?;
+        let index_record_option = index_record_option(field_entry)?;
+        Ok(Box::new(TantivyTermQuery::new(term, index_record_option)))
     }
 }
 
@@ -140,7 +66,16 @@ impl BuildTantivyQuery for TermQuery {
 // It represents the value of a term query. in the json form : `{field: <TermQueryValue>}`.
 #[derive(Serialize, Deserialize)]
 struct TermQueryValue {
-    value: String,
+    value: JsonLiteral,
+}
+
+// Private enum used for deserialization.
+// Elasticsearch also accepts the short form `{field: <value>}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TermQueryValueOrLiteral {
+    TermQueryValue(TermQueryValue),
+    Literal(JsonLiteral),
 }
 
 impl From<TermQuery> for (String, TermQueryValue) {
@@ -148,32 +83,30 @@ impl From<TermQuery> for (String, TermQueryValue) {
         (
             term_query.field,
             TermQueryValue {
-                value: term_query.value,
+                value: JsonLiteral::String(term_query.value),
             },
         )
     }
 }
 
-impl From<(String, TermQueryValue)> for TermQuery {
-    fn from((field, term_query_value): (String, TermQueryValue)) -> Self {
+impl From<(String, TermQueryValueOrLiteral)> for TermQuery {
+    fn from((field, term_query_value): (String, TermQueryValueOrLiteral)) -> Self {
+        let value = match term_query_value {
+            TermQueryValueOrLiteral::TermQueryValue(term_query_value) => term_query_value.value,
+            TermQueryValueOrLiteral::Literal(literal) => literal,
+        };
         Self {
             field,
-            value: term_query_value.value,
+            value: value.to_string(),
         }
     }
 }
 
-impl TryFrom<HashMap<String, TermQueryValue>> for TermQuery {
+impl TryFrom<HashMap<String, TermQueryValueOrLiteral>> for TermQuery {
     type Error = &'static str;
 
-    fn try_from(map: HashMap<String, TermQueryValue>) -> Result<Self, Self::Error> {
-        if map.len() != 1 {
-            return Err("TermQuery must have exactly one entry");
-        }
-        Ok(TermQuery::from(map.into_iter().next().expect(
-            "There should be exactly one element in the map, as checked by the if statement above.",
-        ))) // unwrap justified by the if statement
-            // above.
+    fn try_from(map: HashMap<String, TermQueryValueOrLiteral>) -> Result<Self, Self::Error> {
+        extract_single_entry(map).map(TermQuery::from)
     }
 }
 
@@ -199,4 +132,26 @@ mod tests {
             &TermQuery::from_field_value("product_id", "61809")
         );
     }
+
+    #[test]
+    fn test_term_query_short_form() {
+        let term_query_json = r#"{ "status": 200 }"#;
+        let term_query: TermQuery = serde_json::from_str(term_query_json).unwrap();
+        assert_eq!(&term_query, &TermQuery::from_field_value("status", "200"));
+    }
+
+    #[test]
+    fn test_term_query_serialization_roundtrip() {
+        let term_query = TermQuery::from_field_value("product_id", "61809");
+        let term_query_json = serde_json::to_string(&term_query).unwrap();
+        assert_eq!(term_query_json, r#"{"product_id":{"value":"61809"}}"#);
+        let term_query_deser: TermQuery = serde_json::from_str(&term_query_json).unwrap();
+        assert_eq!(term_query_deser, term_query);
+    }
+
+    #[test]
+    fn test_term_query_too_many_fields() {
+        let term_query_json = r#"{ "product_id": "61809", "status": 200 }"#;
+        serde_json::from_str::<TermQuery>(term_query_json).unwrap_err();
+    }
 }
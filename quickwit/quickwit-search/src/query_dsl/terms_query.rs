@@ -0,0 +1,162 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
+use serde::{Deserialize, Serialize};
+use tantivy::query::{BoostQuery, TermSetQuery};
+
+use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+use crate::query_dsl::json_literal::JsonLiteral;
+use crate::query_dsl::utils::{compute_term, resolve_field};
+
+/// # Unsupported features
+/// - terms lookup
+#[derive(PartialEq, Debug, Serialize, Deserialize, Clone)]
+#[serde(
+    into = "HashMap<String, TermsQueryEntry>",
+    try_from = "HashMap<String, TermsQueryEntry>"
+)]
+pub struct TermsQuery {
+    pub field: String,
+    pub values: Vec<JsonLiteral>,
+    pub boost: Option<f32>,
+}
+
+// `TermsQuery` does not hold NaN boosts, as checked upon deserialization.
+impl Eq for TermsQuery {}
+
+impl BuildTantivyQuery for TermsQuery {
+    fn build_tantivy_query(
+        &self,
+        doc_mapper: &dyn DocMapper,
+    ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
+        let schema = doc_mapper.schema();
+        let (field, field_entry) = resolve_field(&schema, &self.field)?;
+        let terms = self
+            .values
+            .iter()
+            .map(|value| compute_term(field, field_entry, &value.to_string()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let term_set_query = Box::new(TermSetQuery::new(terms));
+        if let Some(boost) = self.boost {
+            return Ok(Box::new(BoostQuery::new(term_set_query, boost)));
+        }
+        Ok(term_set_query)
+    }
+
+    fn warmup_info(&self, doc_mapper: &dyn DocMapper) -> anyhow::Result<WarmupInfo> {
+        let schema = doc_mapper.schema();
+        let (_, field_entry) = resolve_field(&schema, &self.field)?;
+        let field_names: HashSet<String> = [field_entry.name().to_string()].into_iter().collect();
+        Ok(WarmupInfo {
+            term_dict_field_names: field_names.clone(),
+            posting_field_names: field_names,
+            ..Default::default()
+        })
+    }
+}
+
+// Private enum used for serialization.
+// A terms query is expressed as `{field: [<values>], "boost": <boost>}`.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TermsQueryEntry {
+    Values(Vec<JsonLiteral>),
+    Boost(f32),
+}
+
+const BOOST_KEY: &str = "boost";
+
+impl From<TermsQuery> for HashMap<String, TermsQueryEntry> {
+    fn from(terms_query: TermsQuery) -> Self {
+        let mut map = HashMap::with_capacity(2);
+        map.insert(
+            terms_query.field,
+            TermsQueryEntry::Values(terms_query.values),
+        );
+        if let Some(boost) = terms_query.boost {
+            map.insert(BOOST_KEY.to_string(), TermsQueryEntry::Boost(boost));
+        }
+        map
+    }
+}
+
+impl TryFrom<HashMap<String, TermsQueryEntry>> for TermsQuery {
+    type Error = &'static str;
+
+    fn try_from(map: HashMap<String, TermsQueryEntry>) -> Result<Self, Self::Error> {
+        let mut field_values_opt: Option<(String, Vec<JsonLiteral>)> = None;
+        let mut boost = None;
+        for (key, entry) in map {
+            match entry {
+                TermsQueryEntry::Boost(boost_value) if key == BOOST_KEY => {
+                    if boost_value.is_nan() {
+                        return Err("NaN is not supported as a boost value.");
+                    }
+                    boost = Some(boost_value);
+                }
+                TermsQueryEntry::Values(values) if field_values_opt.is_none() => {
+                    field_values_opt = Some((key, values));
+                }
+                TermsQueryEntry::Values(_) => {
+                    return Err("Terms query must target exactly one field.");
+                }
+                TermsQueryEntry::Boost(_) => {
+                    return Err("Terms query values must be an array.");
+                }
+            }
+        }
+        let (field, values) =
+            field_values_opt.ok_or("Terms query must target exactly one field.")?;
+        Ok(TermsQuery {
+            field,
+            values,
+            boost,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TermsQuery;
+    use crate::query_dsl::json_literal::JsonLiteral;
+
+    #[test]
+    fn test_terms_query_deserialize() {
+        let terms_query: TermsQuery =
+            serde_json::from_str(r#"{ "status": [200, "404"], "boost": 2.0 }"#).unwrap();
+        assert_eq!(
+            terms_query,
+            TermsQuery {
+                field: "status".to_string(),
+                values: vec![JsonLiteral::Number(200.into()), JsonLiteral::from("404")],
+                boost: Some(2.0),
+            }
+        );
+    }
+
+    #[test]
+    fn test_terms_query_deserialize_invalid() {
+        serde_json::from_str::<TermsQuery>(r#"{ "boost": 2.0 }"#).unwrap_err();
+        serde_json::from_str::<TermsQuery>(r#"{ "status": [200], "code": [1] }"#).unwrap_err();
+        serde_json::from_str::<TermsQuery>(r#"{ "status": 200 }"#).unwrap_err();
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+use tantivy::schema::{Field, FieldEntry, FieldType, IndexRecordOption, IntoIpv6Addr, Schema};
+use tantivy::time::format_description::well_known::Rfc3339;
+use tantivy::time::OffsetDateTime;
+use tantivy::tokenizer::TokenizerManager;
+use tantivy::{DateTime, Term};
+
+/// Resolves a field of the schema by its name.
+///
+/// Targeting a path within a JSON field is not supported yet.
+pub(crate) fn resolve_field<'a>(
+    schema: &'a Schema,
+    field_name: &str,
+) -> anyhow::Result<(Field, &'a FieldEntry)> {
+    let Some((field, json_path)) = schema.find_field(field_name) else {
+        bail!("Failed to find field `{field_name}` in schema.");
+    };
+    let field_entry = schema.get_field_entry(field);
+    if !json_path.is_empty() {
+        bail!(
+            "Field `{}.{json_path}` targets a path within a JSON field, which is not supported \
+             yet.",
+            field_entry.name()
+        );
+    }
+    Ok((field, field_entry))
+}
+
+/// Builds the term matching `text` exactly, according to the type of the targeted field.
+///
+/// Text values are not tokenized.
+pub(crate) fn compute_term(
+    field: Field,
+    field_entry: &FieldEntry,
+    text: &str,
+) -> anyhow::Result<Term> {
+    let field_type = field_entry.field_type();
+    let field_name = field_entry.name();
+    if !field_type.is_indexed() {
+        bail!("Field `{field_name}` is not indexed.");
+    }
+    let term = match field_type {
+        FieldType::U64(_) => Term::from_field_u64(field, u64::from_str(text)?),
+        FieldType::I64(_) => Term::from_field_i64(field, i64::from_str(text)?),
+        FieldType::F64(_) => Term::from_field_f64(field, f64::from_str(text)?),
+        FieldType::Bool(_) => Term::from_field_bool(field, bool::from_str(text)?),
+        FieldType::Date(_) => {
+            // TODO handle input format.
+            let dt = OffsetDateTime::parse(text, &Rfc3339)?;
+            Term::from_field_date(field, DateTime::from_utc(dt))
+        }
+        FieldType::Str(_) => Term::from_field_text(field, text),
+        FieldType::IpAddr(_) => {
+            let ip_v6 = IpAddr::from_str(text)?.into_ipv6_addr();
+            Term::from_field_ip_addr(field, ip_v6)
+        }
+        FieldType::JsonObject(_) | FieldType::Facet(_) | FieldType::Bytes(_) => {
+            bail!(
+                "Field `{field_name}` is of type `{:?}`, which is not supported by the query DSL \
+                 yet.",
+                field_type.value_type()
+            );
+        }
+    };
+    Ok(term)
+}
+
+/// Returns the index record option of an indexed field.
+pub(crate) fn index_record_option(field_entry: &FieldEntry) -> anyhow::Result<IndexRecordOption> {
+    if let FieldType::Str(text_options) = field_entry.field_type() {
+        let indexing_options = text_options
+            .get_indexing_options()
+            .with_context(|| format!("Field `{}` is not indexed.", field_entry.name()))?;
+        return Ok(indexing_options.index_option());
+    }
+    Ok(IndexRecordOption::Basic)
+}
+
+/// Runs `text` through the tokenizer of the targeted text field and returns the resulting terms
+/// along with their positions.
+///
+/// For fields of any other type, the text is converted into a single term.
+pub(crate) fn compute_tokenized_terms(
+    field: Field,
+    field_entry: &FieldEntry,
+    text: &str,
+    tokenizer_manager: &TokenizerManager,
+) -> anyhow::Result<Vec<(usize, Term)>> {
+    let FieldType::Str(text_options) = field_entry.field_type() else {
+        let term = compute_term(field, field_entry, text)?;
+        return Ok(vec![(0, term)]);
+    };
+    let indexing_options = text_options
+        .get_indexing_options()
+        .with_context(|| format!("Field `{}` is not indexed.", field_entry.name()))?;
+    let tokenizer_name = indexing_options.tokenizer();
+    let tokenizer = tokenizer_manager
+        .get(tokenizer_name)
+        .with_context(|| format!("Tokenizer `{tokenizer_name}` is not registered."))?;
+    let mut terms = Vec::new();
+    let mut token_stream = tokenizer.token_stream(text);
+    while let Some(token) = token_stream.next() {
+        terms.push((token.position, Term::from_field_text(field, &token.text)));
+    }
+    Ok(terms)
+}
+
+/// Escapes the characters that have a special meaning in tantivy regular expressions.
+pub(crate) fn escape_regex(text: &str, regex: &mut String) {
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{' | '}' | '^' | '$'
+        ) {
+            regex.push('\\');
+        }
+        regex.push(c);
+    }
+}
+
+/// Extracts the unique entry of the `{"<field>": <params>}` objects used by leaf queries.
+pub(crate) fn extract_single_entry<V>(
+    map: HashMap<String, V>,
+) -> Result<(String, V), &'static str> {
+    if map.len() != 1 {
+        return Err("Query must target exactly one field.");
+    }
+    Ok(map.into_iter().next().expect(
+        "There should be exactly one element in the map, as checked by the if statement above.",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape_regex;
+
+    #[test]
+    fn test_escape_regex() {
+        let mut regex = String::new();
+        escape_regex("service.name (v2)*", &mut regex);
+        assert_eq!(regex, r"service\.name \(v2\)\*");
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
+use serde::{Deserialize, Serialize};
+use tantivy::query::RegexQuery;
+
+use crate::query_dsl::build_tantivy_query::BuildTantivyQuery;
+use crate::query_dsl::prefix_query::{regex_query_warmup_info, resolve_text_field};
+use crate::query_dsl::utils::{escape_regex, extract_single_entry};
+
+/// `*` matches any sequence of characters, including an empty one, and `?` matches any single
+/// character.
+///
+/// # Unsupported features
+/// - boost
+/// - case_insensitive
+/// - rewrite
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+#[serde(
+    into = "HashMap<String, WildcardQueryValue>",
+    try_from = "HashMap<String, WildcardQueryValueOrText>"
+)]
+pub struct WildcardQuery {
+    pub field: String,
+    pub value: String,
+}
+
+fn wildcard_to_regex(wildcard: &str) -> String {
+    let mut regex = String::with_capacity(wildcard.len() * 2);
+    let mut literal_start = 0;
+    for (pos, c) in wildcard.char_indices() {
+        let regex_operator = match c {
+            '*' => ".*",
+            '?' => ".",
+            _ => continue,
+        };
+        escape_regex(&wildcard[literal_start..pos], &mut regex);
+        regex.push_str(regex_operator);
+        literal_start = pos + 1;
+    }
+    escape_regex(&wildcard[literal_start..], &mut regex);
+    regex
+}
+
+impl BuildTantivyQuery for WildcardQuery {
+    fn build_tantivy_query(
+        &self,
+        doc_mapper: &dyn DocMapper,
+    ) -> anyhow::Result<Box<dyn tantivy::query::Query>> {
+        let schema = doc_mapper.schema();
+        let (field, _) = resolve_text_field(&schema, &self.field)?;
+        let regex = wildcard_to_regex(&self.value);
+        let regex_query = RegexQuery::from_pattern(&regex, field)?;
+        Ok(Box::new(regex_query))
+    }
+
+    fn warmup_info(&self, doc_mapper: &dyn DocMapper) -> anyhow::Result<WarmupInfo> {
+        regex_query_warmup_info(doc_mapper, &self.field)
+    }
+}
+
+// Private struct used for serialization.
+// It represents the value of a wildcard query. in the json form : `{field: <WildcardQueryValue>}`.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WildcardQueryValue {
+    value: String,
+}
+
+// Private enum used for deserialization.
+// Elasticsearch also accepts the short form `{field: <value>}`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WildcardQueryValueOrText {
+    WildcardQueryValue(WildcardQueryValue),
+    Text(String),
+}
+
+impl From<WildcardQuery> for HashMap<String, WildcardQueryValue> {
+    fn from(wildcard_query: WildcardQuery) -> Self {
+        let mut map = HashMap::with_capacity(1);
+        map.insert(
+            wildcard_query.field,
+            WildcardQueryValue {
+                value: wildcard_query.value,
+            },
+        );
+        map
+    }
+}
+
+impl TryFrom<HashMap<String, WildcardQueryValueOrText>> for WildcardQuery {
+    type Error = &'static str;
+
+    fn try_from(map: HashMap<String, WildcardQueryValueOrText>) -> Result<Self, Self::Error> {
+        let (field, value_or_text) = extract_single_entry(map)?;
+        let value = match value_or_text {
+            WildcardQueryValueOrText::WildcardQueryValue(wildcard_query_value) => {
+                wildcard_query_value.value
+            }
+            WildcardQueryValueOrText::Text(text) => text,
+        };
+        Ok(WildcardQuery { field, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{wildcard_to_regex, WildcardQuery};
+
+    #[test]
+    fn test_wildcard_to_regex() {
+        assert_eq!(wildcard_to_regex("ki*y"), "ki.*y");
+        assert_eq!(wildcard_to_regex("k?tty.*"), r"k.tty\..*");
+        assert_eq!(wildcard_to_regex("kitty"), "kitty");
+        assert_eq!(wildcard_to_regex("*"), ".*");
+    }
+
+    #[test]
+    fn test_wildcard_query_deserialize() {
+        let wildcard_query: WildcardQuery =
+            serde_json::from_str(r#"{ "user.id": { "value": "ki*y" } }"#).unwrap();
+        assert_eq!(
+            wildcard_query,
+            WildcardQuery {
+                field: "user.id".to_string(),
+                value: "ki*y".to_string(),
+            }
+        );
+    }
+}
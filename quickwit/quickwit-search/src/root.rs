@@ -17,7 +17,6 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
@@ -25,11 +24,12 @@ use anyhow::Context;
 use futures::future::try_join_all;
 use itertools::Itertools;
 use quickwit_config::{build_doc_mapper, IndexConfig};
-use quickwit_metastore::{Metastore, SplitMetadata};
+use quickwit_metastore::{ListSplitsQuery, Metastore, SplitMetadata, SplitState};
 use quickwit_proto::{
-    FetchDocsRequest, FetchDocsResponse, Hit, LeafHit, LeafListTermsRequest, LeafListTermsResponse,
-    LeafSearchRequest, LeafSearchResponse, ListTermsRequest, ListTermsResponse, PartialHit,
-    SearchRequest, SearchResponse, SplitIdAndFooterOffsets,
+    ExplainHitRequest, ExplainHitResponse, FetchDocsRequest, FetchDocsResponse, Hit, LeafHit,
+    LeafListTermsRequest, LeafListTermsResponse, LeafSearchRequest, LeafSearchResponse,
+    ListTermsRequest, ListTermsResponse, PartialHit, SearchRequest, SearchResponse,
+    SplitIdAndFooterOffsets,
 };
 use tantivy::aggregation::agg_result::AggregationResults;
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
@@ -39,12 +39,15 @@ use tantivy::TantivyError;
 use tracing::{debug, error, info_span, instrument};
 
 use crate::cluster_client::ClusterClient;
-use crate::collector::{make_merge_collector, QuickwitAggregations};
+use crate::collector::{make_merge_collector, sort_values_json, QuickwitAggregations};
+use crate::explain::explain_search;
+use crate::fetch_docs::fetch_docs_requires_search_request;
+use crate::query_dsl::build_query;
 use crate::search_job_placer::Job;
 use crate::service::SearcherContext;
 use crate::{
-    extract_split_and_footer_offsets, list_relevant_splits, SearchError, SearchJobPlacer,
-    SearchServiceClient,
+    extract_split_and_footer_offsets, list_relevant_splits, partial_hit_sorting_key, SearchError,
+    SearchJobPlacer, SearchServiceClient,
 };
 
 /// SearchJob to be assigned to search clients by the [`SearchJobPlacer`].
@@ -133,6 +136,12 @@ pub(crate) fn validate_request(search_request: &SearchRequest) -> crate::Result<
         )));
     }
 
+    if search_request.search_after.is_some() && search_request.start_offset > 0 {
+        return Err(SearchError::InvalidArgument(
+            "start_offset must be 0 when search_after is set".to_string(),
+        ));
+    }
+
     Ok(())
 }
 
@@ -164,7 +173,7 @@ pub async fn root_search(
     validate_request(search_request)?;
 
     // Validates the query by effectively building it against the current schema.
-    doc_mapper.query(doc_mapper.schema(), search_request)?;
+    let (query, _) = build_query(doc_mapper.as_ref(), doc_mapper.schema(), search_request)?;
 
     let doc_mapper_str = serde_json::to_string(&doc_mapper).map_err(|err| {
         SearchError::InternalError(format!("Failed to serialize doc mapper: Cause {err}"))
@@ -173,6 +182,12 @@ pub async fn root_search(
     let split_metadatas: Vec<SplitMetadata> =
         list_relevant_splits(search_request, metastore).await?;
 
+    let explanation = if search_request.explain {
+        Some(explain_search(search_request, query.as_ref(), metastore, &split_metadatas).await?)
+    } else {
+        None
+    };
+
     let split_offsets_map: HashMap<String, SplitIdAndFooterOffsets> = split_metadatas
         .iter()
         .map(|metadata| {
@@ -196,6 +211,7 @@ pub async fn root_search(
                     search_request,
                     &doc_mapper_str,
                     index_uri.as_ref(),
+                    index_config.encryption_key_uri(),
                     client_jobs,
                 );
                 cluster_client.leaf_search(leaf_request, client)
@@ -256,10 +272,10 @@ pub async fn root_search(
                     .map(|fetch_doc_job| fetch_doc_job.into())
                     .collect();
 
-                let search_request_opt = if search_request.snippet_fields.is_empty() {
-                    None
-                } else {
+                let search_request_opt = if fetch_docs_requires_search_request(search_request) {
                     Some(search_request.clone())
+                } else {
+                    None
                 };
                 let fetch_docs_req = FetchDocsRequest {
                     partial_hits,
@@ -268,6 +284,7 @@ pub async fn root_search(
                     index_uri: index_uri.to_string(),
                     search_request: search_request_opt,
                     doc_mapper: doc_mapper_str.clone(),
+                    encryption_key_uri: index_config.encryption_key_uri().map(str::to_string),
                 };
                 cluster_client.fetch_docs(fetch_docs_req, client)
             });
@@ -279,25 +296,34 @@ pub async fn root_search(
         .into_iter()
         .flat_map(|response| response.hits.into_iter());
 
+    let schema = doc_mapper.schema();
     let mut hits: Vec<Hit> = leaf_hits
         .map(|leaf_hit: LeafHit| Hit {
+            sort_values_json: leaf_hit
+                .partial_hit
+                .as_ref()
+                .and_then(|partial_hit| sort_values_json(search_request, &schema, partial_hit)),
             json: leaf_hit.leaf_json,
             partial_hit: leaf_hit.partial_hit,
             snippet: leaf_hit.leaf_snippet_json,
+            explanation: leaf_hit.leaf_explanation_json,
         })
         .collect();
 
-    hits.sort_unstable_by_key(|hit| {
-        Reverse(
-            hit.partial_hit
-                .as_ref()
-                .map(|hit| hit.sorting_field_value)
-                .unwrap_or(0),
-        )
-    });
+    // Ties are broken by hit address, so that the last hit can be used as a `search_after` cursor.
+    hits.sort_by_cached_key(|hit| hit.partial_hit.as_ref().map(partial_hit_sorting_key));
 
     let elapsed = start_instant.elapsed();
 
+    let split_ids = if search_request.pin_splits {
+        split_metadatas
+            .iter()
+            .map(|split_metadata| split_metadata.split_id.clone())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
     let aggregation = if let Some(intermediate_aggregation_result) =
         leaf_search_response.intermediate_aggregation_result
     {
@@ -327,6 +353,100 @@ pub async fn root_search(
         hits,
         elapsed_time_micros: elapsed.as_micros() as u64,
         errors: Vec::new(),
+        split_ids,
+        explanation,
+    })
+}
+
+/// Explains how the score of a single document is computed for a given query.
+/// 1. Looks up the split the document belongs to.
+/// 2. Sends a fetch docs request with `explain` set to the leaf node assigned to the split.
+/// 3. Returns the explanation of the document score, if it matches the query.
+#[instrument(skip(explain_hit_request, cluster_client, search_job_placer, metastore))]
+pub async fn root_explain_hit(
+    explain_hit_request: &ExplainHitRequest,
+    metastore: &dyn Metastore,
+    cluster_client: &ClusterClient,
+    search_job_placer: &SearchJobPlacer,
+) -> crate::Result<ExplainHitResponse> {
+    let (Some(search_request), Some(partial_hit)) = (
+        explain_hit_request.search_request.as_ref(),
+        explain_hit_request.partial_hit.as_ref(),
+    ) else {
+        return Err(SearchError::InvalidArgument(
+            "explain hit request requires a search request and a partial hit".to_string(),
+        ));
+    };
+    let index_config: IndexConfig = metastore
+        .index_metadata(&search_request.index_id)
+        .await?
+        .into_index_config();
+
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|err| {
+            SearchError::InternalError(format!("Failed to build doc mapper. Cause: {err}"))
+        })?;
+
+    // Validates the query by effectively building it against the current schema.
+    build_query(doc_mapper.as_ref(), doc_mapper.schema(), search_request)?;
+
+    let doc_mapper_str = serde_json::to_string(&doc_mapper).map_err(|err| {
+        SearchError::InternalError(format!("Failed to serialize doc mapper: Cause {err}"))
+    })?;
+
+    // The split is looked up regardless of the time range and tags of the query, so that
+    // explaining a document that does not match the query does not fail.
+    let list_splits_query = ListSplitsQuery::for_index(&search_request.index_id)
+        .with_split_states([SplitState::Published, SplitState::MarkedForDeletion]);
+    let split_metadata = metastore
+        .list_splits(list_splits_query)
+        .await?
+        .into_iter()
+        .map(|split| split.split_metadata)
+        .find(|split_metadata| split_metadata.split_id == partial_hit.split_id)
+        .ok_or_else(|| {
+            SearchError::InvalidArgument(format!(
+                "split `{}` does not exist in index `{}`",
+                partial_hit.split_id, search_request.index_id
+            ))
+        })?;
+    let split_offsets_map: HashMap<String, SplitIdAndFooterOffsets> = HashMap::from([(
+        split_metadata.split_id.clone(),
+        extract_split_and_footer_offsets(&split_metadata),
+    )]);
+    let client_fetch_docs_task: Vec<(SearchServiceClient, Vec<FetchDocsJob>)> =
+        assign_client_fetch_doc_tasks(
+            std::slice::from_ref(partial_hit),
+            &split_offsets_map,
+            search_job_placer,
+        )?;
+    let Some((client, fetch_docs_jobs)) = client_fetch_docs_task.into_iter().next() else {
+        return Err(SearchError::InternalError(
+            "failed to assign the fetch docs job".to_string(),
+        ));
+    };
+    let fetch_docs_req = FetchDocsRequest {
+        partial_hits: vec![partial_hit.clone()],
+        index_id: search_request.index_id.clone(),
+        split_offsets: fetch_docs_jobs.into_iter().map(Into::into).collect(),
+        index_uri: index_config.index_uri.to_string(),
+        search_request: Some(SearchRequest {
+            explain: true,
+            snippet_fields: Vec::new(),
+            ..search_request.clone()
+        }),
+        doc_mapper: doc_mapper_str,
+        encryption_key_uri: index_config.encryption_key_uri().map(str::to_string),
+    };
+    let fetch_docs_resp = cluster_client.fetch_docs(fetch_docs_req, client).await?;
+    let explanation = fetch_docs_resp
+        .hits
+        .into_iter()
+        .next()
+        .and_then(|leaf_hit| leaf_hit.leaf_explanation_json);
+    Ok(ExplainHitResponse {
+        matched: explanation.is_some(),
+        explanation,
     })
 }
 
@@ -368,6 +488,9 @@ pub async fn root_list_terms(
             "Trying to list terms on field which isn't indexed".to_string(),
         ));
     }
+    let doc_mapper_str = serde_json::to_string(&doc_mapper).map_err(|err| {
+        SearchError::InternalError(format!("Failed to serialize doc mapper: Cause {err}"))
+    })?;
 
     let mut query = quickwit_metastore::ListSplitsQuery::for_index(&list_terms_request.index_id)
         .with_split_state(quickwit_metastore::SplitState::Published);
@@ -401,6 +524,8 @@ pub async fn root_list_terms(
                         list_terms_request: Some(list_terms_request.clone()),
                         split_offsets: client_jobs.into_iter().map(|job| job.offsets).collect(),
                         index_uri: index_uri.to_string(),
+                        encryption_key_uri: index_config.encryption_key_uri().map(str::to_string),
+                        doc_mapper: doc_mapper_str.clone(),
                     },
                     client,
                 )
@@ -496,6 +621,7 @@ pub fn jobs_to_leaf_request(
     request: &SearchRequest,
     doc_mapper_str: &str,
     index_uri: &str, // TODO make Uri
+    encryption_key_uri_opt: Option<&str>,
     jobs: Vec<SearchJob>,
 ) -> LeafSearchRequest {
     let mut request_with_offset_0 = request.clone();
@@ -506,6 +632,7 @@ pub fn jobs_to_leaf_request(
         split_offsets: jobs.into_iter().map(|job| job.offsets).collect(),
         doc_mapper: doc_mapper_str.to_string(),
         index_uri: index_uri.to_string(),
+        encryption_key_uri: encryption_key_uri_opt.map(str::to_string),
     }
 }
 
@@ -532,6 +659,7 @@ mod tests {
             split_id: split_id.to_string(),
             segment_ord: 1,
             doc_id,
+            secondary_sorting_field_values: Vec::new(),
         }
     }
 
@@ -550,6 +678,7 @@ mod tests {
                 .expect("Json serialization should not fail"),
                 partial_hit: Some(req),
                 leaf_snippet_json: None,
+                leaf_explanation_json: None,
             })
             .collect()
     }
@@ -1580,6 +1709,32 @@ mod tests {
             "Invalid argument: max value for max_hits is 10_000, but got 20000",
         );
 
+        let search_request = quickwit_proto::SearchRequest {
+            index_id: "test-index".to_string(),
+            query: "test".to_string(),
+            search_fields: vec!["body".to_string()],
+            max_hits: 10,
+            start_offset: 10,
+            search_after: Some(PartialHit {
+                split_id: "split1".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let search_response = root_search(
+            Arc::new(SearcherContext::new(SearcherConfig::default())),
+            &search_request,
+            &metastore,
+            &cluster_client,
+            &search_job_placer,
+        )
+        .await;
+        assert_eq!(
+            search_response.unwrap_err().to_string(),
+            "Invalid argument: start_offset must be 0 when search_after is set",
+        );
+
         Ok(())
     }
 }
@@ -0,0 +1,177 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use itertools::Itertools;
+use quickwit_proto::{Hit, PartialHit};
+use serde_json::Value as JsonValue;
+
+use crate::error::SearchError;
+
+/// Encodes a hit address into a `search_after` cursor of the form
+/// `{sorting_field_values}:{split_id}:{segment_ord}:{doc_id}`, where the sorting field values
+/// are comma-separated.
+pub fn encode_search_after_cursor(partial_hit: &PartialHit) -> String {
+    let sorting_field_values = std::iter::once(&partial_hit.sorting_field_value)
+        .chain(&partial_hit.secondary_sorting_field_values)
+        .join(",");
+    format!(
+        "{}:{}:{}:{}",
+        sorting_field_values, partial_hit.split_id, partial_hit.segment_ord, partial_hit.doc_id
+    )
+}
+
+/// Parses a `search_after` cursor produced by [`encode_search_after_cursor`].
+pub fn parse_search_after_cursor(cursor: &str) -> crate::Result<PartialHit> {
+    let invalid_cursor = || SearchError::InvalidArgument(format!("Invalid cursor `{cursor}`."));
+    let mut parts = cursor.rsplitn(3, ':');
+    let doc_id_str = parts.next().ok_or_else(invalid_cursor)?;
+    let segment_ord_str = parts.next().ok_or_else(invalid_cursor)?;
+    let (sorting_field_values_str, split_id) = parts
+        .next()
+        .and_then(|head| head.split_once(':'))
+        .ok_or_else(invalid_cursor)?;
+    if split_id.is_empty() {
+        return Err(invalid_cursor());
+    }
+    let mut sorting_field_values: Vec<u64> = sorting_field_values_str
+        .split(',')
+        .map(|sorting_field_value_str| sorting_field_value_str.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid_cursor())?;
+    let sorting_field_value = sorting_field_values.remove(0);
+    Ok(PartialHit {
+        sorting_field_value,
+        split_id: split_id.to_string(),
+        segment_ord: segment_ord_str.parse().map_err(|_| invalid_cursor())?,
+        doc_id: doc_id_str.parse().map_err(|_| invalid_cursor())?,
+        secondary_sorting_field_values: sorting_field_values,
+    })
+}
+
+/// Returns the sort values of a hit returned by the Elasticsearch-compatible API: the values of
+/// the fields the hit is sorted by, followed by a `search_after` cursor pointing at the hit.
+pub fn search_after_to_sort_values(hit: &Hit) -> Vec<JsonValue> {
+    let Some(partial_hit) = &hit.partial_hit else {
+        return Vec::new();
+    };
+    let mut sort_values: Vec<JsonValue> = hit
+        .sort_values_json
+        .as_deref()
+        .and_then(|sort_values_json| serde_json::from_str(sort_values_json).ok())
+        .unwrap_or_default();
+    sort_values.push(JsonValue::from(encode_search_after_cursor(partial_hit)));
+    sort_values
+}
+
+/// Parses the `search_after` cursor ending the sort values produced by
+/// [`search_after_to_sort_values`].
+pub fn search_after_from_sort_values(sort_values: &[JsonValue]) -> crate::Result<PartialHit> {
+    let Some(JsonValue::String(cursor)) = sort_values.last() else {
+        return Err(SearchError::InvalidArgument(format!(
+            "Invalid `search_after` value `{}`. Expected the `sort` values of a previous hit.",
+            JsonValue::from(sort_values.to_vec())
+        )));
+    };
+    parse_search_after_cursor(cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::PartialHit;
+    use serde_json::json;
+
+    use super::*;
+
+    fn partial_hit() -> PartialHit {
+        PartialHit {
+            sorting_field_value: 1_680_000_000u64,
+            split_id: "01GXKQ9Z3VJ4K7R8Y5T6W2N1M0".to_string(),
+            segment_ord: 2,
+            doc_id: 42,
+            secondary_sorting_field_values: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_search_after_cursor() {
+        let cursor = encode_search_after_cursor(&partial_hit());
+        assert_eq!(cursor, "1680000000:01GXKQ9Z3VJ4K7R8Y5T6W2N1M0:2:42");
+        assert_eq!(parse_search_after_cursor(&cursor).unwrap(), partial_hit());
+
+        let partial_hit_with_secondary_values = PartialHit {
+            secondary_sorting_field_values: vec![7, 0],
+            ..partial_hit()
+        };
+        let cursor = encode_search_after_cursor(&partial_hit_with_secondary_values);
+        assert_eq!(cursor, "1680000000,7,0:01GXKQ9Z3VJ4K7R8Y5T6W2N1M0:2:42");
+        assert_eq!(
+            parse_search_after_cursor(&cursor).unwrap(),
+            partial_hit_with_secondary_values
+        );
+    }
+
+    #[test]
+    fn test_search_after_cursor_invalid() {
+        for cursor in [
+            "",
+            "1:split:2",
+            "1::2:3",
+            "a:split:2:3",
+            "1:split:2:-3",
+            "1,:split:2:3",
+        ] {
+            let error = parse_search_after_cursor(cursor).unwrap_err();
+            assert!(matches!(error, SearchError::InvalidArgument(_)));
+        }
+    }
+
+    #[test]
+    fn test_search_after_sort_values() {
+        let hit = Hit {
+            partial_hit: Some(partial_hit()),
+            sort_values_json: Some("[1680000000000,\"foo\"]".to_string()),
+            ..Default::default()
+        };
+        let sort_values = search_after_to_sort_values(&hit);
+        assert_eq!(
+            JsonValue::from(sort_values.clone()),
+            json!([
+                1_680_000_000_000u64,
+                "foo",
+                "1680000000:01GXKQ9Z3VJ4K7R8Y5T6W2N1M0:2:42"
+            ])
+        );
+        assert_eq!(
+            search_after_from_sort_values(&sort_values).unwrap(),
+            partial_hit()
+        );
+        let hit_without_sort_values = Hit {
+            partial_hit: Some(partial_hit()),
+            ..Default::default()
+        };
+        assert_eq!(
+            JsonValue::from(search_after_to_sort_values(&hit_without_sort_values)),
+            json!(["1680000000:01GXKQ9Z3VJ4K7R8Y5T6W2N1M0:2:42"])
+        );
+        for sort_values in [json!([]), json!([1, "split", 2, 3]), json!(["foo"])] {
+            let error = search_after_from_sort_values(sort_values.as_array().unwrap()).unwrap_err();
+            assert!(matches!(error, SearchError::InvalidArgument(_)));
+        }
+    }
+}
@@ -25,6 +25,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
 use crate::error::SearchError;
+use crate::search_after::encode_search_after_cursor;
 
 /// SearchResponseRest represents the response returned by the REST search API
 /// and is meant to be serialized into JSON.
@@ -47,14 +48,41 @@ pub struct SearchResponseRest {
     #[schema(value_type = Object)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregations: Option<JsonValue>,
+    /// Cursor pointing to the last hit, to pass as `search_after` to fetch the next page.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_search_after: Option<String>,
+    /// Splits the search ran on, to pass as `split_ids` to fetch the next pages from the same
+    /// splits. Only returned if `pin_splits` is set.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub split_ids: Vec<String>,
+    /// Explanation of the parsed query and of the split pruning. Only returned if `explain` is
+    /// set.
+    #[schema(value_type = Object)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<JsonValue>,
+    /// Explanations of the hits scores, in the same order as the hits. Only returned if
+    /// `explain` is set.
+    #[schema(value_type = Vec<Object>)]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hit_explanations: Option<Vec<JsonValue>>,
 }
 
 impl TryFrom<SearchResponse> for SearchResponseRest {
     type Error = SearchError;
 
     fn try_from(search_response: SearchResponse) -> Result<Self, Self::Error> {
+        let next_search_after = search_response
+            .hits
+            .last()
+            .and_then(|hit| hit.partial_hit.as_ref())
+            .map(encode_search_after_cursor);
         let mut documents = Vec::with_capacity(search_response.hits.len());
         let mut snippets = Vec::new();
+        let mut hit_explanations = Vec::with_capacity(search_response.hits.len());
         for hit in search_response.hits {
             let document: JsonValue = serde_json::from_str(&hit.json).map_err(|err| {
                 SearchError::InternalError(format!(
@@ -74,6 +102,17 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
                     })?;
                 snippets.push(snippet_opt);
             }
+
+            let hit_explanation = if let Some(explanation_json) = hit.explanation {
+                serde_json::from_str(&explanation_json).map_err(|err| {
+                    SearchError::InternalError(format!(
+                        "Failed to serialize explanation `{explanation_json}` to JSON: `{err}`."
+                    ))
+                })?
+            } else {
+                JsonValue::Null
+            };
+            hit_explanations.push(hit_explanation);
         }
 
         let snippet_opt = if !snippets.is_empty() {
@@ -82,6 +121,20 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
             None
         };
 
+        let hit_explanations_opt = if hit_explanations.iter().any(|value| !value.is_null()) {
+            Some(hit_explanations)
+        } else {
+            None
+        };
+
+        let explanation_opt = if let Some(explanation_json) = search_response.explanation {
+            let explanation: JsonValue = serde_json::from_str(&explanation_json)
+                .map_err(|err| SearchError::InternalError(err.to_string()))?;
+            Some(explanation)
+        } else {
+            None
+        };
+
         let aggregations_opt = if let Some(aggregation_json) = search_response.aggregation {
             let aggregation: JsonValue = serde_json::from_str(&aggregation_json)
                 .map_err(|err| SearchError::InternalError(err.to_string()))?;
@@ -97,6 +150,10 @@ impl TryFrom<SearchResponse> for SearchResponseRest {
             elapsed_time_micros: search_response.elapsed_time_micros,
             errors: search_response.errors,
             aggregations: aggregations_opt,
+            next_search_after,
+            split_ids: search_response.split_ids,
+            explanation: explanation_opt,
+            hit_explanations: hit_explanations_opt,
         })
     }
 }
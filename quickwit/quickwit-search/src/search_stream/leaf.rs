@@ -57,14 +57,21 @@ pub async fn leaf_search_stream(
     storage: Arc<dyn Storage>,
     splits: Vec<SplitIdAndFooterOffsets>,
     doc_mapper: Arc<dyn DocMapper>,
+    disk_cache_enabled: bool,
 ) -> UnboundedReceiverStream<crate::Result<LeafSearchStreamResponse>> {
     let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
     let span = info_span!("leaf_search_stream",);
     tokio::spawn(
         async move {
-            let mut stream =
-                leaf_search_results_stream(searcher_context, request, storage, splits, doc_mapper)
-                    .await;
+            let mut stream = leaf_search_results_stream(
+                searcher_context,
+                request,
+                storage,
+                splits,
+                doc_mapper,
+                disk_cache_enabled,
+            )
+            .await;
             while let Some(item) = stream.next().await {
                 if let Err(error) = result_sender.send(item) {
                     error!(
@@ -86,6 +93,7 @@ async fn leaf_search_results_stream(
     storage: Arc<dyn Storage>,
     splits: Vec<SplitIdAndFooterOffsets>,
     doc_mapper: Arc<dyn DocMapper>,
+    disk_cache_enabled: bool,
 ) -> impl futures::Stream<Item = crate::Result<LeafSearchStreamResponse>> + Sync + Send + 'static {
     let max_num_concurrent_split_streams = searcher_context
         .searcher_config
@@ -98,6 +106,7 @@ async fn leaf_search_results_stream(
                 doc_mapper.clone(),
                 request.clone(),
                 storage.clone(),
+                disk_cache_enabled,
             )
             .shared()
         })
@@ -112,6 +121,7 @@ async fn leaf_search_stream_single_split(
     doc_mapper: Arc<dyn DocMapper>,
     stream_request: SearchStreamRequest,
     storage: Arc<dyn Storage>,
+    disk_cache_enabled: bool,
 ) -> crate::Result<LeafSearchStreamResponse> {
     let _leaf_split_stream_permit = searcher_context
         .split_stream_semaphore
@@ -119,7 +129,15 @@ async fn leaf_search_stream_single_split(
         .await
         .expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.");
 
-    let index = open_index_with_caches(&searcher_context, storage, &split, true).await?;
+    let index = open_index_with_caches(
+        &searcher_context,
+        storage,
+        &split,
+        doc_mapper.tokenizer_manager(),
+        true,
+        disk_cache_enabled,
+    )
+    .await?;
     let split_schema = index.schema();
 
     let request_fields = Arc::new(SearchStreamRequestFields::from_request(
@@ -494,6 +512,8 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                timestamp_start: None,
+                timestamp_end: None,
             })
             .collect();
         let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default()));
@@ -503,6 +523,7 @@ mod tests {
             test_sandbox.storage(),
             splits_offsets,
             test_sandbox.doc_mapper(),
+            true,
         )
         .await;
         let res = single_node_stream.next().await.expect("no leaf result")?;
@@ -572,6 +593,8 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                timestamp_start: None,
+                timestamp_end: None,
             })
             .collect();
         let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default()));
@@ -581,6 +604,7 @@ mod tests {
             test_sandbox.storage(),
             splits_offsets,
             test_sandbox.doc_mapper(),
+            true,
         )
         .await;
         let res = single_node_stream.next().await.expect("no leaf result")?;
@@ -629,6 +653,8 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                timestamp_start: None,
+                timestamp_end: None,
             })
             .collect();
         let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default()));
@@ -638,6 +664,7 @@ mod tests {
             test_sandbox.storage(),
             splits_offsets,
             test_sandbox.doc_mapper(),
+            true,
         )
         .await;
         let res = single_node_stream.next().await.expect("no leaf result");
@@ -722,6 +749,8 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                timestamp_start: None,
+                timestamp_end: None,
             })
             .collect();
         let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default()));
@@ -731,6 +760,7 @@ mod tests {
             test_sandbox.storage(),
             splits_offsets,
             test_sandbox.doc_mapper(),
+            true,
         )
         .await;
         let res = single_node_stream.next().await.expect("no leaf result")?;
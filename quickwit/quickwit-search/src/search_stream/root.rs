@@ -74,6 +74,7 @@ pub async fn root_search_stream(
             &search_stream_request,
             &doc_mapper_str,
             index_uri.as_ref(),
+            index_config.encryption_key_uri(),
             client_jobs,
         );
         let leaf_stream = cluster_client
@@ -90,6 +91,7 @@ fn jobs_to_leaf_request(
     request: &SearchStreamRequest,
     doc_mapper_str: &str,
     index_uri: &str, // TODO make Uri
+    encryption_key_uri_opt: Option<&str>,
     jobs: Vec<SearchJob>,
 ) -> LeafSearchStreamRequest {
     LeafSearchStreamRequest {
@@ -97,6 +99,7 @@ fn jobs_to_leaf_request(
         split_offsets: jobs.into_iter().map(Into::into).collect(),
         doc_mapper: doc_mapper_str.to_string(),
         index_uri: index_uri.to_string(),
+        encryption_key_uri: encryption_key_uri_opt.map(str::to_string),
     }
 }
 
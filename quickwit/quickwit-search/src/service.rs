@@ -27,19 +27,23 @@ use quickwit_config::SearcherConfig;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_metastore::Metastore;
 use quickwit_proto::{
-    FetchDocsRequest, FetchDocsResponse, LeafListTermsRequest, LeafListTermsResponse,
-    LeafSearchRequest, LeafSearchResponse, LeafSearchStreamRequest, LeafSearchStreamResponse,
-    ListTermsRequest, ListTermsResponse, SearchRequest, SearchResponse, SearchStreamRequest,
+    ExplainHitRequest, ExplainHitResponse, FetchDocsRequest, FetchDocsResponse,
+    LeafListTermsRequest, LeafListTermsResponse, LeafSearchRequest, LeafSearchResponse,
+    LeafSearchStreamRequest, LeafSearchStreamResponse, ListTermsRequest, ListTermsResponse,
+    SearchRequest, SearchResponse, SearchStreamRequest,
+};
+use quickwit_storage::{
+    Cache, LocalDiskCache, MemorySizedCache, QuickwitCache, StorageUriResolver,
 };
-use quickwit_storage::{Cache, MemorySizedCache, QuickwitCache, StorageUriResolver};
 use tokio::sync::Semaphore;
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::info;
 
+use crate::leaf_cache::LeafSearchCache;
 use crate::search_stream::{leaf_search_stream, root_search_stream};
 use crate::{
-    fetch_docs, leaf_list_terms, leaf_search, root_list_terms, root_search, ClusterClient,
-    SearchError, SearchJobPlacer,
+    fetch_docs, leaf_list_terms, leaf_search, root_explain_hit, root_list_terms, root_search,
+    ClusterClient, SearchError, SearchJobPlacer,
 };
 
 #[derive(Clone)]
@@ -111,6 +115,14 @@ pub trait SearchService: 'static + Send + Sync {
         &self,
         request: LeafListTermsRequest,
     ) -> crate::Result<LeafListTermsResponse>;
+
+    /// Explains how the score of a single document is computed for a given query.
+    ///
+    /// The document is fetched from the leaf node the split it belongs to is assigned to.
+    async fn root_explain_hit(
+        &self,
+        request: ExplainHitRequest,
+    ) -> crate::Result<ExplainHitResponse>;
 }
 
 impl SearchServiceImpl {
@@ -121,8 +133,12 @@ impl SearchServiceImpl {
         cluster_client: ClusterClient,
         search_job_placer: SearchJobPlacer,
         searcher_config: SearcherConfig,
+        disk_cache_opt: Option<Arc<LocalDiskCache>>,
     ) -> Self {
-        let searcher_context = Arc::new(SearcherContext::new(searcher_config));
+        let searcher_context = Arc::new(SearcherContext::with_disk_cache(
+            searcher_config,
+            disk_cache_opt,
+        ));
         SearchServiceImpl {
             metastore,
             storage_uri_resolver,
@@ -163,9 +179,12 @@ impl SearchService for SearchServiceImpl {
             .search_request
             .ok_or_else(|| SearchError::InternalError("No search request.".to_string()))?;
         info!(index=?search_request.index_id, splits=?leaf_search_request.split_offsets, "leaf_search");
-        let storage = self
-            .storage_uri_resolver
-            .resolve(&Uri::from_well_formed(leaf_search_request.index_uri))?;
+        let storage = self.storage_uri_resolver.resolve_with_encryption(
+            &Uri::from_well_formed(leaf_search_request.index_uri),
+            leaf_search_request.encryption_key_uri.as_deref(),
+        )?;
+        // Decrypted splits must not be written to the local disk cache.
+        let disk_cache_enabled = leaf_search_request.encryption_key_uri.is_none();
         let split_ids = leaf_search_request.split_offsets;
         let doc_mapper = deserialize_doc_mapper(&leaf_search_request.doc_mapper)?;
 
@@ -175,6 +194,7 @@ impl SearchService for SearchServiceImpl {
             storage.clone(),
             &split_ids[..],
             doc_mapper,
+            disk_cache_enabled,
         )
         .await?;
 
@@ -185,9 +205,11 @@ impl SearchService for SearchServiceImpl {
         &self,
         fetch_docs_request: FetchDocsRequest,
     ) -> crate::Result<FetchDocsResponse> {
-        let storage = self
-            .storage_uri_resolver
-            .resolve(&Uri::from_well_formed(fetch_docs_request.index_uri))?;
+        let storage = self.storage_uri_resolver.resolve_with_encryption(
+            &Uri::from_well_formed(fetch_docs_request.index_uri),
+            fetch_docs_request.encryption_key_uri.as_deref(),
+        )?;
+        let disk_cache_enabled = fetch_docs_request.encryption_key_uri.is_none();
         let search_request_opt = fetch_docs_request.search_request.as_ref();
         let doc_mapper = deserialize_doc_mapper(&fetch_docs_request.doc_mapper)?;
         let fetch_docs_response = fetch_docs(
@@ -197,6 +219,7 @@ impl SearchService for SearchServiceImpl {
             &fetch_docs_request.split_offsets,
             doc_mapper,
             search_request_opt,
+            disk_cache_enabled,
         )
         .await?;
 
@@ -225,9 +248,11 @@ impl SearchService for SearchServiceImpl {
             .request
             .ok_or_else(|| SearchError::InternalError("No search request.".to_string()))?;
         info!(index=?stream_request.index_id, splits=?leaf_stream_request.split_offsets, "leaf_search");
-        let storage = self
-            .storage_uri_resolver
-            .resolve(&Uri::from_well_formed(leaf_stream_request.index_uri))?;
+        let storage = self.storage_uri_resolver.resolve_with_encryption(
+            &Uri::from_well_formed(leaf_stream_request.index_uri),
+            leaf_stream_request.encryption_key_uri.as_deref(),
+        )?;
+        let disk_cache_enabled = leaf_stream_request.encryption_key_uri.is_none();
         let doc_mapper = deserialize_doc_mapper(&leaf_stream_request.doc_mapper)?;
         let leaf_receiver = leaf_search_stream(
             self.searcher_context.clone(),
@@ -235,6 +260,7 @@ impl SearchService for SearchServiceImpl {
             storage,
             leaf_stream_request.split_offsets,
             doc_mapper,
+            disk_cache_enabled,
         )
         .await;
         Ok(leaf_receiver)
@@ -264,21 +290,41 @@ impl SearchService for SearchServiceImpl {
             .ok_or_else(|| SearchError::InternalError("No search request.".to_string()))?;
         info!(index=?search_request.index_id, splits=?leaf_search_request.split_offsets,
          "leaf_search");
-        let storage = self
-            .storage_uri_resolver
-            .resolve(&Uri::from_well_formed(leaf_search_request.index_uri))?;
+        let storage = self.storage_uri_resolver.resolve_with_encryption(
+            &Uri::from_well_formed(leaf_search_request.index_uri),
+            leaf_search_request.encryption_key_uri.as_deref(),
+        )?;
+        let disk_cache_enabled = leaf_search_request.encryption_key_uri.is_none();
         let split_ids = leaf_search_request.split_offsets;
+        let doc_mapper = deserialize_doc_mapper(&leaf_search_request.doc_mapper)?;
 
         let leaf_search_response = leaf_list_terms(
             self.searcher_context.clone(),
             &search_request,
             storage.clone(),
             &split_ids[..],
+            doc_mapper,
+            disk_cache_enabled,
         )
         .await?;
 
         Ok(leaf_search_response)
     }
+
+    async fn root_explain_hit(
+        &self,
+        explain_hit_request: ExplainHitRequest,
+    ) -> crate::Result<ExplainHitResponse> {
+        let explain_hit_response = root_explain_hit(
+            &explain_hit_request,
+            self.metastore.as_ref(),
+            &self.cluster_client,
+            &self.search_job_placer,
+        )
+        .await?;
+
+        Ok(explain_hit_response)
+    }
 }
 
 /// [`SearcherContext`] provides a common set of variables
@@ -295,6 +341,11 @@ pub struct SearcherContext {
     pub split_footer_cache: MemorySizedCache<String>,
     /// Fast fields cache.
     pub fast_fields_cache: Arc<dyn Cache>,
+    /// Local disk cache backing the split footer and fast fields caches. It is bypassed for
+    /// encrypted indexes so that their decrypted content is never written to the searcher disk.
+    pub disk_cache_opt: Option<Arc<LocalDiskCache>>,
+    /// Leaf search responses cache.
+    pub leaf_search_cache: LeafSearchCache,
 }
 
 impl std::fmt::Debug for SearcherContext {
@@ -312,6 +363,13 @@ impl std::fmt::Debug for SearcherContext {
 
 impl SearcherContext {
     pub fn new(searcher_config: SearcherConfig) -> Self {
+        Self::with_disk_cache(searcher_config, None)
+    }
+
+    pub fn with_disk_cache(
+        searcher_config: SearcherConfig,
+        disk_cache_opt: Option<Arc<LocalDiskCache>>,
+    ) -> Self {
         let capacity_in_bytes = searcher_config.split_footer_cache_capacity.get_bytes() as usize;
         let global_split_footer_cache = MemorySizedCache::with_capacity_in_bytes(
             capacity_in_bytes,
@@ -324,12 +382,16 @@ impl SearcherContext {
         let fast_field_cache_capacity =
             searcher_config.fast_field_cache_capacity.get_bytes() as usize;
         let storage_long_term_cache = Arc::new(QuickwitCache::new(fast_field_cache_capacity));
+        let leaf_search_cache =
+            LeafSearchCache::new(searcher_config.leaf_search_cache_capacity.get_bytes() as usize);
         Self {
             searcher_config,
             split_footer_cache: global_split_footer_cache,
             leaf_search_split_semaphore,
             split_stream_semaphore,
             fast_fields_cache: storage_long_term_cache,
+            disk_cache_opt,
+            leaf_search_cache,
         }
     }
 }
@@ -0,0 +1,201 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_common::field_matches_pattern;
+use quickwit_proto::SearchRequest;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+/// Projects the fetched documents on the `source_includes` and `source_excludes` field
+/// patterns of a search request.
+///
+/// Patterns are matched against the dot-separated path of the document fields and support the
+/// `*` wildcard. Like in Elasticsearch:
+/// - when a field is included, so are all its subfields. Objects are kept as long as one of their
+///   subfields is included.
+/// - excludes take precedence over includes.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SourceFilter {
+    includes: Vec<String>,
+    excludes: Vec<String>,
+}
+
+impl SourceFilter {
+    /// Returns `None` if the search request does not restrict the returned fields.
+    pub fn from_search_request(search_request: &SearchRequest) -> Option<Self> {
+        if search_request.source_includes.is_empty() && search_request.source_excludes.is_empty() {
+            return None;
+        }
+        Some(SourceFilter {
+            includes: search_request.source_includes.clone(),
+            excludes: search_request.source_excludes.clone(),
+        })
+    }
+
+    /// Removes the fields of the document that are not included or that are excluded.
+    pub fn filter_document(
+        &self,
+        document: JsonMap<String, JsonValue>,
+    ) -> JsonMap<String, JsonValue> {
+        self.filter_object("", document, self.includes.is_empty())
+    }
+
+    fn filter_object(
+        &self,
+        path: &str,
+        object: JsonMap<String, JsonValue>,
+        included: bool,
+    ) -> JsonMap<String, JsonValue> {
+        object
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let field_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                let filtered_value = self.filter_value(&field_path, value, included)?;
+                Some((key, filtered_value))
+            })
+            .collect()
+    }
+
+    fn filter_value(
+        &self,
+        path: &str,
+        value: JsonValue,
+        parent_included: bool,
+    ) -> Option<JsonValue> {
+        if self
+            .excludes
+            .iter()
+            .any(|exclude| field_matches_pattern(path, exclude))
+        {
+            return None;
+        }
+        let included = parent_included
+            || self
+                .includes
+                .iter()
+                .any(|include| field_matches_pattern(path, include));
+        if let JsonValue::Array(values) = value {
+            // The elements of an array share the path of the array.
+            let filtered_values: Vec<JsonValue> = values
+                .into_iter()
+                .filter_map(|value| self.filter_included_value(path, value, included))
+                .collect();
+            if included || !filtered_values.is_empty() {
+                return Some(JsonValue::Array(filtered_values));
+            }
+            return None;
+        }
+        self.filter_included_value(path, value, included)
+    }
+
+    fn filter_included_value(
+        &self,
+        path: &str,
+        value: JsonValue,
+        included: bool,
+    ) -> Option<JsonValue> {
+        match value {
+            JsonValue::Object(object) => {
+                let filtered_object = self.filter_object(path, object, included);
+                if included || !filtered_object.is_empty() {
+                    Some(JsonValue::Object(filtered_object))
+                } else {
+                    None
+                }
+            }
+            value if included => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn source_filter(includes: &[&str], excludes: &[&str]) -> SourceFilter {
+        let search_request = SearchRequest {
+            source_includes: includes.iter().map(|include| include.to_string()).collect(),
+            source_excludes: excludes.iter().map(|exclude| exclude.to_string()).collect(),
+            ..Default::default()
+        };
+        SourceFilter::from_search_request(&search_request).unwrap()
+    }
+
+    fn filter(source_filter: &SourceFilter, document: JsonValue) -> JsonValue {
+        let JsonValue::Object(document) = document else {
+            panic!("The document should be a JSON object.");
+        };
+        JsonValue::Object(source_filter.filter_document(document))
+    }
+
+    #[test]
+    fn test_source_filter_from_search_request() {
+        assert!(SourceFilter::from_search_request(&SearchRequest::default()).is_none());
+    }
+
+    #[test]
+    fn test_source_filter() {
+        let document = json!({
+            "timestamp": 1,
+            "body": "hello",
+            "attributes": {"server": "srv-1", "status": 200, "tags": ["a", "b"]},
+            "spans": [{"name": "foo", "duration": 3}, {"name": "bar", "duration": 5}],
+        });
+        assert_eq!(
+            filter(
+                &source_filter(&["timestamp", "body"], &[]),
+                document.clone()
+            ),
+            json!({"timestamp": 1, "body": "hello"})
+        );
+        assert_eq!(
+            filter(
+                &source_filter(&["attributes"], &["attributes.status"]),
+                document.clone()
+            ),
+            json!({"attributes": {"server": "srv-1", "tags": ["a", "b"]}})
+        );
+        assert_eq!(
+            filter(&source_filter(&["attributes.s*"], &[]), document.clone()),
+            json!({"attributes": {"server": "srv-1", "status": 200}})
+        );
+        assert_eq!(
+            filter(&source_filter(&["spans.name"], &[]), document.clone()),
+            json!({"spans": [{"name": "foo"}, {"name": "bar"}]})
+        );
+        assert_eq!(
+            filter(
+                &source_filter(&[], &["attributes", "spans.duration"]),
+                document.clone()
+            ),
+            json!({
+                "timestamp": 1,
+                "body": "hello",
+                "spans": [{"name": "foo"}, {"name": "bar"}],
+            })
+        );
+        assert_eq!(filter(&source_filter(&[], &["*"]), document), json!({}));
+    }
+}
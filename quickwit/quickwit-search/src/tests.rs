@@ -24,7 +24,7 @@ use quickwit_config::SearcherConfig;
 use quickwit_doc_mapper::DefaultDocMapper;
 use quickwit_indexing::TestSandbox;
 use quickwit_opentelemetry::otlp::TraceId;
-use quickwit_proto::{LeafListTermsResponse, SearchRequest, SortOrder};
+use quickwit_proto::{HighlightSpec, LeafListTermsResponse, SearchRequest, SortField, SortOrder};
 use serde_json::{json, Value as JsonValue};
 use tantivy::schema::Value as TantivyValue;
 use tantivy::time::OffsetDateTime;
@@ -181,6 +181,138 @@ async fn test_single_search_with_snippet() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_search_with_highlight_spec() -> anyhow::Result<()> {
+    let index_id = "single-node-with-highlight-spec";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: array<text>
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"title": "snoopy & woodstock", "body": ["Snoopy is a beagle.", "He loves his beagle friends."]}),
+    ];
+    test_sandbox.add_documents(docs.clone()).await?;
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "title:snoopy".to_string(),
+        snippet_fields: vec!["title".to_string(), "body".to_string()],
+        max_hits: 10,
+        highlight: Some(HighlightSpec {
+            number_of_fragments: Some(1),
+            pre_tag: Some("<em>".to_string()),
+            post_tag: Some("</em>".to_string()),
+            require_field_match: Some(false),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 1);
+    let highlight_json: JsonValue =
+        serde_json::from_str(single_node_result.hits[0].snippet.as_ref().unwrap())?;
+    // The query only targets `title`, but `require_field_match` is disabled. Custom tags are
+    // inserted as is, but the text is HTML-escaped.
+    let expected_json: JsonValue = json!({
+        "title": ["<em>snoopy</em> &amp; woodstock"],
+        "body": ["<em>Snoopy</em> is a beagle"]
+    });
+    assert_json_eq!(highlight_json, expected_json);
+
+    let search_request = SearchRequest {
+        query: "body:beagle".to_string(),
+        highlight: Some(HighlightSpec {
+            number_of_fragments: Some(0),
+            ..Default::default()
+        }),
+        ..search_request
+    };
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    let highlight_json: JsonValue =
+        serde_json::from_str(single_node_result.hits[0].snippet.as_ref().unwrap())?;
+    let expected_json: JsonValue = json!({
+        "title": [],
+        "body": ["Snoopy is a <b>beagle</b>", "He loves his <b>beagle</b> friends"]
+    });
+    assert_json_eq!(highlight_json, expected_json);
+
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_search_with_explain() -> anyhow::Result<()> {
+    let index_id = "single-node-with-explain";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"title": "snoopy", "body": "Snoopy is an anthropomorphic beagle in the comic strip."}),
+        json!({"title": "lisa", "body": "Lisa is a character in `The Simpsons` animated tv series."}),
+    ];
+    test_sandbox.add_documents(docs.clone()).await?;
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "beagle".to_string(),
+        search_fields: vec!["body".to_string()],
+        max_hits: 10,
+        explain: true,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 1);
+    assert!(single_node_result.hits[0].snippet.is_none());
+
+    let explanation_json: JsonValue =
+        serde_json::from_str(single_node_result.explanation.as_ref().unwrap())?;
+    assert!(explanation_json["query"]
+        .as_str()
+        .unwrap()
+        .contains("beagle"));
+    assert_eq!(
+        explanation_json["searched_splits"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(explanation_json["pruned_splits"], json!([]));
+
+    let hit_explanation_json: JsonValue =
+        serde_json::from_str(single_node_result.hits[0].explanation.as_ref().unwrap())?;
+    assert!(hit_explanation_json["value"].as_f64().unwrap() > 0.0);
+    assert!(!hit_explanation_json["description"]
+        .as_str()
+        .unwrap()
+        .is_empty());
+
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 async fn slop_search_and_check(
     test_sandbox: &TestSandbox,
     index_id: &str,
@@ -555,6 +687,110 @@ async fn test_single_node_sort_by_score_should_fail() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_sort_by_multiple_fields() -> anyhow::Result<()> {
+    let index_id = "single-node-sort-by-multiple-fields";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: description
+                type: text
+              - name: priority
+                type: i64
+                fast: true
+              - name: temperature
+                type: i64
+                fast: true
+        "#;
+    let test_sandbox =
+        TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["description"]).await?;
+
+    let mut docs = Vec::new();
+    for i in 0..20 {
+        let description = format!("city info-{}", i + 1);
+        if i % 5 == 0 {
+            docs.push(json!({"description": description, "temperature": i}));
+        } else {
+            docs.push(json!({"description": description, "priority": i % 3, "temperature": i}));
+        }
+    }
+    test_sandbox.add_documents(docs).await?;
+
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "city".to_string(),
+        max_hits: 20,
+        sort_fields: vec![
+            SortField {
+                field_name: "priority".to_string(),
+                sort_order: SortOrder::Asc as i32,
+                missing_first: false,
+            },
+            SortField {
+                field_name: "temperature".to_string(),
+                sort_order: SortOrder::Desc as i32,
+                missing_first: false,
+            },
+        ],
+        ..Default::default()
+    };
+    let single_node_response = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_response.num_hits, 20);
+    let sort_keys: Vec<(Option<i64>, i64)> = single_node_response
+        .hits
+        .iter()
+        .map(|hit| {
+            let doc: JsonValue = serde_json::from_str(&hit.json).unwrap();
+            (
+                doc["priority"].as_i64(),
+                doc["temperature"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    let mut expected_sort_keys = sort_keys.clone();
+    expected_sort_keys.sort_by_key(|(priority_opt, temperature)| {
+        (
+            priority_opt.is_none(),
+            *priority_opt,
+            std::cmp::Reverse(*temperature),
+        )
+    });
+    assert_eq!(sort_keys, expected_sort_keys);
+    assert_eq!(sort_keys[0], (Some(0), 18));
+    assert_eq!(sort_keys[19], (None, 0));
+
+    // The last page is fetched from the cursor of the previous one.
+    let search_request = SearchRequest {
+        max_hits: 5,
+        search_after: single_node_response.hits[14].partial_hit.clone(),
+        ..search_request
+    };
+    let single_node_response = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    let last_page_sort_keys: Vec<(Option<i64>, i64)> = single_node_response
+        .hits
+        .iter()
+        .map(|hit| {
+            let doc: JsonValue = serde_json::from_str(&hit.json).unwrap();
+            (
+                doc["priority"].as_i64(),
+                doc["temperature"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(last_page_sort_keys, sort_keys[15..]);
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_invalid_sorting_with_query() -> anyhow::Result<()> {
     let index_id = "single-node-invalid-sorting";
@@ -688,6 +924,8 @@ async fn test_search_dynamic_util(test_sandbox: &TestSandbox, query: &str) -> Ve
             split_id: split_meta.split_id().to_string(),
             split_footer_start: split_meta.split_metadata.footer_offsets.start,
             split_footer_end: split_meta.split_metadata.footer_offsets.end,
+            timestamp_start: None,
+            timestamp_end: None,
         })
         .collect();
     let request = quickwit_proto::SearchRequest {
@@ -703,6 +941,7 @@ async fn test_search_dynamic_util(test_sandbox: &TestSandbox, query: &str) -> Ve
         test_sandbox.storage(),
         &splits_offsets,
         test_sandbox.doc_mapper(),
+        true,
     )
     .await
     .unwrap();
@@ -747,6 +986,81 @@ async fn test_search_dynamic_mode() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_leaf_search_disk_cache_enabled() -> anyhow::Result<()> {
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: count
+                type: u64
+                fast: true
+        "#;
+    let test_sandbox =
+        TestSandbox::create("leaf_search_disk_cache", doc_mapping_yaml, "{}", &["body"]).await?;
+    test_sandbox
+        .add_documents(vec![json!({"body": "hello", "count": 1})])
+        .await?;
+    let splits_offsets: Vec<_> = test_sandbox
+        .metastore()
+        .list_all_splits(test_sandbox.index_id())
+        .await?
+        .iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+    let request = quickwit_proto::SearchRequest {
+        index_id: test_sandbox.index_id().to_string(),
+        query: "hello".to_string(),
+        max_hits: 10,
+        sort_by_field: Some("count".to_string()),
+        ..Default::default()
+    };
+    let disk_cache_dir = tempfile::tempdir()?;
+    let disk_cache = Arc::new(LocalDiskCache::open(
+        disk_cache_dir.path(),
+        1_000_000,
+        &quickwit_storage::STORAGE_METRICS.disk_cache,
+    )?);
+    let num_cached_files = || std::fs::read_dir(disk_cache_dir.path()).unwrap().count();
+    {
+        // Encrypted indexes bypass the disk cache.
+        let searcher_context = Arc::new(SearcherContext::with_disk_cache(
+            SearcherConfig::default(),
+            Some(disk_cache.clone()),
+        ));
+        let search_response = leaf_search(
+            searcher_context,
+            &request,
+            test_sandbox.storage(),
+            &splits_offsets,
+            test_sandbox.doc_mapper(),
+            false,
+        )
+        .await?;
+        assert_eq!(search_response.num_hits, 1);
+        assert_eq!(num_cached_files(), 0);
+    }
+    {
+        let searcher_context = Arc::new(SearcherContext::with_disk_cache(
+            SearcherConfig::default(),
+            Some(disk_cache),
+        ));
+        let search_response = leaf_search(
+            searcher_context,
+            &request,
+            test_sandbox.storage(),
+            &splits_offsets,
+            test_sandbox.doc_mapper(),
+            true,
+        )
+        .await?;
+        assert_eq!(search_response.num_hits, 1);
+        assert!(num_cached_files() > 0);
+    }
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_search_dynamic_mode_expand_dots() -> anyhow::Result<()> {
     let doc_mapping_yaml = r#"
@@ -844,7 +1158,7 @@ fn test_convert_leaf_hit_aux(
         serde_json::from_value(default_doc_mapper_json).unwrap();
     let named_field_doc = json_to_named_field_doc(document_json);
     let hit_json_str =
-        convert_document_to_json_string(named_field_doc, &default_doc_mapper).unwrap();
+        convert_document_to_json_string(named_field_doc, &default_doc_mapper, None).unwrap();
     let hit_json: JsonValue = serde_json::from_str(&hit_json_str).unwrap();
     assert_eq!(hit_json, expected_hit_json);
 }
@@ -1355,6 +1669,8 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
             split_id: split_meta.split_id().to_string(),
             split_footer_start: split_meta.split_metadata.footer_offsets.start,
             split_footer_end: split_meta.split_metadata.footer_offsets.end,
+            timestamp_start: None,
+            timestamp_end: None,
         })
         .collect();
     let searcher_context = Arc::new(SearcherContext::new(SearcherConfig::default()));
@@ -1374,6 +1690,8 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
             &request,
             test_sandbox.storage(),
             &splits_offsets,
+            test_sandbox.doc_mapper(),
+            true,
         )
         .await
         .unwrap();
@@ -1395,6 +1713,8 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
             &request,
             test_sandbox.storage(),
             &splits_offsets,
+            test_sandbox.doc_mapper(),
+            true,
         )
         .await
         .unwrap();
@@ -1416,6 +1736,8 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
             &request,
             test_sandbox.storage(),
             &splits_offsets,
+            test_sandbox.doc_mapper(),
+            true,
         )
         .await
         .unwrap();
@@ -1437,6 +1759,8 @@ async fn test_single_node_list_terms() -> anyhow::Result<()> {
             &request,
             test_sandbox.storage(),
             &splits_offsets,
+            test_sandbox.doc_mapper(),
+            true,
         )
         .await
         .unwrap();
@@ -0,0 +1,133 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use warp::{Filter, Rejection};
+
+use super::api_specs::{self, SearchQueryParams};
+use super::{from_simple_list, to_simple_list, SimpleList};
+
+#[serde_with::skip_serializing_none]
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct FieldCapabilityQueryParams {
+    #[serde(serialize_with = "to_simple_list")]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[serde(default)]
+    pub fields: Option<Vec<String>>,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct CatIndicesQueryParams {
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub v: Option<bool>,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct CountQueryParams {
+    #[serde(default)]
+    pub q: Option<String>,
+}
+
+#[serde_with::skip_serializing_none]
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct ExplainQueryParams {
+    #[serde(default)]
+    pub q: Option<String>,
+}
+
+/// Same as the generated `_search` filter, with the request body.
+pub(crate) fn elastic_search_filter(
+) -> impl Filter<Extract = (SearchQueryParams, Bytes), Error = Rejection> + Clone {
+    api_specs::elastic_search_filter().and(warp::body::bytes())
+}
+
+/// Same as the generated `{index}/_search` filter, with the request body.
+pub(crate) fn elastic_index_search_filter(
+) -> impl Filter<Extract = (SimpleList, SearchQueryParams, Bytes), Error = Rejection> + Clone {
+    api_specs::elastic_index_search_filter().and(warp::body::bytes())
+}
+
+#[utoipa::path(get, tag = "Search", path = "/{index}/_mapping")]
+pub(crate) fn elastic_index_mapping_filter(
+) -> impl Filter<Extract = (SimpleList,), Error = Rejection> + Clone {
+    warp::path!("_elastic" / SimpleList / "_mapping").and(warp::get())
+}
+
+#[utoipa::path(get, tag = "Search", path = "/{index}/_field_caps")]
+pub(crate) fn elastic_index_field_capabilities_filter(
+) -> impl Filter<Extract = (SimpleList, FieldCapabilityQueryParams), Error = Rejection> + Clone {
+    warp::path!("_elastic" / SimpleList / "_field_caps")
+        .and(warp::get().or(warp::post()).unify())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
+#[utoipa::path(get, tag = "Search", path = "/_cat/indices")]
+pub(crate) fn elastic_cat_indices_filter(
+) -> impl Filter<Extract = (CatIndicesQueryParams,), Error = Rejection> + Clone {
+    warp::path!("_elastic" / "_cat" / "indices")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
+#[utoipa::path(get, tag = "Search", path = "/_cat/indices/{index}")]
+pub(crate) fn elastic_cat_index_filter(
+) -> impl Filter<Extract = (SimpleList, CatIndicesQueryParams), Error = Rejection> + Clone {
+    warp::path!("_elastic" / "_cat" / "indices" / SimpleList)
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
+#[utoipa::path(post, tag = "Search", path = "/_msearch")]
+pub(crate) fn elastic_multi_search_filter(
+) -> impl Filter<Extract = (Bytes,), Error = Rejection> + Clone {
+    warp::path!("_elastic" / "_msearch")
+        .and(warp::post())
+        .and(warp::body::bytes())
+}
+
+#[utoipa::path(post, tag = "Search", path = "/{index}/_msearch")]
+pub(crate) fn elastic_index_multi_search_filter(
+) -> impl Filter<Extract = (SimpleList, Bytes), Error = Rejection> + Clone {
+    warp::path!("_elastic" / SimpleList / "_msearch")
+        .and(warp::post())
+        .and(warp::body::bytes())
+}
+
+#[utoipa::path(get, tag = "Search", path = "/{index}/_count")]
+pub(crate) fn elastic_index_count_filter(
+) -> impl Filter<Extract = (SimpleList, CountQueryParams, Bytes), Error = Rejection> + Clone {
+    warp::path!("_elastic" / SimpleList / "_count")
+        .and(warp::get().or(warp::post()).unify())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(warp::body::bytes())
+}
+
+#[utoipa::path(get, tag = "Search", path = "/{index}/_explain/{id}")]
+pub(crate) fn elastic_index_explain_filter(
+) -> impl Filter<Extract = (String, String, ExplainQueryParams, Bytes), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_explain" / String)
+        .and(warp::get().or(warp::post()).unify())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(warp::body::bytes())
+}
@@ -18,17 +18,22 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod api_specs;
+mod filter;
+mod model;
 mod rest_handler;
 
 use std::sync::Arc;
 
 pub(crate) use quickwit_common::simple_list::{from_simple_list, to_simple_list, SimpleList};
+use quickwit_metastore::Metastore;
 use quickwit_search::SearchService;
 use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
 
 use crate::elastic_search_api::rest_handler::{
-    es_compat_index_search_handler, es_compat_search_handler,
+    es_compat_cat_indices_handler, es_compat_index_count_handler, es_compat_index_explain_handler,
+    es_compat_index_field_capabilities_handler, es_compat_index_mapping_handler,
+    es_compat_index_search_handler, es_compat_multi_search_handler, es_compat_search_handler,
 };
 
 /// Setup Elasticsearch API handlers
@@ -37,9 +42,18 @@ use crate::elastic_search_api::rest_handler::{
 /// should be registered.
 pub fn elastic_api_handlers(
     search_service: Arc<dyn SearchService>,
+    metastore: Arc<dyn Metastore>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    es_compat_search_handler(search_service.clone())
+    es_compat_search_handler(search_service.clone(), metastore.clone())
         .or(es_compat_index_search_handler(search_service.clone()))
+        .or(es_compat_multi_search_handler(search_service.clone()))
+        .or(es_compat_index_count_handler(search_service.clone()))
+        .or(es_compat_index_explain_handler(search_service.clone()))
+        .or(es_compat_index_mapping_handler(metastore.clone()))
+        .or(es_compat_index_field_capabilities_handler(
+            metastore.clone(),
+        ))
+        .or(es_compat_cat_indices_handler(metastore))
     // Register newly created handlers here.
 }
 
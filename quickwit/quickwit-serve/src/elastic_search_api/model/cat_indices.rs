@@ -0,0 +1,156 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_metastore::{IndexMetadata, Split};
+use serde::{Deserialize, Serialize};
+
+/// Row of the `_cat/indices` endpoint.
+///
+/// Like in Elasticsearch, all the values are strings. Quickwit indexes do not have shards nor
+/// replicas in the Elasticsearch sense, so they are reported as having a single primary shard.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ElasticCatIndexEntry {
+    pub health: String,
+    pub status: String,
+    pub index: String,
+    pub pri: String,
+    pub rep: String,
+    #[serde(rename = "docs.count")]
+    pub docs_count: String,
+    #[serde(rename = "docs.deleted")]
+    pub docs_deleted: String,
+    /// Size of the published splits, in bytes.
+    #[serde(rename = "store.size")]
+    pub store_size: String,
+    #[serde(rename = "pri.store.size")]
+    pub pri_store_size: String,
+}
+
+impl ElasticCatIndexEntry {
+    /// Builds the entry of an index from its metadata and its published splits.
+    pub fn new(index_metadata: &IndexMetadata, published_splits: &[Split]) -> Self {
+        let num_docs: usize = published_splits
+            .iter()
+            .map(|split| split.split_metadata.num_docs)
+            .sum();
+        let size_in_bytes: u64 = published_splits
+            .iter()
+            .map(|split| split.split_metadata.footer_offsets.end)
+            .sum();
+        ElasticCatIndexEntry {
+            health: "green".to_string(),
+            status: "open".to_string(),
+            index: index_metadata.index_id().to_string(),
+            pri: "1".to_string(),
+            rep: "0".to_string(),
+            docs_count: num_docs.to_string(),
+            docs_deleted: "0".to_string(),
+            store_size: size_in_bytes.to_string(),
+            pri_store_size: size_in_bytes.to_string(),
+        }
+    }
+
+    const HEADERS: [&str; 9] = [
+        "health",
+        "status",
+        "index",
+        "pri",
+        "rep",
+        "docs.count",
+        "docs.deleted",
+        "store.size",
+        "pri.store.size",
+    ];
+
+    fn values(&self) -> [&str; 9] {
+        [
+            &self.health,
+            &self.status,
+            &self.index,
+            &self.pri,
+            &self.rep,
+            &self.docs_count,
+            &self.docs_deleted,
+            &self.store_size,
+            &self.pri_store_size,
+        ]
+    }
+}
+
+/// Formats the entries as an aligned text table, which is the default format of the `_cat` APIs.
+pub fn format_cat_indices_table(entries: &[ElasticCatIndexEntry], with_headers: bool) -> String {
+    let mut rows: Vec<[&str; 9]> = Vec::with_capacity(entries.len() + 1);
+    if with_headers {
+        rows.push(ElasticCatIndexEntry::HEADERS);
+    }
+    rows.extend(entries.iter().map(ElasticCatIndexEntry::values));
+    let mut column_widths = [0; 9];
+    for row in &rows {
+        for (column_width, value) in column_widths.iter_mut().zip(row.iter()) {
+            *column_width = (*column_width).max(value.len());
+        }
+    }
+    let mut table = String::new();
+    for row in &rows {
+        let line = row
+            .iter()
+            .zip(column_widths.iter())
+            .map(|(value, column_width)| format!("{value:<column_width$}"))
+            .collect::<Vec<String>>()
+            .join(" ");
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_metastore::{IndexMetadata, Split, SplitMetadata, SplitState};
+
+    use super::{format_cat_indices_table, ElasticCatIndexEntry};
+
+    #[test]
+    fn test_cat_indices_entry() {
+        let index_metadata = IndexMetadata::for_test("my-index", "ram:///indexes/my-index");
+        let split = Split {
+            split_state: SplitState::Published,
+            split_metadata: SplitMetadata {
+                num_docs: 10,
+                footer_offsets: 1000..2000,
+                ..Default::default()
+            },
+            update_timestamp: 0,
+            publish_timestamp: None,
+        };
+        let entry = ElasticCatIndexEntry::new(&index_metadata, &[split.clone(), split]);
+        assert_eq!(entry.docs_count, "20");
+        assert_eq!(entry.store_size, "4000");
+
+        let entry_json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(entry_json["index"], "my-index");
+        assert_eq!(entry_json["docs.count"], "20");
+
+        let table = format_cat_indices_table(&[entry], true);
+        let mut lines = table.lines();
+        assert!(lines.next().unwrap().starts_with("health status index"));
+        assert!(lines.next().unwrap().starts_with("green  open   my-index"));
+        assert!(lines.next().is_none());
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use quickwit_common::field_matches_pattern;
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Schema;
+
+use super::mappings::{elastic_field_type, is_internal_field, split_field_path};
+
+/// Response of the `_field_caps` endpoint.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct FieldCapabilityResponse {
+    pub indices: Vec<String>,
+    /// Capabilities of each field, keyed by field name and then by field type.
+    pub fields: BTreeMap<String, BTreeMap<String, FieldCapabilityEntry>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct FieldCapabilityEntry {
+    #[serde(rename = "type")]
+    pub field_type: String,
+    pub metadata_field: bool,
+    pub searchable: bool,
+    pub aggregatable: bool,
+    /// Indexes in which the field has this type. Omitted if the field has this type in all the
+    /// requested indexes.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indices: Option<Vec<String>>,
+}
+
+impl FieldCapabilityResponse {
+    /// Merges the capabilities of the fields of an index matching one of the `field_patterns`.
+    pub fn add_index(&mut self, index_id: &str, schema: &Schema, field_patterns: &[String]) {
+        self.indices.push(index_id.to_string());
+        for (_field, field_entry) in schema.fields() {
+            if is_internal_field(field_entry.name()) {
+                continue;
+            }
+            let field_name = split_field_path(field_entry.name()).join(".");
+            if !field_patterns
+                .iter()
+                .any(|field_pattern| field_matches_pattern(&field_name, field_pattern))
+            {
+                continue;
+            }
+            let field_type = elastic_field_type(field_entry);
+            let field_capability_entry = self
+                .fields
+                .entry(field_name)
+                .or_default()
+                .entry(field_type.to_string())
+                .or_insert_with(|| FieldCapabilityEntry {
+                    field_type: field_type.to_string(),
+                    metadata_field: false,
+                    searchable: true,
+                    aggregatable: true,
+                    indices: Some(Vec::new()),
+                });
+            // A field is only searchable (resp. aggregatable) if it is in all indexes.
+            field_capability_entry.searchable &= field_entry.is_indexed();
+            field_capability_entry.aggregatable &= field_entry.is_fast();
+            if let Some(indices) = field_capability_entry.indices.as_mut() {
+                indices.push(index_id.to_string());
+            }
+        }
+    }
+
+    /// Drops the `indices` of the entries that cover all the indexes, like Elasticsearch does.
+    pub fn finalize(mut self) -> Self {
+        let num_indices = self.indices.len();
+        for field_capability_entry in self
+            .fields
+            .values_mut()
+            .flat_map(|field_types| field_types.values_mut())
+        {
+            if field_capability_entry
+                .indices
+                .as_ref()
+                .map(|indices| indices.len() == num_indices)
+                .unwrap_or(false)
+            {
+                field_capability_entry.indices = None;
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_doc_mapper::DocMapper;
+
+    use super::FieldCapabilityResponse;
+
+    #[test]
+    fn test_field_capability_response() {
+        let doc_mapper = quickwit_doc_mapper::default_doc_mapper_for_test();
+        let schema = doc_mapper.schema();
+        let mut field_capability_response = FieldCapabilityResponse::default();
+        field_capability_response.add_index("index-1", &schema, &["*".to_string()]);
+        field_capability_response.add_index("index-2", &schema, &["body".to_string()]);
+        let field_capability_response = field_capability_response.finalize();
+        assert_eq!(field_capability_response.indices, ["index-1", "index-2"]);
+
+        let body_entry = &field_capability_response.fields["body"]["text"];
+        assert!(body_entry.searchable);
+        assert!(!body_entry.aggregatable);
+        assert!(body_entry.indices.is_none());
+
+        let timestamp_entry = &field_capability_response.fields["timestamp"]["date"];
+        assert!(timestamp_entry.aggregatable);
+        assert_eq!(timestamp_entry.indices.as_deref().unwrap(), ["index-1"]);
+        assert!(!field_capability_response.fields.contains_key("_source"));
+    }
+}
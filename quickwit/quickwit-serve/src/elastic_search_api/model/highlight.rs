@@ -0,0 +1,171 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use quickwit_proto::HighlightSpec;
+use quickwit_search::SearchError;
+use serde::Deserialize;
+
+// Unlike Quickwit's search API, Elasticsearch highlights terms with `<em>` by default.
+const DEFAULT_PRE_TAG: &str = "<em>";
+const DEFAULT_POST_TAG: &str = "</em>";
+
+/// Highlighting options. Quickwit only supports them at the top level of the `highlight`
+/// clause, so the options of each field must be empty.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ElasticHighlightOptions {}
+
+/// The `fields` of a `highlight` clause: `{"title": {}}` or `[{"title": {}}]`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ElasticHighlightFields {
+    Fields(BTreeMap<String, ElasticHighlightOptions>),
+    FieldsList(Vec<BTreeMap<String, ElasticHighlightOptions>>),
+}
+
+impl Default for ElasticHighlightFields {
+    fn default() -> Self {
+        ElasticHighlightFields::Fields(BTreeMap::new())
+    }
+}
+
+/// The `highlight` clause of a search request body.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ElasticHighlight {
+    #[serde(default)]
+    pub fields: ElasticHighlightFields,
+    #[serde(default)]
+    pub fragment_size: Option<u32>,
+    #[serde(default)]
+    pub number_of_fragments: Option<u32>,
+    #[serde(default)]
+    pub pre_tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub post_tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub require_field_match: Option<bool>,
+}
+
+impl ElasticHighlight {
+    /// Returns the fields to highlight and the highlighting options.
+    pub fn into_snippet_fields_and_spec(self) -> Result<(Vec<String>, HighlightSpec), SearchError> {
+        let snippet_fields = match self.fields {
+            ElasticHighlightFields::Fields(fields) => fields.into_keys().collect(),
+            ElasticHighlightFields::FieldsList(fields_list) => fields_list
+                .into_iter()
+                .flat_map(|fields| fields.into_keys())
+                .collect(),
+        };
+        let highlight_spec = HighlightSpec {
+            fragment_size: self.fragment_size,
+            number_of_fragments: self.number_of_fragments,
+            pre_tag: Some(single_tag(self.pre_tags, "pre_tags", DEFAULT_PRE_TAG)?),
+            post_tag: Some(single_tag(self.post_tags, "post_tags", DEFAULT_POST_TAG)?),
+            require_field_match: self.require_field_match,
+        };
+        Ok((snippet_fields, highlight_spec))
+    }
+}
+
+/// Quickwit highlights all the terms with the same tags, so at most one tag is supported.
+fn single_tag(
+    tags_opt: Option<Vec<String>>,
+    param_name: &str,
+    default_tag: &str,
+) -> Result<String, SearchError> {
+    let Some(mut tags) = tags_opt else {
+        return Ok(default_tag.to_string());
+    };
+    if tags.len() > 1 {
+        return Err(SearchError::InvalidArgument(format!(
+            "Highlighting supports a single tag in `{param_name}`, got {}.",
+            tags.len()
+        )));
+    }
+    Ok(tags.pop().unwrap_or_else(|| default_tag.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::HighlightSpec;
+
+    use super::ElasticHighlight;
+
+    #[test]
+    fn test_elastic_highlight_into_snippet_fields_and_spec() {
+        let highlight: ElasticHighlight = serde_json::from_str(
+            r#"{
+                "fields": { "title": {}, "body": {} },
+                "fragment_size": 50,
+                "number_of_fragments": 2,
+                "pre_tags": ["<em>"],
+                "post_tags": ["</em>"],
+                "require_field_match": false
+            }"#,
+        )
+        .unwrap();
+        let (snippet_fields, highlight_spec) = highlight.into_snippet_fields_and_spec().unwrap();
+        assert_eq!(
+            snippet_fields,
+            vec!["body".to_string(), "title".to_string()]
+        );
+        assert_eq!(
+            highlight_spec,
+            HighlightSpec {
+                fragment_size: Some(50),
+                number_of_fragments: Some(2),
+                pre_tag: Some("<em>".to_string()),
+                post_tag: Some("</em>".to_string()),
+                require_field_match: Some(false),
+            }
+        );
+        let highlight: ElasticHighlight =
+            serde_json::from_str(r#"{ "fields": [{ "title": {} }, { "body": {} }] }"#).unwrap();
+        let (snippet_fields, highlight_spec) = highlight.into_snippet_fields_and_spec().unwrap();
+        assert_eq!(
+            snippet_fields,
+            vec!["title".to_string(), "body".to_string()]
+        );
+        assert_eq!(
+            highlight_spec,
+            HighlightSpec {
+                pre_tag: Some("<em>".to_string()),
+                post_tag: Some("</em>".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_elastic_highlight_invalid() {
+        let highlight: ElasticHighlight = serde_json::from_str(
+            r#"{ "fields": { "title": {} }, "pre_tags": ["<em>", "<strong>"] }"#,
+        )
+        .unwrap();
+        assert!(highlight.into_snippet_fields_and_spec().is_err());
+        assert!(serde_json::from_str::<ElasticHighlight>(
+            r#"{ "fields": { "title": { "fragment_size": 10 } } }"#
+        )
+        .is_err());
+        assert!(serde_json::from_str::<ElasticHighlight>(r#"{ "type": "unified" }"#).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use quickwit_doc_mapper::{ModeType, DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME};
+use serde::{Deserialize, Serialize};
+use tantivy::schema::{FieldEntry, FieldType, Schema};
+
+/// Response of the `_mapping` endpoint, keyed by index ID.
+pub type ElasticMappingsResponse = BTreeMap<String, ElasticIndexMappings>;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ElasticIndexMappings {
+    pub mappings: ElasticMappings,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ElasticMappings {
+    pub dynamic: String,
+    pub properties: BTreeMap<String, ElasticFieldMapping>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ElasticFieldMapping {
+    Object {
+        properties: BTreeMap<String, ElasticFieldMapping>,
+    },
+    Leaf {
+        #[serde(rename = "type")]
+        field_type: String,
+    },
+}
+
+impl ElasticMappings {
+    /// Builds the Elasticsearch mappings of an index from its tantivy schema. Object fields are
+    /// rebuilt from the field paths.
+    pub fn from_schema(schema: &Schema, mode: &ModeType) -> Self {
+        let dynamic = match mode {
+            ModeType::Lenient => "false",
+            ModeType::Strict => "strict",
+            ModeType::Dynamic => "true",
+        };
+        let mut properties = BTreeMap::new();
+        for (_field, field_entry) in schema.fields() {
+            if is_internal_field(field_entry.name()) {
+                continue;
+            }
+            let field_path = split_field_path(field_entry.name());
+            insert_field_mapping(
+                &mut properties,
+                &field_path,
+                elastic_field_type(field_entry),
+            );
+        }
+        ElasticMappings {
+            dynamic: dynamic.to_string(),
+            properties,
+        }
+    }
+}
+
+fn insert_field_mapping(
+    properties: &mut BTreeMap<String, ElasticFieldMapping>,
+    field_path: &[String],
+    field_type: &str,
+) {
+    let Some((field_name, sub_path)) = field_path.split_first() else {
+        return;
+    };
+    if sub_path.is_empty() {
+        properties.insert(
+            field_name.clone(),
+            ElasticFieldMapping::Leaf {
+                field_type: field_type.to_string(),
+            },
+        );
+        return;
+    }
+    let object_mapping =
+        properties
+            .entry(field_name.clone())
+            .or_insert_with(|| ElasticFieldMapping::Object {
+                properties: BTreeMap::new(),
+            });
+    if let ElasticFieldMapping::Object { properties } = object_mapping {
+        insert_field_mapping(properties, sub_path, field_type);
+    }
+}
+
+/// Returns `true` for the fields Quickwit adds to the schema for its own needs.
+pub(crate) fn is_internal_field(field_name: &str) -> bool {
+    field_name == SOURCE_FIELD_NAME || field_name == DYNAMIC_FIELD_NAME
+}
+
+/// Splits a schema field name into its path, unescaping the dots that are part of a field name.
+pub(crate) fn split_field_path(field_name: &str) -> Vec<String> {
+    let mut field_path = Vec::new();
+    let mut current = String::new();
+    let mut chars = field_name.chars();
+    while let Some(chr) = chars.next() {
+        match chr {
+            '\\' => {
+                if let Some(escaped_chr) = chars.next() {
+                    current.push(escaped_chr);
+                }
+            }
+            '.' => field_path.push(std::mem::take(&mut current)),
+            _ => current.push(chr),
+        }
+    }
+    field_path.push(current);
+    field_path
+}
+
+/// Maps a tantivy field to the closest Elasticsearch field type.
+pub(crate) fn elastic_field_type(field_entry: &FieldEntry) -> &'static str {
+    match field_entry.field_type() {
+        FieldType::Str(text_options) => {
+            let is_raw = text_options
+                .get_indexing_options()
+                .map(|indexing_options| indexing_options.tokenizer() == "raw")
+                .unwrap_or(false);
+            if is_raw {
+                "keyword"
+            } else {
+                "text"
+            }
+        }
+        FieldType::U64(_) => "unsigned_long",
+        FieldType::I64(_) => "long",
+        FieldType::F64(_) => "double",
+        FieldType::Bool(_) => "boolean",
+        FieldType::Date(_) => "date",
+        FieldType::Facet(_) => "keyword",
+        FieldType::Bytes(_) => "binary",
+        FieldType::JsonObject(_) => "object",
+        FieldType::IpAddr(_) => "ip",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_doc_mapper::{DocMapper, ModeType};
+
+    use super::{split_field_path, ElasticMappings};
+
+    #[test]
+    fn test_split_field_path() {
+        assert_eq!(split_field_path("body"), ["body"]);
+        assert_eq!(
+            split_field_path("attributes.server"),
+            ["attributes", "server"]
+        );
+        assert_eq!(
+            split_field_path(r#"attributes.server\.status"#),
+            ["attributes", "server.status"]
+        );
+    }
+
+    #[test]
+    fn test_mappings_from_schema() {
+        let doc_mapper = quickwit_doc_mapper::default_doc_mapper_for_test();
+        let mappings = ElasticMappings::from_schema(&doc_mapper.schema(), &ModeType::Lenient);
+        let mappings_json = serde_json::to_value(&mappings).unwrap();
+        assert_eq!(mappings_json["dynamic"], "false");
+        assert_eq!(mappings_json["properties"]["body"]["type"], "text");
+        assert_eq!(mappings_json["properties"]["timestamp"]["type"], "date");
+        assert!(mappings_json["properties"].get("_source").is_none());
+    }
+}
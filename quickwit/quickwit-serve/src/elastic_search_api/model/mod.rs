@@ -0,0 +1,44 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod cat_indices;
+mod count_response;
+mod explain_response;
+mod field_capability;
+mod highlight;
+mod mappings;
+mod multi_search;
+mod search_body;
+mod sort;
+mod source_filter;
+
+pub use cat_indices::{format_cat_indices_table, ElasticCatIndexEntry};
+pub use count_response::ElasticCountResponse;
+pub use explain_response::ElasticExplainResponse;
+pub use field_capability::{FieldCapabilityEntry, FieldCapabilityResponse};
+pub use highlight::ElasticHighlight;
+pub use mappings::{
+    ElasticFieldMapping, ElasticIndexMappings, ElasticMappings, ElasticMappingsResponse,
+};
+pub use multi_search::{
+    ElasticErrorCause, MultiSearchHeader, MultiSearchResponse, MultiSearchSingleResponse,
+};
+pub use search_body::SearchBody;
+pub use sort::{parse_sort_param, ElasticSort};
+pub use source_filter::ElasticSourceFilter;
@@ -0,0 +1,151 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use elasticsearch_dsl::search::SearchResponse as ElasticSearchResponse;
+use quickwit_proto::ServiceError;
+use quickwit_search::SearchError;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Header line of a `_msearch` request. The fields of the header that are not supported are
+/// ignored.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+pub struct MultiSearchHeader {
+    /// Overrides the indexes of the request path.
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_indexes")]
+    pub index: Option<Vec<String>>,
+}
+
+/// The indexes can be passed as a comma separated string or as an array of strings.
+fn deserialize_indexes<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where D: Deserializer<'de> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrArray {
+        String(String),
+        Array(Vec<String>),
+    }
+    let indexes_opt =
+        Option::<StringOrArray>::deserialize(deserializer)?.map(|indexes| match indexes {
+            StringOrArray::String(indexes) => indexes
+                .split(',')
+                .filter(|index_id| !index_id.is_empty())
+                .map(|index_id| index_id.to_string())
+                .collect(),
+            StringOrArray::Array(indexes) => indexes,
+        });
+    Ok(indexes_opt)
+}
+
+/// Response of the `_msearch` endpoint. `responses` are in the order of the requests.
+#[derive(Debug, Serialize)]
+pub struct MultiSearchResponse {
+    pub took: u64,
+    pub responses: Vec<MultiSearchSingleResponse>,
+}
+
+/// Outcome of one of the searches of a `_msearch` request: either a search response or an
+/// error, along with its HTTP status.
+#[derive(Debug, Serialize)]
+pub struct MultiSearchSingleResponse {
+    pub status: u16,
+    #[serde(flatten)]
+    pub response: Option<ElasticSearchResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ElasticErrorCause>,
+}
+
+impl From<Result<ElasticSearchResponse, SearchError>> for MultiSearchSingleResponse {
+    fn from(result: Result<ElasticSearchResponse, SearchError>) -> Self {
+        match result {
+            Ok(response) => MultiSearchSingleResponse {
+                status: 200,
+                response: Some(response),
+                error: None,
+            },
+            Err(error) => MultiSearchSingleResponse {
+                status: error.status_code().to_http_status_code().as_u16(),
+                response: None,
+                error: Some(ElasticErrorCause::from(error)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ElasticErrorCause {
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub reason: String,
+}
+
+impl From<SearchError> for ElasticErrorCause {
+    fn from(error: SearchError) -> Self {
+        let error_type = match &error {
+            SearchError::IndexDoesNotExist { .. } => "index_not_found_exception",
+            SearchError::InvalidQuery(_) => "query_shard_exception",
+            SearchError::InvalidArgument(_) | SearchError::InvalidAggregationRequest(_) => {
+                "illegal_argument_exception"
+            }
+            SearchError::InternalError(_) | SearchError::StorageResolverError(_) => "exception",
+        };
+        ElasticErrorCause {
+            error_type: error_type.to_string(),
+            reason: error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_search::SearchError;
+
+    use super::{MultiSearchHeader, MultiSearchSingleResponse};
+
+    #[test]
+    fn test_multi_search_header_serde() {
+        let header: MultiSearchHeader = serde_json::from_str("{}").unwrap();
+        assert_eq!(header.index, None);
+        let header: MultiSearchHeader =
+            serde_json::from_str(r#"{"index": "index-1,index-2", "preference": "local"}"#).unwrap();
+        assert_eq!(
+            header.index.unwrap(),
+            ["index-1".to_string(), "index-2".to_string()]
+        );
+        let header: MultiSearchHeader = serde_json::from_str(r#"{"index": ["index-1"]}"#).unwrap();
+        assert_eq!(header.index.unwrap(), ["index-1".to_string()]);
+    }
+
+    #[test]
+    fn test_multi_search_single_response_serialize() {
+        let response = MultiSearchSingleResponse::from(Ok(Default::default()));
+        let response_json = serde_json::to_value(response).unwrap();
+        assert_eq!(response_json["status"], 200);
+        assert!(response_json.get("hits").is_some());
+        assert!(response_json.get("error").is_none());
+
+        let response = MultiSearchSingleResponse::from(Err(SearchError::IndexDoesNotExist {
+            index_id: "my-index".to_string(),
+        }));
+        let response_json = serde_json::to_value(response).unwrap();
+        assert_eq!(response_json["status"], 404);
+        assert_eq!(response_json["error"]["type"], "index_not_found_exception");
+        assert!(response_json.get("hits").is_none());
+    }
+}
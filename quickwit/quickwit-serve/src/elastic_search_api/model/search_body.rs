@@ -0,0 +1,93 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_search::QueryDsl;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use super::{ElasticHighlight, ElasticSort, ElasticSourceFilter};
+use crate::elastic_search_api::TrackTotalHits;
+
+/// Body of an Elasticsearch search request.
+///
+/// Parameters that are not supported are rejected rather than silently ignored.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SearchBody {
+    #[serde(default)]
+    pub from: Option<u64>,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub query: Option<QueryDsl>,
+    /// Accepted for compatibility: Quickwit always counts the number of hits accurately.
+    #[serde(default)]
+    pub track_total_hits: Option<TrackTotalHits>,
+    #[serde(default)]
+    pub sort: Option<ElasticSort>,
+    /// The `sort` values of the last hit of the previous page.
+    #[serde(default)]
+    pub search_after: Option<Vec<JsonValue>>,
+    /// Whether to return the explanation of the score of each hit.
+    #[serde(default)]
+    pub explain: Option<bool>,
+    #[serde(default)]
+    pub highlight: Option<ElasticHighlight>,
+    #[serde(default)]
+    #[serde(rename = "_source")]
+    pub source: Option<ElasticSourceFilter>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value as JsonValue;
+
+    use super::SearchBody;
+
+    #[test]
+    fn test_search_body_deserialize() {
+        let search_body_json = r#"{
+            "from": 10,
+            "size": 20,
+            "query": { "match": { "body": "hello" } },
+            "track_total_hits": true,
+            "search_after": [10, "split", 0, 3]
+        }"#;
+        let search_body: SearchBody = serde_json::from_str(search_body_json).unwrap();
+        assert_eq!(search_body.from, Some(10));
+        assert_eq!(search_body.size, Some(20));
+        assert!(search_body.query.is_some());
+        assert_eq!(
+            search_body.search_after,
+            Some(vec![
+                JsonValue::from(10),
+                JsonValue::from("split"),
+                JsonValue::from(0),
+                JsonValue::from(3)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_search_body_unsupported_parameter() {
+        let search_body_json = r#"{ "script_fields": {} }"#;
+        let error = serde_json::from_str::<SearchBody>(search_body_json).unwrap_err();
+        assert!(error.to_string().contains("unknown field `script_fields`"));
+    }
+}
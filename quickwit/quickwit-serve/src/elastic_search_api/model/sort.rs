@@ -0,0 +1,213 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use quickwit_proto::{SortField, SortOrder};
+use quickwit_search::SearchError;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ElasticSortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum ElasticSortMissing {
+    #[serde(rename = "_first")]
+    First,
+    #[serde(rename = "_last")]
+    Last,
+}
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ElasticSortOptions {
+    #[serde(default)]
+    pub order: Option<ElasticSortOrder>,
+    #[serde(default)]
+    pub missing: Option<ElasticSortMissing>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ElasticSortOrderOrOptions {
+    Order(ElasticSortOrder),
+    Options(ElasticSortOptions),
+}
+
+/// A single sort clause: `"field"`, `{"field": "desc"}` or
+/// `{"field": {"order": "desc", "missing": "_first"}}`.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ElasticSortClause {
+    FieldName(String),
+    Field(BTreeMap<String, ElasticSortOrderOrOptions>),
+}
+
+/// The `sort` parameter of a search request body, made of one or several sort clauses.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ElasticSort {
+    Clauses(Vec<ElasticSortClause>),
+    Clause(ElasticSortClause),
+}
+
+impl ElasticSort {
+    pub fn into_sort_fields(self) -> Result<Vec<SortField>, SearchError> {
+        let clauses = match self {
+            ElasticSort::Clauses(clauses) => clauses,
+            ElasticSort::Clause(clause) => vec![clause],
+        };
+        clauses.into_iter().map(sort_field_from_clause).collect()
+    }
+}
+
+fn sort_field_from_clause(clause: ElasticSortClause) -> Result<SortField, SearchError> {
+    let (field_name, options) = match clause {
+        ElasticSortClause::FieldName(field_name) => (field_name, ElasticSortOptions::default()),
+        ElasticSortClause::Field(field_options) => {
+            if field_options.len() != 1 {
+                return Err(SearchError::InvalidArgument(format!(
+                    "A sort clause must have exactly one field, got {}.",
+                    field_options.len()
+                )));
+            }
+            let (field_name, order_or_options) = field_options
+                .into_iter()
+                .next()
+                .expect("There should be exactly one field, as checked by the if statement above.");
+            let options = match order_or_options {
+                ElasticSortOrderOrOptions::Order(order) => ElasticSortOptions {
+                    order: Some(order),
+                    missing: None,
+                },
+                ElasticSortOrderOrOptions::Options(options) => options,
+            };
+            (field_name, options)
+        }
+    };
+    Ok(build_sort_field(field_name, options))
+}
+
+/// Parses a `sort` URL parameter item of the form `field` or `field:order`.
+pub fn parse_sort_param(sort_param: &str) -> Result<SortField, SearchError> {
+    let (field_name, order) = match sort_param.rsplit_once(':') {
+        Some((field_name, "asc")) => (field_name, Some(ElasticSortOrder::Asc)),
+        Some((field_name, "desc")) => (field_name, Some(ElasticSortOrder::Desc)),
+        Some(_) => {
+            return Err(SearchError::InvalidArgument(format!(
+                "Invalid sort parameter `{sort_param}`. Expected `field` or `field:asc|desc`."
+            )));
+        }
+        None => (sort_param, None),
+    };
+    let options = ElasticSortOptions {
+        order,
+        missing: None,
+    };
+    Ok(build_sort_field(field_name.to_string(), options))
+}
+
+fn build_sort_field(field_name: String, options: ElasticSortOptions) -> SortField {
+    // Like in Elasticsearch, the score is sorted in descending order by default, and fields in
+    // ascending order.
+    let default_order = if field_name == "_score" {
+        ElasticSortOrder::Desc
+    } else {
+        ElasticSortOrder::Asc
+    };
+    let sort_order = match options.order.unwrap_or(default_order) {
+        ElasticSortOrder::Asc => SortOrder::Asc,
+        ElasticSortOrder::Desc => SortOrder::Desc,
+    };
+    SortField {
+        field_name,
+        sort_order: sort_order as i32,
+        missing_first: options.missing == Some(ElasticSortMissing::First),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_proto::{SortField, SortOrder};
+
+    use super::{parse_sort_param, ElasticSort};
+
+    fn sort_field(field_name: &str, sort_order: SortOrder, missing_first: bool) -> SortField {
+        SortField {
+            field_name: field_name.to_string(),
+            sort_order: sort_order as i32,
+            missing_first,
+        }
+    }
+
+    #[test]
+    fn test_elastic_sort_into_sort_fields() {
+        let sort: ElasticSort = serde_json::from_str(
+            r#"[
+                "service",
+                { "timestamp": "desc" },
+                { "latency": { "order": "desc", "missing": "_first" } },
+                "_score"
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sort.into_sort_fields().unwrap(),
+            vec![
+                sort_field("service", SortOrder::Asc, false),
+                sort_field("timestamp", SortOrder::Desc, false),
+                sort_field("latency", SortOrder::Desc, true),
+                sort_field("_score", SortOrder::Desc, false),
+            ]
+        );
+        let sort: ElasticSort = serde_json::from_str(r#"{ "timestamp": "asc" }"#).unwrap();
+        assert_eq!(
+            sort.into_sort_fields().unwrap(),
+            vec![sort_field("timestamp", SortOrder::Asc, false)]
+        );
+    }
+
+    #[test]
+    fn test_elastic_sort_invalid() {
+        let sort: ElasticSort =
+            serde_json::from_str(r#"{ "timestamp": "asc", "service": "asc" }"#).unwrap();
+        assert!(sort.into_sort_fields().is_err());
+        assert!(serde_json::from_str::<ElasticSort>(r#"{ "timestamp": "up" }"#).is_err());
+        assert!(
+            serde_json::from_str::<ElasticSort>(r#"{ "timestamp": { "mode": "avg" } }"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_sort_param() {
+        assert_eq!(
+            parse_sort_param("service").unwrap(),
+            sort_field("service", SortOrder::Asc, false)
+        );
+        assert_eq!(
+            parse_sort_param("timestamp:desc").unwrap(),
+            sort_field("timestamp", SortOrder::Desc, false)
+        );
+        assert!(parse_sort_param("timestamp:down").is_err());
+    }
+}
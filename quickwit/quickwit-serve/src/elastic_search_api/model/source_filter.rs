@@ -0,0 +1,111 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::Deserialize;
+
+/// The `_source` parameter of a search request: `false`, `"field"`, `["field", ..]` or
+/// `{"includes": [..], "excludes": [..]}`. Field names may contain the `*` wildcard.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ElasticSourceFilter {
+    Enabled(bool),
+    Field(String),
+    Fields(Vec<String>),
+    IncludesExcludes {
+        #[serde(default)]
+        includes: Vec<String>,
+        #[serde(default)]
+        excludes: Vec<String>,
+    },
+}
+
+impl ElasticSourceFilter {
+    /// Parses the `_source` URL parameter, which is either a boolean or a list of fields.
+    pub fn from_param(source_param: Vec<String>) -> Self {
+        match source_param.as_slice() {
+            [value] if value == "true" => ElasticSourceFilter::Enabled(true),
+            [value] if value == "false" => ElasticSourceFilter::Enabled(false),
+            _ => ElasticSourceFilter::Fields(source_param),
+        }
+    }
+
+    /// Returns the field patterns to include and to exclude.
+    pub fn into_includes_excludes(self) -> (Vec<String>, Vec<String>) {
+        match self {
+            ElasticSourceFilter::Enabled(true) => (Vec::new(), Vec::new()),
+            // Quickwit always returns the `_source` of the hits, so it is emptied instead.
+            ElasticSourceFilter::Enabled(false) => (Vec::new(), vec!["*".to_string()]),
+            ElasticSourceFilter::Field(field) => (vec![field], Vec::new()),
+            ElasticSourceFilter::Fields(fields) => (fields, Vec::new()),
+            ElasticSourceFilter::IncludesExcludes { includes, excludes } => (includes, excludes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ElasticSourceFilter;
+
+    fn includes_excludes(source_json: &str) -> (Vec<String>, Vec<String>) {
+        serde_json::from_str::<ElasticSourceFilter>(source_json)
+            .unwrap()
+            .into_includes_excludes()
+    }
+
+    #[test]
+    fn test_elastic_source_filter_deserialize() {
+        assert_eq!(includes_excludes("true"), (Vec::new(), Vec::new()));
+        assert_eq!(
+            includes_excludes("false"),
+            (Vec::new(), vec!["*".to_string()])
+        );
+        assert_eq!(
+            includes_excludes(r#""body""#),
+            (vec!["body".to_string()], Vec::new())
+        );
+        assert_eq!(
+            includes_excludes(r#"["body", "attributes.*"]"#),
+            (
+                vec!["body".to_string(), "attributes.*".to_string()],
+                Vec::new()
+            )
+        );
+        assert_eq!(
+            includes_excludes(
+                r#"{"includes": ["attributes.*"], "excludes": ["attributes.secret"]}"#
+            ),
+            (
+                vec!["attributes.*".to_string()],
+                vec!["attributes.secret".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn test_elastic_source_filter_from_param() {
+        assert_eq!(
+            ElasticSourceFilter::from_param(vec!["false".to_string()]),
+            ElasticSourceFilter::Enabled(false)
+        );
+        assert_eq!(
+            ElasticSourceFilter::from_param(vec!["body".to_string(), "timestamp".to_string()]),
+            ElasticSourceFilter::Fields(vec!["body".to_string(), "timestamp".to_string()])
+        );
+    }
+}
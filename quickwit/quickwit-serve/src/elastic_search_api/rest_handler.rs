@@ -20,30 +20,72 @@
 use std::sync::Arc;
 use std::time::Instant;
 
+use bytes::Bytes;
 use elasticsearch_dsl::search::{Hit as ElasticHit, SearchResponse as ElasticSearchResponse};
 use elasticsearch_dsl::{HitsMetadata, Source, TotalHits, TotalHitsRelation};
+use futures::StreamExt;
+use hyper::header::CONTENT_TYPE;
 use quickwit_common::simple_list::SimpleList;
-use quickwit_proto::SearchResponse;
-use quickwit_search::{SearchError, SearchService};
+use quickwit_config::build_doc_mapper;
+use quickwit_metastore::{IndexMetadata, ListSplitsQuery, Metastore, SplitState};
+use quickwit_proto::{ExplainHitRequest, PartialHit, SearchResponse};
+use quickwit_search::{
+    partial_hit_sorting_key, search_after_from_sort_values, search_after_to_sort_values,
+    SearchError, SearchService,
+};
+use tantivy::schema::Schema;
 use warp::{Filter, Rejection};
 
 use super::api_specs::SearchQueryParams;
-use crate::elastic_search_api::api_specs::{elastic_index_search_filter, elastic_search_filter};
+use super::filter::{
+    elastic_cat_index_filter, elastic_cat_indices_filter, elastic_index_count_filter,
+    elastic_index_explain_filter, elastic_index_field_capabilities_filter,
+    elastic_index_mapping_filter, elastic_index_multi_search_filter, elastic_index_search_filter,
+    elastic_multi_search_filter, elastic_search_filter, CatIndicesQueryParams, CountQueryParams,
+    ExplainQueryParams, FieldCapabilityQueryParams,
+};
+use super::model::{
+    format_cat_indices_table, parse_sort_param, ElasticCatIndexEntry, ElasticCountResponse,
+    ElasticExplainResponse, ElasticIndexMappings, ElasticMappings, ElasticMappingsResponse,
+    ElasticSourceFilter, FieldCapabilityResponse, MultiSearchHeader, MultiSearchResponse,
+    MultiSearchSingleResponse, SearchBody,
+};
 use crate::format::BodyFormat;
 use crate::with_arg;
 
 /// GET or POST _elastic/_search
 pub fn es_compat_search_handler(
-    _search_service: Arc<dyn SearchService>,
+    search_service: Arc<dyn SearchService>,
+    metastore: Arc<dyn Metastore>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    elastic_search_filter().then(|params: SearchQueryParams| async move {
-        // TODO: implement
-        let resp = serde_json::json!({
-            "index": "all indexes",
-            "params": params,
-        });
-        warp::reply::json(&resp)
-    })
+    elastic_search_filter()
+        .and(with_arg(search_service))
+        .and(with_arg(metastore))
+        .then(es_compat_search)
+        .map(|resp| BodyFormat::Json.make_rest_reply(resp))
+}
+
+/// Like Elasticsearch, searching without an index searches all the indexes.
+async fn es_compat_search(
+    search_params: SearchQueryParams,
+    search_body: Bytes,
+    search_service: Arc<dyn SearchService>,
+    metastore: Arc<dyn Metastore>,
+) -> Result<ElasticSearchResponse, SearchError> {
+    let search_body = parse_search_body(&search_body)?;
+    let index_ids: Vec<String> = metastore
+        .list_indexes_metadatas()
+        .await?
+        .into_iter()
+        .map(|index_metadata| index_metadata.index_id().to_string())
+        .collect();
+    es_multi_index_search(
+        index_ids,
+        search_params,
+        search_body,
+        search_service.as_ref(),
+    )
+    .await
 }
 
 /// GET or POST _elastic/{index}/_search
@@ -56,26 +98,197 @@ pub fn es_compat_index_search_handler(
         .map(|resp| BodyFormat::Json.make_rest_reply(resp))
 }
 
+/// Parses the body of a search request. GET requests usually come without a body.
+fn parse_search_body(search_body: &[u8]) -> Result<SearchBody, SearchError> {
+    if search_body.is_empty() {
+        return Ok(SearchBody::default());
+    }
+    serde_json::from_slice(search_body)
+        .map_err(|error| SearchError::InvalidArgument(format!("Invalid search body: {error}")))
+}
+
+fn build_search_request(
+    index_id: String,
+    search_params: SearchQueryParams,
+    search_body: SearchBody,
+) -> Result<quickwit_proto::SearchRequest, SearchError> {
+    if search_params.q.is_some() && search_body.query.is_some() {
+        return Err(SearchError::InvalidArgument(
+            "The `q` parameter and the body `query` are mutually exclusive.".to_string(),
+        ));
+    }
+    let query_dsl = search_body
+        .query
+        .map(|query_dsl| serde_json::to_string(&query_dsl))
+        .transpose()?;
+    let max_hits = search_params
+        .size
+        .map(|size| size.max(0i64) as u64)
+        .or(search_body.size)
+        .unwrap_or(10);
+    let start_offset = search_params
+        .from
+        .map(|from| from.max(0i64) as u64)
+        .or(search_body.from)
+        .unwrap_or(0);
+    let search_after = search_body
+        .search_after
+        .as_deref()
+        .map(search_after_from_sort_values)
+        .transpose()?;
+    if search_after.is_some() && start_offset > 0 {
+        return Err(SearchError::InvalidArgument(
+            "`from` must be 0 when `search_after` is set.".to_string(),
+        ));
+    }
+    let sort_fields = if let Some(sort_params) = &search_params.sort {
+        sort_params
+            .iter()
+            .map(|sort_param| parse_sort_param(sort_param))
+            .collect::<Result<Vec<_>, _>>()?
+    } else if let Some(sort) = search_body.sort {
+        sort.into_sort_fields()?
+    } else {
+        Vec::new()
+    };
+    // The `_source` URL parameters take precedence over the body `_source`.
+    let (source_includes, source_excludes) = if search_params._source.is_some()
+        || search_params._source_includes.is_some()
+        || search_params._source_excludes.is_some()
+    {
+        let (source_includes, source_excludes) = search_params
+            ._source
+            .map(|source_param| {
+                ElasticSourceFilter::from_param(source_param).into_includes_excludes()
+            })
+            .unwrap_or_default();
+        (
+            search_params._source_includes.unwrap_or(source_includes),
+            search_params._source_excludes.unwrap_or(source_excludes),
+        )
+    } else {
+        search_body
+            .source
+            .map(ElasticSourceFilter::into_includes_excludes)
+            .unwrap_or_default()
+    };
+    let (snippet_fields, highlight) = if let Some(highlight) = search_body.highlight {
+        let (snippet_fields, highlight_spec) = highlight.into_snippet_fields_and_spec()?;
+        (snippet_fields, Some(highlight_spec))
+    } else {
+        (Vec::new(), None)
+    };
+    Ok(quickwit_proto::SearchRequest {
+        index_id,
+        query: search_params.q.unwrap_or_else(|| "*".to_string()),
+        query_dsl,
+        max_hits,
+        start_offset,
+        search_after,
+        sort_fields,
+        snippet_fields,
+        highlight,
+        source_includes,
+        source_excludes,
+        explain: search_params
+            .explain
+            .or(search_body.explain)
+            .unwrap_or(false),
+        ..Default::default()
+    })
+}
+
+/// Quickwit searches are scoped to a single index.
+fn single_index_id(indexes: Vec<String>) -> Result<String, SearchError> {
+    if indexes.len() != 1 {
+        let error_msg = format!("Expected exaclty one index, got {indexes:?}.");
+        return Err(SearchError::InvalidArgument(error_msg));
+    }
+    let index_id: String = indexes.into_iter().next().expect(
+        "There should be exactly once index in the list, as checked by the if statement above.",
+    );
+    Ok(index_id)
+}
+
 async fn es_compat_index_search(
     indexes: SimpleList,
     search_params: SearchQueryParams,
+    search_body: Bytes,
     search_service: Arc<dyn SearchService>,
+) -> Result<ElasticSearchResponse, SearchError> {
+    let search_body = parse_search_body(&search_body)?;
+    if indexes.0.len() > 1 {
+        return es_multi_index_search(
+            indexes.0,
+            search_params,
+            search_body,
+            search_service.as_ref(),
+        )
+        .await;
+    }
+    let index_id = single_index_id(indexes.0)?;
+    let search_request = build_search_request(index_id, search_params, search_body)?;
+    es_search(search_request, search_service.as_ref()).await
+}
+
+/// Quickwit searches are scoped to a single index, so each index is searched for its first
+/// `from + size` hits, and the hits are merged in the order of a single index search.
+async fn es_multi_index_search(
+    index_ids: Vec<String>,
+    search_params: SearchQueryParams,
+    search_body: SearchBody,
+    search_service: &dyn SearchService,
 ) -> Result<ElasticSearchResponse, SearchError> {
     let start_instant = Instant::now();
-    if indexes.0.len() != 1 {
-        let error_msg = format!("Expected exaclty one index, got {indexes:?}.");
-        return Err(SearchError::InvalidArgument(error_msg));
+    let search_request = build_search_request(String::new(), search_params, search_body)?;
+    let max_hits = search_request.max_hits;
+    let start_offset = search_request.start_offset;
+
+    let search_futures = index_ids.into_iter().map(|index_id| {
+        let index_search_request = quickwit_proto::SearchRequest {
+            index_id: index_id.clone(),
+            max_hits: start_offset + max_hits,
+            start_offset: 0,
+            ..search_request.clone()
+        };
+        async move {
+            let search_response = search_service.root_search(index_search_request).await?;
+            Ok::<_, SearchError>((index_id, search_response))
+        }
+    });
+    let search_responses = futures::future::try_join_all(search_futures).await?;
+
+    let mut num_hits = 0;
+    let mut hits = Vec::new();
+    for (index_id, search_response) in search_responses {
+        num_hits += search_response.num_hits;
+        hits.extend(
+            search_response
+                .hits
+                .into_iter()
+                .map(|hit| (index_id.clone(), hit)),
+        );
     }
-    let index_id: String = indexes.0.into_iter().next().expect(
-        "There should be exactly once index in the list, as checked by the if statement above.",
-    );
-    let search_request = quickwit_proto::SearchRequest {
-        index_id,
-        query: search_params.q.unwrap_or_else(|| "*".to_string()),
-        max_hits: search_params.size.unwrap_or(10).max(0i64) as u64,
-        start_offset: search_params.from.unwrap_or(0).max(0i64) as u64,
-        ..Default::default()
-    };
+    hits.sort_by_cached_key(|(_, hit)| hit.partial_hit.as_ref().map(partial_hit_sorting_key));
+    let hits = hits
+        .into_iter()
+        .skip(start_offset as usize)
+        .take(max_hits as usize)
+        .map(|(index_id, hit)| ElasticHit {
+            index: index_id,
+            ..convert_hit(hit)
+        })
+        .collect();
+    let mut search_response_rest = build_es_search_response(num_hits, hits);
+    search_response_rest.took = start_instant.elapsed().as_millis() as u32;
+    Ok(search_response_rest)
+}
+
+async fn es_search(
+    search_request: quickwit_proto::SearchRequest,
+    search_service: &dyn SearchService,
+) -> Result<ElasticSearchResponse, SearchError> {
+    let start_instant = Instant::now();
     let search_response: SearchResponse = search_service.root_search(search_request).await?;
     let elapsed = start_instant.elapsed();
     let mut search_response_rest: ElasticSearchResponse =
@@ -84,32 +297,353 @@ async fn es_compat_index_search(
     Ok(search_response_rest)
 }
 
+/// POST _elastic/_msearch and _elastic/{index}/_msearch
+pub fn es_compat_multi_search_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let multi_search_without_index = elastic_multi_search_filter()
+        .map(|payload: Bytes| (SimpleList(Vec::new()), payload))
+        .untuple_one();
+    multi_search_without_index
+        .or(elastic_index_multi_search_filter())
+        .unify()
+        .and(with_arg(search_service))
+        .then(es_compat_multi_search)
+        .map(|resp| BodyFormat::Json.make_rest_reply(resp))
+}
+
+/// Maximum number of searches of a `_msearch` request running concurrently.
+const MULTI_SEARCH_MAX_CONCURRENCY: usize = 10;
+
+/// Runs the searches of a `_msearch` request concurrently.
+///
+/// A payload that cannot be split into header and body lines fails the whole request, whereas
+/// invalid headers, invalid bodies and search errors are reported in their respective responses.
+async fn es_compat_multi_search(
+    default_indexes: SimpleList,
+    payload: Bytes,
+    search_service: Arc<dyn SearchService>,
+) -> Result<MultiSearchResponse, SearchError> {
+    let start_instant = Instant::now();
+    let payload_str = std::str::from_utf8(&payload)
+        .map_err(|_| SearchError::InvalidArgument("Body is not utf-8.".to_string()))?;
+    let mut payload_lines = payload_str
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    let mut search_requests = Vec::new();
+
+    while let Some(header_line) = payload_lines.next() {
+        let body_line = payload_lines.next().ok_or_else(|| {
+            SearchError::InvalidArgument("Expected a search body after the header.".to_string())
+        })?;
+        let search_request_result = serde_json::from_str::<MultiSearchHeader>(header_line)
+            .map_err(|error| {
+                SearchError::InvalidArgument(format!("Invalid `_msearch` header: {error}"))
+            })
+            .and_then(|header| {
+                let search_body = parse_search_body(body_line.as_bytes())?;
+                let indexes = header.index.unwrap_or_else(|| default_indexes.0.clone());
+                let index_id = single_index_id(indexes)?;
+                build_search_request(index_id, SearchQueryParams::default(), search_body)
+            });
+        search_requests.push(search_request_result);
+    }
+    let search_futures = search_requests.into_iter().map(|search_request_result| {
+        let search_service = search_service.clone();
+        async move { es_search(search_request_result?, search_service.as_ref()).await }
+    });
+    // `buffered` preserves the order of the requests.
+    let responses: Vec<MultiSearchSingleResponse> = futures::stream::iter(search_futures)
+        .buffered(MULTI_SEARCH_MAX_CONCURRENCY)
+        .map(MultiSearchSingleResponse::from)
+        .collect()
+        .await;
+    Ok(MultiSearchResponse {
+        took: start_instant.elapsed().as_millis() as u64,
+        responses,
+    })
+}
+
+/// GET or POST _elastic/{index}/_count
+pub fn es_compat_index_count_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_index_count_filter()
+        .and(with_arg(search_service))
+        .then(es_compat_index_count)
+        .map(|resp| BodyFormat::Json.make_rest_reply(resp))
+}
+
+async fn es_compat_index_count(
+    indexes: SimpleList,
+    count_params: CountQueryParams,
+    count_body: Bytes,
+    search_service: Arc<dyn SearchService>,
+) -> Result<ElasticCountResponse, SearchError> {
+    let index_id = single_index_id(indexes.0)?;
+    let count_body = parse_search_body(&count_body)?;
+    let search_params = SearchQueryParams {
+        q: count_params.q,
+        ..Default::default()
+    };
+    let search_request = quickwit_proto::SearchRequest {
+        max_hits: 0,
+        start_offset: 0,
+        ..build_search_request(index_id, search_params, count_body)?
+    };
+    let search_response: SearchResponse = search_service.root_search(search_request).await?;
+    Ok(ElasticCountResponse {
+        count: search_response.num_hits,
+    })
+}
+
+/// GET or POST _elastic/{index}/_explain/{id}
+pub fn es_compat_index_explain_handler(
+    search_service: Arc<dyn SearchService>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_index_explain_filter()
+        .and(with_arg(search_service))
+        .then(es_compat_index_explain)
+        .map(|resp| BodyFormat::Json.make_rest_reply(resp))
+}
+
+async fn es_compat_index_explain(
+    index_id: String,
+    hit_id: String,
+    explain_params: ExplainQueryParams,
+    explain_body: Bytes,
+    search_service: Arc<dyn SearchService>,
+) -> Result<ElasticExplainResponse, SearchError> {
+    let partial_hit = parse_hit_id(&hit_id)?;
+    let explain_body = parse_search_body(&explain_body)?;
+    let search_params = SearchQueryParams {
+        q: explain_params.q,
+        ..Default::default()
+    };
+    let search_request = build_search_request(index_id.clone(), search_params, explain_body)?;
+    let explain_hit_request = ExplainHitRequest {
+        search_request: Some(search_request),
+        partial_hit: Some(partial_hit),
+    };
+    let explain_hit_response = search_service.root_explain_hit(explain_hit_request).await?;
+    let explanation = explain_hit_response
+        .explanation
+        .map(|explanation_json| serde_json::from_str(&explanation_json))
+        .transpose()?;
+    Ok(ElasticExplainResponse {
+        index: index_id,
+        id: hit_id,
+        matched: explain_hit_response.matched,
+        explanation,
+    })
+}
+
+/// GET _elastic/{index}/_mapping
+pub fn es_compat_index_mapping_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_index_mapping_filter()
+        .and(with_arg(metastore))
+        .then(es_compat_index_mapping)
+        .map(|resp| BodyFormat::Json.make_rest_reply(resp))
+}
+
+/// GET or POST _elastic/{index}/_field_caps
+pub fn es_compat_index_field_capabilities_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_index_field_capabilities_filter()
+        .and(with_arg(metastore))
+        .then(es_compat_index_field_capabilities)
+        .map(|resp| BodyFormat::Json.make_rest_reply(resp))
+}
+
+/// GET _elastic/_cat/indices and _elastic/_cat/indices/{index}
+pub fn es_compat_cat_indices_handler(
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    let cat_all_indices = elastic_cat_indices_filter()
+        .map(|cat_params: CatIndicesQueryParams| (SimpleList(Vec::new()), cat_params))
+        .untuple_one();
+    cat_all_indices
+        .or(elastic_cat_index_filter())
+        .unify()
+        .and(with_arg(metastore))
+        .then(
+            |indexes: SimpleList,
+             cat_params: CatIndicesQueryParams,
+             metastore: Arc<dyn Metastore>| async move {
+                let result = es_compat_cat_indices(indexes, metastore).await;
+                make_cat_reply(result, &cat_params)
+            },
+        )
+}
+
+fn index_schema(index_metadata: &IndexMetadata) -> Result<Schema, SearchError> {
+    let index_config = index_metadata.index_config();
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|error| {
+            SearchError::InternalError(format!("Failed to build doc mapper: {error}"))
+        })?;
+    Ok(doc_mapper.schema())
+}
+
+async fn es_compat_index_mapping(
+    indexes: SimpleList,
+    metastore: Arc<dyn Metastore>,
+) -> Result<ElasticMappingsResponse, SearchError> {
+    let mut mappings_response = ElasticMappingsResponse::new();
+    for index_id in indexes.0 {
+        let index_metadata = metastore.index_metadata(&index_id).await?;
+        let schema = index_schema(&index_metadata)?;
+        let mappings =
+            ElasticMappings::from_schema(&schema, &index_metadata.index_config().doc_mapping.mode);
+        mappings_response.insert(index_id, ElasticIndexMappings { mappings });
+    }
+    Ok(mappings_response)
+}
+
+async fn es_compat_index_field_capabilities(
+    indexes: SimpleList,
+    field_caps_params: FieldCapabilityQueryParams,
+    metastore: Arc<dyn Metastore>,
+) -> Result<FieldCapabilityResponse, SearchError> {
+    let field_patterns = field_caps_params
+        .fields
+        .unwrap_or_else(|| vec!["*".to_string()]);
+    let mut field_capability_response = FieldCapabilityResponse::default();
+    for index_id in indexes.0 {
+        let index_metadata = metastore.index_metadata(&index_id).await?;
+        let schema = index_schema(&index_metadata)?;
+        field_capability_response.add_index(&index_id, &schema, &field_patterns);
+    }
+    Ok(field_capability_response.finalize())
+}
+
+/// Lists the given indexes, or all the indexes if none is given.
+async fn es_compat_cat_indices(
+    indexes: SimpleList,
+    metastore: Arc<dyn Metastore>,
+) -> Result<Vec<ElasticCatIndexEntry>, SearchError> {
+    let indexes_metadatas = if indexes.0.is_empty() {
+        metastore.list_indexes_metadatas().await?
+    } else {
+        let mut indexes_metadatas = Vec::with_capacity(indexes.0.len());
+        for index_id in &indexes.0 {
+            indexes_metadatas.push(metastore.index_metadata(index_id).await?);
+        }
+        indexes_metadatas
+    };
+    let mut cat_index_entries = Vec::with_capacity(indexes_metadatas.len());
+    for index_metadata in &indexes_metadatas {
+        let query = ListSplitsQuery::for_index(index_metadata.index_id())
+            .with_split_state(SplitState::Published);
+        let published_splits = metastore.list_splits(query).await?;
+        cat_index_entries.push(ElasticCatIndexEntry::new(index_metadata, &published_splits));
+    }
+    Ok(cat_index_entries)
+}
+
+/// The `_cat` APIs default to a text table. JSON is returned with `?format=json`.
+fn make_cat_reply(
+    result: Result<Vec<ElasticCatIndexEntry>, SearchError>,
+    cat_params: &CatIndicesQueryParams,
+) -> Box<dyn warp::Reply> {
+    match (result, cat_params.format.as_deref()) {
+        (Ok(cat_index_entries), None | Some("text") | Some("txt")) => {
+            let table = format_cat_indices_table(&cat_index_entries, cat_params.v.unwrap_or(false));
+            Box::new(warp::reply::with_header(
+                table,
+                CONTENT_TYPE,
+                "text/plain; charset=UTF-8",
+            ))
+        }
+        (Ok(cat_index_entries), Some("json")) => {
+            Box::new(BodyFormat::Json.make_rest_reply(Ok::<_, SearchError>(cat_index_entries)))
+        }
+        (Ok(_), Some(format)) => Box::new(BodyFormat::Json.make_rest_reply(Err::<(), _>(
+            SearchError::InvalidArgument(format!("Unsupported `_cat` format `{format}`.")),
+        ))),
+        (Err(error), _) => Box::new(BodyFormat::Json.make_rest_reply(Err::<(), _>(error))),
+    }
+}
+
+/// Quickwit documents have no IDs, so hits are identified by their address:
+/// `{split_id}:{segment_ord}:{doc_id}`.
+fn hit_id(partial_hit: &PartialHit) -> String {
+    format!(
+        "{}:{}:{}",
+        partial_hit.split_id, partial_hit.segment_ord, partial_hit.doc_id
+    )
+}
+
+fn parse_hit_id(hit_id: &str) -> Result<PartialHit, SearchError> {
+    let invalid_hit_id = || {
+        SearchError::InvalidArgument(format!(
+            "Invalid document ID `{hit_id}`. Expected `{{split_id}}:{{segment_ord}}:{{doc_id}}`."
+        ))
+    };
+    let mut parts = hit_id.rsplitn(3, ':');
+    let doc_id = parts.next().and_then(|part| part.parse().ok());
+    let segment_ord = parts.next().and_then(|part| part.parse().ok());
+    let split_id = parts.next().filter(|split_id| !split_id.is_empty());
+    let (Some(split_id), Some(segment_ord), Some(doc_id)) = (split_id, segment_ord, doc_id) else {
+        return Err(invalid_hit_id());
+    };
+    Ok(PartialHit {
+        split_id: split_id.to_string(),
+        segment_ord,
+        doc_id,
+        ..Default::default()
+    })
+}
+
+fn convert_snippet_to_highlight(snippet_json: &str) -> elasticsearch_dsl::Map<String, Vec<String>> {
+    let mut highlight: elasticsearch_dsl::Map<String, Vec<String>> =
+        serde_json::from_str(snippet_json).unwrap_or_default();
+    // Like Elasticsearch, the fields without any highlighted fragment are omitted.
+    highlight.retain(|_, fragments| !fragments.is_empty());
+    highlight
+}
+
 fn convert_hit(hit: quickwit_proto::Hit) -> ElasticHit {
     let fields: elasticsearch_dsl::Map<String, serde_json::Value> =
         serde_json::from_str(&hit.json).unwrap_or_default();
+    let sort = search_after_to_sort_values(&hit);
     ElasticHit {
         fields,
-        explanation: None,
+        explanation: hit
+            .explanation
+            .and_then(|explanation_json| serde_json::from_str(&explanation_json).ok()),
         index: "".to_string(),
-        id: "".to_string(),
+        id: hit.partial_hit.as_ref().map(hit_id).unwrap_or_default(),
         score: None,
         nested: None,
         source: Source::from_string(hit.json)
             .unwrap_or_else(|_| Source::from_string("{}".to_string()).unwrap()),
-        highlight: Default::default(),
+        highlight: hit
+            .snippet
+            .as_deref()
+            .map(convert_snippet_to_highlight)
+            .unwrap_or_default(),
         inner_hits: Default::default(),
         matched_queries: Vec::default(),
-        sort: Vec::default(),
+        sort,
     }
 }
 
 fn convert_to_es_search_response(resp: SearchResponse) -> ElasticSearchResponse {
     let hits: Vec<ElasticHit> = resp.hits.into_iter().map(convert_hit).collect();
+    build_es_search_response(resp.num_hits, hits)
+}
+
+fn build_es_search_response(num_hits: u64, hits: Vec<ElasticHit>) -> ElasticSearchResponse {
     ElasticSearchResponse {
         timed_out: false,
         hits: HitsMetadata {
             total: Some(TotalHits {
-                value: resp.num_hits,
+                value: num_hits,
                 relation: TotalHitsRelation::Equal,
             }),
             max_score: None,
@@ -118,3 +652,669 @@ fn convert_to_es_search_response(resp: SearchResponse) -> ElasticSearchResponse
         ..Default::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use mockall::predicate;
+    use quickwit_metastore::{
+        IndexMetadata, Metastore, MetastoreError, MockMetastore, Split, SplitMetadata, SplitState,
+    };
+    use quickwit_proto::{PartialHit, SearchResponse};
+    use quickwit_search::{MockSearchService, SearchError};
+    use serde_json::Value as JsonValue;
+
+    use super::{
+        es_compat_cat_indices_handler, es_compat_index_count_handler,
+        es_compat_index_explain_handler, es_compat_index_field_capabilities_handler,
+        es_compat_index_mapping_handler, es_compat_index_search_handler,
+        es_compat_multi_search_handler, es_compat_search_handler,
+    };
+
+    fn mock_metastore() -> Arc<dyn Metastore> {
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|index_id: &str| {
+                if index_id == "my-index" {
+                    Ok(IndexMetadata::for_test(index_id, "ram:///indexes/my-index"))
+                } else {
+                    Err(MetastoreError::IndexDoesNotExist {
+                        index_id: index_id.to_string(),
+                    })
+                }
+            });
+        metastore.expect_list_indexes_metadatas().returning(|| {
+            Ok(vec![IndexMetadata::for_test(
+                "my-index",
+                "ram:///indexes/my-index",
+            )])
+        });
+        metastore.expect_list_splits().returning(|_| {
+            Ok(vec![Split {
+                split_state: SplitState::Published,
+                split_metadata: SplitMetadata {
+                    num_docs: 42,
+                    footer_offsets: 0..1024,
+                    ..Default::default()
+                },
+                update_timestamp: 0,
+                publish_timestamp: None,
+            }])
+        });
+        Arc::new(metastore)
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_search_with_query_dsl() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.index_id == "my-index"
+                        && search_request.query == "*"
+                        && search_request.query_dsl.as_deref()
+                            == Some(r#"{"term":{"severity":{"value":"ERROR"}}}"#)
+                        && search_request.max_hits == 5
+                        && search_request.start_offset == 10
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let handler = es_compat_index_search_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_search?from=10")
+            .body(r#"{ "size": 5, "query": { "term": { "severity": "ERROR" } } }"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["hits"]["total"]["value"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_search_without_body() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.query == "severity:ERROR"
+                        && search_request.query_dsl.is_none()
+                        && search_request.max_hits == 10
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let handler = es_compat_index_search_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("GET")
+            .path("/_elastic/my-index/_search?q=severity:ERROR")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_search_search_after() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.search_after
+                        == Some(quickwit_proto::PartialHit {
+                            sorting_field_value: 10,
+                            split_id: "split-1".to_string(),
+                            segment_ord: 0,
+                            doc_id: 3,
+                            secondary_sorting_field_values: Vec::new(),
+                        })
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::SearchResponse {
+                    hits: vec![quickwit_proto::Hit {
+                        json: r#"{"severity": "ERROR"}"#.to_string(),
+                        partial_hit: Some(quickwit_proto::PartialHit {
+                            sorting_field_value: 8,
+                            split_id: "split-2".to_string(),
+                            segment_ord: 1,
+                            doc_id: 5,
+                            secondary_sorting_field_values: Vec::new(),
+                        }),
+                        snippet: None,
+                        explanation: None,
+                        sort_values_json: Some("[1680000000000]".to_string()),
+                    }],
+                    num_hits: 1,
+                    ..Default::default()
+                })
+            });
+        let handler = es_compat_index_search_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_search")
+            .body(r#"{ "search_after": [1680000000001, "10:split-1:0:3"] }"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json["hits"]["hits"][0]["sort"],
+            serde_json::json!([1680000000000u64, "8:split-2:1:5"])
+        );
+
+        for search_body in [
+            r#"{ "search_after": ["foo"] }"#,
+            r#"{ "search_after": [10, "split-1", 0, 3] }"#,
+            r#"{ "from": 10, "search_after": ["10:split-1:0:3"] }"#,
+        ] {
+            let handler = es_compat_index_search_handler(Arc::new(MockSearchService::new()));
+            let resp = warp::test::request()
+                .method("POST")
+                .path("/_elastic/my-index/_search")
+                .body(search_body)
+                .reply(&handler)
+                .await;
+            assert_eq!(resp.status(), 400);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_search_sort() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.sort_fields
+                        == [
+                            quickwit_proto::SortField {
+                                field_name: "service".to_string(),
+                                sort_order: quickwit_proto::SortOrder::Asc as i32,
+                                missing_first: false,
+                            },
+                            quickwit_proto::SortField {
+                                field_name: "timestamp".to_string(),
+                                sort_order: quickwit_proto::SortOrder::Desc as i32,
+                                missing_first: true,
+                            },
+                        ]
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let handler = es_compat_index_search_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_search")
+            .body(
+                r#"{ "sort": ["service", { "timestamp": { "order": "desc", "missing": "_first" } }] }"#,
+            )
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.sort_fields
+                        == [quickwit_proto::SortField {
+                            field_name: "timestamp".to_string(),
+                            sort_order: quickwit_proto::SortOrder::Desc as i32,
+                            missing_first: false,
+                        }]
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let handler = es_compat_index_search_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_search?sort=timestamp:desc")
+            .body(r#"{ "sort": ["service"] }"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_search_invalid_body() {
+        let mock_search_service = MockSearchService::new();
+        let handler = es_compat_index_search_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_search")
+            .body(r#"{ "query": { "fuzzy": { "body": "helo" } } }"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_search_all_indexes() {
+        let mut metastore = MockMetastore::new();
+        metastore.expect_list_indexes_metadatas().returning(|| {
+            Ok(vec![
+                IndexMetadata::for_test("index-1", "ram:///indexes/index-1"),
+                IndexMetadata::for_test("index-2", "ram:///indexes/index-2"),
+            ])
+        });
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service.expect_root_search().times(2).returning(
+            |search_request: quickwit_proto::SearchRequest| {
+                assert_eq!(search_request.max_hits, 3);
+                assert_eq!(search_request.start_offset, 0);
+                let hits = if search_request.index_id == "index-1" {
+                    vec![(3, "split-1"), (1, "split-1")]
+                } else {
+                    vec![(2, "split-2")]
+                }
+                .into_iter()
+                .map(|(sorting_field_value, split_id)| quickwit_proto::Hit {
+                    json: r#"{"body": "hello"}"#.to_string(),
+                    partial_hit: Some(PartialHit {
+                        sorting_field_value,
+                        split_id: split_id.to_string(),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+                .collect();
+                Ok(SearchResponse {
+                    num_hits: if search_request.index_id == "index-1" {
+                        2
+                    } else {
+                        1
+                    },
+                    hits,
+                    ..Default::default()
+                })
+            },
+        );
+        let handler = es_compat_search_handler(Arc::new(mock_search_service), Arc::new(metastore));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/_search")
+            .body(r#"{ "from": 1, "size": 2, "query": { "match_all": {} } }"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["hits"]["total"]["value"], 3);
+        let hit_indexes: Vec<&str> = resp_json["hits"]["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["_index"].as_str().unwrap())
+            .collect();
+        assert_eq!(hit_indexes, ["index-2", "index-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_multi_search() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .times(2)
+            .returning(|search_request| {
+                if search_request.index_id == "index-1" {
+                    assert_eq!(search_request.max_hits, 3);
+                    Ok(quickwit_proto::SearchResponse {
+                        num_hits: 7,
+                        ..Default::default()
+                    })
+                } else {
+                    Err(SearchError::IndexDoesNotExist {
+                        index_id: search_request.index_id,
+                    })
+                }
+            });
+        let handler = es_compat_multi_search_handler(Arc::new(mock_search_service));
+        let payload = r#"
+            {"index": "index-1"}
+            {"size": 3, "query": {"match_all": {}}}
+            {}
+            {"query": {"term": {"severity": "ERROR"}}}
+            {"index": ["index-1", "index-2"]}
+            {}
+            {"index": 1}
+            {}
+            {"index": "index-1"}
+            {"unknown_param": 1}
+        "#;
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/index-2/_msearch")
+            .body(payload)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let responses = resp_json["responses"].as_array().unwrap();
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0]["status"], 200);
+        assert_eq!(responses[0]["hits"]["total"]["value"], 7);
+        assert_eq!(responses[1]["status"], 404);
+        assert_eq!(responses[1]["error"]["type"], "index_not_found_exception");
+        assert_eq!(responses[2]["status"], 400);
+        // Invalid headers and bodies only fail their own search.
+        assert_eq!(responses[3]["status"], 400);
+        assert_eq!(responses[3]["error"]["type"], "illegal_argument_exception");
+        assert_eq!(responses[4]["status"], 400);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_multi_search_malformed_payload() {
+        let handler = es_compat_multi_search_handler(Arc::new(MockSearchService::new()));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/_msearch")
+            .body(r#"{"index": "index-1"}"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_count() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.index_id == "my-index"
+                        && search_request.max_hits == 0
+                        && search_request.query_dsl.is_some()
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::SearchResponse {
+                    num_hits: 12,
+                    ..Default::default()
+                })
+            });
+        let handler = es_compat_index_count_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_count")
+            .body(r#"{"query": {"term": {"severity": "ERROR"}}}"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json, serde_json::json!({"count": 12}));
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_search_explain() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| search_request.explain,
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::SearchResponse {
+                    hits: vec![quickwit_proto::Hit {
+                        json: r#"{"severity": "ERROR"}"#.to_string(),
+                        partial_hit: Some(quickwit_proto::PartialHit {
+                            sorting_field_value: 8,
+                            split_id: "split-2".to_string(),
+                            segment_ord: 1,
+                            doc_id: 5,
+                            secondary_sorting_field_values: Vec::new(),
+                        }),
+                        snippet: None,
+                        explanation: Some(
+                            r#"{"value": 0.5, "description": "TermQuery", "details": []}"#
+                                .to_string(),
+                        ),
+                        sort_values_json: None,
+                    }],
+                    num_hits: 1,
+                    ..Default::default()
+                })
+            });
+        let handler = es_compat_index_search_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_search")
+            .body(r#"{"query": {"term": {"severity": "ERROR"}}, "explain": true}"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let hit_json = &resp_json["hits"]["hits"][0];
+        assert_eq!(hit_json["_id"], "split-2:1:5");
+        assert_eq!(hit_json["_explanation"]["value"], 0.5);
+        assert_eq!(hit_json["_explanation"]["description"], "TermQuery");
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_search_highlight() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.snippet_fields == ["body"]
+                        && search_request.highlight
+                            == Some(quickwit_proto::HighlightSpec {
+                                fragment_size: Some(20),
+                                number_of_fragments: Some(1),
+                                pre_tag: Some("<em>".to_string()),
+                                post_tag: Some("</em>".to_string()),
+                                require_field_match: None,
+                            })
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::SearchResponse {
+                    hits: vec![quickwit_proto::Hit {
+                        json: r#"{"body": "disk is full", "title": "error"}"#.to_string(),
+                        partial_hit: None,
+                        snippet: Some(
+                            r#"{"body": ["disk is <em>full</em>"], "title": []}"#.to_string(),
+                        ),
+                        explanation: None,
+                        sort_values_json: None,
+                    }],
+                    num_hits: 1,
+                    ..Default::default()
+                })
+            });
+        let handler = es_compat_index_search_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_search")
+            .body(
+                r#"{
+                    "query": {"match": {"body": "full"}},
+                    "highlight": {
+                        "fields": {"body": {}},
+                        "fragment_size": 20,
+                        "number_of_fragments": 1,
+                        "pre_tags": ["<em>"],
+                        "post_tags": ["</em>"]
+                    }
+                }"#,
+            )
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json["hits"]["hits"][0]["highlight"],
+            serde_json::json!({"body": ["disk is <em>full</em>"]})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_search_source_filter() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.source_includes == ["attributes.*"]
+                        && search_request.source_excludes == ["attributes.secret"]
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let handler = es_compat_index_search_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_search")
+            .body(
+                r#"{"_source": {"includes": ["attributes.*"], "excludes": ["attributes.secret"]}}"#,
+            )
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.source_includes == ["body", "timestamp"]
+                        && search_request.source_excludes.is_empty()
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let handler = es_compat_index_search_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_search?_source_includes=body,timestamp")
+            .body(r#"{"_source": false}"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_explain() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_explain_hit()
+            .with(predicate::function(
+                |explain_hit_request: &quickwit_proto::ExplainHitRequest| {
+                    let search_request = explain_hit_request.search_request.as_ref().unwrap();
+                    let partial_hit = explain_hit_request.partial_hit.as_ref().unwrap();
+                    search_request.index_id == "my-index"
+                        && search_request.query_dsl.is_some()
+                        && partial_hit.split_id == "split-2"
+                        && partial_hit.segment_ord == 1
+                        && partial_hit.doc_id == 5
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::ExplainHitResponse {
+                    matched: true,
+                    explanation: Some(r#"{"value": 0.5, "description": "TermQuery"}"#.to_string()),
+                })
+            });
+        let handler = es_compat_index_explain_handler(Arc::new(mock_search_service));
+        let resp = warp::test::request()
+            .method("POST")
+            .path("/_elastic/my-index/_explain/split-2:1:5")
+            .body(r#"{"query": {"term": {"severity": "ERROR"}}}"#)
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(
+            resp_json,
+            serde_json::json!({
+                "_index": "my-index",
+                "_id": "split-2:1:5",
+                "matched": true,
+                "explanation": {"value": 0.5, "description": "TermQuery"},
+            })
+        );
+
+        let handler = es_compat_index_explain_handler(Arc::new(MockSearchService::new()));
+        let resp = warp::test::request()
+            .path("/_elastic/my-index/_explain/not-a-hit-id")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_mapping() {
+        let handler = es_compat_index_mapping_handler(mock_metastore());
+        let resp = warp::test::request()
+            .path("/_elastic/my-index/_mapping")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        let properties = &resp_json["my-index"]["mappings"]["properties"];
+        assert_eq!(properties["body"]["type"], "text");
+        assert_eq!(properties["owner"]["type"], "keyword");
+        assert_eq!(properties["response_time"]["type"], "double");
+        assert_eq!(
+            properties["attributes"]["properties"]["server.status"]["type"],
+            "text"
+        );
+
+        let resp = warp::test::request()
+            .path("/_elastic/unknown-index/_mapping")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_index_field_capabilities() {
+        let handler = es_compat_index_field_capabilities_handler(mock_metastore());
+        let resp = warp::test::request()
+            .path("/_elastic/my-index/_field_caps?fields=response_*,body")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["indices"], serde_json::json!(["my-index"]));
+        let fields = resp_json["fields"].as_object().unwrap();
+        assert_eq!(
+            fields.keys().collect::<Vec<_>>(),
+            ["body", "response_date", "response_payload", "response_time"]
+        );
+        assert_eq!(fields["response_time"]["double"]["aggregatable"], true);
+        assert_eq!(fields["body"]["text"]["aggregatable"], false);
+    }
+
+    #[tokio::test]
+    async fn test_es_compat_cat_indices() {
+        let handler = es_compat_cat_indices_handler(mock_metastore());
+        let resp = warp::test::request()
+            .path("/_elastic/_cat/indices?format=json")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json[0]["index"], "my-index");
+        assert_eq!(resp_json[0]["docs.count"], "42");
+        assert_eq!(resp_json[0]["store.size"], "1024");
+
+        let resp = warp::test::request()
+            .path("/_elastic/_cat/indices/my-index?v=true")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let table = std::str::from_utf8(resp.body()).unwrap();
+        let mut lines = table.lines();
+        assert!(lines.next().unwrap().starts_with("health"));
+        assert!(lines.next().unwrap().contains("my-index"));
+
+        let resp = warp::test::request()
+            .path("/_elastic/_cat/indices/unknown-index")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
+}
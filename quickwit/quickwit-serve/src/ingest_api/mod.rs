@@ -19,5 +19,5 @@
 
 mod rest_handler;
 
-pub(crate) use rest_handler::ingest_api_handlers;
-pub use rest_handler::{IngestApi, IngestApiSchemas};
+pub(crate) use rest_handler::{dead_letters_handlers, ingest_api_handlers};
+pub use rest_handler::{IngestApi, IngestApiSchemas, ReplayDeadLettersResponse};
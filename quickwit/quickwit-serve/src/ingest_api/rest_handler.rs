@@ -17,24 +17,38 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
 
 use bytes::Bytes;
+use quickwit_actors::Mailbox;
+use quickwit_config::{build_doc_mapper, SourceInputFormat};
+use quickwit_doc_mapper::{DocMapper, DocParsingError};
 use quickwit_ingest::{
-    CommitType, DocBatchBuilder, FetchResponse, IngestRequest, IngestResponse, IngestService,
+    fetch_dead_letters, replay_dead_letters, CommitType, DeadLetterRecord, DocBatchBuilder,
+    FetchResponse, IngestApiService, IngestRequest, IngestResponse, IngestService,
     IngestServiceClient, IngestServiceError, TailRequest,
 };
+use quickwit_metastore::{Metastore, MetastoreError};
 use quickwit_proto::{ServiceError, ServiceErrorCode};
-use serde::Deserialize;
-use serde_json::Value as JsonValue;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use warp::http::StatusCode;
 use warp::{reject, Filter, Rejection};
 
 use crate::format::{extract_format_from_qs, make_response};
-use crate::{with_arg, BodyFormat};
+use crate::{require, with_arg, BodyFormat};
 
 #[derive(utoipa::OpenApi)]
-#[openapi(paths(ingest, tail_endpoint, elastic_ingest,))]
+#[openapi(paths(
+    ingest,
+    tail_endpoint,
+    elastic_ingest,
+    list_dead_letters,
+    replay_dead_letters_endpoint,
+))]
 pub struct IngestApi;
 
 #[derive(utoipa::OpenApi)]
@@ -43,7 +57,16 @@ pub struct IngestApi;
     quickwit_ingest::FetchResponse,
     quickwit_ingest::IngestResponse,
     quickwit_ingest::CommitType,
+    quickwit_ingest::DeadLetter,
+    quickwit_ingest::DeadLetterErrorKind,
+    quickwit_ingest::DeadLetterRecord,
+    quickwit_ingest::DeadLetterSourcePosition,
+    ReplayDeadLettersResponse,
     ElasticRefresh,
+    ElasticBulkResponse,
+    ElasticBulkItem,
+    ElasticBulkItemStatus,
+    ElasticBulkError,
 )))]
 pub struct IngestApiSchemas;
 
@@ -61,8 +84,14 @@ pub enum IngestRestApiError {
     BulkInvalidAction(String),
     #[error("Failed to parse source `{0}`.")]
     BulkInvalidSource(String),
+    #[error("Failed to build doc mapper for index `{index_id}`: {error}")]
+    InvalidDocMapping { index_id: String, error: String },
+    #[error("Failed to replay dead letters of source `{source_id}`: {reason}")]
+    DeadLetterReplay { source_id: String, reason: String },
     #[error(transparent)]
     IngestApi(#[from] IngestServiceError),
+    #[error(transparent)]
+    Metastore(#[from] MetastoreError),
 }
 
 impl ServiceError for IngestRestApiError {
@@ -70,7 +99,10 @@ impl ServiceError for IngestRestApiError {
         match self {
             Self::BulkInvalidAction(_) => ServiceErrorCode::BadRequest,
             Self::BulkInvalidSource(_) => ServiceErrorCode::BadRequest,
+            Self::InvalidDocMapping { .. } => ServiceErrorCode::Internal,
+            Self::DeadLetterReplay { .. } => ServiceErrorCode::BadRequest,
             Self::IngestApi(ingest_api_error) => ingest_api_error.status_code(),
+            Self::Metastore(metastore_error) => metastore_error.status_code(),
         }
     }
 }
@@ -83,10 +115,21 @@ enum BulkAction {
 }
 
 impl BulkAction {
-    fn into_index(self) -> String {
+    fn meta(&self) -> &BulkActionMeta {
         match self {
-            BulkAction::Index(meta) => meta.index,
-            BulkAction::Create(meta) => meta.index,
+            BulkAction::Index(meta) => meta,
+            BulkAction::Create(meta) => meta,
+        }
+    }
+
+    fn into_response_item(self, result: Result<(), ElasticBulkError>) -> ElasticBulkItem {
+        match self {
+            BulkAction::Index(meta) => {
+                ElasticBulkItem::Index(ElasticBulkItemStatus::new(meta, result))
+            }
+            BulkAction::Create(meta) => {
+                ElasticBulkItem::Create(ElasticBulkItemStatus::new(meta, result))
+            }
         }
     }
 }
@@ -100,6 +143,103 @@ struct BulkActionMeta {
     id: Option<String>,
 }
 
+/// Response of the Elasticsearch-compatible bulk API.
+///
+/// Like in Elasticsearch, `errors` is set to `true` as soon as one of the items failed, and
+/// `items` lists the outcome of each action in the order of the request.
+#[derive(Debug, Serialize, Deserialize, PartialEq, utoipa::ToSchema)]
+pub struct ElasticBulkResponse {
+    /// Time spent processing the request, in milliseconds.
+    pub took: u64,
+    pub errors: bool,
+    pub items: Vec<ElasticBulkItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ElasticBulkItem {
+    Index(ElasticBulkItemStatus),
+    Create(ElasticBulkItemStatus),
+}
+
+impl ElasticBulkItem {
+    fn is_error(&self) -> bool {
+        match self {
+            ElasticBulkItem::Index(item_status) => item_status.error.is_some(),
+            ElasticBulkItem::Create(item_status) => item_status.error.is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, utoipa::ToSchema)]
+pub struct ElasticBulkItemStatus {
+    #[serde(rename = "_index")]
+    pub index: String,
+    #[serde(rename = "_id")]
+    pub id: Option<String>,
+    pub status: u16,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ElasticBulkError>,
+}
+
+impl ElasticBulkItemStatus {
+    fn new(meta: BulkActionMeta, result: Result<(), ElasticBulkError>) -> Self {
+        match result {
+            Ok(()) => ElasticBulkItemStatus {
+                index: meta.index,
+                id: meta.id,
+                status: StatusCode::CREATED.as_u16(),
+                result: Some("created".to_string()),
+                error: None,
+            },
+            Err(error) => ElasticBulkItemStatus {
+                index: meta.index,
+                id: meta.id,
+                status: error.status_code().as_u16(),
+                result: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, utoipa::ToSchema)]
+pub struct ElasticBulkError {
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub reason: String,
+}
+
+impl ElasticBulkError {
+    fn index_not_found(index_id: &str) -> Self {
+        ElasticBulkError {
+            error_type: "index_not_found_exception".to_string(),
+            reason: format!("no such index [{index_id}]"),
+        }
+    }
+
+    fn status_code(&self) -> StatusCode {
+        if self.error_type == "index_not_found_exception" {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::BAD_REQUEST
+        }
+    }
+}
+
+impl From<DocParsingError> for ElasticBulkError {
+    fn from(error: DocParsingError) -> Self {
+        ElasticBulkError {
+            error_type: "mapper_parsing_exception".to_string(),
+            reason: error.to_string(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
 struct IngestOptions {
     #[serde(default)]
@@ -108,10 +248,11 @@ struct IngestOptions {
 
 pub(crate) fn ingest_api_handlers(
     ingest_service: IngestServiceClient,
+    metastore: Arc<dyn Metastore>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     ingest_handler(ingest_service.clone())
         .or(tail_handler(ingest_service.clone()))
-        .or(elastic_bulk_handler(ingest_service))
+        .or(elastic_bulk_handler(ingest_service, metastore))
 }
 
 fn ingest_filter(
@@ -216,6 +357,145 @@ async fn tail_endpoint(
     Ok(fetch_response)
 }
 
+/// Dead-letter queues are hosted by the ingest API service of indexer nodes: these routes are only
+/// served when the node runs the indexer service.
+pub(crate) fn dead_letters_handlers(
+    ingest_api_service_opt: Option<Mailbox<IngestApiService>>,
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    list_dead_letters_handler(ingest_api_service_opt.clone()).or(replay_dead_letters_handler(
+        ingest_api_service_opt,
+        metastore,
+    ))
+}
+
+fn default_dead_letters_limit() -> usize {
+    100
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams, utoipa::ToSchema)]
+#[into_params(parameter_in = Query)]
+struct ListDeadLettersQueryParams {
+    /// If set, only returns the dead letters located after this position in the queue.
+    #[serde(default)]
+    start_after: Option<u64>,
+    /// Maximum number of dead letters to return. Defaults to 100.
+    #[serde(default = "default_dead_letters_limit")]
+    limit: usize,
+}
+
+fn list_dead_letters_handler(
+    ingest_api_service_opt: Option<Mailbox<IngestApiService>>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String / "dead-letters")
+        .and(warp::get())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and(require(ingest_api_service_opt))
+        .then(list_dead_letters)
+        .and(extract_format_from_qs())
+        .map(make_response)
+}
+
+#[utoipa::path(
+    get,
+    tag = "Sources",
+    path = "/indexes/{index_id}/sources/{source_id}/dead-letters",
+    responses(
+        (status = 200, description = "Successfully fetched dead letters.", body = [DeadLetterRecord])
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the source."),
+        ("source_id" = String, Path, description = "The source ID whose dead letters are fetched."),
+        ListDeadLettersQueryParams,
+    )
+)]
+/// Returns the documents rejected by the indexing pipeline of a source, oldest first.
+async fn list_dead_letters(
+    index_id: String,
+    source_id: String,
+    query_params: ListDeadLettersQueryParams,
+    ingest_api_service: Mailbox<IngestApiService>,
+) -> Result<Vec<DeadLetterRecord>, IngestServiceError> {
+    fetch_dead_letters(
+        &ingest_api_service,
+        &index_id,
+        &source_id,
+        query_params.start_after,
+        query_params.limit,
+    )
+    .await
+}
+
+fn replay_dead_letters_handler(
+    ingest_api_service_opt: Option<Mailbox<IngestApiService>>,
+    metastore: Arc<dyn Metastore>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    warp::path!("indexes" / String / "sources" / String / "dead-letters" / "replay")
+        .and(warp::post())
+        .and(require(ingest_api_service_opt))
+        .and(with_arg(metastore))
+        .then(replay_dead_letters_endpoint)
+        .and(extract_format_from_qs())
+        .map(make_response)
+}
+
+#[derive(Debug, Deserialize, Serialize, utoipa::ToSchema)]
+pub struct ReplayDeadLettersResponse {
+    pub num_replayed_docs: u64,
+}
+
+#[utoipa::path(
+    post,
+    tag = "Sources",
+    path = "/indexes/{index_id}/sources/{source_id}/dead-letters/replay",
+    responses(
+        (status = 200, description = "Successfully replayed dead letters.", body = ReplayDeadLettersResponse)
+    ),
+    params(
+        ("index_id" = String, Path, description = "The index ID of the source."),
+        ("source_id" = String, Path, description = "The source ID whose dead letters are replayed."),
+    )
+)]
+/// Re-ingests the documents rejected by the indexing pipeline of a source through the ingest API
+/// and clears its dead-letter queue.
+///
+/// Replayed documents are indexed by the ingest API source of the index and skip the input format
+/// and the transform of the original source, so the replay is rejected for sources that define
+/// either.
+async fn replay_dead_letters_endpoint(
+    index_id: String,
+    source_id: String,
+    ingest_api_service: Mailbox<IngestApiService>,
+    metastore: Arc<dyn Metastore>,
+) -> Result<ReplayDeadLettersResponse, IngestRestApiError> {
+    let index_metadata = metastore.index_metadata(&index_id).await?;
+    let source_config = index_metadata.sources.get(&source_id).ok_or_else(|| {
+        MetastoreError::SourceDoesNotExist {
+            source_id: source_id.clone(),
+        }
+    })?;
+    let unsupported_reason_opt = if source_config.transform_config.is_some() {
+        Some("the source defines a transform".to_string())
+    } else if source_config.input_format != SourceInputFormat::Json {
+        Some("the source input format is not JSON".to_string())
+    } else {
+        None
+    };
+    if let Some(reason) = unsupported_reason_opt {
+        return Err(IngestRestApiError::DeadLetterReplay { source_id, reason });
+    }
+    let num_replayed_docs = replay_dead_letters(&ingest_api_service, &index_id, &source_id)
+        .await
+        .map_err(|error| match error {
+            IngestServiceError::IndexNotFound { .. } => IngestRestApiError::DeadLetterReplay {
+                source_id: source_id.clone(),
+                reason: format!("index `{index_id}` has no ingest API queue"),
+            },
+            error => error.into(),
+        })?;
+    Ok(ReplayDeadLettersResponse { num_replayed_docs })
+}
+
 /// ?refresh parameter for elasticsearch bulk request
 ///
 /// The syntax for this parameter is a bit confusing for backward compatibility reasons.
@@ -271,9 +551,11 @@ fn elastic_bulk_filter(
 
 pub fn elastic_bulk_handler(
     ingest_service: IngestServiceClient,
+    metastore: Arc<dyn Metastore>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
     elastic_bulk_filter()
         .and(with_arg(ingest_service))
+        .and(with_arg(metastore))
         .then(elastic_ingest)
         .and(extract_format_from_qs())
         .map(make_response)
@@ -285,68 +567,118 @@ pub fn elastic_bulk_handler(
     path = "/_bulk",
     request_body(content = String, description = "Elasticsearch compatible bulk request body limited to 10MB", content_type = "application/json"),
     responses(
-        (status = 200, description = "Successfully ingested documents.", body = IngestResponse)
+        (status = 200, description = "Successfully processed the bulk request. Failed documents are reported per item.", body = ElasticBulkResponse)
     ),
     params(
         ("refresh" = Option<ElasticRefresh>, Query, description = "Force or wait for commit at the end of the indexing operation."),
     )
 )]
 /// Elasticsearch-compatible Bulk Ingest
+///
+/// Documents are validated against the doc mapping of their target index before being ingested.
+/// Invalid documents are not ingested and are reported in the `items` of the response.
 async fn elastic_ingest(
     payload: String,
     ingest_options: ElasticIngestOptions,
     mut ingest_service: IngestServiceClient,
-) -> Result<IngestResponse, IngestRestApiError> {
-    let mut batches = HashMap::new();
+    metastore: Arc<dyn Metastore>,
+) -> Result<ElasticBulkResponse, IngestRestApiError> {
+    let start_instant = Instant::now();
+    // `None` means the index does not exist.
+    let mut doc_mappers: HashMap<String, Option<Arc<dyn DocMapper>>> = HashMap::new();
+    let mut batches: HashMap<String, DocBatchBuilder> = HashMap::new();
+    let mut items = Vec::new();
     let mut payload_lines = lines(&payload);
 
     while let Some(json_str) = payload_lines.next() {
         let action = serde_json::from_str::<BulkAction>(json_str)
             .map_err(|e| IngestRestApiError::BulkInvalidAction(e.to_string()))?;
-        let source = payload_lines
-            .next()
-            .ok_or_else(|| {
-                IngestRestApiError::BulkInvalidSource("Expected source for the action.".to_string())
-            })
-            .and_then(|source| {
-                serde_json::from_str::<JsonValue>(source)
-                    .map_err(|err| IngestRestApiError::BulkInvalidSource(err.to_string()))
-            })?;
-
-        let index_id = action.into_index();
-        let doc_batch = batches
-            .entry(index_id.clone())
-            .or_insert(DocBatchBuilder::new(index_id));
-
-        doc_batch.ingest_doc(source.to_string().as_bytes());
+        let source = payload_lines.next().ok_or_else(|| {
+            IngestRestApiError::BulkInvalidSource("Expected source for the action.".to_string())
+        })?;
+        let index_id = &action.meta().index;
+        let doc_mapper_opt = match doc_mappers.entry(index_id.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let doc_mapper_opt = fetch_doc_mapper(metastore.as_ref(), index_id).await?;
+                entry.insert(doc_mapper_opt)
+            }
+        };
+        let result = if let Some(doc_mapper) = doc_mapper_opt {
+            doc_mapper
+                .doc_from_json_str(source)
+                .map(|_| ())
+                .map_err(ElasticBulkError::from)
+        } else {
+            Err(ElasticBulkError::index_not_found(index_id))
+        };
+        if result.is_ok() {
+            batches
+                .entry(index_id.clone())
+                .or_insert_with(|| DocBatchBuilder::new(index_id.clone()))
+                .ingest_doc(source.as_bytes());
+        }
+        items.push(action.into_response_item(result));
+    }
+    if !batches.is_empty() {
+        let ingest_request = IngestRequest {
+            doc_batches: batches
+                .into_values()
+                .map(|builder| builder.build())
+                .collect(),
+            commit: Into::<CommitType>::into(ingest_options.refresh) as u32,
+        };
+        ingest_service.ingest(ingest_request).await?;
     }
+    let errors = items.iter().any(ElasticBulkItem::is_error);
+    Ok(ElasticBulkResponse {
+        took: start_instant.elapsed().as_millis() as u64,
+        errors,
+        items,
+    })
+}
 
-    let ingest_request = IngestRequest {
-        doc_batches: batches
-            .into_values()
-            .map(|builder| builder.build())
-            .collect(),
-        commit: Into::<CommitType>::into(ingest_options.refresh) as u32,
+/// Builds the doc mapper of an index. Returns `None` if the index does not exist.
+async fn fetch_doc_mapper(
+    metastore: &dyn Metastore,
+    index_id: &str,
+) -> Result<Option<Arc<dyn DocMapper>>, IngestRestApiError> {
+    let index_config = match metastore.index_metadata(index_id).await {
+        Ok(index_metadata) => index_metadata.into_index_config(),
+        Err(MetastoreError::IndexDoesNotExist { .. }) => return Ok(None),
+        Err(metastore_error) => return Err(metastore_error.into()),
     };
-    let ingest_response = ingest_service.ingest(ingest_request).await?;
-    Ok(ingest_response)
+    let doc_mapper = build_doc_mapper(&index_config.doc_mapping, &index_config.search_settings)
+        .map_err(|error| IngestRestApiError::InvalidDocMapping {
+            index_id: index_id.to_string(),
+            error: error.to_string(),
+        })?;
+    Ok(Some(doc_mapper))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
     use std::time::Duration;
 
     use byte_unit::Byte;
     use quickwit_actors::{Mailbox, Universe};
-    use quickwit_config::IngestApiConfig;
+    use quickwit_config::{IngestApiConfig, SourceConfig, SourceInputFormat, SourceParams};
     use quickwit_ingest::{
-        init_ingest_api, CreateQueueIfNotExistsRequest, FetchRequest, FetchResponse,
-        IngestApiService, IngestResponse, IngestServiceClient, SuggestTruncateRequest,
-        QUEUES_DIR_NAME,
+        dead_letter_queue_id, init_ingest_api, CommitType, CreateQueueIfNotExistsRequest,
+        DeadLetter, DeadLetterErrorKind, DeadLetterRecord, DeadLetterSourcePosition,
+        DocBatchBuilder, FetchRequest, FetchResponse, IngestApiService, IngestRequest,
+        IngestResponse, IngestServiceClient, SuggestTruncateRequest, QUEUES_DIR_NAME,
     };
+    use quickwit_metastore::{IndexMetadata, Metastore, MetastoreError, MockMetastore};
 
-    use super::{ingest_api_handlers, BulkAction, BulkActionMeta};
-    use crate::ingest_api::rest_handler::{ElasticIngestOptions, ElasticRefresh};
+    use super::{
+        dead_letters_handlers, ingest_api_handlers, BulkAction, BulkActionMeta,
+        ReplayDeadLettersResponse,
+    };
+    use crate::ingest_api::rest_handler::{
+        ElasticBulkItem, ElasticBulkResponse, ElasticIngestOptions, ElasticRefresh,
+    };
 
     #[test]
     fn test_bulk_action_serde() {
@@ -392,6 +724,32 @@ mod tests {
         }
     }
 
+    fn mock_metastore(index_ids: &[&str]) -> Arc<dyn Metastore> {
+        let index_ids: Vec<String> = index_ids
+            .iter()
+            .map(|index_id| index_id.to_string())
+            .collect();
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(move |index_id: &str| {
+                if index_ids
+                    .iter()
+                    .any(|known_index_id| known_index_id == index_id)
+                {
+                    Ok(IndexMetadata::for_test(
+                        index_id,
+                        &format!("ram:///indexes/{index_id}"),
+                    ))
+                } else {
+                    Err(MetastoreError::IndexDoesNotExist {
+                        index_id: index_id.to_string(),
+                    })
+                }
+            });
+        Arc::new(metastore)
+    }
+
     async fn setup_ingest_service(
         queues: &[&str],
         config: &IngestApiConfig,
@@ -424,7 +782,8 @@ mod tests {
     async fn test_ingest_api_returns_200_when_ingest_json_and_fetch() {
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
-        let ingest_api_handlers = ingest_api_handlers(ingest_service);
+        let ingest_api_handlers =
+            ingest_api_handlers(ingest_service, mock_metastore(&["my-index"]));
         let resp = warp::test::request()
             .path("/my-index/ingest")
             .method("POST")
@@ -458,7 +817,8 @@ mod tests {
     async fn test_ingest_api_returns_200_when_ingest_ndjson_and_fetch() {
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
-        let ingest_api_handlers = ingest_api_handlers(ingest_service);
+        let ingest_api_handlers =
+            ingest_api_handlers(ingest_service, mock_metastore(&["my-index"]));
         let payload = r#"
             {"id": 1, "message": "push"}
             {"id": 2, "message": "push"}
@@ -478,15 +838,16 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_ingest_api_bulk_request_returns_404_if_index_id_does_not_exist() {
+    async fn test_ingest_api_bulk_request_reports_missing_index_per_item() {
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
-        let ingest_api_handlers = ingest_api_handlers(ingest_service);
+        let ingest_api_handlers =
+            ingest_api_handlers(ingest_service, mock_metastore(&["my-index"]));
         let payload = r#"
             { "create" : { "_index" : "my-index", "_id" : "1"} }
-            {"id": 1, "message": "push"}
+            {"id": 1, "message": "push", "timestamp": 1684159200}
             { "create" : { "_index" : "index-2", "_id" : "1" } }
-            {"id": 1, "message": "push"}
+            {"id": 1, "message": "push", "timestamp": 1684159200}
         "#;
         let resp = warp::test::request()
             .path("/_bulk")
@@ -494,18 +855,87 @@ mod tests {
             .body(payload)
             .reply(&ingest_api_handlers)
             .await;
-        assert_eq!(resp.status(), 404);
+        assert_eq!(resp.status(), 200);
+        let bulk_response: ElasticBulkResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert!(bulk_response.errors);
+        assert_eq!(bulk_response.items.len(), 2);
+        let ElasticBulkItem::Create(item_status) = &bulk_response.items[0] else {
+            panic!("Expected a create item.");
+        };
+        assert_eq!(item_status.status, 201);
+        assert!(item_status.error.is_none());
+        let ElasticBulkItem::Create(item_status) = &bulk_response.items[1] else {
+            panic!("Expected a create item.");
+        };
+        assert_eq!(item_status.index, "index-2");
+        assert_eq!(item_status.status, 404);
+        assert_eq!(
+            item_status.error.as_ref().unwrap().error_type,
+            "index_not_found_exception"
+        );
         universe.assert_quit().await;
     }
 
     #[tokio::test]
-    async fn test_ingest_api_bulk_request_returns_400_if_malformed_source() {
-        let (universe, _temp_dir, ingest_service, _) =
+    async fn test_ingest_api_bulk_request_reports_invalid_docs_per_item() {
+        let (universe, _temp_dir, ingest_service, ingest_service_mailbox) =
             setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
-        let ingest_api_handlers = ingest_api_handlers(ingest_service);
+        let ingest_api_handlers =
+            ingest_api_handlers(ingest_service, mock_metastore(&["my-index"]));
         let payload = r#"
             { "create" : { "_index" : "my-index", "_id" : "1" } }
             {"id": 1, "message": "bad json}
+            { "index" : { "_index" : "my-index", "_id" : "2" } }
+            {"id": 2, "message": "missing timestamp"}
+            { "create" : { "_index" : "my-index", "_id" : "3" } }
+            {"id": 3, "message": "push", "timestamp": 1684159200}
+        "#;
+        let resp = warp::test::request()
+            .path("/_bulk")
+            .method("POST")
+            .body(payload)
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let bulk_response: ElasticBulkResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert!(bulk_response.errors);
+        let statuses: Vec<u16> = bulk_response
+            .items
+            .iter()
+            .map(|item| match item {
+                ElasticBulkItem::Index(item_status) => item_status.status,
+                ElasticBulkItem::Create(item_status) => item_status.status,
+            })
+            .collect();
+        assert_eq!(statuses, [400, 400, 201]);
+        let ElasticBulkItem::Index(item_status) = &bulk_response.items[1] else {
+            panic!("Expected an index item.");
+        };
+        assert_eq!(item_status.id.as_deref(), Some("2"));
+        let error = item_status.error.as_ref().unwrap();
+        assert_eq!(error.error_type, "mapper_parsing_exception");
+        assert!(error.reason.contains("timestamp"));
+        // Only the valid document is ingested.
+        let fetch_response = ingest_service_mailbox
+            .ask_for_res(FetchRequest {
+                index_id: "my-index".to_string(),
+                start_after: None,
+                num_bytes_limit: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(fetch_response.doc_batch.unwrap().num_docs(), 1);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_ingest_api_bulk_request_returns_400_if_missing_source() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
+        let ingest_api_handlers =
+            ingest_api_handlers(ingest_service, mock_metastore(&["my-index"]));
+        let payload = r#"
+            { "create" : { "_index" : "my-index", "_id" : "1" } }
         "#;
         let resp = warp::test::request()
             .path("/_bulk")
@@ -521,14 +951,17 @@ mod tests {
     async fn test_ingest_api_bulk_returns_200() {
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index-1", "my-index-2"], &IngestApiConfig::default()).await;
-        let ingest_api_handlers = ingest_api_handlers(ingest_service);
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_service,
+            mock_metastore(&["my-index-1", "my-index-2"]),
+        );
         let payload = r#"
             { "create" : { "_index" : "my-index-1", "_id" : "1"} }
-            {"id": 1, "message": "push"}
+            {"id": 1, "message": "push", "timestamp": 1684159200}
             { "create" : { "_index" : "my-index-2", "_id" : "1"} }
-            {"id": 1, "message": "push"}
+            {"id": 1, "message": "push", "timestamp": 1684159200}
             { "create" : { "_index" : "my-index-1" } }
-            {"id": 2, "message": "push"}
+            {"id": 2, "message": "push", "timestamp": 1684159200}
         "#;
         let resp = warp::test::request()
             .path("/_bulk")
@@ -537,8 +970,9 @@ mod tests {
             .reply(&ingest_api_handlers)
             .await;
         assert_eq!(resp.status(), 200);
-        let ingest_response: IngestResponse = serde_json::from_slice(resp.body()).unwrap();
-        assert_eq!(ingest_response.num_docs_for_processing, 3);
+        let bulk_response: ElasticBulkResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert!(!bulk_response.errors);
+        assert_eq!(bulk_response.items.len(), 3);
         universe.assert_quit().await;
     }
 
@@ -550,7 +984,8 @@ mod tests {
         };
         let (universe, _temp_dir, ingest_service, _) =
             setup_ingest_service(&["my-index"], &config).await;
-        let ingest_api_handlers = ingest_api_handlers(ingest_service);
+        let ingest_api_handlers =
+            ingest_api_handlers(ingest_service, mock_metastore(&["my-index"]));
         let resp = warp::test::request()
             .path("/my-index/ingest")
             .method("POST")
@@ -566,7 +1001,8 @@ mod tests {
     async fn test_ingest_api_blocks_when_wait_is_specified() {
         let (universe, _temp_dir, ingest_service_client, ingest_service_mailbox) =
             setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
-        let ingest_api_handlers = ingest_api_handlers(ingest_service_client);
+        let ingest_api_handlers =
+            ingest_api_handlers(ingest_service_client, mock_metastore(&["my-index"]));
         let handle = tokio::spawn(async move {
             let resp = warp::test::request()
                 .path("/my-index/ingest?commit=wait_for")
@@ -610,7 +1046,8 @@ mod tests {
     async fn test_ingest_api_blocks_when_force_is_specified() {
         let (universe, _temp_dir, ingest_service_client, ingest_service_mailbox) =
             setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
-        let ingest_api_handlers = ingest_api_handlers(ingest_service_client);
+        let ingest_api_handlers =
+            ingest_api_handlers(ingest_service_client, mock_metastore(&["my-index"]));
         let handle = tokio::spawn(async move {
             let resp = warp::test::request()
                 .path("/my-index/ingest?commit=force")
@@ -688,14 +1125,17 @@ mod tests {
     async fn test_bulk_api_blocks_when_refresh_wait_for_is_specified() {
         let (universe, _temp_dir, ingest_service, ingest_service_mailbox) =
             setup_ingest_service(&["my-index-1", "my-index-2"], &IngestApiConfig::default()).await;
-        let ingest_api_handlers = ingest_api_handlers(ingest_service);
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_service,
+            mock_metastore(&["my-index-1", "my-index-2"]),
+        );
         let payload = r#"
             { "create" : { "_index" : "my-index-1", "_id" : "1"} }
-            {"id": 1, "message": "push"}
+            {"id": 1, "message": "push", "timestamp": 1684159200}
             { "create" : { "_index" : "my-index-2", "_id" : "1"} }
-            {"id": 1, "message": "push"}
+            {"id": 1, "message": "push", "timestamp": 1684159200}
             { "create" : { "_index" : "my-index-1" } }
-            {"id": 2, "message": "push"}
+            {"id": 2, "message": "push", "timestamp": 1684159200}
         "#;
         let handle = tokio::spawn(async move {
             let resp = warp::test::request()
@@ -706,8 +1146,9 @@ mod tests {
                 .await;
 
             assert_eq!(resp.status(), 200);
-            let ingest_response: IngestResponse = serde_json::from_slice(resp.body()).unwrap();
-            assert_eq!(ingest_response.num_docs_for_processing, 3);
+            let bulk_response: ElasticBulkResponse = serde_json::from_slice(resp.body()).unwrap();
+            assert!(!bulk_response.errors);
+            assert_eq!(bulk_response.items.len(), 3);
         });
         universe.sleep(Duration::from_secs(10)).await;
         assert!(!handle.is_finished());
@@ -764,14 +1205,17 @@ mod tests {
     async fn test_bulk_api_blocks_when_refresh_true_is_specified() {
         let (universe, _temp_dir, ingest_service, ingest_service_mailbox) =
             setup_ingest_service(&["my-index-1", "my-index-2"], &IngestApiConfig::default()).await;
-        let ingest_api_handlers = ingest_api_handlers(ingest_service);
+        let ingest_api_handlers = ingest_api_handlers(
+            ingest_service,
+            mock_metastore(&["my-index-1", "my-index-2"]),
+        );
         let payload = r#"
             { "create" : { "_index" : "my-index-1", "_id" : "1"} }
-            {"id": 1, "message": "push"}
+            {"id": 1, "message": "push", "timestamp": 1684159200}
             { "create" : { "_index" : "my-index-2", "_id" : "1"} }
-            {"id": 1, "message": "push"}
+            {"id": 1, "message": "push", "timestamp": 1684159200}
             { "create" : { "_index" : "my-index-1" } }
-            {"id": 2, "message": "push"}
+            {"id": 2, "message": "push", "timestamp": 1684159200}
         "#;
         let handle = tokio::spawn(async move {
             let resp = warp::test::request()
@@ -782,8 +1226,9 @@ mod tests {
                 .await;
 
             assert_eq!(resp.status(), 200);
-            let ingest_response: IngestResponse = serde_json::from_slice(resp.body()).unwrap();
-            assert_eq!(ingest_response.num_docs_for_processing, 3);
+            let bulk_response: ElasticBulkResponse = serde_json::from_slice(resp.body()).unwrap();
+            assert!(!bulk_response.errors);
+            assert_eq!(bulk_response.items.len(), 3);
         });
         universe.sleep(Duration::from_secs(10)).await;
         assert!(!handle.is_finished());
@@ -834,4 +1279,131 @@ mod tests {
         handle.await.unwrap();
         universe.assert_quit().await;
     }
+
+    #[tokio::test]
+    async fn test_dead_letters_list_and_replay() {
+        let queue_id = dead_letter_queue_id("my-index", "my-source");
+        let (universe, _temp_dir, _, ingest_service_mailbox) = setup_ingest_service(
+            &["my-index", queue_id.as_str()],
+            &IngestApiConfig::default(),
+        )
+        .await;
+        let mut doc_batch = DocBatchBuilder::new(queue_id).json_writer();
+        for id in 1..=2 {
+            doc_batch
+                .ingest_doc(DeadLetter {
+                    doc: Bytes::from(format!(r#"{{"id": {id}, "message": "push"}}"#)),
+                    error_kind: DeadLetterErrorKind::ParsingError,
+                    error_message: "Failed to parse document.".to_string(),
+                    source_position: DeadLetterSourcePosition {
+                        batch_delta: "∆(:(..00000000000000000001])".to_string(),
+                        doc_offset: id - 1,
+                    },
+                })
+                .unwrap();
+        }
+        ingest_service_mailbox
+            .ask_for_res(IngestRequest {
+                doc_batches: vec![doc_batch.build()],
+                commit: CommitType::Auto as u32,
+            })
+            .await
+            .unwrap();
+        let mut index_metadata = IndexMetadata::for_test("my-index", "ram:///indexes/my-index");
+        index_metadata
+            .add_source(SourceConfig::for_test("my-source", SourceParams::void()))
+            .unwrap();
+        let mut csv_source_config = SourceConfig::for_test("my-csv-source", SourceParams::void());
+        csv_source_config.input_format = SourceInputFormat::Csv;
+        index_metadata.add_source(csv_source_config).unwrap();
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(move |_| Ok(index_metadata.clone()));
+        let dead_letters_handlers =
+            dead_letters_handlers(Some(ingest_service_mailbox.clone()), Arc::new(metastore));
+
+        let resp = warp::test::request()
+            .path("/indexes/my-index/sources/my-source/dead-letters?limit=1")
+            .method("GET")
+            .reply(&dead_letters_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let dead_letters: Vec<DeadLetterRecord> = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(
+            dead_letters[0].dead_letter.error_kind,
+            DeadLetterErrorKind::ParsingError
+        );
+        let resp = warp::test::request()
+            .path(&format!(
+                "/indexes/my-index/sources/my-source/dead-letters?start_after={}",
+                dead_letters[0].position
+            ))
+            .method("GET")
+            .reply(&dead_letters_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let dead_letters: Vec<DeadLetterRecord> = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(
+            dead_letters[0].dead_letter.doc,
+            r#"{"id": 2, "message": "push"}"#
+        );
+
+        let resp = warp::test::request()
+            .path("/indexes/my-index/sources/my-csv-source/dead-letters/replay")
+            .method("POST")
+            .reply(&dead_letters_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .path("/indexes/my-index/sources/my-unknown-source/dead-letters/replay")
+            .method("POST")
+            .reply(&dead_letters_handlers)
+            .await;
+        assert_eq!(resp.status(), 404);
+
+        let resp = warp::test::request()
+            .path("/indexes/my-index/sources/my-source/dead-letters/replay")
+            .method("POST")
+            .reply(&dead_letters_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let replay_response: ReplayDeadLettersResponse =
+            serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(replay_response.num_replayed_docs, 2);
+
+        let resp = warp::test::request()
+            .path("/indexes/my-index/sources/my-source/dead-letters")
+            .method("GET")
+            .reply(&dead_letters_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let dead_letters: Vec<DeadLetterRecord> = serde_json::from_slice(resp.body()).unwrap();
+        assert!(dead_letters.is_empty());
+
+        let fetch_response = ingest_service_mailbox
+            .ask_for_res(FetchRequest {
+                index_id: "my-index".to_string(),
+                start_after: None,
+                num_bytes_limit: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(fetch_response.doc_batch.unwrap().num_docs(), 2);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_dead_letters_requires_indexer() {
+        let dead_letters_handlers = dead_letters_handlers(None, mock_metastore(&["my-index"]));
+        let resp = warp::test::request()
+            .path("/indexes/my-index/sources/my-source/dead-letters")
+            .method("GET")
+            .reply(&dead_letters_handlers)
+            .await;
+        assert_eq!(resp.status(), 404);
+    }
 }
@@ -67,7 +67,8 @@ use quickwit_grpc_clients::service_client_pool::ServiceClientPool;
 use quickwit_indexing::actors::IndexingService;
 use quickwit_indexing::start_indexing_service;
 use quickwit_ingest::{
-    start_ingest_api_service, GetMemoryCapacity, IngestRequest, IngestServiceClient, MemoryCapacity,
+    start_ingest_api_service, GetMemoryCapacity, IngestApiService, IngestRequest,
+    IngestServiceClient, MemoryCapacity,
 };
 use quickwit_janitor::{start_janitor_service, JanitorService};
 use quickwit_metastore::{
@@ -83,6 +84,7 @@ use tracing::{debug, error, warn};
 use warp::{Filter, Rejection};
 
 pub use crate::index_api::ListSplitsQueryParams;
+pub use crate::ingest_api::ReplayDeadLettersResponse;
 pub use crate::metrics::SERVE_METRICS;
 #[cfg(test)]
 use crate::rest::recover_fn;
@@ -111,6 +113,8 @@ struct QuickwitServices {
     pub indexing_service: Option<Mailbox<IndexingService>>,
     pub janitor_service: Option<Mailbox<JanitorService>>,
     pub ingest_service: IngestServiceClient,
+    /// Local ingest API service, only available on nodes running the indexer service.
+    pub ingest_api_service: Option<Mailbox<IngestApiService>>,
     pub index_service: Arc<IndexService>,
     pub services: HashSet<QuickwitService>,
 }
@@ -208,7 +212,7 @@ pub async fn serve_quickwit(config: QuickwitConfig) -> anyhow::Result<()> {
             event_broker.subscribe::<MetastoreEvent>(scheduler_service.clone())
         });
 
-    let (ingest_service, indexing_service) = if config
+    let (ingest_service, ingest_api_service_opt, indexing_service) = if config
         .enabled_services
         .contains(&QuickwitService::Indexer)
     {
@@ -259,8 +263,12 @@ pub async fn serve_quickwit(config: QuickwitConfig) -> anyhow::Result<()> {
                     .layer(RateLimitLayer::new(rate_modulator))
                     .into_inner(),
             )
-            .build_from_mailbox(ingest_api_service);
-        (ingest_service, Some(indexing_service))
+            .build_from_mailbox(ingest_api_service.clone());
+        (
+            ingest_service,
+            Some(ingest_api_service),
+            Some(indexing_service),
+        )
     } else {
         let (channel, _) = create_balance_channel_from_watched_members(
             cluster.ready_member_change_watcher(),
@@ -268,7 +276,7 @@ pub async fn serve_quickwit(config: QuickwitConfig) -> anyhow::Result<()> {
         )
         .await?;
         let ingest_service = IngestServiceClient::from_channel(channel);
-        (ingest_service, None)
+        (ingest_service, None, None)
     };
 
     let search_job_placer = SearchJobPlacer::new(
@@ -311,6 +319,7 @@ pub async fn serve_quickwit(config: QuickwitConfig) -> anyhow::Result<()> {
         indexing_service,
         janitor_service,
         ingest_service,
+        ingest_api_service: ingest_api_service_opt,
         index_service,
         services,
     };
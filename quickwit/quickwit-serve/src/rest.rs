@@ -38,7 +38,7 @@ use crate::format::ApiError;
 use crate::health_check_api::health_check_handlers;
 use crate::index_api::index_management_handlers;
 use crate::indexing_api::indexing_get_handler;
-use crate::ingest_api::ingest_api_handlers;
+use crate::ingest_api::{dead_letters_handlers, ingest_api_handlers};
 use crate::node_info_handler::node_info_handler;
 use crate::search_api::{search_get_handler, search_post_handler, search_stream_handler};
 use crate::ui_handler::ui_handler;
@@ -94,7 +94,14 @@ pub(crate) async fn start_rest_server(
         .or(search_stream_handler(
             quickwit_services.search_service.clone(),
         ))
-        .or(ingest_api_handlers(ingest_service.clone()))
+        .or(ingest_api_handlers(
+            ingest_service.clone(),
+            quickwit_services.metastore.clone(),
+        ))
+        .or(dead_letters_handlers(
+            quickwit_services.ingest_api_service.clone(),
+            quickwit_services.metastore.clone(),
+        ))
         .or(index_management_handlers(
             quickwit_services.index_service.clone(),
             quickwit_services.config.clone(),
@@ -104,6 +111,7 @@ pub(crate) async fn start_rest_server(
         ))
         .or(elastic_api_handlers(
             quickwit_services.search_service.clone(),
+            quickwit_services.metastore.clone(),
         ));
 
     let api_v1_root_route = api_v1_root_url.and(api_v1_routes);
@@ -23,9 +23,10 @@ use std::sync::Arc;
 use futures::stream::StreamExt;
 use hyper::header::HeaderValue;
 use hyper::HeaderMap;
+use quickwit_common::is_false;
 use quickwit_common::simple_list::{from_simple_list, to_simple_list};
 use quickwit_proto::{OutputFormat, ServiceError, SortOrder};
-use quickwit_search::{SearchError, SearchResponseRest, SearchService};
+use quickwit_search::{parse_search_after_cursor, SearchError, SearchResponseRest, SearchService};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value as JsonValue;
 use tracing::info;
@@ -167,6 +168,42 @@ pub struct SearchRequestQueryString {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sort_by_field: Option<SortByField>,
+    /// If set, only returns hits sorted after the hit pointed by this cursor. Cursors are
+    /// returned as `next_search_after` in search responses and allow deep pagination
+    /// without `start_offset`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_after: Option<String>,
+    /// If set, restricts search to these splits, even if they have been marked for deletion
+    /// since.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "to_simple_list")]
+    pub split_ids: Option<Vec<String>>,
+    /// If set, the response lists the splits the search ran on, so that the following pages
+    /// can be fetched from the same splits.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub pin_splits: bool,
+    /// If set, the response explains how the query was parsed, which splits were searched or
+    /// pruned, and how the score of each hit was computed.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub explain: bool,
+    /// If set, the returned documents only contain the fields matching one of these patterns.
+    /// Comma-separated list, e.g. "timestamp,attributes.*".
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "to_simple_list")]
+    pub source_includes: Option<Vec<String>>,
+    /// The fields matching one of these patterns are removed from the returned documents.
+    /// Comma-separated list, e.g. "payload,attributes.*".
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "to_simple_list")]
+    pub source_excludes: Option<Vec<String>>,
 }
 
 fn get_proto_search_by(search_request: &SearchRequestQueryString) -> (Option<i32>, Option<String>) {
@@ -186,6 +223,11 @@ async fn search_endpoint(
     search_service: &dyn SearchService,
 ) -> Result<SearchResponseRest, SearchError> {
     let (sort_order, sort_by_field) = get_proto_search_by(&search_request);
+    let search_after = search_request
+        .search_after
+        .as_deref()
+        .map(parse_search_after_cursor)
+        .transpose()?;
     let search_request = quickwit_proto::SearchRequest {
         index_id,
         query: search_request.query,
@@ -200,6 +242,15 @@ async fn search_endpoint(
             .map(|agg| serde_json::to_string(&agg).expect("could not serialize JsonValue")),
         sort_order,
         sort_by_field,
+        query_dsl: None,
+        search_after,
+        split_ids: search_request.split_ids.unwrap_or_default(),
+        pin_splits: search_request.pin_splits,
+        sort_fields: Vec::new(),
+        explain: search_request.explain,
+        highlight: None,
+        source_includes: search_request.source_includes.unwrap_or_default(),
+        source_excludes: search_request.source_excludes.unwrap_or_default(),
     };
     let search_response = search_service.root_search(search_request).await?;
     let search_response_rest = SearchResponseRest::try_from(search_response)?;
@@ -452,6 +503,10 @@ mod tests {
             elapsed_time_micros: 0u64,
             errors: Vec::new(),
             aggregations: None,
+            next_search_after: None,
+            split_ids: Vec::new(),
+            explanation: None,
+            hit_explanations: None,
         };
         let search_response_json: JsonValue = serde_json::to_value(&search_response)?;
         let expected_search_response_json: JsonValue = json!({
@@ -658,7 +713,7 @@ mod tests {
         assert_eq!(resp.status(), 400);
         let resp_json: JsonValue = serde_json::from_slice(resp.body())?;
         let exp_resp_json = serde_json::json!({
-            "message": "unknown field `end_unix_timestamp`, expected one of `query`, `aggs`, `search_field`, `snippet_fields`, `start_timestamp`, `end_timestamp`, `max_hits`, `start_offset`, `format`, `sort_by_field`"
+            "message": "unknown field `end_unix_timestamp`, expected one of `query`, `aggs`, `search_field`, `snippet_fields`, `start_timestamp`, `end_timestamp`, `max_hits`, `start_offset`, `format`, `sort_by_field`, `search_after`, `split_ids`, `pin_splits`, `explain`, `source_includes`, `source_excludes`"
         });
         assert_eq!(resp_json, exp_resp_json);
         Ok(())
@@ -730,6 +785,146 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_search_after() -> anyhow::Result<()> {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.search_after
+                        == Some(quickwit_proto::PartialHit {
+                            sorting_field_value: 10,
+                            split_id: "split-1".to_string(),
+                            segment_ord: 0,
+                            doc_id: 3,
+                            secondary_sorting_field_values: Vec::new(),
+                        })
+                        && search_request.split_ids == ["split-1", "split-2"]
+                        && search_request.pin_splits
+                },
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::SearchResponse {
+                    hits: vec![quickwit_proto::Hit {
+                        json: r#"{"title": "foo"}"#.to_string(),
+                        partial_hit: Some(quickwit_proto::PartialHit {
+                            sorting_field_value: 8,
+                            split_id: "split-2".to_string(),
+                            segment_ord: 1,
+                            doc_id: 5,
+                            secondary_sorting_field_values: Vec::new(),
+                        }),
+                        snippet: None,
+                        explanation: None,
+                        sort_values_json: None,
+                    }],
+                    num_hits: 12,
+                    split_ids: vec!["split-1".to_string(), "split-2".to_string()],
+                    ..Default::default()
+                })
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .path(
+                "/quickwit-demo-index/search?query=*&search_after=10:split-1:0:3&\
+                 split_ids=split-1,split-2&pin_splits=true",
+            )
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body())?;
+        let expected_response_json = serde_json::json!({
+            "num_hits": 12,
+            "next_search_after": "8:split-2:1:5",
+            "split_ids": ["split-1", "split-2"],
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_invalid_search_after() {
+        let rest_search_api_handler = search_handler(MockSearchService::new());
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search?query=*&search_after=foo")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        let content = String::from_utf8_lossy(resp.body());
+        assert!(content.contains("Invalid cursor `foo`."));
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_source_includes_excludes() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| {
+                    search_request.source_includes == ["timestamp", "attributes.*"]
+                        && search_request.source_excludes == ["attributes.secret"]
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .path(
+                "/quickwit-demo-index/search?query=*&source_includes=timestamp,attributes.*&\
+                 source_excludes=attributes.secret",
+            )
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_api_explain() -> anyhow::Result<()> {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::SearchRequest| search_request.explain,
+            ))
+            .returning(|_| {
+                Ok(quickwit_proto::SearchResponse {
+                    hits: vec![quickwit_proto::Hit {
+                        json: r#"{"title": "foo"}"#.to_string(),
+                        partial_hit: None,
+                        snippet: None,
+                        explanation: Some(
+                            r#"{"value": 0.5, "description": "TermQuery"}"#.to_string(),
+                        ),
+                        sort_values_json: None,
+                    }],
+                    num_hits: 1,
+                    explanation: Some(
+                        r#"{"query": "TermQuery", "searched_splits": ["split-1"], "pruned_splits": []}"#
+                            .to_string(),
+                    ),
+                    ..Default::default()
+                })
+            });
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .path("/quickwit-demo-index/search?query=title:foo&explain=true")
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body())?;
+        let expected_response_json = serde_json::json!({
+            "num_hits": 1,
+            "explanation": {
+                "query": "TermQuery",
+                "searched_splits": ["split-1"],
+                "pruned_splits": [],
+            },
+            "hit_explanations": [{"value": 0.5, "description": "TermQuery"}],
+        });
+        assert_json_include!(actual: resp_json, expected: expected_response_json);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_with_index_does_not_exist() -> anyhow::Result<()> {
         let mut mock_search_service = MockSearchService::new();
@@ -899,6 +1094,8 @@ mod tests {
                     json: r#"{"title": "foo", "body": "foo bar baz"}"#.to_string(),
                     partial_hit: None,
                     snippet: Some(r#"{"title": [], "body": ["foo <em>bar</em> baz"]}"#.to_string()),
+                    explanation: None,
+                    sort_values_json: None,
                 }],
                 num_hits: 1,
                 elapsed_time_micros: 16,
@@ -121,6 +121,15 @@ async fn test_standalone_server() {
                 sort_by_field: None,
                 sort_order: None,
                 start_offset: 0,
+                query_dsl: None,
+                search_after: None,
+                split_ids: Vec::new(),
+                pin_splits: false,
+                sort_fields: Vec::new(),
+                explain: false,
+                highlight: None,
+                source_includes: Vec::new(),
+                source_excludes: Vec::new(),
             })
             .await
             .unwrap();
@@ -203,6 +212,15 @@ async fn test_multi_nodes_cluster() {
         sort_order: None,
         start_offset: 0,
         snippet_fields: Vec::new(),
+        query_dsl: None,
+        search_after: None,
+        split_ids: Vec::new(),
+        pin_splits: false,
+        sort_fields: Vec::new(),
+        explain: false,
+        highlight: None,
+        source_includes: Vec::new(),
+        source_excludes: Vec::new(),
     };
     let search_response_empty = search_client
         .root_search(search_request.clone())
@@ -0,0 +1,558 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+use tracing::{error, warn};
+
+use crate::cache::Cache;
+use crate::metrics::CacheMetrics;
+use crate::OwnedBytes;
+
+const FULL_SLICE: Range<usize> = 0..usize::MAX;
+
+const INDEX_FILE_NAME: &str = "index.json";
+
+const SLICE_FILE_EXTENSION: &str = "slice";
+
+/// Rewriting the index on every put would be prohibitive for large caches, so it is flushed
+/// periodically and when the cache is dropped. Slices written after the last flush are reloaded on
+/// restart, but lose their recency.
+const INDEX_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexEntry {
+    file_name: String,
+    num_bytes: u64,
+}
+
+struct NeedMutLocalDiskCache {
+    // Maps the slice file names to their size.
+    lru_cache: LruCache<String, u64>,
+    num_bytes: u64,
+    // Whether the cached slices or their recency changed since the last index flush.
+    is_index_dirty: bool,
+}
+
+impl NeedMutLocalDiskCache {
+    /// Lists the cached slices ordered from the least to the most recently used.
+    fn index_entries(&self) -> Vec<IndexEntry> {
+        self.lru_cache
+            .iter()
+            .rev()
+            .map(|(file_name, num_bytes)| IndexEntry {
+                file_name: file_name.clone(),
+                num_bytes: *num_bytes,
+            })
+            .collect()
+    }
+}
+
+/// A size-bounded cache storing slices as files in a local directory. The slices are evicted in
+/// LRU order.
+///
+/// The cache survives restarts: the list of cached slices and their recency is persisted in an
+/// index file and reloaded by [`LocalDiskCache::open`].
+pub struct LocalDiskCache {
+    root_path: PathBuf,
+    capacity_in_bytes: u64,
+    inner: Mutex<NeedMutLocalDiskCache>,
+    index_flush_lock: tokio::sync::Mutex<()>,
+    cache_counters: &'static CacheMetrics,
+}
+
+impl LocalDiskCache {
+    /// Opens the cache located in `root_path`, creating the directory if necessary.
+    ///
+    /// Slices written after the last index flush are kept as the least recently used ones: slices
+    /// are written atomically, so their files are always complete. Slices whose size does not
+    /// match the index, and any other file, are removed.
+    pub fn open(
+        root_path: &Path,
+        capacity_in_bytes: u64,
+        cache_counters: &'static CacheMetrics,
+    ) -> io::Result<Self> {
+        std::fs::create_dir_all(root_path)?;
+
+        let index_entries: Vec<IndexEntry> = match std::fs::read(root_path.join(INDEX_FILE_NAME))
+        {
+            Ok(index_bytes) => serde_json::from_slice(&index_bytes).unwrap_or_else(|error| {
+                warn!(error=?error, root_path=%root_path.display(), "Disk cache index is corrupted.");
+                Vec::new()
+            }),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => return Err(error),
+        };
+        let indexed_num_bytes: HashMap<&str, u64> = index_entries
+            .iter()
+            .map(|index_entry| (index_entry.file_name.as_str(), index_entry.num_bytes))
+            .collect();
+        let mut valid_indexed_file_names: HashSet<String> = HashSet::new();
+        let mut unindexed_slices: Vec<(String, u64)> = Vec::new();
+
+        for dir_entry_res in std::fs::read_dir(root_path)? {
+            let dir_entry = dir_entry_res?;
+            let file_name = dir_entry.file_name().to_string_lossy().to_string();
+
+            if file_name == INDEX_FILE_NAME {
+                continue;
+            }
+            let file_num_bytes_opt = dir_entry
+                .metadata()
+                .ok()
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| metadata.len());
+            let is_slice_file = Path::new(&file_name)
+                .extension()
+                .map_or(false, |extension| extension == SLICE_FILE_EXTENSION);
+
+            match (
+                indexed_num_bytes.get(file_name.as_str()),
+                file_num_bytes_opt,
+            ) {
+                (Some(indexed_num_bytes), Some(file_num_bytes))
+                    if *indexed_num_bytes == file_num_bytes =>
+                {
+                    valid_indexed_file_names.insert(file_name);
+                }
+                (None, Some(file_num_bytes)) if is_slice_file => {
+                    unindexed_slices.push((file_name, file_num_bytes));
+                }
+                _ => remove_file_if_exists(&dir_entry.path()),
+            }
+        }
+        let mut lru_cache = LruCache::unbounded();
+        let mut num_bytes = 0;
+
+        for (file_name, file_num_bytes) in unindexed_slices {
+            num_bytes += file_num_bytes;
+            lru_cache.put(file_name, file_num_bytes);
+        }
+        // The index entries are ordered from the least to the most recently used.
+        for index_entry in index_entries {
+            if valid_indexed_file_names.contains(&index_entry.file_name) {
+                num_bytes += index_entry.num_bytes;
+                lru_cache.put(index_entry.file_name, index_entry.num_bytes);
+            }
+        }
+        // The capacity may have been lowered since the cache was last used.
+        while num_bytes > capacity_in_bytes {
+            let Some((file_name, file_num_bytes)) = lru_cache.pop_lru() else {
+                break;
+            };
+            num_bytes -= file_num_bytes;
+            remove_file_if_exists(&root_path.join(file_name));
+        }
+        cache_counters.in_cache_count.add(lru_cache.len() as i64);
+        cache_counters.in_cache_num_bytes.add(num_bytes as i64);
+
+        let inner = NeedMutLocalDiskCache {
+            lru_cache,
+            num_bytes,
+            is_index_dirty: true,
+        };
+        Ok(Self {
+            root_path: root_path.to_path_buf(),
+            capacity_in_bytes,
+            inner: Mutex::new(inner),
+            index_flush_lock: tokio::sync::Mutex::new(()),
+            cache_counters,
+        })
+    }
+
+    async fn get_slice(&self, path: &Path, byte_range: Range<usize>) -> Option<OwnedBytes> {
+        let file_name = slice_file_name(path, &byte_range);
+        let num_bytes_opt = {
+            let mut inner = self.inner.lock().unwrap();
+            let num_bytes_opt = inner.lru_cache.get(&file_name).copied();
+            inner.is_index_dirty |= num_bytes_opt.is_some();
+            num_bytes_opt
+        };
+
+        let Some(num_bytes) = num_bytes_opt else {
+            self.cache_counters.misses_num_items.inc();
+            return None;
+        };
+        match tokio::fs::read(self.root_path.join(&file_name)).await {
+            Ok(bytes) if bytes.len() as u64 == num_bytes => {
+                self.cache_counters.hits_num_items.inc();
+                self.cache_counters.hits_num_bytes.inc_by(num_bytes);
+                Some(OwnedBytes::new(bytes))
+            }
+            // The slice was evicted concurrently.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                self.cache_counters.misses_num_items.inc();
+                None
+            }
+            read_res => {
+                if let Err(error) = read_res {
+                    error!(error=?error, file_name=%file_name, "Failed to read disk cache slice.");
+                }
+                self.cache_counters.misses_num_items.inc();
+                self.evict(&file_name);
+                None
+            }
+        }
+    }
+
+    async fn put_slice(&self, path: &Path, byte_range: Range<usize>, bytes: OwnedBytes) {
+        let num_bytes = bytes.len() as u64;
+
+        if num_bytes > self.capacity_in_bytes {
+            // The value does not fit in the cache. We simply don't store it.
+            return;
+        }
+        let file_name = slice_file_name(path, &byte_range);
+
+        if self.inner.lock().unwrap().lru_cache.contains(&file_name) {
+            return;
+        }
+        // Slices are written to a unique temporary file first so that neither a crash nor a
+        // concurrent put of the same slice leaves a truncated slice behind a valid file name.
+        let root_path = self.root_path.clone();
+        let tmp_file_name = file_name.clone();
+        let write_res = tokio::task::spawn_blocking(move || {
+            write_file_atomically(&root_path, &tmp_file_name, &bytes)
+        })
+        .await
+        .unwrap_or_else(|join_error| Err(join_error.into()));
+
+        if let Err(error) = write_res {
+            error!(error=?error, file_name=%file_name, "Failed to write disk cache slice.");
+            return;
+        }
+        let mut evicted_file_names = Vec::new();
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.is_index_dirty = true;
+
+            if let Some(previous_num_bytes) = inner.lru_cache.put(file_name, num_bytes) {
+                inner.num_bytes -= previous_num_bytes;
+                self.cache_counters.in_cache_count.dec();
+                self.cache_counters
+                    .in_cache_num_bytes
+                    .sub(previous_num_bytes as i64);
+            }
+            inner.num_bytes += num_bytes;
+            self.cache_counters.in_cache_count.inc();
+            self.cache_counters.in_cache_num_bytes.add(num_bytes as i64);
+
+            while inner.num_bytes > self.capacity_in_bytes {
+                let Some((evicted_file_name, evicted_num_bytes)) = inner.lru_cache.pop_lru() else {
+                    break;
+                };
+                inner.num_bytes -= evicted_num_bytes;
+                self.cache_counters.in_cache_count.dec();
+                self.cache_counters
+                    .in_cache_num_bytes
+                    .sub(evicted_num_bytes as i64);
+                evicted_file_names.push(evicted_file_name);
+            }
+        }
+        for evicted_file_name in evicted_file_names {
+            remove_file_if_exists(&self.root_path.join(evicted_file_name));
+        }
+    }
+
+    fn evict(&self, file_name: &str) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(num_bytes) = inner.lru_cache.pop(file_name) {
+            inner.is_index_dirty = true;
+            inner.num_bytes -= num_bytes;
+            self.cache_counters.in_cache_count.dec();
+            self.cache_counters.in_cache_num_bytes.sub(num_bytes as i64);
+        }
+        remove_file_if_exists(&self.root_path.join(file_name));
+    }
+
+    /// Persists the list of cached slices ordered from the least to the most recently used.
+    pub async fn flush_index(&self) -> io::Result<()> {
+        // A concurrent flush is about to persist an equivalent index.
+        let Ok(_guard) = self.index_flush_lock.try_lock() else {
+            return Ok(());
+        };
+        let index_entries = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.is_index_dirty = false;
+            inner.index_entries()
+        };
+        let index_bytes = serde_json::to_vec(&index_entries)?;
+        let root_path = self.root_path.clone();
+        tokio::task::spawn_blocking(move || {
+            write_file_atomically(&root_path, INDEX_FILE_NAME, &index_bytes)
+        })
+        .await?
+    }
+
+    /// Spawns a task flushing the index every [`INDEX_FLUSH_INTERVAL`] if it changed. The task
+    /// stops once the cache is dropped.
+    pub fn spawn_index_flush_loop(self: &Arc<Self>) {
+        let cache_weak = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(INDEX_FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                let Some(cache) = cache_weak.upgrade() else {
+                    return;
+                };
+                if !cache.inner.lock().unwrap().is_index_dirty {
+                    continue;
+                }
+                if let Err(error) = cache.flush_index().await {
+                    error!(error=?error, "Failed to flush disk cache index.");
+                }
+            }
+        });
+    }
+}
+
+impl Drop for LocalDiskCache {
+    fn drop(&mut self) {
+        let inner = self.inner.lock().unwrap();
+        self.cache_counters
+            .in_cache_count
+            .sub(inner.lru_cache.len() as i64);
+        self.cache_counters
+            .in_cache_num_bytes
+            .sub(inner.num_bytes as i64);
+
+        if !inner.is_index_dirty {
+            return;
+        }
+        // The cache may be dropped outside of a runtime, so the index is written synchronously.
+        let flush_res = serde_json::to_vec(&inner.index_entries())
+            .map_err(io::Error::from)
+            .and_then(|index_bytes| {
+                write_file_atomically(&self.root_path, INDEX_FILE_NAME, &index_bytes)
+            });
+        if let Err(error) = flush_res {
+            error!(error=?error, "Failed to flush disk cache index.");
+        }
+    }
+}
+
+#[async_trait]
+impl Cache for LocalDiskCache {
+    async fn get(&self, path: &Path, byte_range: Range<usize>) -> Option<OwnedBytes> {
+        self.get_slice(path, byte_range).await
+    }
+
+    async fn get_all(&self, path: &Path) -> Option<OwnedBytes> {
+        self.get_slice(path, FULL_SLICE).await
+    }
+
+    async fn put(&self, path: PathBuf, byte_range: Range<usize>, bytes: OwnedBytes) {
+        self.put_slice(&path, byte_range, bytes).await
+    }
+
+    async fn put_all(&self, path: PathBuf, bytes: OwnedBytes) {
+        self.put_slice(&path, FULL_SLICE, bytes).await
+    }
+}
+
+/// Returns the name of the file storing a slice. Paths may contain separators and be arbitrarily
+/// long, so we use a digest of the path and the byte range instead.
+fn slice_file_name(path: &Path, byte_range: &Range<usize>) -> String {
+    let slice_key = format!(
+        "{}:{}..{}",
+        path.display(),
+        byte_range.start,
+        byte_range.end
+    );
+    format!("{:x}.{SLICE_FILE_EXTENSION}", md5::compute(slice_key))
+}
+
+/// Writes `bytes` to a temporary file unique to this write, then renames it to `file_name`.
+fn write_file_atomically(root_path: &Path, file_name: &str, bytes: &[u8]) -> io::Result<()> {
+    let mut tmp_file = NamedTempFile::new_in(root_path)?;
+    tmp_file.write_all(bytes)?;
+    tmp_file.persist(root_path.join(file_name))?;
+    Ok(())
+}
+
+fn remove_file_if_exists(path: &Path) {
+    if let Err(error) = std::fs::remove_file(path) {
+        if error.kind() != io::ErrorKind::NotFound {
+            warn!(error=?error, path=%path.display(), "Failed to remove disk cache file.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::CACHE_METRICS_FOR_TESTS;
+
+    #[tokio::test]
+    async fn test_local_disk_cache_get_put() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = LocalDiskCache::open(temp_dir.path(), 1_000, &CACHE_METRICS_FOR_TESTS).unwrap();
+        let path = Path::new("foo/bar.fast");
+
+        assert!(cache.get(path, 0..3).await.is_none());
+        cache
+            .put(path.to_path_buf(), 0..3, OwnedBytes::new(&b"abc"[..]))
+            .await;
+        assert_eq!(cache.get(path, 0..3).await.unwrap().as_slice(), b"abc");
+        assert!(cache.get(path, 0..4).await.is_none());
+
+        assert!(cache.get_all(path).await.is_none());
+        cache
+            .put_all(path.to_path_buf(), OwnedBytes::new(&b"abcdef"[..]))
+            .await;
+        assert_eq!(cache.get_all(path).await.unwrap().as_slice(), b"abcdef");
+    }
+
+    #[tokio::test]
+    async fn test_local_disk_cache_lru_eviction() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = LocalDiskCache::open(temp_dir.path(), 10, &CACHE_METRICS_FOR_TESTS).unwrap();
+
+        cache
+            .put_all(PathBuf::from("a"), OwnedBytes::new(&b"aaaa"[..]))
+            .await;
+        cache
+            .put_all(PathBuf::from("b"), OwnedBytes::new(&b"bbbb"[..]))
+            .await;
+        // Reading `a` makes `b` the least recently used slice.
+        assert!(cache.get_all(Path::new("a")).await.is_some());
+        cache
+            .put_all(PathBuf::from("c"), OwnedBytes::new(&b"cccc"[..]))
+            .await;
+
+        assert!(cache.get_all(Path::new("a")).await.is_some());
+        assert!(cache.get_all(Path::new("b")).await.is_none());
+        assert!(cache.get_all(Path::new("c")).await.is_some());
+
+        // Slices larger than the capacity are not cached.
+        cache
+            .put_all(PathBuf::from("d"), OwnedBytes::new(vec![0u8; 11]))
+            .await;
+        assert!(cache.get_all(Path::new("d")).await.is_none());
+        assert!(cache.get_all(Path::new("a")).await.is_some());
+
+        let num_files = std::fs::read_dir(temp_dir.path()).unwrap().count();
+        assert_eq!(num_files, 2);
+    }
+
+    #[tokio::test]
+    async fn test_local_disk_cache_concurrent_puts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = Arc::new(
+            LocalDiskCache::open(temp_dir.path(), 1_000_000, &CACHE_METRICS_FOR_TESTS).unwrap(),
+        );
+        let put_futures = (0..10).map(|_| {
+            let cache = cache.clone();
+            tokio::spawn(async move {
+                cache
+                    .put_all(PathBuf::from("a"), OwnedBytes::new(vec![1u8; 100_000]))
+                    .await;
+            })
+        });
+        for put_res in futures::future::join_all(put_futures).await {
+            put_res.unwrap();
+        }
+        let slice = cache.get_all(Path::new("a")).await.unwrap();
+        assert_eq!(slice.as_slice(), &[1u8; 100_000][..]);
+
+        let num_files = std::fs::read_dir(temp_dir.path()).unwrap().count();
+        assert_eq!(num_files, 1);
+    }
+
+    #[tokio::test]
+    async fn test_local_disk_cache_survives_restart() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        {
+            let cache =
+                LocalDiskCache::open(temp_dir.path(), 10, &CACHE_METRICS_FOR_TESTS).unwrap();
+            cache
+                .put_all(PathBuf::from("a"), OwnedBytes::new(&b"aaaa"[..]))
+                .await;
+            cache
+                .put_all(PathBuf::from("b"), OwnedBytes::new(&b"bbbb"[..]))
+                .await;
+            assert!(cache.get_all(Path::new("a")).await.is_some());
+            cache.flush_index().await.unwrap();
+            // This slice is not referenced by the index but is reloaded on restart.
+            cache
+                .put_all(PathBuf::from("c"), OwnedBytes::new(&b"cc"[..]))
+                .await;
+            // Simulates a crash: the index is not flushed on drop.
+            std::mem::forget(cache);
+        }
+        std::fs::write(temp_dir.path().join("d.slice.tmp"), b"dd").unwrap();
+
+        let cache = LocalDiskCache::open(temp_dir.path(), 10, &CACHE_METRICS_FOR_TESTS).unwrap();
+        // Partially written slices are removed.
+        assert!(!temp_dir.path().join("d.slice.tmp").exists());
+        assert_eq!(
+            cache.get_all(Path::new("a")).await.unwrap().as_slice(),
+            b"aaaa"
+        );
+        assert_eq!(
+            cache.get_all(Path::new("c")).await.unwrap().as_slice(),
+            b"cc"
+        );
+        assert_eq!(
+            cache.get_all(Path::new("b")).await.unwrap().as_slice(),
+            b"bbbb"
+        );
+        // The index is flushed on drop. Lowering the capacity evicts the least recently used
+        // slices, i.e. `a` and `c` since `b` was read last.
+        drop(cache);
+        let cache = LocalDiskCache::open(temp_dir.path(), 4, &CACHE_METRICS_FOR_TESTS).unwrap();
+        assert!(cache.get_all(Path::new("a")).await.is_none());
+        assert!(cache.get_all(Path::new("b")).await.is_some());
+        assert!(cache.get_all(Path::new("c")).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_local_disk_cache_index_flush_loop() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache =
+            Arc::new(LocalDiskCache::open(temp_dir.path(), 10, &CACHE_METRICS_FOR_TESTS).unwrap());
+        cache
+            .put_all(PathBuf::from("a"), OwnedBytes::new(&b"aaaa"[..]))
+            .await;
+        // The first flush happens right away.
+        cache.spawn_index_flush_loop();
+
+        let index_path = temp_dir.path().join(INDEX_FILE_NAME);
+        for _ in 0..100 {
+            if !cache.inner.lock().unwrap().is_index_dirty && index_path.exists() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let index_entries: Vec<IndexEntry> =
+            serde_json::from_slice(&std::fs::read(index_path).unwrap()).unwrap();
+        assert_eq!(index_entries.len(), 1);
+        assert_eq!(index_entries[0].num_bytes, 4);
+    }
+}
@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod byte_range_cache;
+mod local_disk_cache;
 mod memory_sized_cache;
 mod quickwit_cache;
 mod slice_address;
@@ -33,6 +34,7 @@ pub use quickwit_cache::QuickwitCache;
 pub use storage_with_cache::StorageWithCache;
 
 pub use self::byte_range_cache::ByteRangeCache;
+pub use self::local_disk_cache::LocalDiskCache;
 pub use self::memory_sized_cache::MemorySizedCache;
 use crate::{OwnedBytes, Storage};
 
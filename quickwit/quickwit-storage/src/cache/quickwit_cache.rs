@@ -58,6 +58,21 @@ impl QuickwitCache {
         quickwit_cache
     }
 
+    /// Creates a [`QuickwitCache`] caching fast fields in `memory_cache`, backed by a local disk
+    /// cache. The disk cache also holds the term dictionaries.
+    pub fn with_disk_cache(memory_cache: Arc<dyn Cache>, disk_cache: Arc<dyn Cache>) -> Self {
+        let mut quickwit_cache = QuickwitCache::empty();
+        quickwit_cache.add_route(
+            ".fast",
+            Arc::new(TieredCache {
+                memory_cache,
+                disk_cache: disk_cache.clone(),
+            }),
+        );
+        quickwit_cache.add_route(".term", disk_cache);
+        quickwit_cache
+    }
+
     /// Empties cache.
     pub fn empty() -> QuickwitCache {
         QuickwitCache::from(Vec::new())
@@ -157,12 +172,55 @@ impl Cache for SimpleCache {
     }
 }
 
+/// A memory cache backed by a disk cache. Slices found on disk are promoted to the memory cache.
+struct TieredCache {
+    memory_cache: Arc<dyn Cache>,
+    disk_cache: Arc<dyn Cache>,
+}
+
+#[async_trait]
+impl Cache for TieredCache {
+    async fn get(&self, path: &Path, byte_range: Range<usize>) -> Option<OwnedBytes> {
+        if let Some(bytes) = self.memory_cache.get(path, byte_range.clone()).await {
+            return Some(bytes);
+        }
+        let bytes = self.disk_cache.get(path, byte_range.clone()).await?;
+        self.memory_cache
+            .put(path.to_path_buf(), byte_range, bytes.clone())
+            .await;
+        Some(bytes)
+    }
+
+    async fn get_all(&self, path: &Path) -> Option<OwnedBytes> {
+        if let Some(bytes) = self.memory_cache.get_all(path).await {
+            return Some(bytes);
+        }
+        let bytes = self.disk_cache.get_all(path).await?;
+        self.memory_cache
+            .put_all(path.to_path_buf(), bytes.clone())
+            .await;
+        Some(bytes)
+    }
+
+    async fn put(&self, path: PathBuf, byte_range: Range<usize>, bytes: OwnedBytes) {
+        self.memory_cache
+            .put(path.clone(), byte_range.clone(), bytes.clone())
+            .await;
+        self.disk_cache.put(path, byte_range, bytes).await;
+    }
+
+    async fn put_all(&self, path: PathBuf, bytes: OwnedBytes) {
+        self.memory_cache.put_all(path.clone(), bytes.clone()).await;
+        self.disk_cache.put_all(path, bytes).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
     use std::sync::Arc;
 
-    use super::QuickwitCache;
+    use super::{QuickwitCache, TieredCache};
     use crate::cache::{Cache, MockCache};
     use crate::OwnedBytes;
 
@@ -216,4 +274,64 @@ mod tests {
             &b"aaaaa"[..]
         );
     }
+
+    #[tokio::test]
+    async fn test_tiered_cache_promotes_disk_hits() {
+        let mut mock_memory_cache = MockCache::default();
+        mock_memory_cache
+            .expect_get()
+            .times(1)
+            .returning(|_, _| None);
+        mock_memory_cache
+            .expect_put()
+            .times(1)
+            .withf(|path, byte_range, bytes| {
+                path == Path::new("bubu/toto.fast")
+                    && *byte_range == (5..10)
+                    && bytes.as_slice() == b"aaaaa"
+            })
+            .returning(|_, _, _| ());
+        let mut mock_disk_cache = MockCache::default();
+        mock_disk_cache
+            .expect_get()
+            .times(1)
+            .withf(|path, _| path == Path::new("bubu/toto.fast"))
+            .returning(|_, _| Some(OwnedBytes::new(&b"aaaaa"[..])));
+        let tiered_cache = TieredCache {
+            memory_cache: Arc::new(mock_memory_cache),
+            disk_cache: Arc::new(mock_disk_cache),
+        };
+        assert_eq!(
+            tiered_cache
+                .get(Path::new("bubu/toto.fast"), 5..10)
+                .await
+                .unwrap(),
+            &b"aaaaa"[..]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tiered_cache_put_writes_both_tiers() {
+        let mut mock_memory_cache = MockCache::default();
+        mock_memory_cache
+            .expect_put_all()
+            .times(1)
+            .returning(|_, _| ());
+        let mut mock_disk_cache = MockCache::default();
+        mock_disk_cache
+            .expect_put_all()
+            .times(1)
+            .withf(|path, _| path == &PathBuf::from("bubu/toto.fast"))
+            .returning(|_, _| ());
+        let tiered_cache = TieredCache {
+            memory_cache: Arc::new(mock_memory_cache),
+            disk_cache: Arc::new(mock_disk_cache),
+        };
+        tiered_cache
+            .put_all(
+                PathBuf::from("bubu/toto.fast"),
+                OwnedBytes::new(&b"aaaaa"[..]),
+            )
+            .await;
+    }
 }
@@ -0,0 +1,437 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::io;
+use std::num::NonZeroUsize;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::Bytes;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use quickwit_common::uri::Uri;
+use rusoto_core::ByteStream;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use super::format::{ChunkCipher, HEADER_LEN};
+use super::{decrypt_file, open_header, seal_header, KeyProvider};
+use crate::storage::{BulkDeleteError, SendableAsync};
+use crate::{OwnedBytes, PutPayload, Storage, StorageErrorKind, StorageResult};
+
+/// Number of chunks fetched and decrypted at once by `copy_to`.
+const COPY_NUM_CHUNKS_PER_REQUEST: u64 = 16;
+
+/// Caches the ciphers of the files read recently, so that range reads do not have to fetch the
+/// header of the file and unwrap its data key every time.
+///
+/// This relies on files never being overwritten, which holds for splits.
+static FILE_CIPHERS: Lazy<Mutex<LruCache<(Uri, PathBuf), Arc<ChunkCipher>>>> = Lazy::new(|| {
+    Mutex::new(LruCache::new(
+        NonZeroUsize::new(10_000).expect("Capacity should be non-zero."),
+    ))
+});
+
+/// Storage proxy encrypting the files put into the underlying storage and decrypting the files
+/// read from it.
+pub(crate) struct EncryptedStorage {
+    storage: Arc<dyn Storage>,
+    key_provider: Arc<dyn KeyProvider>,
+}
+
+impl EncryptedStorage {
+    pub fn new(storage: Arc<dyn Storage>, key_provider: Arc<dyn KeyProvider>) -> Self {
+        EncryptedStorage {
+            storage,
+            key_provider,
+        }
+    }
+
+    fn cache_key(&self, path: &Path) -> (Uri, PathBuf) {
+        (self.storage.uri().clone(), path.to_path_buf())
+    }
+
+    async fn file_cipher(&self, path: &Path) -> StorageResult<Arc<ChunkCipher>> {
+        let cache_key = self.cache_key(path);
+
+        if let Some(cipher) = FILE_CIPHERS.lock().unwrap().get(&cache_key) {
+            return Ok(cipher.clone());
+        }
+        let header_bytes = self.storage.get_slice(path, 0..HEADER_LEN).await?;
+        let cipher = Arc::new(open_header(Some(&self.key_provider), &header_bytes).await?);
+        FILE_CIPHERS.lock().unwrap().put(cache_key, cipher.clone());
+        Ok(cipher)
+    }
+
+    /// Fetches and decrypts the chunks `chunk_ords` of a file.
+    async fn get_chunks(
+        &self,
+        path: &Path,
+        cipher: &ChunkCipher,
+        chunk_ords: RangeInclusive<u64>,
+    ) -> StorageResult<Vec<u8>> {
+        let layout = cipher.layout();
+        let ciphertext_range = layout.ciphertext_chunk_range(*chunk_ords.start()).start
+            ..layout.ciphertext_chunk_range(*chunk_ords.end()).end;
+        let ciphertext = self
+            .storage
+            .get_slice(
+                path,
+                ciphertext_range.start as usize..ciphertext_range.end as usize,
+            )
+            .await?;
+        cipher.open_chunks(*chunk_ords.start(), ciphertext.as_slice())
+    }
+}
+
+#[async_trait]
+impl Storage for EncryptedStorage {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.storage.check_connectivity().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        let (header_bytes, cipher) = seal_header(&*self.key_provider, payload.len()).await?;
+        let cipher = Arc::new(cipher);
+        let encrypted_payload = EncryptedPutPayload {
+            payload,
+            header_bytes: Arc::new(header_bytes),
+            cipher: cipher.clone(),
+        };
+        FILE_CIPHERS.lock().unwrap().pop(&self.cache_key(path));
+        self.storage.put(path, Box::new(encrypted_payload)).await?;
+        FILE_CIPHERS
+            .lock()
+            .unwrap()
+            .put(self.cache_key(path), cipher);
+        Ok(())
+    }
+
+    async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
+        let cipher = self.file_cipher(path).await?;
+        let num_chunks = cipher.layout().num_chunks();
+        let mut chunk_ord = 0;
+
+        while chunk_ord < num_chunks {
+            let last_chunk_ord = (chunk_ord + COPY_NUM_CHUNKS_PER_REQUEST).min(num_chunks) - 1;
+            let plaintext = self
+                .get_chunks(path, &cipher, chunk_ord..=last_chunk_ord)
+                .await?;
+            output.write_all(&plaintext).await?;
+            chunk_ord = last_chunk_ord + 1;
+        }
+        output.flush().await?;
+        Ok(())
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        if range.is_empty() {
+            return Ok(OwnedBytes::empty());
+        }
+        let cipher = self.file_cipher(path).await?;
+        let layout = cipher.layout();
+
+        if range.end as u64 > layout.plaintext_len {
+            return Err(StorageErrorKind::InternalError.with_error(anyhow!(
+                "Range {range:?} is out of bounds for file `{}` of {} bytes.",
+                path.display(),
+                layout.plaintext_len
+            )));
+        }
+        let first_chunk_ord = layout.chunk_containing_plaintext_offset(range.start as u64);
+        let last_chunk_ord = layout.chunk_containing_plaintext_offset(range.end as u64 - 1);
+        let plaintext = self
+            .get_chunks(path, &cipher, first_chunk_ord..=last_chunk_ord)
+            .await?;
+        let offset = layout.plaintext_chunk_range(first_chunk_ord).start as usize;
+        Ok(OwnedBytes::new(plaintext).slice(range.start - offset..range.end - offset))
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        let ciphertext = self.storage.get_all(path).await?;
+        decrypt_file(Some(&self.key_provider), ciphertext.as_slice()).await
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        FILE_CIPHERS.lock().unwrap().pop(&self.cache_key(path));
+        self.storage.delete(path).await
+    }
+
+    async fn bulk_delete<'a>(&self, paths: &[&'a Path]) -> Result<(), BulkDeleteError> {
+        {
+            let mut file_ciphers = FILE_CIPHERS.lock().unwrap();
+            for path in paths {
+                file_ciphers.pop(&self.cache_key(path));
+            }
+        }
+        self.storage.bulk_delete(paths).await
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.storage.exists(path).await
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        let cipher = self.file_cipher(path).await?;
+        Ok(cipher.layout().plaintext_len)
+    }
+
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        self.storage.list_files(prefix).await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.storage.uri()
+    }
+}
+
+/// Payload encrypting another payload on the fly as it is streamed to the storage.
+#[derive(Clone)]
+struct EncryptedPutPayload {
+    payload: Box<dyn PutPayload>,
+    header_bytes: Arc<Vec<u8>>,
+    cipher: Arc<ChunkCipher>,
+}
+
+impl EncryptedPutPayload {
+    /// Encrypts the chunks `chunk_ords` and sends the parts of their ciphertext within `range`.
+    async fn stream_chunks(
+        &self,
+        chunk_ords: RangeInclusive<u64>,
+        range: Range<u64>,
+        chunk_tx: &mpsc::Sender<io::Result<Bytes>>,
+    ) -> io::Result<()> {
+        let layout = self.cipher.layout();
+        let plaintext_range = layout.plaintext_chunk_range(*chunk_ords.start()).start
+            ..layout.plaintext_chunk_range(*chunk_ords.end()).end;
+        // The plaintext of an empty file is empty, and there is nothing to read.
+        let mut reader_opt = if plaintext_range.is_empty() {
+            None
+        } else {
+            Some(
+                self.payload
+                    .range_byte_stream(plaintext_range)
+                    .await?
+                    .into_async_read(),
+            )
+        };
+        for chunk_ord in chunk_ords {
+            let plaintext_chunk_range = layout.plaintext_chunk_range(chunk_ord);
+            let mut plaintext =
+                vec![0u8; (plaintext_chunk_range.end - plaintext_chunk_range.start) as usize];
+
+            if let Some(reader) = reader_opt.as_mut() {
+                reader.read_exact(&mut plaintext).await?;
+            }
+            let mut ciphertext = Vec::new();
+            self.cipher
+                .seal_chunk(chunk_ord, &plaintext, &mut ciphertext)?;
+
+            let ciphertext_chunk_range = layout.ciphertext_chunk_range(chunk_ord);
+            let start =
+                range.start.max(ciphertext_chunk_range.start) - ciphertext_chunk_range.start;
+            let end = range.end.min(ciphertext_chunk_range.end) - ciphertext_chunk_range.start;
+            let ciphertext_part = Bytes::from(ciphertext).slice(start as usize..end as usize);
+
+            if chunk_tx.send(Ok(ciphertext_part)).await.is_err() {
+                // The stream was dropped.
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PutPayload for EncryptedPutPayload {
+    fn len(&self) -> u64 {
+        self.cipher.layout().ciphertext_len()
+    }
+
+    async fn range_byte_stream(&self, range: Range<u64>) -> io::Result<ByteStream> {
+        let header_len = HEADER_LEN as u64;
+        let header_part = if range.start < header_len {
+            self.header_bytes[range.start as usize..range.end.min(header_len) as usize].to_vec()
+        } else {
+            Vec::new()
+        };
+        if range.end <= header_len {
+            return Ok(ByteStream::from(header_part));
+        }
+        let layout = self.cipher.layout();
+        let first_chunk_ord = layout.chunk_containing_ciphertext_offset(range.start);
+        let last_chunk_ord = layout.chunk_containing_ciphertext_offset(range.end - 1);
+
+        // Chunks are encrypted in a background task so that the payload is never loaded entirely
+        // in memory.
+        let (chunk_tx, chunk_rx) = mpsc::channel(2);
+
+        if !header_part.is_empty() {
+            chunk_tx
+                .try_send(Ok(Bytes::from(header_part)))
+                .expect("The channel should be empty.");
+        }
+        let payload = self.clone();
+        tokio::spawn(async move {
+            if let Err(error) = payload
+                .stream_chunks(first_chunk_ord..=last_chunk_ord, range, &chunk_tx)
+                .await
+            {
+                let _ = chunk_tx.send(Err(error)).await;
+            }
+        });
+        Ok(ByteStream::new(ReceiverStream::new(chunk_rx)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encryption::format::FileHeader;
+    use crate::encryption::tests::keyfile_for_test;
+    use crate::encryption::{is_encrypted, resolve_key_provider};
+    use crate::RamStorage;
+
+    fn plaintext_for_test(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn encrypted_storage_for_test(
+        key_byte: u8,
+    ) -> (tempfile::TempDir, Arc<dyn Storage>, EncryptedStorage) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let key_uri = keyfile_for_test(temp_dir.path(), key_byte);
+        let key_provider = resolve_key_provider(&key_uri).unwrap();
+        let ram_storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let encrypted_storage = EncryptedStorage::new(ram_storage.clone(), key_provider);
+        (temp_dir, ram_storage, encrypted_storage)
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage_put_get() {
+        let (_temp_dir, ram_storage, encrypted_storage) = encrypted_storage_for_test(10);
+        let path = Path::new("split.split");
+        // Four chunks, the last one being partial.
+        let plaintext = plaintext_for_test(200_000);
+
+        encrypted_storage
+            .put(path, Box::new(plaintext.clone()))
+            .await
+            .unwrap();
+
+        let ciphertext = ram_storage.get_all(path).await.unwrap();
+        assert!(is_encrypted(ciphertext.as_slice()));
+        assert_eq!(ciphertext.len(), HEADER_LEN + 200_000 + 4 * 16);
+
+        assert_eq!(
+            encrypted_storage.file_num_bytes(path).await.unwrap(),
+            200_000
+        );
+        assert_eq!(
+            encrypted_storage.get_all(path).await.unwrap().as_slice(),
+            &plaintext[..]
+        );
+        for range in [
+            0..10,
+            65_530..65_540,
+            1_000..150_000,
+            199_990..200_000,
+            5..5,
+        ] {
+            let slice = encrypted_storage
+                .get_slice(path, range.clone())
+                .await
+                .unwrap();
+            assert_eq!(slice.as_slice(), &plaintext[range]);
+        }
+        encrypted_storage
+            .get_slice(path, 199_990..200_001)
+            .await
+            .unwrap_err();
+
+        let mut output = Vec::new();
+        encrypted_storage.copy_to(path, &mut output).await.unwrap();
+        assert_eq!(output, plaintext);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage_empty_file() {
+        let (_temp_dir, _ram_storage, encrypted_storage) = encrypted_storage_for_test(11);
+        let path = Path::new("empty");
+        encrypted_storage
+            .put(path, Box::new(Vec::new()))
+            .await
+            .unwrap();
+        assert_eq!(encrypted_storage.file_num_bytes(path).await.unwrap(), 0);
+        assert!(encrypted_storage.get_all(path).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage_wrong_key() {
+        let (_temp_dir, ram_storage, encrypted_storage) = encrypted_storage_for_test(12);
+        let path = Path::new("split.split");
+        encrypted_storage
+            .put(path, Box::new(plaintext_for_test(1_000)))
+            .await
+            .unwrap();
+
+        // Simulate a tampered header pointing to another key.
+        let mut ciphertext = ram_storage.get_all(path).await.unwrap().to_vec();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let other_key_uri = keyfile_for_test(temp_dir.path(), 13);
+        let mut header = FileHeader::deserialize(&ciphertext).unwrap();
+        header.key_uri = other_key_uri;
+        ciphertext[..HEADER_LEN].copy_from_slice(&header.serialize());
+
+        let error = decrypt_file(None, &ciphertext).await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::Unauthorized);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_put_payload_range_byte_stream() {
+        let (_temp_dir, _ram_storage, encrypted_storage) = encrypted_storage_for_test(14);
+        let plaintext = plaintext_for_test(150_000);
+        let (header_bytes, cipher) = seal_header(&*encrypted_storage.key_provider, 150_000)
+            .await
+            .unwrap();
+        let payload = EncryptedPutPayload {
+            payload: Box::new(plaintext),
+            header_bytes: Arc::new(header_bytes),
+            cipher: Arc::new(cipher),
+        };
+        let ciphertext = payload.read_all().await.unwrap();
+        assert_eq!(ciphertext.len() as u64, payload.len());
+
+        // Parts of a multipart upload must match the corresponding slices of the whole payload.
+        for range in [0..100, 100..600, 600..70_000, 70_000..payload.len()] {
+            let mut part = Vec::new();
+            let mut reader = payload
+                .range_byte_stream(range.clone())
+                .await
+                .unwrap()
+                .into_async_read();
+            tokio::io::copy(&mut reader, &mut part).await.unwrap();
+            assert_eq!(part, &ciphertext[range.start as usize..range.end as usize]);
+        }
+    }
+}
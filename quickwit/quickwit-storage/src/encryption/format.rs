@@ -0,0 +1,391 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::ops::Range;
+
+use anyhow::{anyhow, bail};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::{StorageError, StorageErrorKind, StorageResult};
+
+// Layout of an encrypted file:
+//
+// [header: HEADER_LEN bytes][chunk 0][chunk 1]...[chunk n-1]
+//
+// The header starts with a fixed-size prefix, authenticated along with the data key when it
+// is wrapped:
+// - magic number (4 bytes),
+// - chunk size (u32, little endian),
+// - plaintext length (u64, little endian),
+// - nonce prefix (7 bytes) followed by a reserved byte,
+//
+// and continues with:
+// - the data key wrapped with the key encryption key (60 bytes),
+// - the length of the key URI (u16, little endian),
+// - the key URI,
+// - zero padding.
+//
+// Each chunk holds up to `chunk_size` bytes of plaintext encrypted with AES-256-GCM using the
+// data key, followed by its authentication tag. The nonce of a chunk is made of the nonce
+// prefix, the chunk ordinal, and a flag marking the last chunk, which prevents chunks from
+// being reordered or the file from being truncated.
+
+/// Magic number identifying encrypted files.
+pub(crate) const MAGIC_NUMBER: &[u8; 4] = b"QWE1";
+
+/// Length of the header of encrypted files.
+pub(crate) const HEADER_LEN: usize = 512;
+
+/// Default amount of plaintext encrypted in each chunk.
+pub(crate) const DEFAULT_CHUNK_SIZE: u32 = 64 * 1024;
+
+pub(crate) const DATA_KEY_LEN: usize = 32;
+
+pub(crate) const TAG_LEN: usize = 16;
+
+pub(crate) const WRAPPED_DATA_KEY_LEN: usize = NONCE_LEN + DATA_KEY_LEN + TAG_LEN;
+
+const NONCE_PREFIX_LEN: usize = 7;
+
+const AUTHENTICATED_PREFIX_LEN: usize = 24;
+
+const KEY_URI_OFFSET: usize = AUTHENTICATED_PREFIX_LEN + WRAPPED_DATA_KEY_LEN + 2;
+
+/// Maximum length of the key URI stored in the header.
+pub(crate) const MAX_KEY_URI_LEN: usize = HEADER_LEN - KEY_URI_OFFSET;
+
+pub(crate) type DataKey = [u8; DATA_KEY_LEN];
+
+pub(crate) type WrappedDataKey = [u8; WRAPPED_DATA_KEY_LEN];
+
+/// Returns whether `bytes` starts like an encrypted file.
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.len() >= HEADER_LEN && bytes.starts_with(MAGIC_NUMBER)
+}
+
+pub(crate) fn corrupted_error(message: impl Into<String>) -> StorageError {
+    StorageErrorKind::InternalError
+        .with_error(anyhow!("Failed to decrypt file: {}", message.into()))
+}
+
+pub(crate) fn random_bytes<const N: usize>() -> StorageResult<[u8; N]> {
+    let mut bytes = [0u8; N];
+    SystemRandom::new().fill(&mut bytes).map_err(|_| {
+        StorageErrorKind::InternalError.with_error(anyhow!("Failed to generate random bytes."))
+    })?;
+    Ok(bytes)
+}
+
+pub(crate) fn aes_256_gcm_key(key_bytes: &[u8]) -> StorageResult<LessSafeKey> {
+    let unbound_key = UnboundKey::new(&AES_256_GCM, key_bytes).map_err(|_| {
+        StorageErrorKind::InternalError.with_error(anyhow!(
+            "Invalid AES-256 key: expected {DATA_KEY_LEN} bytes, got {}.",
+            key_bytes.len()
+        ))
+    })?;
+    Ok(LessSafeKey::new(unbound_key))
+}
+
+/// Header of an encrypted file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct FileHeader {
+    pub layout: ChunkLayout,
+    pub nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    pub wrapped_data_key: WrappedDataKey,
+    pub key_uri: String,
+}
+
+impl FileHeader {
+    pub fn new(plaintext_len: u64, key_uri: &str) -> StorageResult<Self> {
+        if key_uri.len() > MAX_KEY_URI_LEN {
+            return Err(StorageErrorKind::InternalError.with_error(anyhow!(
+                "Key URI `{key_uri}` exceeds {MAX_KEY_URI_LEN} bytes."
+            )));
+        }
+        Ok(FileHeader {
+            layout: ChunkLayout {
+                chunk_size: DEFAULT_CHUNK_SIZE,
+                plaintext_len,
+            },
+            nonce_prefix: random_bytes()?,
+            wrapped_data_key: [0u8; WRAPPED_DATA_KEY_LEN],
+            key_uri: key_uri.to_string(),
+        })
+    }
+
+    /// The part of the header that must be authenticated when wrapping the data key.
+    pub fn authenticated_prefix(&self) -> [u8; AUTHENTICATED_PREFIX_LEN] {
+        let mut prefix = [0u8; AUTHENTICATED_PREFIX_LEN];
+        prefix[0..4].copy_from_slice(MAGIC_NUMBER);
+        prefix[4..8].copy_from_slice(&self.layout.chunk_size.to_le_bytes());
+        prefix[8..16].copy_from_slice(&self.layout.plaintext_len.to_le_bytes());
+        prefix[16..23].copy_from_slice(&self.nonce_prefix);
+        prefix
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut header_bytes = Vec::with_capacity(HEADER_LEN);
+        header_bytes.extend_from_slice(&self.authenticated_prefix());
+        header_bytes.extend_from_slice(&self.wrapped_data_key);
+        header_bytes.extend_from_slice(&(self.key_uri.len() as u16).to_le_bytes());
+        header_bytes.extend_from_slice(self.key_uri.as_bytes());
+        header_bytes.resize(HEADER_LEN, 0u8);
+        header_bytes
+    }
+
+    pub fn deserialize(header_bytes: &[u8]) -> anyhow::Result<Self> {
+        if !is_encrypted(header_bytes) {
+            bail!("the file is not encrypted or its header is truncated");
+        }
+        let chunk_size = u32::from_le_bytes(header_bytes[4..8].try_into()?);
+        if chunk_size == 0 {
+            bail!("invalid chunk size");
+        }
+        let plaintext_len = u64::from_le_bytes(header_bytes[8..16].try_into()?);
+        let nonce_prefix: [u8; NONCE_PREFIX_LEN] = header_bytes[16..23].try_into()?;
+        let wrapped_data_key: WrappedDataKey =
+            header_bytes[AUTHENTICATED_PREFIX_LEN..KEY_URI_OFFSET - 2].try_into()?;
+        let key_uri_len =
+            u16::from_le_bytes(header_bytes[KEY_URI_OFFSET - 2..KEY_URI_OFFSET].try_into()?)
+                as usize;
+        if key_uri_len > MAX_KEY_URI_LEN {
+            bail!("invalid key URI length");
+        }
+        let key_uri =
+            String::from_utf8(header_bytes[KEY_URI_OFFSET..KEY_URI_OFFSET + key_uri_len].to_vec())?;
+        Ok(FileHeader {
+            layout: ChunkLayout {
+                chunk_size,
+                plaintext_len,
+            },
+            nonce_prefix,
+            wrapped_data_key,
+            key_uri,
+        })
+    }
+}
+
+/// Maps the plaintext of an encrypted file to its chunks.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct ChunkLayout {
+    pub chunk_size: u32,
+    pub plaintext_len: u64,
+}
+
+impl ChunkLayout {
+    /// Empty files still have one empty chunk, so that their truncation can be detected.
+    pub fn num_chunks(&self) -> u64 {
+        let chunk_size = self.chunk_size as u64;
+        ((self.plaintext_len + chunk_size - 1) / chunk_size).max(1)
+    }
+
+    pub fn ciphertext_len(&self) -> u64 {
+        HEADER_LEN as u64 + self.plaintext_len + self.num_chunks() * TAG_LEN as u64
+    }
+
+    pub fn is_last_chunk(&self, chunk_ord: u64) -> bool {
+        chunk_ord + 1 == self.num_chunks()
+    }
+
+    pub fn plaintext_chunk_range(&self, chunk_ord: u64) -> Range<u64> {
+        let start = chunk_ord * self.chunk_size as u64;
+        let end = (start + self.chunk_size as u64).min(self.plaintext_len);
+        start..end
+    }
+
+    pub fn ciphertext_chunk_range(&self, chunk_ord: u64) -> Range<u64> {
+        let start = HEADER_LEN as u64 + chunk_ord * (self.chunk_size as u64 + TAG_LEN as u64);
+        let plaintext_chunk_range = self.plaintext_chunk_range(chunk_ord);
+        let end = start + plaintext_chunk_range.end - plaintext_chunk_range.start + TAG_LEN as u64;
+        start..end
+    }
+
+    pub fn chunk_containing_plaintext_offset(&self, offset: u64) -> u64 {
+        (offset / self.chunk_size as u64).min(self.num_chunks() - 1)
+    }
+
+    /// Offsets within the header belong to the first chunk.
+    pub fn chunk_containing_ciphertext_offset(&self, offset: u64) -> u64 {
+        let chunk_ord =
+            offset.saturating_sub(HEADER_LEN as u64) / (self.chunk_size as u64 + TAG_LEN as u64);
+        chunk_ord.min(self.num_chunks() - 1)
+    }
+}
+
+/// Encrypts and decrypts the chunks of a file with its data key.
+pub(crate) struct ChunkCipher {
+    key: LessSafeKey,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    layout: ChunkLayout,
+}
+
+impl ChunkCipher {
+    pub fn new(data_key: &DataKey, header: &FileHeader) -> StorageResult<Self> {
+        Ok(ChunkCipher {
+            key: aes_256_gcm_key(data_key)?,
+            nonce_prefix: header.nonce_prefix,
+            layout: header.layout,
+        })
+    }
+
+    pub fn layout(&self) -> &ChunkLayout {
+        &self.layout
+    }
+
+    fn nonce(&self, chunk_ord: u64) -> anyhow::Result<Nonce> {
+        let chunk_ord_u32 = u32::try_from(chunk_ord)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[..NONCE_PREFIX_LEN].copy_from_slice(&self.nonce_prefix);
+        nonce[NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&chunk_ord_u32.to_be_bytes());
+        nonce[NONCE_LEN - 1] = self.layout.is_last_chunk(chunk_ord) as u8;
+        Ok(Nonce::assume_unique_for_key(nonce))
+    }
+
+    /// Encrypts the chunk `chunk_ord` and appends it to `output`.
+    pub fn seal_chunk(
+        &self,
+        chunk_ord: u64,
+        plaintext: &[u8],
+        output: &mut Vec<u8>,
+    ) -> StorageResult<()> {
+        let nonce = self
+            .nonce(chunk_ord)
+            .map_err(|error| StorageErrorKind::InternalError.with_error(error))?;
+        let mut in_out = plaintext.to_vec();
+        self.key
+            .seal_in_place_append_tag(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| {
+                StorageErrorKind::InternalError.with_error(anyhow!("Failed to encrypt chunk."))
+            })?;
+        output.extend_from_slice(&in_out);
+        Ok(())
+    }
+
+    /// Decrypts the consecutive chunks starting at `first_chunk_ord` contained in `ciphertext`.
+    pub fn open_chunks(&self, first_chunk_ord: u64, ciphertext: &[u8]) -> StorageResult<Vec<u8>> {
+        let mut plaintext = Vec::with_capacity(ciphertext.len());
+        let mut chunk_ord = first_chunk_ord;
+        let mut offset = 0;
+
+        while offset < ciphertext.len() {
+            let chunk_range = self.layout.ciphertext_chunk_range(chunk_ord);
+            let chunk_len = (chunk_range.end - chunk_range.start) as usize;
+
+            if offset + chunk_len > ciphertext.len() {
+                return Err(corrupted_error(format!("chunk {chunk_ord} is truncated")));
+            }
+            let nonce = self
+                .nonce(chunk_ord)
+                .map_err(|error| corrupted_error(error.to_string()))?;
+            let mut in_out = ciphertext[offset..offset + chunk_len].to_vec();
+            let decrypted = self
+                .key
+                .open_in_place(nonce, Aad::empty(), &mut in_out)
+                .map_err(|_| corrupted_error(format!("chunk {chunk_ord} failed authentication")))?;
+            plaintext.extend_from_slice(decrypted);
+            offset += chunk_len;
+            chunk_ord += 1;
+        }
+        Ok(plaintext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_header_serialization() {
+        let mut header = FileHeader::new(1_000_000, "kms://my-key").unwrap();
+        header.wrapped_data_key = [7u8; WRAPPED_DATA_KEY_LEN];
+        let header_bytes = header.serialize();
+        assert_eq!(header_bytes.len(), HEADER_LEN);
+        assert!(is_encrypted(&header_bytes));
+        assert_eq!(FileHeader::deserialize(&header_bytes).unwrap(), header);
+
+        assert!(!is_encrypted(b"{\"index_id\": \"my-index\"}"));
+        assert!(FileHeader::deserialize(&header_bytes[..100]).is_err());
+    }
+
+    #[test]
+    fn test_chunk_layout() {
+        let layout = ChunkLayout {
+            chunk_size: 10,
+            plaintext_len: 25,
+        };
+        assert_eq!(layout.num_chunks(), 3);
+        assert_eq!(layout.ciphertext_len(), HEADER_LEN as u64 + 25 + 3 * 16);
+        assert_eq!(layout.plaintext_chunk_range(2), 20..25);
+        assert_eq!(layout.ciphertext_chunk_range(0), 512..538);
+        assert_eq!(layout.ciphertext_chunk_range(2), 564..585);
+        assert_eq!(layout.chunk_containing_plaintext_offset(19), 1);
+        assert_eq!(layout.chunk_containing_ciphertext_offset(0), 0);
+        assert_eq!(layout.chunk_containing_ciphertext_offset(538), 1);
+        assert_eq!(layout.chunk_containing_ciphertext_offset(584), 2);
+        assert!(layout.is_last_chunk(2));
+
+        let empty_layout = ChunkLayout {
+            chunk_size: 10,
+            plaintext_len: 0,
+        };
+        assert_eq!(empty_layout.num_chunks(), 1);
+        assert_eq!(empty_layout.ciphertext_len(), HEADER_LEN as u64 + 16);
+    }
+
+    #[test]
+    fn test_chunk_cipher() {
+        let mut header = FileHeader::new(25, "kms://my-key").unwrap();
+        header.layout.chunk_size = 10;
+        let data_key: DataKey = random_bytes().unwrap();
+        let cipher = ChunkCipher::new(&data_key, &header).unwrap();
+
+        let plaintext = b"hello encrypted world!!!!";
+        let mut ciphertext = Vec::new();
+        for chunk_ord in 0..3 {
+            let chunk_range = cipher.layout().plaintext_chunk_range(chunk_ord);
+            cipher
+                .seal_chunk(
+                    chunk_ord,
+                    &plaintext[chunk_range.start as usize..chunk_range.end as usize],
+                    &mut ciphertext,
+                )
+                .unwrap();
+        }
+        assert_eq!(cipher.open_chunks(0, &ciphertext).unwrap(), plaintext);
+        assert_eq!(
+            cipher.open_chunks(1, &ciphertext[26..]).unwrap(),
+            &plaintext[10..]
+        );
+
+        // Chunks cannot be reordered.
+        assert!(cipher.open_chunks(0, &ciphertext[26..52]).is_err());
+        // A file cannot be truncated at a chunk boundary.
+        let mut truncated_header = header.clone();
+        truncated_header.layout.plaintext_len = 20;
+        let truncated_cipher = ChunkCipher::new(&data_key, &truncated_header).unwrap();
+        assert!(truncated_cipher
+            .open_chunks(1, &ciphertext[26..52])
+            .is_err());
+        // Tampered chunks are rejected.
+        let mut tampered_ciphertext = ciphertext.clone();
+        tampered_ciphertext[3] ^= 1;
+        assert!(cipher.open_chunks(0, &tampered_ciphertext).is_err());
+    }
+}
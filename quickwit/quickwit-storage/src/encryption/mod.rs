@@ -0,0 +1,358 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod encrypted_storage;
+mod format;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use once_cell::sync::Lazy;
+use ring::aead::{Aad, Nonce, NONCE_LEN};
+
+use self::encrypted_storage::EncryptedStorage;
+pub use self::format::is_encrypted;
+use self::format::{
+    aes_256_gcm_key, corrupted_error, random_bytes, ChunkCipher, DataKey, FileHeader,
+    WrappedDataKey, HEADER_LEN, WRAPPED_DATA_KEY_LEN,
+};
+use crate::{OwnedBytes, Storage, StorageErrorKind, StorageResult};
+
+/// Wraps a storage so that the files it stores are encrypted at rest with the key identified by
+/// `key_uri`.
+///
+/// Each file is encrypted with its own data key, which is itself encrypted with the key
+/// encryption key and stored in the header of the file (envelope encryption). The content of the
+/// file is encrypted in chunks so that range reads only have to fetch and decrypt the chunks they
+/// overlap.
+pub fn wrap_storage_with_encryption(
+    storage: Arc<dyn Storage>,
+    key_uri: &str,
+) -> StorageResult<Arc<dyn Storage>> {
+    let key_provider = resolve_key_provider(key_uri)?;
+    Ok(Arc::new(EncryptedStorage::new(storage, key_provider)))
+}
+
+/// Encrypts the content of a small file with the key identified by `key_uri`.
+pub async fn encrypt_bytes(key_uri: &str, plaintext: &[u8]) -> StorageResult<Vec<u8>> {
+    let key_provider = resolve_key_provider(key_uri)?;
+    let (mut ciphertext, cipher) = seal_header(&*key_provider, plaintext.len() as u64).await?;
+    let layout = *cipher.layout();
+    ciphertext.reserve(layout.ciphertext_len() as usize - HEADER_LEN);
+
+    for chunk_ord in 0..layout.num_chunks() {
+        let chunk_range = layout.plaintext_chunk_range(chunk_ord);
+        cipher.seal_chunk(
+            chunk_ord,
+            &plaintext[chunk_range.start as usize..chunk_range.end as usize],
+            &mut ciphertext,
+        )?;
+    }
+    Ok(ciphertext)
+}
+
+/// Decrypts the content of a small file if it is encrypted, and returns it unchanged otherwise.
+///
+/// The key is looked up from the URI stored in the header of the file.
+pub async fn decrypt_bytes(bytes: OwnedBytes) -> StorageResult<OwnedBytes> {
+    if !is_encrypted(bytes.as_slice()) {
+        return Ok(bytes);
+    }
+    decrypt_file(None, bytes.as_slice()).await
+}
+
+/// Decrypts the entire content of an encrypted file.
+async fn decrypt_file(
+    key_provider_opt: Option<&Arc<dyn KeyProvider>>,
+    ciphertext: &[u8],
+) -> StorageResult<OwnedBytes> {
+    let header_bytes = ciphertext
+        .get(..HEADER_LEN)
+        .ok_or_else(|| corrupted_error("the header is truncated"))?;
+    let cipher = open_header(key_provider_opt, header_bytes).await?;
+
+    if ciphertext.len() as u64 != cipher.layout().ciphertext_len() {
+        return Err(corrupted_error(format!(
+            "expected {} bytes, got {}",
+            cipher.layout().ciphertext_len(),
+            ciphertext.len()
+        )));
+    }
+    let plaintext = cipher.open_chunks(0, &ciphertext[HEADER_LEN..])?;
+    Ok(OwnedBytes::new(plaintext))
+}
+
+/// Creates the header of a new encrypted file and the cipher encrypting its chunks.
+async fn seal_header(
+    key_provider: &dyn KeyProvider,
+    plaintext_len: u64,
+) -> StorageResult<(Vec<u8>, ChunkCipher)> {
+    let mut header = FileHeader::new(plaintext_len, key_provider.key_uri())?;
+    let data_key: DataKey = random_bytes()?;
+    header.wrapped_data_key = key_provider
+        .wrap_data_key(&data_key, &header.authenticated_prefix())
+        .await?;
+    let cipher = ChunkCipher::new(&data_key, &header)?;
+    Ok((header.serialize(), cipher))
+}
+
+/// Parses the header of an encrypted file and unwraps its data key.
+///
+/// The data key is unwrapped with the key referenced in the header, so that files encrypted with a
+/// previous key remain readable.
+async fn open_header(
+    key_provider_opt: Option<&Arc<dyn KeyProvider>>,
+    header_bytes: &[u8],
+) -> StorageResult<ChunkCipher> {
+    let header = FileHeader::deserialize(header_bytes)
+        .map_err(|error| corrupted_error(error.to_string()))?;
+    let key_provider = match key_provider_opt {
+        Some(key_provider) if key_provider.key_uri() == header.key_uri => key_provider.clone(),
+        _ => resolve_key_provider(&header.key_uri)?,
+    };
+    let data_key = key_provider
+        .unwrap_data_key(&header.wrapped_data_key, &header.authenticated_prefix())
+        .await?;
+    ChunkCipher::new(&data_key, &header)
+}
+
+/// Encrypts and decrypts the data keys of files with a key encryption key.
+///
+/// This is the extension point for key management services, which never disclose the key
+/// encryption key and wrap and unwrap data keys remotely instead.
+#[async_trait]
+pub(crate) trait KeyProvider: Send + Sync + 'static {
+    /// Returns the URI identifying the key encryption key.
+    fn key_uri(&self) -> &str;
+
+    /// Encrypts a data key. The `context` is authenticated along with the data key.
+    async fn wrap_data_key(
+        &self,
+        data_key: &DataKey,
+        context: &[u8],
+    ) -> StorageResult<WrappedDataKey>;
+
+    /// Decrypts a data key wrapped with the same `context`.
+    async fn unwrap_data_key(
+        &self,
+        wrapped_data_key: &WrappedDataKey,
+        context: &[u8],
+    ) -> StorageResult<DataKey>;
+}
+
+/// Key provider holding the key encryption key in memory, loaded from a keyfile or from the KMS
+/// stand-in.
+struct LocalKeyProvider {
+    key_uri: String,
+    key_encryption_key: DataKey,
+}
+
+#[async_trait]
+impl KeyProvider for LocalKeyProvider {
+    fn key_uri(&self) -> &str {
+        &self.key_uri
+    }
+
+    async fn wrap_data_key(
+        &self,
+        data_key: &DataKey,
+        context: &[u8],
+    ) -> StorageResult<WrappedDataKey> {
+        let key = aes_256_gcm_key(&self.key_encryption_key)?;
+        let nonce: [u8; NONCE_LEN] = random_bytes()?;
+        let mut in_out = data_key.to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(context),
+            &mut in_out,
+        )
+        .map_err(|_| {
+            StorageErrorKind::InternalError.with_error(anyhow!("Failed to wrap data key."))
+        })?;
+        let mut wrapped_data_key = [0u8; WRAPPED_DATA_KEY_LEN];
+        wrapped_data_key[..NONCE_LEN].copy_from_slice(&nonce);
+        wrapped_data_key[NONCE_LEN..].copy_from_slice(&in_out);
+        Ok(wrapped_data_key)
+    }
+
+    async fn unwrap_data_key(
+        &self,
+        wrapped_data_key: &WrappedDataKey,
+        context: &[u8],
+    ) -> StorageResult<DataKey> {
+        let key = aes_256_gcm_key(&self.key_encryption_key)?;
+        let nonce: [u8; NONCE_LEN] = wrapped_data_key[..NONCE_LEN]
+            .try_into()
+            .expect("The wrapped data key should start with a nonce.");
+        let mut in_out = wrapped_data_key[NONCE_LEN..].to_vec();
+        let data_key = key
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(context),
+                &mut in_out,
+            )
+            .map_err(|_| {
+                StorageErrorKind::Unauthorized.with_error(anyhow!(
+                    "Failed to unwrap data key with key `{}`: the key is wrong or the file header \
+                     is corrupted.",
+                    self.key_uri
+                ))
+            })?;
+        Ok(data_key
+            .try_into()
+            .expect("The unwrapped data key should be 32 bytes long."))
+    }
+}
+
+/// Key providers are cached so that keyfiles are read once.
+static KEY_PROVIDERS: Lazy<Mutex<HashMap<String, Arc<dyn KeyProvider>>>> =
+    Lazy::new(Default::default);
+
+/// Returns the key provider for `key_uri`:
+/// - `file:///path/to/keyfile` loads the key from a local keyfile;
+/// - `kms://key-id` loads the key from the KMS stand-in, which reads the key from the environment
+///   variable `QW_KMS_KEY_{KEY_ID}`, where `KEY_ID` is the key ID in uppercase with non
+///   alphanumeric characters replaced by underscores.
+///
+/// In both cases, the key is expected to be 32 bytes encoded in base64.
+pub(crate) fn resolve_key_provider(key_uri: &str) -> StorageResult<Arc<dyn KeyProvider>> {
+    let mut key_providers = KEY_PROVIDERS.lock().unwrap();
+
+    if let Some(key_provider) = key_providers.get(key_uri) {
+        return Ok(key_provider.clone());
+    }
+    let key_encryption_key = load_key_encryption_key(key_uri)
+        .map_err(|error| StorageErrorKind::Unauthorized.with_error(error))?;
+    let key_provider: Arc<dyn KeyProvider> = Arc::new(LocalKeyProvider {
+        key_uri: key_uri.to_string(),
+        key_encryption_key,
+    });
+    key_providers.insert(key_uri.to_string(), key_provider.clone());
+    Ok(key_provider)
+}
+
+fn load_key_encryption_key(key_uri: &str) -> anyhow::Result<DataKey> {
+    let encoded_key = if let Some(keyfile_path) = key_uri.strip_prefix("file://") {
+        std::fs::read_to_string(Path::new(keyfile_path))
+            .with_context(|| format!("Failed to read keyfile `{keyfile_path}`."))?
+    } else if let Some(key_id) = key_uri.strip_prefix("kms://") {
+        let env_var_key = kms_env_var_key(key_id);
+        std::env::var(&env_var_key).with_context(|| {
+            format!(
+                "Failed to load key `{key_id}`: environment variable `{env_var_key}` is not set."
+            )
+        })?
+    } else {
+        anyhow::bail!("Unsupported key URI `{key_uri}`: expected `file://` or `kms://`.");
+    };
+    let key_bytes = BASE64_STANDARD
+        .decode(encoded_key.trim())
+        .with_context(|| format!("Failed to decode key `{key_uri}` from base64."))?;
+    key_bytes.try_into().map_err(|key_bytes: Vec<u8>| {
+        anyhow!(
+            "Invalid key `{key_uri}`: expected 32 bytes, got {}.",
+            key_bytes.len()
+        )
+    })
+}
+
+fn kms_env_var_key(key_id: &str) -> String {
+    let key_id_suffix: String = key_id
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() {
+                ch.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("QW_KMS_KEY_{key_id_suffix}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) fn keyfile_for_test(dir: &Path, key_byte: u8) -> String {
+        let keyfile_path = dir.join(format!("key-{key_byte}"));
+        std::fs::write(&keyfile_path, BASE64_STANDARD.encode([key_byte; 32])).unwrap();
+        format!("file://{}", keyfile_path.display())
+    }
+
+    #[test]
+    fn test_kms_env_var_key() {
+        assert_eq!(kms_env_var_key("logs-key.v2"), "QW_KMS_KEY_LOGS_KEY_V2");
+    }
+
+    #[test]
+    fn test_resolve_key_provider() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let key_uri = keyfile_for_test(temp_dir.path(), 1);
+        let key_provider = resolve_key_provider(&key_uri).unwrap();
+        assert_eq!(key_provider.key_uri(), key_uri);
+
+        std::env::set_var(
+            "QW_KMS_KEY_TEST_RESOLVE_KEY_PROVIDER",
+            BASE64_STANDARD.encode([2; 32]),
+        );
+        resolve_key_provider("kms://test-resolve-key-provider").unwrap();
+
+        let error = resolve_key_provider("kms://missing-key").err().unwrap();
+        assert_eq!(error.kind(), StorageErrorKind::Unauthorized);
+
+        let invalid_keyfile_path = temp_dir.path().join("invalid-key");
+        std::fs::write(&invalid_keyfile_path, BASE64_STANDARD.encode([3; 16])).unwrap();
+        let invalid_key_uri = format!("file://{}", invalid_keyfile_path.display());
+        let error = resolve_key_provider(&invalid_key_uri).err().unwrap();
+        assert!(error.to_string().contains("expected 32 bytes, got 16"));
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_decrypt_bytes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let key_uri = keyfile_for_test(temp_dir.path(), 4);
+        let plaintext = br#"{"index_id": "my-index"}"#;
+
+        let ciphertext = encrypt_bytes(&key_uri, plaintext).await.unwrap();
+        assert!(is_encrypted(&ciphertext));
+        assert!(!ciphertext
+            .windows(plaintext.len())
+            .any(|window| window == plaintext));
+
+        let decrypted = decrypt_bytes(OwnedBytes::new(ciphertext.clone()))
+            .await
+            .unwrap();
+        assert_eq!(decrypted.as_slice(), plaintext);
+
+        // Plaintext files are returned unchanged.
+        let unencrypted = decrypt_bytes(OwnedBytes::new(plaintext.to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(unencrypted.as_slice(), plaintext);
+
+        // Truncated files are rejected.
+        let truncated = OwnedBytes::new(ciphertext[..ciphertext.len() - 1].to_vec());
+        decrypt_bytes(truncated).await.unwrap_err();
+    }
+}
@@ -20,6 +20,7 @@
 use std::sync::Arc;
 use std::{fmt, io};
 
+use quickwit_aws::retry::Retryable;
 use serde::{Deserialize, Serialize};
 use tantivy::directory::error::{OpenDirectoryError, OpenReadError};
 use thiserror::Error;
@@ -111,6 +112,15 @@ impl StorageError {
     }
 }
 
+impl Retryable for StorageError {
+    fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind,
+            StorageErrorKind::InternalError | StorageErrorKind::Io
+        )
+    }
+}
+
 impl From<io::Error> for StorageError {
     fn from(err: io::Error) -> StorageError {
         match err.kind() {
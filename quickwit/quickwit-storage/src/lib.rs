@@ -24,7 +24,7 @@
 
 //! `quickwit-storage` is the abstraction used in quickwit to interface itself
 //! to different storage:
-//! - object storages (S3)
+//! - object storages (S3, Azure, GCS)
 //! - local filesystem
 //! - distributed filesystems.
 //! etc.
@@ -42,8 +42,10 @@ pub use self::payload::PutPayload;
 pub use self::storage::Storage;
 
 mod bundle_storage;
+mod encryption;
 mod error;
 mod local_file_storage;
+mod middleware;
 mod object_storage;
 mod payload;
 mod prefix_storage;
@@ -58,11 +60,18 @@ pub use self::bundle_storage::{BundleStorage, BundleStorageFileOffsets};
 #[cfg(any(test, feature = "testsuite"))]
 pub use self::cache::MockCache;
 pub use self::cache::{
-    wrap_storage_with_long_term_cache, ByteRangeCache, Cache, MemorySizedCache, QuickwitCache,
+    wrap_storage_with_long_term_cache, ByteRangeCache, Cache, LocalDiskCache, MemorySizedCache,
+    QuickwitCache,
+};
+pub use self::encryption::{
+    decrypt_bytes, encrypt_bytes, is_encrypted, wrap_storage_with_encryption,
 };
 pub use self::local_file_storage::{LocalFileStorage, LocalFileStorageFactory};
+pub use self::middleware::StorageMiddlewareConfig;
 #[cfg(feature = "azure")]
 pub use self::object_storage::{AzureBlobStorage, AzureBlobStorageFactory};
+#[cfg(feature = "gcs")]
+pub use self::object_storage::{GoogleCloudStorage, GoogleCloudStorageFactory};
 pub use self::object_storage::{
     MultiPartPolicy, S3CompatibleObjectStorage, S3CompatibleObjectStorageFactory,
 };
@@ -266,6 +275,42 @@ pub(crate) mod test_suite {
         Ok(())
     }
 
+    async fn test_list_files(storage: &mut dyn Storage) -> anyhow::Result<()> {
+        let test_paths = [
+            Path::new("list_files/2023-01.json"),
+            Path::new("list_files/2023-02.json"),
+            Path::new("list_files/2023/03.json"),
+            Path::new("list_files/2024-01.json"),
+        ];
+        for test_path in test_paths {
+            storage.put(test_path, Box::new(b"123".to_vec())).await?;
+        }
+        assert_eq!(
+            storage.list_files(Path::new("list_files/")).await?,
+            test_paths
+                .iter()
+                .map(|path| path.to_path_buf())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            storage.list_files(Path::new("list_files/2023")).await?,
+            test_paths[..3]
+                .iter()
+                .map(|path| path.to_path_buf())
+                .collect::<Vec<_>>()
+        );
+        assert!(storage
+            .list_files(Path::new("list_files/2025"))
+            .await?
+            .is_empty());
+        storage.bulk_delete(&test_paths).await?;
+        assert!(storage
+            .list_files(Path::new("list_files/"))
+            .await?
+            .is_empty());
+        Ok(())
+    }
+
     /// Generic test suite for a storage.
     pub async fn storage_test_suite(storage: &mut dyn Storage) -> anyhow::Result<()> {
         test_get_inexistent_file(storage)
@@ -292,6 +337,7 @@ pub(crate) mod test_suite {
         test_delete_missing_file(storage)
             .await
             .context("delete_missing_file")?;
+        test_list_files(storage).await.context("list_files")?;
         Ok(())
     }
 
@@ -331,6 +331,45 @@ impl Storage for LocalFileStorage {
             }
         }
     }
+
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        ensure_valid_relative_path(prefix)?;
+        let prefix_str = prefix.to_string_lossy();
+        // The prefix does not necessarily end at a directory separator, so we start walking from
+        // its parent directory unless it explicitly designates a directory.
+        let walk_root = if prefix_str.is_empty() || prefix_str.ends_with('/') {
+            prefix.to_path_buf()
+        } else {
+            prefix.parent().map(Path::to_path_buf).unwrap_or_default()
+        };
+        let mut paths = Vec::new();
+        let mut dirs_to_visit = vec![walk_root];
+
+        while let Some(dir) = dirs_to_visit.pop() {
+            let mut read_dir = match fs::read_dir(self.root.join(&dir)).await {
+                Ok(read_dir) => read_dir,
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            while let Some(entry) = read_dir.next_entry().await? {
+                let path = dir.join(entry.file_name());
+                let path_str = path.to_string_lossy().to_string();
+
+                if entry.file_type().await?.is_dir() {
+                    // Only descend into directories that may contain matching files.
+                    if path_str.starts_with(prefix_str.as_ref())
+                        || prefix_str.starts_with(&path_str)
+                    {
+                        dirs_to_visit.push(path);
+                    }
+                } else if path_str.starts_with(prefix_str.as_ref()) {
+                    paths.push(path);
+                }
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
 }
 
 /// A File storage resolver
@@ -20,18 +20,28 @@
 // See https://prometheus.io/docs/practices/naming/
 
 use once_cell::sync::Lazy;
-use quickwit_common::metrics::{new_counter, new_gauge, IntCounter, IntGauge};
+use quickwit_common::metrics::{
+    exponential_buckets, new_counter, new_counter_vec, new_gauge, new_histogram_vec,
+    new_histogram_vec_with_buckets, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+};
 
 /// Counters associated to storage operations.
 pub struct StorageMetrics {
     pub shortlived_cache: CacheMetrics,
     pub fast_field_cache: CacheMetrics,
     pub split_footer_cache: CacheMetrics,
+    pub disk_cache: CacheMetrics,
+    pub leaf_search_cache: CacheMetrics,
     pub object_storage_get_total: IntCounter,
     pub object_storage_put_total: IntCounter,
     pub object_storage_put_parts: IntCounter,
     pub object_storage_download_num_bytes: IntCounter,
     pub object_storage_upload_num_bytes: IntCounter,
+    pub requests_total: IntCounterVec<2>,
+    pub request_errors_total: IntCounterVec<2>,
+    pub request_retries_total: IntCounterVec<2>,
+    pub request_duration_seconds: HistogramVec<2>,
+    pub request_num_bytes: HistogramVec<2>,
 }
 
 impl Default for StorageMetrics {
@@ -40,6 +50,8 @@ impl Default for StorageMetrics {
             fast_field_cache: CacheMetrics::for_component("fastfields"),
             shortlived_cache: CacheMetrics::for_component("shortlived"),
             split_footer_cache: CacheMetrics::for_component("splitfooter"),
+            disk_cache: CacheMetrics::for_component("disk"),
+            leaf_search_cache: CacheMetrics::for_component("leafsearch"),
             object_storage_get_total: new_counter(
                 "object_storage_gets_total",
                 "Number of objects fetched.",
@@ -66,6 +78,38 @@ impl Default for StorageMetrics {
                 "Amount of data uploaded to an object storage.",
                 "quickwit_storage",
             ),
+            requests_total: new_counter_vec(
+                "requests_total",
+                "Number of storage requests.",
+                "quickwit_storage",
+                ["protocol", "operation"],
+            ),
+            request_errors_total: new_counter_vec(
+                "request_errors_total",
+                "Number of failed storage requests.",
+                "quickwit_storage",
+                ["protocol", "operation"],
+            ),
+            request_retries_total: new_counter_vec(
+                "request_retries_total",
+                "Number of storage requests retried after a retryable error.",
+                "quickwit_storage",
+                ["protocol", "operation"],
+            ),
+            request_duration_seconds: new_histogram_vec(
+                "request_duration_seconds",
+                "Duration of storage requests in seconds, retries included.",
+                "quickwit_storage",
+                ["protocol", "operation"],
+            ),
+            request_num_bytes: new_histogram_vec_with_buckets(
+                "request_num_bytes",
+                "Number of bytes read or written by storage requests.",
+                "quickwit_storage",
+                ["protocol", "operation"],
+                // 1KiB to 1GiB.
+                exponential_buckets(1024.0, 4.0, 11).unwrap(),
+            ),
         }
     }
 }
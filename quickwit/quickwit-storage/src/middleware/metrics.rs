@@ -0,0 +1,222 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use quickwit_common::uri::{Protocol, Uri};
+use tower::Layer;
+
+use super::{downloaded_file_num_bytes, CountingWriter};
+use crate::storage::{BulkDeleteError, SendableAsync};
+use crate::{OwnedBytes, PutPayload, Storage, StorageResult, STORAGE_METRICS};
+
+/// Records the number of requests, errors, latency and bytes transferred of each storage
+/// operation, labelled by storage protocol and operation.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct MetricsStorageLayer;
+
+impl Layer<Arc<dyn Storage>> for MetricsStorageLayer {
+    type Service = Arc<dyn Storage>;
+
+    fn layer(&self, storage: Arc<dyn Storage>) -> Self::Service {
+        Arc::new(MetricsStorage {
+            protocol: storage.uri().protocol(),
+            inner: storage,
+        })
+    }
+}
+
+struct MetricsStorage {
+    inner: Arc<dyn Storage>,
+    protocol: Protocol,
+}
+
+impl MetricsStorage {
+    fn record(&self, operation: &str, start: Instant, is_ok: bool, num_bytes_opt: Option<u64>) {
+        let label_values = [self.protocol.as_str(), operation];
+        STORAGE_METRICS
+            .requests_total
+            .with_label_values(label_values)
+            .inc();
+        if !is_ok {
+            STORAGE_METRICS
+                .request_errors_total
+                .with_label_values(label_values)
+                .inc();
+        }
+        STORAGE_METRICS
+            .request_duration_seconds
+            .with_label_values(label_values)
+            .observe(start.elapsed().as_secs_f64());
+
+        if let Some(num_bytes) = num_bytes_opt {
+            STORAGE_METRICS
+                .request_num_bytes
+                .with_label_values(label_values)
+                .observe(num_bytes as f64);
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for MetricsStorage {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.inner.check_connectivity().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        let start = Instant::now();
+        let num_bytes = payload.len();
+        let result = self.inner.put(path, payload).await;
+        self.record("put", start, result.is_ok(), Some(num_bytes));
+        result
+    }
+
+    async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
+        let start = Instant::now();
+        let mut counting_output = CountingWriter::new(output);
+        let result = self.inner.copy_to(path, &mut counting_output).await;
+        let num_bytes = counting_output.num_bytes;
+        self.record("copy_to", start, result.is_ok(), Some(num_bytes));
+        result
+    }
+
+    async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<()> {
+        let start = Instant::now();
+        let result = self.inner.copy_to_file(path, output_path).await;
+        let num_bytes_opt = if result.is_ok() {
+            Some(downloaded_file_num_bytes(output_path).await)
+        } else {
+            None
+        };
+        self.record("copy_to_file", start, result.is_ok(), num_bytes_opt);
+        result
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        let start = Instant::now();
+        let result = self.inner.get_slice(path, range).await;
+        let num_bytes_opt = result.as_ref().ok().map(|bytes| bytes.len() as u64);
+        self.record("get_slice", start, result.is_ok(), num_bytes_opt);
+        result
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        let start = Instant::now();
+        let result = self.inner.get_all(path).await;
+        let num_bytes_opt = result.as_ref().ok().map(|bytes| bytes.len() as u64);
+        self.record("get_all", start, result.is_ok(), num_bytes_opt);
+        result
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        let start = Instant::now();
+        let result = self.inner.delete(path).await;
+        self.record("delete", start, result.is_ok(), None);
+        result
+    }
+
+    async fn bulk_delete<'a>(&self, paths: &[&'a Path]) -> Result<(), BulkDeleteError> {
+        let start = Instant::now();
+        let result = self.inner.bulk_delete(paths).await;
+        self.record("bulk_delete", start, result.is_ok(), None);
+        result
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        let start = Instant::now();
+        let result = self.inner.exists(path).await;
+        self.record("exists", start, result.is_ok(), None);
+        result
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        let start = Instant::now();
+        let result = self.inner.file_num_bytes(path).await;
+        self.record("file_num_bytes", start, result.is_ok(), None);
+        result
+    }
+
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        let start = Instant::now();
+        let result = self.inner.list_files(prefix).await;
+        self.record("list_files", start, result.is_ok(), None);
+        result
+    }
+
+    fn uri(&self) -> &Uri {
+        self.inner.uri()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockStorage, StorageErrorKind};
+
+    #[tokio::test]
+    async fn test_metrics_storage() {
+        let mut mock_storage = MockStorage::default();
+        // We use an unusual protocol so that the metrics are not polluted by concurrent tests.
+        mock_storage
+            .expect_uri()
+            .return_const(Uri::from_well_formed("grpc://localhost:7281/metrics-test"));
+        mock_storage
+            .expect_get_slice()
+            .times(2)
+            .returning(|path, range| {
+                if path == Path::new("foo") {
+                    Ok(OwnedBytes::new(vec![0u8; range.len()]))
+                } else {
+                    Err(StorageErrorKind::DoesNotExist
+                        .with_error(anyhow::anyhow!("File `{}` does not exist.", path.display())))
+                }
+            });
+        let storage = MetricsStorageLayer.layer(Arc::new(mock_storage) as Arc<dyn Storage>);
+
+        storage.get_slice(Path::new("foo"), 0..3).await.unwrap();
+        storage.get_slice(Path::new("bar"), 0..3).await.unwrap_err();
+
+        let label_values = ["grpc", "get_slice"];
+        assert_eq!(
+            STORAGE_METRICS
+                .requests_total
+                .with_label_values(label_values)
+                .get(),
+            2
+        );
+        assert_eq!(
+            STORAGE_METRICS
+                .request_errors_total
+                .with_label_values(label_values)
+                .get(),
+            1
+        );
+        assert_eq!(
+            STORAGE_METRICS
+                .request_num_bytes
+                .with_label_values(label_values)
+                .get_sample_sum(),
+            3.0
+        );
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+mod metrics;
+mod rate_limit;
+mod retry;
+
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use quickwit_common::get_from_env;
+use quickwit_common::uri::Protocol;
+use tokio::io::AsyncWrite;
+use tower::{Layer, ServiceBuilder};
+
+use self::metrics::MetricsStorageLayer;
+use self::rate_limit::RateLimitStorageLayer;
+use self::retry::RetryStorageLayer;
+use crate::storage::SendableAsync;
+use crate::Storage;
+
+const DEFAULT_MAX_RETRY_ATTEMPTS: usize = 3;
+
+/// Settings of the middleware stack applied to every storage resolved by the
+/// [`StorageUriResolver`](crate::StorageUriResolver).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StorageMiddlewareConfig {
+    /// Maximum number of attempts for operations failing with a retryable error.
+    pub max_retry_attempts: usize,
+    /// Maximum number of requests per second sent to a storage protocol.
+    pub max_requests_per_sec_opt: Option<u64>,
+    /// Maximum number of bytes per second read from or written to a storage protocol.
+    pub max_bytes_per_sec_opt: Option<u64>,
+}
+
+impl StorageMiddlewareConfig {
+    /// Loads the middleware settings from the `QW_STORAGE_*` environment variables.
+    pub fn from_env() -> Self {
+        let max_retry_attempts =
+            get_from_env("QW_STORAGE_MAX_RETRY_ATTEMPTS", DEFAULT_MAX_RETRY_ATTEMPTS).max(1);
+        let max_requests_per_sec = get_from_env("QW_STORAGE_MAX_REQUESTS_PER_SEC", 0u64);
+        let max_bytes_per_sec = get_from_env("QW_STORAGE_MAX_BYTES_PER_SEC", 0u64);
+        Self {
+            max_retry_attempts,
+            max_requests_per_sec_opt: Some(max_requests_per_sec).filter(|limit| *limit > 0),
+            max_bytes_per_sec_opt: Some(max_bytes_per_sec).filter(|limit| *limit > 0),
+        }
+    }
+}
+
+impl Default for StorageMiddlewareConfig {
+    fn default() -> Self {
+        Self {
+            max_retry_attempts: DEFAULT_MAX_RETRY_ATTEMPTS,
+            max_requests_per_sec_opt: None,
+            max_bytes_per_sec_opt: None,
+        }
+    }
+}
+
+/// Middleware stack wrapping a storage, from the outermost to the innermost layer:
+/// - metrics, so that the recorded latencies include the retries;
+/// - retry, with jittered exponential backoff;
+/// - rate limiting, so that each attempt consumes tokens.
+///
+/// The rate limiters are shared by all the storages wrapped by the same middleware.
+#[derive(Clone)]
+pub(crate) struct StorageMiddleware {
+    retry_layer: RetryStorageLayer,
+    rate_limit_layer: RateLimitStorageLayer,
+}
+
+/// Object storages retry their requests on their own, so wrapping them with the retry layer as
+/// well would multiply the number of attempts.
+fn retries_on_its_own(protocol: Protocol) -> bool {
+    matches!(protocol, Protocol::Azure | Protocol::Gcs | Protocol::S3)
+}
+
+impl StorageMiddleware {
+    /// Builds the middleware stack of the storages of `protocol`. The retry layer is skipped
+    /// for object storages.
+    pub fn new(config: &StorageMiddlewareConfig, protocol: Protocol) -> Self {
+        let max_retry_attempts = if retries_on_its_own(protocol) {
+            1
+        } else {
+            config.max_retry_attempts
+        };
+        Self {
+            retry_layer: RetryStorageLayer::new(max_retry_attempts),
+            rate_limit_layer: RateLimitStorageLayer::new(
+                config.max_requests_per_sec_opt,
+                config.max_bytes_per_sec_opt,
+            ),
+        }
+    }
+}
+
+impl Layer<Arc<dyn Storage>> for StorageMiddleware {
+    type Service = Arc<dyn Storage>;
+
+    fn layer(&self, storage: Arc<dyn Storage>) -> Self::Service {
+        ServiceBuilder::new()
+            .layer(MetricsStorageLayer)
+            .layer(self.retry_layer.clone())
+            .layer(self.rate_limit_layer.clone())
+            .service(storage)
+    }
+}
+
+/// Returns the size of a file downloaded with `copy_to_file`.
+async fn downloaded_file_num_bytes(output_path: &Path) -> u64 {
+    tokio::fs::metadata(output_path)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0)
+}
+
+/// Writer counting the number of bytes written to the underlying writer.
+struct CountingWriter<'a> {
+    inner: &'a mut dyn SendableAsync,
+    num_bytes: u64,
+}
+
+impl<'a> CountingWriter<'a> {
+    fn new(inner: &'a mut dyn SendableAsync) -> Self {
+        Self {
+            inner,
+            num_bytes: 0,
+        }
+    }
+}
+
+impl AsyncWrite for CountingWriter<'_> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut *this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(num_bytes)) = &poll {
+            this.num_bytes += *num_bytes as u64;
+        }
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_common::uri::Uri;
+
+    use super::*;
+    use crate::{RamStorage, StorageErrorKind};
+
+    #[tokio::test]
+    async fn test_storage_middleware() {
+        let middleware = StorageMiddleware::new(&StorageMiddlewareConfig::default(), Protocol::Ram);
+        let ram_storage: Arc<dyn Storage> =
+            Arc::new(RamStorage::builder().put("foo", b"foo_content").build());
+        let storage = middleware.layer(ram_storage);
+        assert_eq!(storage.uri(), &Uri::from_well_formed("ram:///"));
+
+        storage
+            .put(Path::new("bar"), Box::new(b"bar_content".to_vec()))
+            .await
+            .unwrap();
+        let bar_content = storage.get_all(Path::new("bar")).await.unwrap();
+        assert_eq!(bar_content.as_slice(), b"bar_content");
+
+        let foo_slice = storage.get_slice(Path::new("foo"), 4..11).await.unwrap();
+        assert_eq!(foo_slice.as_slice(), b"content");
+
+        let mut output = Vec::new();
+        storage
+            .copy_to(Path::new("foo"), &mut output)
+            .await
+            .unwrap();
+        assert_eq!(output, b"foo_content");
+
+        let error = storage.get_all(Path::new("qux")).await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::DoesNotExist);
+    }
+
+    #[test]
+    fn test_storage_middleware_skips_retries_of_object_storages() {
+        let config = StorageMiddlewareConfig::default();
+        let middleware = StorageMiddleware::new(&config, Protocol::S3);
+        assert_eq!(middleware.retry_layer.max_attempts(), 1);
+
+        let middleware = StorageMiddleware::new(&config, Protocol::File);
+        assert_eq!(
+            middleware.retry_layer.max_attempts(),
+            DEFAULT_MAX_RETRY_ATTEMPTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_counting_writer() {
+        let mut output = Vec::new();
+        let mut counting_writer = CountingWriter::new(&mut output);
+        tokio::io::copy(&mut &b"hello world"[..], &mut counting_writer)
+            .await
+            .unwrap();
+        assert_eq!(counting_writer.num_bytes, 11);
+        assert_eq!(output, b"hello world");
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::{ready, Ready};
+use once_cell::sync::OnceCell;
+use quickwit_common::tower::{Buffer, BufferError, ConstantRate, Cost, RateLimit};
+use quickwit_common::uri::Uri;
+use tower::{Layer, Service, ServiceExt};
+use tracing::warn;
+
+use super::{downloaded_file_num_bytes, CountingWriter};
+use crate::storage::{BulkDeleteError, SendableAsync};
+use crate::{OwnedBytes, PutPayload, Storage, StorageResult};
+
+/// Limits the number of requests and bytes per second sent to the underlying storages. The
+/// limits are shared by all the storages wrapped by the same layer.
+#[derive(Clone)]
+pub(crate) struct RateLimitStorageLayer {
+    requests_bucket_opt: Option<TokenBucket>,
+    bytes_bucket_opt: Option<TokenBucket>,
+}
+
+impl RateLimitStorageLayer {
+    pub fn new(max_requests_per_sec_opt: Option<u64>, max_bytes_per_sec_opt: Option<u64>) -> Self {
+        Self {
+            requests_bucket_opt: max_requests_per_sec_opt.map(TokenBucket::per_second),
+            bytes_bucket_opt: max_bytes_per_sec_opt.map(TokenBucket::per_second),
+        }
+    }
+}
+
+impl Layer<Arc<dyn Storage>> for RateLimitStorageLayer {
+    type Service = Arc<dyn Storage>;
+
+    fn layer(&self, storage: Arc<dyn Storage>) -> Self::Service {
+        if self.requests_bucket_opt.is_none() && self.bytes_bucket_opt.is_none() {
+            return storage;
+        }
+        Arc::new(RateLimitStorage {
+            inner: storage,
+            requests_bucket_opt: self.requests_bucket_opt.clone(),
+            bytes_bucket_opt: self.bytes_bucket_opt.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("The storage rate limiter is closed.")]
+struct TokenBucketError;
+
+impl From<BufferError> for TokenBucketError {
+    fn from(_: BufferError) -> Self {
+        Self
+    }
+}
+
+struct Tokens(u64);
+
+impl Cost for Tokens {
+    fn cost(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Service granting tokens unconditionally. Wrapped in a [`RateLimit`], it behaves as a token
+/// bucket: callers wait for the service to be ready before withdrawing their tokens.
+struct TokenIssuer;
+
+impl Service<Tokens> for TokenIssuer {
+    type Response = ();
+    type Error = TokenBucketError;
+    type Future = Ready<Result<(), TokenBucketError>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _tokens: Tokens) -> Self::Future {
+        ready(Ok(()))
+    }
+}
+
+type TokenBucketService = Buffer<RateLimit<TokenIssuer, ConstantRate>, Tokens>;
+
+#[derive(Clone)]
+struct TokenBucket {
+    rate: ConstantRate,
+    // The buffer spawns its worker on the current runtime, so we create it lazily on first use.
+    service: Arc<OnceCell<TokenBucketService>>,
+}
+
+impl TokenBucket {
+    fn per_second(num_tokens: u64) -> Self {
+        Self {
+            rate: ConstantRate::new(num_tokens, Duration::from_secs(1)),
+            service: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Waits until the bucket is not in debt anymore, then withdraws `num_tokens`. Withdrawing
+    /// more tokens than available puts the bucket in debt, delaying the next callers.
+    async fn withdraw(&self, num_tokens: u64) {
+        let mut service = self
+            .service
+            .get_or_init(|| Buffer::new(RateLimit::new(TokenIssuer, self.rate), 1_024))
+            .clone();
+        let withdraw_res = async { service.ready().await?.call(Tokens(num_tokens)).await }.await;
+        if let Err(error) = withdraw_res {
+            // This only happens if the runtime that spawned the buffer worker was shut down. We
+            // let the request through rather than failing it.
+            warn!(error=?error, "Failed to withdraw tokens from the storage rate limiter.");
+        }
+    }
+}
+
+struct RateLimitStorage {
+    inner: Arc<dyn Storage>,
+    requests_bucket_opt: Option<TokenBucket>,
+    bytes_bucket_opt: Option<TokenBucket>,
+}
+
+impl RateLimitStorage {
+    /// Withdraws one request and `num_bytes` bytes from the buckets.
+    async fn throttle(&self, num_bytes: u64) {
+        if let Some(requests_bucket) = &self.requests_bucket_opt {
+            requests_bucket.withdraw(1).await;
+        }
+        self.throttle_bytes(num_bytes).await;
+    }
+
+    /// Withdraws `num_bytes` bytes from the bytes bucket. Used on its own for operations whose
+    /// number of bytes is only known once they complete.
+    async fn throttle_bytes(&self, num_bytes: u64) {
+        if num_bytes == 0 {
+            return;
+        }
+        if let Some(bytes_bucket) = &self.bytes_bucket_opt {
+            bytes_bucket.withdraw(num_bytes).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for RateLimitStorage {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.inner.check_connectivity().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        self.throttle(payload.len()).await;
+        self.inner.put(path, payload).await
+    }
+
+    async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
+        self.throttle(0).await;
+        let mut counting_output = CountingWriter::new(output);
+        let result = self.inner.copy_to(path, &mut counting_output).await;
+        self.throttle_bytes(counting_output.num_bytes).await;
+        result
+    }
+
+    async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<()> {
+        self.throttle(0).await;
+        self.inner.copy_to_file(path, output_path).await?;
+        self.throttle_bytes(downloaded_file_num_bytes(output_path).await)
+            .await;
+        Ok(())
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        self.throttle(range.len() as u64).await;
+        self.inner.get_slice(path, range).await
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        self.throttle(0).await;
+        let bytes = self.inner.get_all(path).await?;
+        self.throttle_bytes(bytes.len() as u64).await;
+        Ok(bytes)
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        self.throttle(0).await;
+        self.inner.delete(path).await
+    }
+
+    async fn bulk_delete<'a>(&self, paths: &[&'a Path]) -> Result<(), BulkDeleteError> {
+        self.throttle(0).await;
+        self.inner.bulk_delete(paths).await
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.throttle(0).await;
+        self.inner.exists(path).await
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        self.throttle(0).await;
+        self.inner.file_num_bytes(path).await
+    }
+
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        self.throttle(0).await;
+        self.inner.list_files(prefix).await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.inner.uri()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::Instant;
+
+    use super::*;
+    use crate::RamStorage;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_storage_requests() {
+        let ram_storage: Arc<dyn Storage> =
+            Arc::new(RamStorage::builder().put("foo", b"foo_content").build());
+        let storage = RateLimitStorageLayer::new(Some(10), None).layer(ram_storage);
+        let now = Instant::now();
+        for _ in 0..10 {
+            storage.get_slice(Path::new("foo"), 0..3).await.unwrap();
+        }
+        assert!(now.elapsed() < Duration::from_millis(500));
+
+        // The bucket is empty: the next request goes through but puts the bucket in debt, and the
+        // following one has to wait for the next period.
+        storage.get_slice(Path::new("foo"), 0..3).await.unwrap();
+        assert!(now.elapsed() < Duration::from_millis(500));
+
+        storage.get_slice(Path::new("foo"), 0..3).await.unwrap();
+        assert!(now.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_storage_bytes() {
+        let ram_storage: Arc<dyn Storage> =
+            Arc::new(RamStorage::builder().put("foo", &[0u8; 1_000]).build());
+        let storage = RateLimitStorageLayer::new(None, Some(1_000)).layer(ram_storage);
+        let now = Instant::now();
+        // The first two requests go through, but the second one puts the bucket in debt.
+        storage.get_all(Path::new("foo")).await.unwrap();
+        storage.get_slice(Path::new("foo"), 0..500).await.unwrap();
+        assert!(now.elapsed() < Duration::from_millis(500));
+
+        storage.get_slice(Path::new("foo"), 0..500).await.unwrap();
+        assert!(now.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_rate_limit_storage_layer_is_noop_without_limits() {
+        let ram_storage: Arc<dyn Storage> = Arc::new(RamStorage::default());
+        let storage = RateLimitStorageLayer::new(None, None).layer(ram_storage.clone());
+        assert!(Arc::ptr_eq(&storage, &ram_storage));
+    }
+}
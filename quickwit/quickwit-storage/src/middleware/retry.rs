@@ -0,0 +1,208 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::Future;
+use quickwit_aws::retry::{retry, RetryParams};
+use quickwit_common::uri::Uri;
+use tower::Layer;
+
+use crate::storage::{BulkDeleteError, SendableAsync};
+use crate::{OwnedBytes, PutPayload, Storage, StorageResult, STORAGE_METRICS};
+
+/// Retries the storage operations failing with a retryable error, i.e. an `Io` or
+/// `InternalError` error, with jittered exponential backoff.
+///
+/// `copy_to` and `bulk_delete` are not retried: the former may have already written to its
+/// output when it fails, and the latter reports partial failures on its own.
+#[derive(Clone)]
+pub(crate) struct RetryStorageLayer {
+    retry_params: RetryParams,
+}
+
+impl RetryStorageLayer {
+    pub fn new(max_attempts: usize) -> Self {
+        Self {
+            retry_params: RetryParams {
+                max_attempts,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[cfg(test)]
+    pub fn max_attempts(&self) -> usize {
+        self.retry_params.max_attempts
+    }
+}
+
+impl Layer<Arc<dyn Storage>> for RetryStorageLayer {
+    type Service = Arc<dyn Storage>;
+
+    fn layer(&self, storage: Arc<dyn Storage>) -> Self::Service {
+        if self.retry_params.max_attempts <= 1 {
+            return storage;
+        }
+        Arc::new(RetryStorage {
+            inner: storage,
+            retry_params: self.retry_params.clone(),
+        })
+    }
+}
+
+struct RetryStorage {
+    inner: Arc<dyn Storage>,
+    retry_params: RetryParams,
+}
+
+impl RetryStorage {
+    async fn retry<T, Fut>(&self, operation: &str, f: impl Fn() -> Fut) -> StorageResult<T>
+    where Fut: Future<Output = StorageResult<T>> {
+        let num_attempts = AtomicUsize::new(0);
+        retry(&self.retry_params, || {
+            if num_attempts.fetch_add(1, Ordering::Relaxed) > 0 {
+                STORAGE_METRICS
+                    .request_retries_total
+                    .with_label_values([self.inner.uri().protocol().as_str(), operation])
+                    .inc();
+            }
+            f()
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl Storage for RetryStorage {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.inner.check_connectivity().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        self.retry("put", || self.inner.put(path, payload.clone()))
+            .await
+    }
+
+    async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
+        self.inner.copy_to(path, output).await
+    }
+
+    async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<()> {
+        // The output file is truncated on each attempt.
+        self.retry("copy_to_file", || {
+            self.inner.copy_to_file(path, output_path)
+        })
+        .await
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        self.retry("get_slice", || self.inner.get_slice(path, range.clone()))
+            .await
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        self.retry("get_all", || self.inner.get_all(path)).await
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        self.retry("delete", || self.inner.delete(path)).await
+    }
+
+    async fn bulk_delete<'a>(&self, paths: &[&'a Path]) -> Result<(), BulkDeleteError> {
+        self.inner.bulk_delete(paths).await
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.retry("exists", || self.inner.exists(path)).await
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        self.retry("file_num_bytes", || self.inner.file_num_bytes(path))
+            .await
+    }
+
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        self.retry("list_files", || self.inner.list_files(prefix))
+            .await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.inner.uri()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockStorage, StorageErrorKind};
+
+    fn mock_storage_failing_n_times(
+        error_kind: StorageErrorKind,
+        num_failures: usize,
+    ) -> (Arc<dyn Storage>, Arc<AtomicUsize>) {
+        let num_calls = Arc::new(AtomicUsize::new(0));
+        let num_calls_clone = num_calls.clone();
+        let mut mock_storage = MockStorage::default();
+        mock_storage
+            .expect_uri()
+            .return_const(Uri::from_well_formed("ram:///retry-test"));
+        mock_storage.expect_get_all().returning(move |_path| {
+            if num_calls_clone.fetch_add(1, Ordering::SeqCst) < num_failures {
+                Err(error_kind.with_error(anyhow::anyhow!("Failed to get file.")))
+            } else {
+                Ok(OwnedBytes::new(b"content".to_vec()))
+            }
+        });
+        let mock_storage: Arc<dyn Storage> = Arc::new(mock_storage);
+        (mock_storage, num_calls)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_storage_retries_retryable_errors() {
+        let (mock_storage, num_calls) = mock_storage_failing_n_times(StorageErrorKind::Io, 2);
+        let storage = RetryStorageLayer::new(3).layer(mock_storage);
+        let content = storage.get_all(Path::new("foo")).await.unwrap();
+        assert_eq!(content.as_slice(), b"content");
+        assert_eq!(num_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_storage_gives_up_after_max_attempts() {
+        let (mock_storage, num_calls) =
+            mock_storage_failing_n_times(StorageErrorKind::InternalError, 3);
+        let storage = RetryStorageLayer::new(3).layer(mock_storage);
+        let error = storage.get_all(Path::new("foo")).await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::InternalError);
+        assert_eq!(num_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_storage_does_not_retry_permanent_errors() {
+        let (mock_storage, num_calls) =
+            mock_storage_failing_n_times(StorageErrorKind::DoesNotExist, 1);
+        let storage = RetryStorageLayer::new(3).layer(mock_storage);
+        let error = storage.get_all(Path::new("foo")).await.unwrap_err();
+        assert_eq!(error.kind(), StorageErrorKind::DoesNotExist);
+        assert_eq!(num_calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,623 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{fmt, io};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::StreamExt;
+use google_cloud_storage::client::{Client, ClientConfig};
+use google_cloud_storage::http::objects::delete::DeleteObjectRequest;
+use google_cloud_storage::http::objects::download::Range as GcsRange;
+use google_cloud_storage::http::objects::get::GetObjectRequest;
+use google_cloud_storage::http::objects::list::ListObjectsRequest;
+use google_cloud_storage::http::objects::upload::{Media, UploadObjectRequest, UploadType};
+use google_cloud_storage::http::objects::Object;
+use google_cloud_storage::http::resumable_upload_client::{ChunkSize, UploadStatus};
+use google_cloud_storage::http::Error as GcsError;
+use once_cell::sync::OnceCell;
+use quickwit_aws::retry::{retry, RetryParams, Retryable};
+use quickwit_common::uri::{Protocol, Uri};
+use quickwit_common::{chunk_range, ignore_error_kind, into_u64_range};
+use regex::Regex;
+use tantivy::directory::OwnedBytes;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tracing::instrument;
+
+use crate::debouncer::DebouncedStorage;
+use crate::storage::{BulkDeleteError, DeleteFailure, SendableAsync};
+use crate::{
+    MultiPartPolicy, PutPayload, Storage, StorageError, StorageErrorKind, StorageFactory,
+    StorageResolverError, StorageResult, STORAGE_METRICS,
+};
+
+/// The chunks of a resumable upload must be a multiple of 256 KiB, except for the last one.
+const RESUMABLE_UPLOAD_CHUNK_ALIGNMENT: u64 = 256 * 1_024;
+
+/// Google Cloud Storage URI resolver.
+#[derive(Default)]
+pub struct GoogleCloudStorageFactory;
+
+impl StorageFactory for GoogleCloudStorageFactory {
+    fn protocol(&self) -> Protocol {
+        Protocol::Gcs
+    }
+
+    fn resolve(&self, uri: &Uri) -> Result<Arc<dyn Storage>, StorageResolverError> {
+        let storage = GoogleCloudStorage::from_uri(uri)?;
+        Ok(Arc::new(DebouncedStorage::new(storage)))
+    }
+}
+
+/// Google Cloud Storage implementation.
+pub struct GoogleCloudStorage {
+    /// The client is lazily initialized because loading the credentials is asynchronous.
+    client: tokio::sync::OnceCell<Client>,
+    endpoint_opt: Option<String>,
+    anonymous: bool,
+    bucket: String,
+    uri: Uri,
+    prefix: PathBuf,
+    multipart_policy: MultiPartPolicy,
+    retry_params: RetryParams,
+}
+
+impl fmt::Debug for GoogleCloudStorage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GoogleCloudStorage")
+            .field("uri", &self.uri)
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+impl GoogleCloudStorage {
+    /// Creates an object storage.
+    ///
+    /// The credentials are looked up when the storage is first accessed, using the file pointed
+    /// to by the `GOOGLE_APPLICATION_CREDENTIALS` environment variable, the gcloud application
+    /// default credentials, or the metadata server, in that order.
+    pub fn new(bucket: &str, uri: Uri, endpoint_opt: Option<String>) -> Self {
+        Self {
+            client: tokio::sync::OnceCell::new(),
+            endpoint_opt,
+            anonymous: false,
+            bucket: bucket.to_string(),
+            uri,
+            prefix: PathBuf::new(),
+            multipart_policy: default_multipart_policy(),
+            retry_params: RetryParams {
+                max_attempts: 3,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Sets the prefix path.
+    ///
+    /// The existing prefix is overwritten.
+    pub fn with_prefix(self, prefix: &Path) -> Self {
+        Self {
+            prefix: prefix.to_path_buf(),
+            ..self
+        }
+    }
+
+    /// Creates an emulated storage for testing.
+    ///
+    /// The emulator, e.g. fake-gcs-server, is expected to listen on the endpoint set in the
+    /// `QW_GCS_ENDPOINT` environment variable, or on `http://localhost:4443` by default.
+    #[cfg(feature = "testsuite")]
+    pub fn new_emulated(bucket: &str) -> Self {
+        let endpoint = std::env::var("QW_GCS_ENDPOINT")
+            .unwrap_or_else(|_| "http://localhost:4443".to_string());
+        Self {
+            anonymous: true,
+            ..Self::new(
+                bucket,
+                Uri::from_well_formed(format!("gs://{bucket}")),
+                Some(endpoint),
+            )
+        }
+    }
+
+    /// Sets the multipart policy.
+    ///
+    /// See `MultiPartPolicy`.
+    pub fn set_policy(&mut self, multipart_policy: MultiPartPolicy) {
+        self.multipart_policy = multipart_policy;
+    }
+
+    /// Builds instance from URI.
+    pub fn from_uri(uri: &Uri) -> Result<GoogleCloudStorage, StorageResolverError> {
+        let (bucket, path) =
+            parse_gcs_uri(uri).ok_or_else(|| StorageResolverError::InvalidUri {
+                message: format!("Invalid URI: {uri}"),
+            })?;
+        let endpoint_opt = std::env::var("QW_GCS_ENDPOINT").ok();
+        let gcs_storage = GoogleCloudStorage::new(&bucket, uri.clone(), endpoint_opt);
+        Ok(gcs_storage.with_prefix(&path))
+    }
+
+    async fn client(&self) -> StorageResult<&Client> {
+        self.client
+            .get_or_try_init(|| async {
+                let mut client_config = if self.anonymous {
+                    ClientConfig::default().anonymous()
+                } else {
+                    ClientConfig::default().with_auth().await.map_err(|error| {
+                        StorageErrorKind::Unauthorized.with_error(anyhow::anyhow!(
+                            "Failed to load Google Cloud credentials: {error}"
+                        ))
+                    })?
+                };
+                if let Some(endpoint) = &self.endpoint_opt {
+                    client_config.storage_endpoint = endpoint.trim_end_matches('/').to_string();
+                }
+                Ok::<_, StorageError>(Client::new(client_config))
+            })
+            .await
+    }
+
+    /// Returns the object name (a.k.a object key).
+    fn object_name(&self, relative_path: &Path) -> String {
+        let key_path = self.prefix.join(relative_path);
+        key_path.to_string_lossy().to_string()
+    }
+
+    fn upload_request(&self) -> UploadObjectRequest {
+        UploadObjectRequest {
+            bucket: self.bucket.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn get_request(&self, object_name: String) -> GetObjectRequest {
+        GetObjectRequest {
+            bucket: self.bucket.clone(),
+            object: object_name,
+            ..Default::default()
+        }
+    }
+
+    /// Downloads an object as vector of bytes.
+    async fn get_to_vec(
+        &self,
+        path: &Path,
+        range_opt: Option<Range<usize>>,
+    ) -> StorageResult<Vec<u8>> {
+        let gcs_range = match range_opt {
+            // GCS rejects empty ranges.
+            Some(range) if range.is_empty() => return Ok(Vec::new()),
+            // GCS ranges are inclusive.
+            Some(range) => GcsRange(Some(range.start as u64), Some(range.end as u64 - 1)),
+            None => GcsRange::default(),
+        };
+        let client = self.client().await?;
+        let get_request = self.get_request(self.object_name(path));
+        let data = retry(&self.retry_params, || async {
+            client
+                .download_object(&get_request, &gcs_range)
+                .await
+                .map_err(GcsErrorWrapper::from)
+        })
+        .await?;
+        STORAGE_METRICS
+            .object_storage_download_num_bytes
+            .inc_by(data.len() as u64);
+        Ok(data)
+    }
+
+    /// Performs a single part upload.
+    async fn put_single_part(
+        &self,
+        object_name: &str,
+        payload: Box<dyn PutPayload>,
+    ) -> StorageResult<()> {
+        STORAGE_METRICS.object_storage_put_parts.inc();
+        STORAGE_METRICS
+            .object_storage_upload_num_bytes
+            .inc_by(payload.len());
+        let client = self.client().await?;
+        let upload_request = self.upload_request();
+        let upload_type = UploadType::Simple(Media::new(object_name.to_string()));
+        retry(&self.retry_params, || async {
+            let data = Bytes::from(payload.read_all().await?.to_vec());
+            client
+                .upload_object(&upload_request, data, &upload_type)
+                .await?;
+            Result::<(), GcsErrorWrapper>::Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Performs a multipart upload using a resumable upload session. GCS requires the parts of a
+    /// resumable upload to be sent in order, so they are uploaded sequentially.
+    async fn put_multi_part(
+        &self,
+        object_name: &str,
+        payload: Box<dyn PutPayload>,
+        part_len: u64,
+        total_len: u64,
+    ) -> StorageResult<()> {
+        assert!(total_len > 0);
+        let client = self.client().await?;
+        let upload_request = self.upload_request();
+        let upload_type = UploadType::Multipart(Box::new(Object {
+            name: object_name.to_string(),
+            ..Default::default()
+        }));
+        let uploader = retry(&self.retry_params, || async {
+            client
+                .prepare_resumable_upload(&upload_request, &upload_type)
+                .await
+                .map_err(GcsErrorWrapper::from)
+        })
+        .await?;
+
+        let part_len = align_part_num_bytes(part_len);
+        let multipart_ranges =
+            chunk_range(0..total_len as usize, part_len as usize).map(into_u64_range);
+
+        let mut upload_status = UploadStatus::NotStarted;
+        for range in multipart_ranges {
+            STORAGE_METRICS.object_storage_put_parts.inc();
+            STORAGE_METRICS
+                .object_storage_upload_num_bytes
+                .inc_by(range.end - range.start);
+            let chunk_size = ChunkSize::new(range.start, range.end - 1, Some(total_len));
+            upload_status = retry(&self.retry_params, || async {
+                let data = extract_range_data(payload.box_clone(), range.clone()).await?;
+                let upload_status = uploader.upload_multiple_chunk(data, &chunk_size).await?;
+                Result::<_, GcsErrorWrapper>::Ok(upload_status)
+            })
+            .await?;
+        }
+        if !matches!(upload_status, UploadStatus::Ok(_)) {
+            return Err(StorageErrorKind::Service.with_error(anyhow::anyhow!(
+                "Resumable upload of object `{object_name}` did not complete."
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for GoogleCloudStorage {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        let list_request = ListObjectsRequest {
+            bucket: self.bucket.clone(),
+            max_results: Some(1),
+            ..Default::default()
+        };
+        self.client()
+            .await?
+            .list_objects(&list_request)
+            .await
+            .map_err(GcsErrorWrapper::from)?;
+        Ok(())
+    }
+
+    async fn put(
+        &self,
+        path: &Path,
+        payload: Box<dyn crate::PutPayload>,
+    ) -> crate::StorageResult<()> {
+        STORAGE_METRICS.object_storage_put_total.inc();
+        let object_name = self.object_name(path);
+        let total_len = payload.len();
+        let part_num_bytes = self.multipart_policy.part_num_bytes(total_len);
+
+        if part_num_bytes >= total_len {
+            self.put_single_part(&object_name, payload).await?;
+        } else {
+            self.put_multi_part(&object_name, payload, part_num_bytes, total_len)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn copy_to(&self, path: &Path, output: &mut dyn SendableAsync) -> StorageResult<()> {
+        let client = self.client().await?;
+        let get_request = self.get_request(self.object_name(path));
+        let mut chunk_stream = client
+            .download_streamed_object(&get_request, &GcsRange::default())
+            .await
+            .map_err(GcsErrorWrapper::from)?;
+
+        while let Some(chunk_result) = chunk_stream.next().await {
+            let chunk = chunk_result.map_err(GcsErrorWrapper::from)?;
+            output.write_all(&chunk).await?;
+            STORAGE_METRICS
+                .object_storage_download_num_bytes
+                .inc_by(chunk.len() as u64);
+        }
+        output.flush().await?;
+        Ok(())
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        let delete_request = DeleteObjectRequest {
+            bucket: self.bucket.clone(),
+            object: self.object_name(path),
+            ..Default::default()
+        };
+        let client = self.client().await?;
+        let delete_res = retry(&self.retry_params, || async {
+            client
+                .delete_object(&delete_request)
+                .await
+                .map_err(GcsErrorWrapper::from)
+        })
+        .await
+        .map_err(StorageError::from);
+        ignore_error_kind!(StorageErrorKind::DoesNotExist, delete_res)?;
+        Ok(())
+    }
+
+    async fn bulk_delete<'a>(&self, paths: &[&'a Path]) -> Result<(), BulkDeleteError> {
+        // The JSON API only offers batching through multipart HTTP requests, which the client
+        // does not support, so the objects are deleted concurrently instead.
+        let mut successes = Vec::with_capacity(paths.len());
+        let mut failures = HashMap::new();
+
+        let futures = paths
+            .iter()
+            .map(|path| async move {
+                let delete_res = self.delete(path).await;
+                (path, delete_res)
+            })
+            .collect::<Vec<_>>();
+        let mut stream = futures::stream::iter(futures).buffer_unordered(100);
+
+        while let Some((path, delete_res)) = stream.next().await {
+            match delete_res {
+                Ok(_) => successes.push(path.to_path_buf()),
+                Err(error) => {
+                    let failure = DeleteFailure {
+                        error: Some(error),
+                        ..Default::default()
+                    };
+                    failures.insert(path.to_path_buf(), failure);
+                }
+            };
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(BulkDeleteError {
+                successes,
+                failures,
+                ..Default::default()
+            })
+        }
+    }
+
+    #[instrument(level = "debug", skip(self, range), fields(range.start = range.start, range.end = range.end))]
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        self.get_to_vec(path, Some(range.clone()))
+            .await
+            .map(OwnedBytes::new)
+            .map_err(|err| {
+                err.add_context(format!(
+                    "Failed to fetch slice {:?} for object: {}/{}",
+                    range,
+                    self.uri,
+                    path.display(),
+                ))
+            })
+    }
+
+    #[instrument(level = "debug", skip(self), fields(fetched_bytes_len))]
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        let data = self
+            .get_to_vec(path, None)
+            .await
+            .map(OwnedBytes::new)
+            .map_err(|err| {
+                err.add_context(format!(
+                    "Failed to fetch object: {}/{}",
+                    self.uri,
+                    path.display()
+                ))
+            })?;
+        tracing::Span::current().record("fetched_bytes_len", data.len());
+        Ok(data)
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        let get_request = self.get_request(self.object_name(path));
+        let object = self
+            .client()
+            .await?
+            .get_object(&get_request)
+            .await
+            .map_err(GcsErrorWrapper::from)?;
+        Ok(object.size as u64)
+    }
+
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        let client = self.client().await?;
+        let name_prefix = self.object_name(prefix);
+        let mut paths = Vec::new();
+        let mut page_token_opt: Option<String> = None;
+
+        loop {
+            let list_request = ListObjectsRequest {
+                bucket: self.bucket.clone(),
+                prefix: Some(name_prefix.clone()),
+                page_token: page_token_opt.take(),
+                ..Default::default()
+            };
+            let list_response = client
+                .list_objects(&list_request)
+                .await
+                .map_err(GcsErrorWrapper::from)?;
+            for object in list_response.items.unwrap_or_default() {
+                if let Ok(path) = Path::new(&object.name).strip_prefix(&self.prefix) {
+                    paths.push(path.to_path_buf());
+                }
+            }
+            page_token_opt = list_response.next_page_token;
+            if page_token_opt.is_none() {
+                break;
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn uri(&self) -> &Uri {
+        &self.uri
+    }
+}
+
+/// Unlike S3, GCS does not charge per uploaded part, so we favor smaller parts that can be
+/// retried cheaply.
+fn default_multipart_policy() -> MultiPartPolicy {
+    MultiPartPolicy {
+        target_part_num_bytes: 64 * 1_024 * 1_024, // 64 MiB
+        max_num_parts: 10_000,
+        multipart_threshold_num_bytes: 128 * 1_024 * 1_024, // 128 MiB
+        max_object_num_bytes: 5 * 1_024 * 1_024 * 1_024 * 1_024, // GCS allows up to 5TiB objects
+        max_concurrent_upload: 1,
+    }
+}
+
+/// Rounds the part size up to the next multiple of 256 KiB.
+fn align_part_num_bytes(part_num_bytes: u64) -> u64 {
+    let num_chunks =
+        (part_num_bytes + RESUMABLE_UPLOAD_CHUNK_ALIGNMENT - 1) / RESUMABLE_UPLOAD_CHUNK_ALIGNMENT;
+    num_chunks.max(1) * RESUMABLE_UPLOAD_CHUNK_ALIGNMENT
+}
+
+/// Copy range of payload into `Bytes`.
+async fn extract_range_data(payload: Box<dyn PutPayload>, range: Range<u64>) -> io::Result<Bytes> {
+    let mut reader = payload
+        .range_byte_stream(range.clone())
+        .await?
+        .into_async_read();
+    let mut buf: Vec<u8> = Vec::with_capacity(range.count());
+    tokio::io::copy(&mut reader, &mut buf).await?;
+    Ok(Bytes::from(buf))
+}
+
+fn parse_gcs_uri(uri: &Uri) -> Option<(String, PathBuf)> {
+    // Ex: gs://bucket/prefix.
+    static URI_PTN: OnceCell<Regex> = OnceCell::new();
+    URI_PTN
+        .get_or_init(|| Regex::new(r"gs://(?P<bucket>[^/]+)(/(?P<path>.+))?").unwrap())
+        .captures(uri.as_str())
+        .and_then(|captures| {
+            let bucket = captures.name("bucket")?.as_str().to_string();
+            let path = captures.name("path").map_or_else(
+                || PathBuf::from(""),
+                |path_match| PathBuf::from(path_match.as_str()),
+            );
+            Some((bucket, path))
+        })
+}
+
+#[derive(Error, Debug)]
+enum GcsErrorWrapper {
+    #[error("GcsError(inner={0})")]
+    Gcs(#[from] GcsError),
+    #[error("IoError(inner={0})")]
+    Io(#[from] io::Error),
+}
+
+impl GcsErrorWrapper {
+    fn status_code_opt(&self) -> Option<u16> {
+        match self {
+            GcsErrorWrapper::Gcs(GcsError::Response(response)) => Some(response.code),
+            GcsErrorWrapper::Gcs(GcsError::HttpClient(error)) => {
+                error.status().map(|status| status.as_u16())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Retryable for GcsErrorWrapper {
+    fn is_retryable(&self) -> bool {
+        if let Some(status_code) = self.status_code_opt() {
+            return status_code == 408 || status_code == 429 || status_code >= 500;
+        }
+        match self {
+            GcsErrorWrapper::Gcs(GcsError::HttpClient(error)) => {
+                error.is_timeout() || error.is_connect()
+            }
+            GcsErrorWrapper::Io(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<GcsErrorWrapper> for StorageError {
+    fn from(err: GcsErrorWrapper) -> Self {
+        match err.status_code_opt() {
+            Some(404) => StorageErrorKind::DoesNotExist.with_error(err),
+            Some(401) | Some(403) => StorageErrorKind::Unauthorized.with_error(err),
+            Some(_) => StorageErrorKind::Service.with_error(err),
+            None => match err {
+                GcsErrorWrapper::Gcs(GcsError::TokenSource(_)) => {
+                    StorageErrorKind::Unauthorized.with_error(err)
+                }
+                GcsErrorWrapper::Io(_) | GcsErrorWrapper::Gcs(GcsError::HttpClient(_)) => {
+                    StorageErrorKind::Io.with_error(err)
+                }
+                _ => StorageErrorKind::InternalError.with_error(err),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gcs_uri() {
+        let (bucket, path) =
+            parse_gcs_uri(&Uri::from_well_formed("gs://quickwit/indexes/wiki")).unwrap();
+        assert_eq!(bucket, "quickwit");
+        assert_eq!(path.to_string_lossy().to_string(), "indexes/wiki");
+
+        let (bucket, path) = parse_gcs_uri(&Uri::from_well_formed("gs://quickwit")).unwrap();
+        assert_eq!(bucket, "quickwit");
+        assert_eq!(path.to_string_lossy().to_string(), "");
+
+        assert_eq!(parse_gcs_uri(&Uri::from_well_formed("gs://")), None);
+    }
+
+    #[test]
+    fn test_align_part_num_bytes() {
+        assert_eq!(align_part_num_bytes(0), 256 * 1_024);
+        assert_eq!(align_part_num_bytes(1), 256 * 1_024);
+        assert_eq!(align_part_num_bytes(256 * 1_024), 256 * 1_024);
+        assert_eq!(align_part_num_bytes(256 * 1_024 + 1), 512 * 1_024);
+        assert_eq!(align_part_num_bytes(64 * 1_024 * 1_024), 64 * 1_024 * 1_024);
+    }
+}
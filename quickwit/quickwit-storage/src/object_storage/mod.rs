@@ -32,3 +32,8 @@ mod s3_compatible_storage_uri_resolver;
 mod azure_blob_storage;
 #[cfg(feature = "azure")]
 pub use self::azure_blob_storage::{AzureBlobStorage, AzureBlobStorageFactory};
+
+#[cfg(feature = "gcs")]
+mod gcs_storage;
+#[cfg(feature = "gcs")]
+pub use self::gcs_storage::{GoogleCloudStorage, GoogleCloudStorageFactory};
@@ -736,6 +736,40 @@ impl Storage for S3CompatibleObjectStorage {
         }
     }
 
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>> {
+        let key_prefix = self.key(prefix);
+        let mut paths = Vec::new();
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let list_objects_req = ListObjectsV2Request {
+                bucket: self.bucket.clone(),
+                prefix: Some(key_prefix.clone()),
+                continuation_token: continuation_token.clone(),
+                ..Default::default()
+            };
+            let list_objects_output = retry(&self.retry_params, || async {
+                self.s3_client
+                    .list_objects_v2(list_objects_req.clone())
+                    .await
+                    .map_err(RusotoErrorWrapper::from)
+            })
+            .await?;
+
+            for object in list_objects_output.contents.unwrap_or_default() {
+                if let Some(key) = object.key {
+                    paths.push(self.relative_path(&key));
+                }
+            }
+            if list_objects_output.is_truncated != Some(true) {
+                break;
+            }
+            continuation_token = list_objects_output.next_continuation_token;
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
     fn uri(&self) -> &Uri {
         &self.uri
     }
@@ -98,6 +98,19 @@ impl Storage for PrefixStorage {
     async fn file_num_bytes(&self, path: &Path) -> crate::StorageResult<u64> {
         self.storage.file_num_bytes(&self.prefix.join(path)).await
     }
+
+    async fn list_files(&self, prefix: &Path) -> crate::StorageResult<Vec<PathBuf>> {
+        let prefixed_paths = self.storage.list_files(&self.prefix.join(prefix)).await?;
+        let paths = prefixed_paths
+            .into_iter()
+            .filter_map(|path| {
+                path.strip_prefix(&self.prefix)
+                    .ok()
+                    .map(|path| path.to_path_buf())
+            })
+            .collect();
+        Ok(paths)
+    }
 }
 
 /// Creates a [`PrefixStorage`] using an underlying storage and a prefix.
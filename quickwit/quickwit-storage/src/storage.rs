@@ -123,6 +123,14 @@ pub trait Storage: Send + Sync + 'static {
     /// Returns a file size.
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64>;
 
+    /// Lists the files whose path starts with `prefix`, sorted in lexicographic order.
+    ///
+    /// As with object storage listings, the prefix is matched against the path string and
+    /// does not need to end at a directory separator: `logs/2023` matches both
+    /// `logs/2023-01.json` and `logs/2023/01.json`. The returned paths are relative to the
+    /// storage.
+    async fn list_files(&self, prefix: &Path) -> StorageResult<Vec<PathBuf>>;
+
     /// Returns an URI identifying the storage
     fn uri(&self) -> &Uri;
 }
@@ -22,12 +22,19 @@ use std::sync::Arc;
 
 use once_cell::sync::OnceCell;
 use quickwit_common::uri::{Protocol, Uri};
+use tower::Layer;
 
 use crate::local_file_storage::LocalFileStorageFactory;
+use crate::middleware::StorageMiddleware;
 use crate::ram_storage::RamStorageFactory;
 #[cfg(feature = "azure")]
 use crate::AzureBlobStorageFactory;
-use crate::{S3CompatibleObjectStorageFactory, Storage, StorageResolverError};
+#[cfg(feature = "gcs")]
+use crate::GoogleCloudStorageFactory;
+use crate::{
+    wrap_storage_with_encryption, S3CompatibleObjectStorageFactory, Storage,
+    StorageMiddlewareConfig, StorageResolverError,
+};
 
 /// Quickwit supported storage resolvers.
 pub fn quickwit_storage_uri_resolver() -> &'static StorageUriResolver {
@@ -35,6 +42,7 @@ pub fn quickwit_storage_uri_resolver() -> &'static StorageUriResolver {
     STORAGE_URI_RESOLVER.get_or_init(|| {
         #[allow(unused_mut)]
         let mut builder = StorageUriResolver::builder()
+            .middleware_config(StorageMiddlewareConfig::from_env())
             .register(RamStorageFactory::default())
             .register(LocalFileStorageFactory::default())
             .register(S3CompatibleObjectStorageFactory::default());
@@ -51,6 +59,18 @@ pub fn quickwit_storage_uri_resolver() -> &'static StorageUriResolver {
             })
         }
 
+        #[cfg(feature = "gcs")]
+        {
+            builder = builder.register(GoogleCloudStorageFactory::default());
+        }
+
+        #[cfg(not(feature = "gcs"))]
+        {
+            builder = builder.register(UnsupportedStorage {
+                protocol: Protocol::Gcs,
+            })
+        }
+
         builder.build()
     })
 }
@@ -84,15 +104,17 @@ impl StorageFactory for UnsupportedStorage {
 }
 
 /// Resolves an URI by dispatching it to the right [`StorageFactory`]
-/// based on its protocol.
+/// based on its protocol, and wraps the resulting storage with the middleware
+/// stack (metrics, retries, rate limiting) of that protocol.
 #[derive(Clone)]
 pub struct StorageUriResolver {
-    per_protocol_resolver: Arc<HashMap<Protocol, Arc<dyn StorageFactory>>>,
+    per_protocol_resolver: Arc<HashMap<Protocol, (Arc<dyn StorageFactory>, StorageMiddleware)>>,
 }
 
 #[derive(Default)]
 pub struct StorageUriResolverBuilder {
     per_protocol_resolver: HashMap<Protocol, Arc<dyn StorageFactory>>,
+    middleware_config: StorageMiddlewareConfig,
 }
 
 impl StorageUriResolverBuilder {
@@ -106,10 +128,26 @@ impl StorageUriResolverBuilder {
         self
     }
 
+    /// Sets the settings of the middleware stack wrapping the resolved storages.
+    pub fn middleware_config(mut self, middleware_config: StorageMiddlewareConfig) -> Self {
+        self.middleware_config = middleware_config;
+        self
+    }
+
     /// Builds the `StorageUriResolver`.
+    ///
+    /// Each protocol gets its own middleware stack, so rate limits apply per protocol.
     pub fn build(self) -> StorageUriResolver {
+        let per_protocol_resolver = self
+            .per_protocol_resolver
+            .into_iter()
+            .map(|(protocol, factory)| {
+                let middleware = StorageMiddleware::new(&self.middleware_config, protocol);
+                (protocol, (factory, middleware))
+            })
+            .collect();
         StorageUriResolver {
-            per_protocol_resolver: Arc::new(self.per_protocol_resolver),
+            per_protocol_resolver: Arc::new(per_protocol_resolver),
         }
     }
 }
@@ -134,19 +172,43 @@ impl StorageUriResolver {
             builder = builder.register(AzureBlobStorageFactory::default());
         }
 
+        #[cfg(feature = "gcs")]
+        {
+            builder = builder.register(GoogleCloudStorageFactory::default());
+        }
+
         builder.build()
     }
 
     /// Resolves the given URI.
     pub fn resolve(&self, uri: &Uri) -> Result<Arc<dyn Storage>, StorageResolverError> {
-        let resolver = self
-            .per_protocol_resolver
-            .get(&uri.protocol())
-            .ok_or_else(|| StorageResolverError::ProtocolUnsupported {
-                protocol: uri.protocol().to_string(),
-            })?;
+        let (resolver, middleware) =
+            self.per_protocol_resolver
+                .get(&uri.protocol())
+                .ok_or_else(|| StorageResolverError::ProtocolUnsupported {
+                    protocol: uri.protocol().to_string(),
+                })?;
         let storage = resolver.resolve(uri)?;
-        Ok(storage)
+        Ok(middleware.layer(storage))
+    }
+
+    /// Resolves the given URI. If `encryption_key_uri_opt` is set, the files of the storage are
+    /// encrypted at rest with the corresponding key.
+    pub fn resolve_with_encryption(
+        &self,
+        uri: &Uri,
+        encryption_key_uri_opt: Option<&str>,
+    ) -> Result<Arc<dyn Storage>, StorageResolverError> {
+        let storage = self.resolve(uri)?;
+        let Some(encryption_key_uri) = encryption_key_uri_opt else {
+            return Ok(storage);
+        };
+        wrap_storage_with_encryption(storage, encryption_key_uri).map_err(|storage_error| {
+            StorageResolverError::FailedToOpenStorage {
+                kind: storage_error.kind(),
+                message: storage_error.to_string(),
+            }
+        })
     }
 }
 
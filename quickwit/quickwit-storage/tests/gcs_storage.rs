@@ -0,0 +1,79 @@
+// Copyright (C) 2023 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+// This file is an integration test that assumes that a connection
+// to fake-gcs-server (the emulated Google Cloud Storage environment)
+// listening on `QW_GCS_ENDPOINT` or `http://localhost:4443` is possible.
+
+#[cfg(all(feature = "testsuite", feature = "gcs"))]
+#[tokio::test]
+#[cfg_attr(not(feature = "ci-test"), ignore)]
+async fn test_suite_on_gcs_storage() -> anyhow::Result<()> {
+    use std::path::Path;
+
+    use anyhow::Context;
+    use google_cloud_storage::client::{Client, ClientConfig};
+    use google_cloud_storage::http::buckets::insert::{BucketCreationConfig, InsertBucketRequest};
+    use google_cloud_storage::http::Error as GcsError;
+    use quickwit_storage::{GoogleCloudStorage, MultiPartPolicy};
+    let _ = tracing_subscriber::fmt::try_init();
+
+    // Setup bucket.
+    const BUCKET: &str = "quickwit";
+    let endpoint =
+        std::env::var("QW_GCS_ENDPOINT").unwrap_or_else(|_| "http://localhost:4443".to_string());
+    let mut client_config = ClientConfig::default().anonymous();
+    client_config.storage_endpoint = endpoint;
+    let client = Client::new(client_config);
+    let insert_bucket_request = InsertBucketRequest {
+        name: BUCKET.to_string(),
+        bucket: BucketCreationConfig::default(),
+        ..Default::default()
+    };
+    match client.insert_bucket(&insert_bucket_request).await {
+        Ok(_) => {}
+        // The bucket survives previous runs because the emulator refuses to delete non-empty
+        // buckets.
+        Err(GcsError::Response(response)) if response.code == 409 => {}
+        Err(error) => return Err(error.into()),
+    }
+
+    let mut object_storage = GoogleCloudStorage::new_emulated(BUCKET);
+    quickwit_storage::storage_test_suite(&mut object_storage).await?;
+
+    let mut object_storage = GoogleCloudStorage::new_emulated(BUCKET)
+        .with_prefix(Path::new("integration-tests/test-gcs-storage"));
+    quickwit_storage::storage_test_single_part_upload(&mut object_storage)
+        .await
+        .context("test_single_part_upload")?;
+
+    object_storage.set_policy(MultiPartPolicy {
+        // Resumable upload chunks must be a multiple of 256KiB.
+        target_part_num_bytes: 5 * 1_024 * 1_024, // 5MiB
+        max_num_parts: 10_000,
+        multipart_threshold_num_bytes: 10_000_000,
+        max_object_num_bytes: 5_000_000_000_000,
+        max_concurrent_upload: 1,
+    });
+    quickwit_storage::storage_test_multi_part_upload(&mut object_storage)
+        .await
+        .context("test_multi_part_upload")?;
+
+    Ok(())
+}